@@ -0,0 +1,130 @@
+// =====================================================================================
+// File: core-defi/src/amm/fixed_point.rs
+// Description: Integer fixed-point helpers shared by the AMM pool math
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use ethers::types::{U256, U512};
+use rust_decimal::Decimal;
+
+use crate::error::{DeFiError, DeFiResult};
+
+/// Largest mantissa a `Decimal` can hold (2^96 - 1)
+const MAX_DECIMAL_MANTISSA: u128 = 79_228_162_514_264_337_593_543_950_335;
+
+/// Largest scale a `Decimal` can hold
+const MAX_DECIMAL_SCALE: u32 = 28;
+
+/// Convert a token amount into its integer base units, truncating extra precision
+pub fn to_fixed(value: Decimal, decimals: u32) -> DeFiResult<U256> {
+    if value.is_sign_negative() && !value.is_zero() {
+        return Err(DeFiError::validation_error("amount", "Amount cannot be negative"));
+    }
+
+    let mantissa = U256::from(value.mantissa().unsigned_abs());
+    let scale = value.scale();
+
+    if scale <= decimals {
+        mantissa
+            .checked_mul(U256::exp10((decimals - scale) as usize))
+            .ok_or_else(|| DeFiError::internal_error("Fixed-point conversion overflow"))
+    } else {
+        Ok(mantissa / U256::exp10((scale - decimals) as usize))
+    }
+}
+
+/// Convert integer base units back into a token amount
+///
+/// Digits beyond what a `Decimal` can represent are truncated from the right.
+pub fn from_fixed(value: U256, decimals: u32) -> DeFiResult<Decimal> {
+    let mut value = value;
+    let mut scale = decimals;
+
+    if scale > MAX_DECIMAL_SCALE {
+        value /= U256::exp10((scale - MAX_DECIMAL_SCALE) as usize);
+        scale = MAX_DECIMAL_SCALE;
+    }
+
+    let max_mantissa = U256::from(MAX_DECIMAL_MANTISSA);
+    while value > max_mantissa && scale > 0 {
+        value /= U256::from(10u8);
+        scale -= 1;
+    }
+
+    if value > max_mantissa {
+        return Err(DeFiError::internal_error("Amount exceeds decimal range"));
+    }
+
+    Ok(Decimal::from_i128_with_scale(value.as_u128() as i128, scale))
+}
+
+/// Convert an integer to `f64`, for informational values such as spot prices
+pub fn to_f64(value: U256) -> f64 {
+    value.to_string().parse().unwrap_or(f64::MAX)
+}
+
+/// Compute `floor(a * b / denominator)` with a 512-bit intermediate
+pub fn mul_div(a: U256, b: U256, denominator: U256) -> DeFiResult<U256> {
+    if denominator.is_zero() {
+        return Err(DeFiError::internal_error("Division by zero in pool math"));
+    }
+
+    let result = a.full_mul(b) / U512::from(denominator);
+    U256::try_from(result).map_err(|_| DeFiError::internal_error("Arithmetic overflow in pool math"))
+}
+
+/// Compute `ceil(a * b / denominator)` with a 512-bit intermediate
+pub fn mul_div_rounding_up(a: U256, b: U256, denominator: U256) -> DeFiResult<U256> {
+    let result = mul_div(a, b, denominator)?;
+    if (a.full_mul(b) % U512::from(denominator)).is_zero() {
+        Ok(result)
+    } else {
+        result
+            .checked_add(U256::one())
+            .ok_or_else(|| DeFiError::internal_error("Arithmetic overflow in pool math"))
+    }
+}
+
+/// Compute `ceil(a / b)`
+pub fn div_rounding_up(a: U256, b: U256) -> DeFiResult<U256> {
+    if b.is_zero() {
+        return Err(DeFiError::internal_error("Division by zero in pool math"));
+    }
+
+    let quotient = a / b;
+    if (a % b).is_zero() {
+        Ok(quotient)
+    } else {
+        Ok(quotient + U256::one())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decimal_round_trip() {
+        let amount = Decimal::new(1234567, 6); // 1.234567
+        let fixed = to_fixed(amount, 18).unwrap();
+        assert_eq!(fixed, U256::from(1_234_567_000_000_000_000u128));
+        assert_eq!(from_fixed(fixed, 18).unwrap(), amount);
+
+        // Precision beyond the token decimals is truncated
+        assert_eq!(to_fixed(Decimal::new(1999, 3), 2).unwrap(), U256::from(199u8));
+        assert!(to_fixed(Decimal::new(-1, 0), 18).is_err());
+    }
+
+    #[test]
+    fn test_mul_div_rounding() {
+        let a = U256::MAX;
+        assert_eq!(mul_div(a, U256::from(2u8), U256::from(4u8)).unwrap(), a / 2);
+        assert_eq!(mul_div(U256::from(7u8), U256::from(3u8), U256::from(2u8)).unwrap(), U256::from(10u8));
+        assert_eq!(
+            mul_div_rounding_up(U256::from(7u8), U256::from(3u8), U256::from(2u8)).unwrap(),
+            U256::from(11u8)
+        );
+        assert_eq!(div_rounding_up(U256::from(9u8), U256::from(3u8)).unwrap(), U256::from(3u8));
+        assert!(mul_div(a, a, U256::one()).is_err());
+    }
+}
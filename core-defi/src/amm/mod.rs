@@ -1,5 +1,5 @@
 // =====================================================================================
-// File: core-defi/src/amm.rs
+// File: core-defi/src/amm/mod.rs
 // Description: Automated Market Maker (AMM) implementation
 // Author: arkSong (arksong2018@gmail.com)
 // =====================================================================================
@@ -9,7 +9,19 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+
+pub mod fixed_point;
+pub mod uniswap_v3;
+pub mod stable_swap;
+pub mod weighted;
+pub mod pool;
+pub mod router;
+pub mod service;
+
+pub use pool::AmmPool;
+pub use router::{RouteSplit, Router, SplitRoute};
+pub use service::AMMServiceImpl;
 
 use crate::{
     error::{DeFiError, DeFiResult},
@@ -231,6 +243,22 @@ impl UniswapV2Pool {
         
         Ok(price_impact.abs())
     }
+
+    /// Execute a swap against the pool reserves and return the output amount
+    pub fn apply_swap(&mut self, amount_in: Decimal, token_in: &Token) -> DeFiResult<Decimal> {
+        let amount_out = self.get_amount_out(amount_in, token_in)?;
+
+        if token_in.address == self.token_pair.token_a.address {
+            self.reserve_a += amount_in;
+            self.reserve_b -= amount_out;
+        } else {
+            self.reserve_b += amount_in;
+            self.reserve_a -= amount_out;
+        }
+        self.last_updated = Utc::now();
+
+        Ok(amount_out)
+    }
 }
 
 /// Uniswap V3 style pool with concentrated liquidity
@@ -243,6 +271,9 @@ pub struct UniswapV3Pool {
     pub current_tick: i32,
     pub sqrt_price_x96: String, // Q64.96 format
     pub liquidity: Decimal,
+    /// Net liquidity change when crossing each initialized tick left to right
+    #[serde(default)]
+    pub ticks: BTreeMap<i32, Decimal>,
     pub last_updated: DateTime<Utc>,
 }
 
@@ -0,0 +1,290 @@
+// =====================================================================================
+// File: core-defi/src/amm/pool.rs
+// Description: Unified view over the supported AMM pool types
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use rust_decimal::prelude::*;
+
+use super::fixed_point::{from_fixed, to_f64, to_fixed};
+use super::{BalancerPool, CurvePool, UniswapV2Pool, UniswapV3Pool};
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::{AMMProtocol, LiquidityPool, Token, TokenPair},
+};
+
+/// Base gas cost of a constant product swap
+const V2_SWAP_GAS: u64 = 100_000;
+/// Base gas cost of a concentrated liquidity swap
+const V3_SWAP_GAS: u64 = 130_000;
+/// Additional gas for each initialized tick crossed
+const V3_TICK_CROSS_GAS: u64 = 20_000;
+/// Gas cost of a StableSwap exchange
+const CURVE_SWAP_GAS: u64 = 180_000;
+/// Gas cost of a weighted pool swap
+const BALANCER_SWAP_GAS: u64 = 150_000;
+
+/// Pool the AMM service can quote and execute against
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AmmPool {
+    UniswapV2(UniswapV2Pool),
+    UniswapV3(UniswapV3Pool),
+    Curve(CurvePool),
+    Balancer(BalancerPool),
+}
+
+/// Quote for a swap through a single pool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolQuote {
+    pub pool_address: String,
+    pub protocol: AMMProtocol,
+    pub amount_out: Decimal,
+    pub price_impact: Decimal,
+    pub gas_estimate: u64,
+}
+
+impl AmmPool {
+    /// Pool address
+    pub fn address(&self) -> &str {
+        match self {
+            Self::UniswapV2(pool) => &pool.address,
+            Self::UniswapV3(pool) => &pool.address,
+            Self::Curve(pool) => &pool.address,
+            Self::Balancer(pool) => &pool.address,
+        }
+    }
+
+    /// Protocol implementing the pool
+    pub fn protocol(&self) -> AMMProtocol {
+        match self {
+            Self::UniswapV2(_) => AMMProtocol::UniswapV2,
+            Self::UniswapV3(_) => AMMProtocol::UniswapV3,
+            Self::Curve(_) => AMMProtocol::Curve,
+            Self::Balancer(_) => AMMProtocol::Balancer,
+        }
+    }
+
+    /// Check protocol-specific pool parameters
+    pub fn validate(&self) -> DeFiResult<()> {
+        match self {
+            Self::Curve(pool) => pool.validate(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Tokens held by the pool
+    pub fn tokens(&self) -> Vec<Token> {
+        match self {
+            Self::UniswapV2(pool) => vec![pool.token_pair.token_a.clone(), pool.token_pair.token_b.clone()],
+            Self::UniswapV3(pool) => vec![pool.token_pair.token_a.clone(), pool.token_pair.token_b.clone()],
+            Self::Curve(pool) => pool.tokens.clone(),
+            Self::Balancer(pool) => pool.tokens.clone(),
+        }
+    }
+
+    /// Check whether the pool can swap between two tokens
+    pub fn supports_pair(&self, token_in: &Token, token_out: &Token) -> bool {
+        let tokens = self.tokens();
+        token_in.address != token_out.address
+            && tokens.iter().any(|t| t.address == token_in.address)
+            && tokens.iter().any(|t| t.address == token_out.address)
+    }
+
+    /// Quote an exact-input swap without changing pool state
+    pub fn quote(&self, token_in: &Token, token_out: &Token, amount_in: Decimal) -> DeFiResult<PoolQuote> {
+        if !self.supports_pair(token_in, token_out) {
+            return Err(DeFiError::validation_error("tokens", "Token pair not in pool"));
+        }
+
+        let (amount_out, price_impact, gas_estimate) = match self {
+            Self::UniswapV2(pool) => (
+                pool.get_amount_out(amount_in, token_in)?,
+                pool.calculate_price_impact(amount_in, token_in)?,
+                V2_SWAP_GAS,
+            ),
+            Self::UniswapV3(pool) => {
+                let zero_for_one = token_in.address == pool.token_pair.token_a.address;
+                let result = pool.simulate_swap(zero_for_one, to_fixed(amount_in, token_in.decimals as u32)?)?;
+                let amount_out = from_fixed(result.amount_out, token_out.decimals as u32)?;
+                let current_price = pool.spot_price(token_in)?;
+                let execution_price = amount_out / amount_in;
+                (
+                    amount_out,
+                    ((current_price - execution_price) / current_price).abs(),
+                    V3_SWAP_GAS + result.ticks_crossed.len() as u64 * V3_TICK_CROSS_GAS,
+                )
+            }
+            Self::Curve(pool) => (
+                pool.get_amount_out(amount_in, token_in, token_out)?,
+                pool.calculate_price_impact(amount_in, token_in, token_out)?,
+                CURVE_SWAP_GAS,
+            ),
+            Self::Balancer(pool) => (
+                pool.get_amount_out(amount_in, token_in, token_out)?,
+                pool.calculate_price_impact(amount_in, token_in, token_out)?,
+                BALANCER_SWAP_GAS,
+            ),
+        };
+
+        Ok(PoolQuote {
+            pool_address: self.address().to_string(),
+            protocol: self.protocol(),
+            amount_out,
+            price_impact,
+            gas_estimate,
+        })
+    }
+
+    /// Execute an exact-input swap against the pool state
+    pub fn apply_swap(&mut self, token_in: &Token, token_out: &Token, amount_in: Decimal) -> DeFiResult<Decimal> {
+        if !self.supports_pair(token_in, token_out) {
+            return Err(DeFiError::validation_error("tokens", "Token pair not in pool"));
+        }
+
+        match self {
+            Self::UniswapV2(pool) => pool.apply_swap(amount_in, token_in),
+            Self::UniswapV3(pool) => pool.apply_swap(amount_in, token_in),
+            Self::Curve(pool) => pool.apply_swap(amount_in, token_in, token_out),
+            Self::Balancer(pool) => pool.apply_swap(amount_in, token_in, token_out),
+        }
+    }
+
+    /// Swap fee as a fraction of the input amount
+    pub fn fee_rate(&self) -> Decimal {
+        match self {
+            Self::UniswapV2(pool) => pool.fee_rate,
+            Self::UniswapV3(pool) => Decimal::from(pool.fee_tier) / Decimal::from(1_000_000),
+            Self::Curve(pool) => pool.fee_rate,
+            Self::Balancer(pool) => pool.swap_fee,
+        }
+    }
+
+    /// Balance of a token held by the pool
+    ///
+    /// V3 pools report virtual reserves of the active range.
+    pub fn reserve_of(&self, token: &Token) -> Decimal {
+        match self {
+            Self::UniswapV2(pool) => {
+                if token.address == pool.token_pair.token_a.address {
+                    pool.reserve_a
+                } else if token.address == pool.token_pair.token_b.address {
+                    pool.reserve_b
+                } else {
+                    Decimal::ZERO
+                }
+            }
+            Self::UniswapV3(pool) => {
+                let sqrt_price = pool.sqrt_price().map(|p| to_f64(p) / 2f64.powi(96)).unwrap_or(0.0);
+                let liquidity = pool.liquidity.to_f64().unwrap_or(0.0);
+                let reserve = if sqrt_price <= 0.0 {
+                    0.0
+                } else if token.address == pool.token_pair.token_a.address {
+                    liquidity / sqrt_price
+                } else if token.address == pool.token_pair.token_b.address {
+                    liquidity * sqrt_price
+                } else {
+                    0.0
+                };
+                Decimal::from_f64(reserve / 10f64.powi(token.decimals as i32)).unwrap_or(Decimal::ZERO)
+            }
+            Self::Curve(pool) => pool
+                .token_index(token)
+                .map(|i| pool.balances[i])
+                .unwrap_or(Decimal::ZERO),
+            Self::Balancer(pool) => pool
+                .token_index(token)
+                .map(|i| pool.balances[i])
+                .unwrap_or(Decimal::ZERO),
+        }
+    }
+
+    /// Summarize the pool as a `LiquidityPool` oriented on the given pair
+    pub fn to_liquidity_pool(
+        &self,
+        id: Uuid,
+        token_a: &Token,
+        token_b: &Token,
+        created_at: DateTime<Utc>,
+    ) -> LiquidityPool {
+        let (total_supply, updated_at) = match self {
+            Self::UniswapV2(pool) => (pool.total_supply, pool.last_updated),
+            Self::UniswapV3(pool) => (pool.liquidity, pool.last_updated),
+            Self::Curve(pool) => (Decimal::ZERO, pool.last_updated),
+            Self::Balancer(pool) => (Decimal::ZERO, pool.last_updated),
+        };
+
+        LiquidityPool {
+            id,
+            address: self.address().to_string(),
+            protocol: self.protocol(),
+            token_pair: TokenPair::new(token_a.clone(), token_b.clone()),
+            reserve_a: self.reserve_of(token_a),
+            reserve_b: self.reserve_of(token_b),
+            total_supply,
+            fee_rate: self.fee_rate(),
+            volume_24h: Decimal::ZERO,
+            fees_24h: Decimal::ZERO,
+            apy: Decimal::ZERO,
+            tvl: Decimal::ZERO,
+            created_at,
+            updated_at,
+            is_active: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_tokens() -> (Token, Token, Token) {
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+        let dai = Token::new("0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), "DAI".to_string(), "Dai Stablecoin".to_string(), 18, 1);
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        (usdc, dai, weth)
+    }
+
+    fn create_curve_pool(usdc: &Token, dai: &Token) -> AmmPool {
+        AmmPool::Curve(CurvePool {
+            address: "0xcurve".to_string(),
+            tokens: vec![usdc.clone(), dai.clone()],
+            balances: vec![Decimal::from(1_000_000), Decimal::from(1_000_000)],
+            amplification_parameter: 100,
+            fee_rate: Decimal::new(4, 4),
+            admin_fee_rate: Decimal::new(5, 1),
+            last_updated: Utc::now(),
+        })
+    }
+
+    #[test]
+    fn test_quote_matches_underlying_pool() {
+        let (usdc, dai, weth) = create_tokens();
+        let pool = create_curve_pool(&usdc, &dai);
+
+        let quote = pool.quote(&usdc, &dai, Decimal::from(1000)).unwrap();
+        assert_eq!(quote.protocol, AMMProtocol::Curve);
+        assert_eq!(quote.amount_out, Decimal::from_i128_with_scale(999590103058584712249, 18));
+        assert_eq!(quote.gas_estimate, CURVE_SWAP_GAS);
+
+        assert!(pool.quote(&usdc, &weth, Decimal::from(1000)).is_err());
+        assert!(!pool.supports_pair(&usdc, &usdc));
+    }
+
+    #[test]
+    fn test_apply_swap_and_liquidity_view() {
+        let (usdc, dai, _) = create_tokens();
+        let mut pool = create_curve_pool(&usdc, &dai);
+
+        let out = pool.apply_swap(&dai, &usdc, Decimal::from(1000)).unwrap();
+        assert_eq!(out, Decimal::new(999590103, 6));
+
+        let view = pool.to_liquidity_pool(Uuid::new_v4(), &dai, &usdc, Utc::now());
+        assert_eq!(view.protocol, AMMProtocol::Curve);
+        assert_eq!(view.reserve_a, Decimal::from(1_001_000));
+        assert!(view.reserve_b < Decimal::from(1_000_000));
+        assert_eq!(view.fee_rate, Decimal::new(4, 4));
+    }
+}
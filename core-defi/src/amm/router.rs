@@ -0,0 +1,523 @@
+// =====================================================================================
+// File: core-defi/src/amm/router.rs
+// Description: Multi-hop path search and split routing over AMM pools
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Pools reported by `get_pools_for_pair` form a directed token graph, with
+//! the pools able to swap each token pair collected on one edge. The router
+//! extends paths one hop at a time up to the hop limit, keeping only the
+//! partial paths with the best output into each token, then hands out the
+//! input in equal chunks, each to the path with the best marginal output on
+//! the state left by the previous chunks. Output is concave in the input for
+//! every pool type, so this greedy split is optimal for the chunk size.
+
+use serde::{Deserialize, Serialize};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{AmmPool, SwapRoute};
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::{LiquidityPool, Token},
+};
+
+/// Number of chunks the input is split into
+const SPLIT_PARTS: u32 = 20;
+/// Paths considered for splitting, ranked by full-amount output
+const MAX_SPLIT_PATHS: usize = 4;
+/// Partial paths kept per token at each hop of the search
+const PATHS_PER_TOKEN: usize = MAX_SPLIT_PATHS;
+
+/// Share of the input sent along one route
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteSplit {
+    pub amount_in: Decimal,
+    pub route: SwapRoute,
+}
+
+/// Input split across parallel routes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitRoute {
+    pub splits: Vec<RouteSplit>,
+    pub amount_in: Decimal,
+    pub expected_amount_out: Decimal,
+    /// Input-weighted price impact of the routes
+    pub price_impact: Decimal,
+    pub gas_estimate: u64,
+}
+
+impl SplitRoute {
+    /// Addresses of all pools used, in execution order
+    pub fn pools(&self) -> Vec<String> {
+        let mut pools: Vec<String> = Vec::new();
+        for pool in self.splits.iter().flat_map(|split| &split.route.pools) {
+            if !pools.contains(pool) {
+                pools.push(pool.clone());
+            }
+        }
+        pools
+    }
+}
+
+/// Sequence of pools and the tokens swapped between them
+#[derive(Debug, Clone, PartialEq)]
+struct Path {
+    pools: Vec<String>,
+    tokens: Vec<Token>,
+}
+
+/// Pools able to swap from one token into another
+struct Edge {
+    token_out: Token,
+    pools: Vec<String>,
+}
+
+/// Directed token graph keyed by token address
+struct TokenGraph {
+    edges: HashMap<String, BTreeMap<String, Edge>>,
+}
+
+impl TokenGraph {
+    fn build(pair_pools: &[LiquidityPool]) -> Self {
+        let mut edges: HashMap<String, BTreeMap<String, Edge>> = HashMap::new();
+        for pool in pair_pools {
+            let pair = &pool.token_pair;
+            for (token_in, token_out) in [(&pair.token_a, &pair.token_b), (&pair.token_b, &pair.token_a)] {
+                let edge = edges
+                    .entry(token_in.address.clone())
+                    .or_default()
+                    .entry(token_out.address.clone())
+                    .or_insert_with(|| Edge { token_out: token_out.clone(), pools: Vec::new() });
+                // A pool is reported once per pair orientation
+                if !edge.pools.contains(&pool.address) {
+                    edge.pools.push(pool.address.clone());
+                }
+            }
+        }
+        // Deterministic search order regardless of pool listing order
+        for edge in edges.values_mut().flat_map(|neighbours| neighbours.values_mut()) {
+            edge.pools.sort();
+        }
+
+        Self { edges }
+    }
+
+    /// Simple paths from `token_in` to `token_out` with at most `max_hops` pools
+    ///
+    /// Each hop keeps the `PATHS_PER_TOKEN` paths with the highest output into
+    /// every token, so the search grows linearly with the pool count. With
+    /// `pinned` set, hop `i` must go through `pinned[i]`.
+    fn paths(
+        &self,
+        pools: &HashMap<String, AmmPool>,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+        max_hops: usize,
+        pinned: Option<&[String]>,
+    ) -> DeFiResult<Vec<Path>> {
+        let mut frontier = vec![(Path { pools: Vec::new(), tokens: vec![token_in.clone()] }, amount_in)];
+        let mut found = Vec::new();
+        let mut last_error = None;
+
+        for hop in 0..max_hops {
+            let mut reached: BTreeMap<String, Vec<(Path, Decimal)>> = BTreeMap::new();
+            for (path, amount) in &frontier {
+                let current = path.tokens.last().expect("path starts with the input token");
+                for edge in self.edges.get(&current.address).into_iter().flat_map(|neighbours| neighbours.values()) {
+                    if path.tokens.iter().any(|t| t.address == edge.token_out.address) {
+                        continue;
+                    }
+                    for pool in &edge.pools {
+                        let allowed = match pinned {
+                            Some(route) => route.get(hop) == Some(pool),
+                            None => !path.pools.contains(pool),
+                        };
+                        let Some(state) = pools.get(pool).filter(|_| allowed) else {
+                            continue;
+                        };
+
+                        match state.quote(current, &edge.token_out, *amount) {
+                            Ok(quote) => {
+                                let mut next = path.clone();
+                                next.pools.push(pool.clone());
+                                next.tokens.push(edge.token_out.clone());
+                                reached
+                                    .entry(edge.token_out.address.clone())
+                                    .or_default()
+                                    .push((next, quote.amount_out));
+                            }
+                            Err(error) => last_error = Some(error),
+                        }
+                    }
+                }
+            }
+
+            frontier.clear();
+            for (address, mut candidates) in reached {
+                // Bound the search to the best paths into each token
+                candidates.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+                candidates.truncate(PATHS_PER_TOKEN);
+                if address == token_out.address {
+                    found.extend(candidates.into_iter().map(|(path, _)| path));
+                } else {
+                    frontier.extend(candidates);
+                }
+            }
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        if let Some(route) = pinned {
+            found.retain(|path| path.pools.len() == route.len());
+        }
+        match last_error {
+            Some(error) if found.is_empty() => Err(error),
+            _ => Ok(found),
+        }
+    }
+}
+
+/// Route finder over the pools reported for each token pair
+pub struct Router {
+    max_hops: usize,
+    graph: TokenGraph,
+}
+
+impl Router {
+    pub fn new(max_hops: u8, pair_pools: &[LiquidityPool]) -> Self {
+        Self {
+            max_hops: max_hops.max(1) as usize,
+            graph: TokenGraph::build(pair_pools),
+        }
+    }
+
+    /// Single path with the highest output for the full amount
+    pub fn best_route(
+        &self,
+        pools: &HashMap<String, AmmPool>,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<SwapRoute> {
+        let mut ranked = self.ranked_routes(pools, token_in, token_out, amount_in)?;
+        Ok(ranked.remove(0).1)
+    }
+
+    /// Quote a swap along the given pool addresses
+    pub fn pinned_route(
+        &self,
+        pools: &HashMap<String, AmmPool>,
+        route: &[String],
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<SwapRoute> {
+        validate_amount(amount_in)?;
+        if route.is_empty() || route.len() > self.max_hops {
+            return Err(DeFiError::validation_error(
+                "route".to_string(),
+                format!("Route must use between 1 and {} pools", self.max_hops),
+            ));
+        }
+        if let Some(missing) = route.iter().find(|pool| !pools.contains_key(*pool)) {
+            return Err(DeFiError::not_found("Pool", missing.as_str()));
+        }
+
+        let path = self
+            .graph
+            .paths(pools, token_in, token_out, amount_in, route.len(), Some(route))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| DeFiError::validation_error("route", "Route does not connect the swap tokens"))?;
+        quote_path(pools, &path, amount_in)
+    }
+
+    /// Split the input across the best paths to maximize total output
+    pub fn split_route(
+        &self,
+        pools: &HashMap<String, AmmPool>,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<SplitRoute> {
+        let candidates: Vec<Path> = self
+            .ranked_routes(pools, token_in, token_out, amount_in)?
+            .into_iter()
+            .take(MAX_SPLIT_PATHS)
+            .map(|(path, _)| path)
+            .collect();
+
+        let parts = Decimal::from(SPLIT_PARTS);
+        let chunk = amount_in / parts;
+        let mut allocations = vec![Decimal::ZERO; candidates.len()];
+        let mut state = pools.clone();
+
+        for part in 0..SPLIT_PARTS {
+            // The last chunk absorbs the rounding remainder
+            let amount = if part + 1 == SPLIT_PARTS {
+                amount_in - chunk * (parts - Decimal::ONE)
+            } else {
+                chunk
+            };
+
+            let mut best: Option<(usize, Decimal)> = None;
+            for (index, path) in candidates.iter().enumerate() {
+                if let Ok(route) = quote_path(&state, path, amount) {
+                    match best {
+                        Some((_, out)) if out >= route.expected_amount_out => {}
+                        _ => best = Some((index, route.expected_amount_out)),
+                    }
+                }
+            }
+            let (index, _) = best
+                .ok_or_else(|| DeFiError::insufficient_liquidity("No route can absorb the swap amount"))?;
+            execute_path(&mut state, &candidates[index], amount)?;
+            allocations[index] += amount;
+        }
+
+        // Re-quote the merged splits in execution order
+        let mut state = pools.clone();
+        let mut splits = Vec::new();
+        for (path, amount) in candidates.iter().zip(allocations) {
+            if amount.is_zero() {
+                continue;
+            }
+            let route = quote_path(&state, path, amount)?;
+            execute_path(&mut state, path, amount)?;
+            splits.push(RouteSplit { amount_in: amount, route });
+        }
+
+        let expected_amount_out = splits.iter().map(|split| split.route.expected_amount_out).sum();
+        let price_impact = splits
+            .iter()
+            .map(|split| split.route.price_impact * split.amount_in)
+            .sum::<Decimal>()
+            / amount_in;
+        let gas_estimate = splits.iter().map(|split| split.route.gas_estimate).sum();
+
+        Ok(SplitRoute {
+            splits,
+            amount_in,
+            expected_amount_out,
+            price_impact,
+            gas_estimate,
+        })
+    }
+
+    /// All quotable paths, best output first
+    fn ranked_routes(
+        &self,
+        pools: &HashMap<String, AmmPool>,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<Vec<(Path, SwapRoute)>> {
+        validate_amount(amount_in)?;
+
+        let mut ranked = Vec::new();
+        let mut last_error = None;
+        for path in self.graph.paths(pools, token_in, token_out, amount_in, self.max_hops, None)? {
+            match quote_path(pools, &path, amount_in) {
+                Ok(route) => ranked.push((path, route)),
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        if ranked.is_empty() {
+            return Err(last_error.unwrap_or_else(|| DeFiError::not_found(
+                "Route".to_string(),
+                format!("{}/{}", token_in.symbol, token_out.symbol),
+            )));
+        }
+        ranked.sort_by(|a, b| {
+            b.1.expected_amount_out
+                .cmp(&a.1.expected_amount_out)
+                .then(a.1.gas_estimate.cmp(&b.1.gas_estimate))
+        });
+        Ok(ranked)
+    }
+}
+
+/// Execute a route against pool state, returning the final output amount
+pub fn execute_route(pools: &mut HashMap<String, AmmPool>, route: &SwapRoute, amount_in: Decimal) -> DeFiResult<Decimal> {
+    let path = Path { pools: route.pools.clone(), tokens: route.tokens.clone() };
+    execute_path(pools, &path, amount_in)
+}
+
+fn quote_path(pools: &HashMap<String, AmmPool>, path: &Path, amount_in: Decimal) -> DeFiResult<SwapRoute> {
+    let mut amount = amount_in;
+    let mut retained = Decimal::ONE;
+    let mut gas_estimate = 0;
+
+    for (hop, address) in path.pools.iter().enumerate() {
+        let pool = pools.get(address).ok_or_else(|| DeFiError::not_found("Pool", address.as_str()))?;
+        let quote = pool.quote(&path.tokens[hop], &path.tokens[hop + 1], amount)?;
+        amount = quote.amount_out;
+        retained *= Decimal::ONE - quote.price_impact;
+        gas_estimate += quote.gas_estimate;
+    }
+
+    Ok(SwapRoute {
+        pools: path.pools.clone(),
+        tokens: path.tokens.clone(),
+        expected_amount_out: amount,
+        price_impact: Decimal::ONE - retained,
+        gas_estimate,
+    })
+}
+
+fn execute_path(pools: &mut HashMap<String, AmmPool>, path: &Path, amount_in: Decimal) -> DeFiResult<Decimal> {
+    if path.tokens.len() != path.pools.len() + 1 {
+        return Err(DeFiError::validation_error("route", "Route tokens do not match its pools"));
+    }
+
+    let mut amount = amount_in;
+    for (hop, address) in path.pools.iter().enumerate() {
+        let pool = pools.get_mut(address).ok_or_else(|| DeFiError::not_found("Pool", address.as_str()))?;
+        amount = pool.apply_swap(&path.tokens[hop], &path.tokens[hop + 1], amount)?;
+    }
+    Ok(amount)
+}
+
+fn validate_amount(amount_in: Decimal) -> DeFiResult<()> {
+    if amount_in <= Decimal::ZERO {
+        return Err(DeFiError::validation_error("amount_in", "Amount must be positive"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::UniswapV2Pool;
+    use crate::types::TokenPair;
+    use chrono::Utc;
+    use uuid::Uuid;
+
+    fn token(address: &str, symbol: &str) -> Token {
+        Token::new(address.to_string(), symbol.to_string(), symbol.to_string(), 18, 1)
+    }
+
+    fn v2_pool(address: &str, token_a: &Token, token_b: &Token, reserve_a: i64, reserve_b: i64) -> AmmPool {
+        AmmPool::UniswapV2(UniswapV2Pool {
+            address: address.to_string(),
+            token_pair: TokenPair::new(token_a.clone(), token_b.clone()),
+            reserve_a: Decimal::from(reserve_a),
+            reserve_b: Decimal::from(reserve_b),
+            total_supply: Decimal::from(1_000),
+            fee_rate: Decimal::new(3, 3),
+            last_updated: Utc::now(),
+        })
+    }
+
+    fn pool_map(pools: Vec<AmmPool>) -> HashMap<String, AmmPool> {
+        pools.into_iter().map(|pool| (pool.address().to_string(), pool)).collect()
+    }
+
+    /// Router over every pair of each pool, listed in both orientations like `get_pools_for_pair`
+    fn router(max_hops: u8, pools: &HashMap<String, AmmPool>) -> Router {
+        let mut pair_pools = Vec::new();
+        for pool in pools.values() {
+            let tokens = pool.tokens();
+            for token_a in &tokens {
+                for token_b in tokens.iter().filter(|t| t.address != token_a.address) {
+                    pair_pools.push(pool.to_liquidity_pool(Uuid::new_v4(), token_a, token_b, Utc::now()));
+                }
+            }
+        }
+        Router::new(max_hops, &pair_pools)
+    }
+
+    #[test]
+    fn test_multi_hop_route_respects_hop_limit() {
+        let (usdc, weth, dai) = (token("0x1", "USDC"), token("0x2", "WETH"), token("0x3", "DAI"));
+        let pools = pool_map(vec![
+            v2_pool("0xusdc-weth", &usdc, &weth, 2_000_000, 1_000),
+            v2_pool("0xweth-dai", &weth, &dai, 1_000, 2_000_000),
+        ]);
+
+        let route = router(3, &pools).best_route(&pools, &usdc, &dai, Decimal::from(1000)).unwrap();
+        assert_eq!(route.pools, vec!["0xusdc-weth".to_string(), "0xweth-dai".to_string()]);
+        assert_eq!(route.tokens, vec![usdc.clone(), weth.clone(), dai.clone()]);
+        assert_eq!(route.gas_estimate, 200_000);
+        // Two 0.3% fees plus a little price impact on each hop
+        assert!(route.expected_amount_out > Decimal::from(992) && route.expected_amount_out < Decimal::from(994));
+
+        assert!(router(1, &pools).best_route(&pools, &usdc, &dai, Decimal::from(1000)).is_err());
+    }
+
+    #[test]
+    fn test_split_route_beats_best_single_path() {
+        let (usdc, weth, dai) = (token("0x1", "USDC"), token("0x2", "WETH"), token("0x3", "DAI"));
+        let pools = pool_map(vec![
+            v2_pool("0xdirect", &usdc, &dai, 100_000, 100_000),
+            v2_pool("0xusdc-weth", &usdc, &weth, 2_000_000, 1_000),
+            v2_pool("0xweth-dai", &weth, &dai, 1_000, 2_000_000),
+        ]);
+        let router = router(3, &pools);
+        let amount_in = Decimal::from(50_000);
+
+        let single = router.best_route(&pools, &usdc, &dai, amount_in).unwrap();
+        let split = router.split_route(&pools, &usdc, &dai, amount_in).unwrap();
+
+        assert_eq!(split.splits.len(), 2);
+        assert_eq!(split.splits.iter().map(|s| s.amount_in).sum::<Decimal>(), amount_in);
+        assert!(split.expected_amount_out > single.expected_amount_out);
+        assert!(split.price_impact < single.price_impact);
+        assert_eq!(split.pools().len(), 3);
+
+        // Executing the splits in order reproduces the quote
+        let mut state = pools.clone();
+        let executed: Decimal = split
+            .splits
+            .iter()
+            .map(|s| execute_route(&mut state, &s.route, s.amount_in).unwrap())
+            .sum();
+        assert_eq!(executed, split.expected_amount_out);
+    }
+
+    #[test]
+    fn test_pinned_route_must_connect_tokens() {
+        let (usdc, weth, dai) = (token("0x1", "USDC"), token("0x2", "WETH"), token("0x3", "DAI"));
+        let pools = pool_map(vec![
+            v2_pool("0xusdc-weth", &usdc, &weth, 2_000_000, 1_000),
+            v2_pool("0xweth-dai", &weth, &dai, 1_000, 2_000_000),
+        ]);
+        let router = router(3, &pools);
+        let route = ["0xusdc-weth".to_string(), "0xweth-dai".to_string()];
+
+        let pinned = router.pinned_route(&pools, &route, &usdc, &dai, Decimal::from(1000)).unwrap();
+        assert_eq!(pinned.tokens, vec![usdc.clone(), weth, dai.clone()]);
+
+        let reversed = [route[1].clone(), route[0].clone()];
+        assert!(router.pinned_route(&pools, &reversed, &usdc, &dai, Decimal::from(1000)).is_err());
+        assert!(router.pinned_route(&pools, &route[..1], &usdc, &dai, Decimal::from(1000)).is_err());
+        assert!(router.pinned_route(&pools, &["0xmissing".to_string()], &usdc, &dai, Decimal::from(1000)).is_err());
+    }
+
+    #[test]
+    fn test_search_dedupes_pools_and_keeps_best_paths_per_hop() {
+        let (usdc, weth, wbtc, dai) =
+            (token("0x1", "USDC"), token("0x2", "WETH"), token("0x3", "WBTC"), token("0x4", "DAI"));
+        let mut pools = Vec::new();
+        for i in 1..=8 {
+            pools.push(v2_pool(&format!("0xa{i}"), &usdc, &weth, 2_000_000, 1_000 + i));
+            pools.push(v2_pool(&format!("0xb{i}"), &weth, &wbtc, 1_000, 50 + i));
+            pools.push(v2_pool(&format!("0xc{i}"), &wbtc, &dai, 50, 2_000_000 + i));
+        }
+        let pools = pool_map(pools);
+        let router = router(3, &pools);
+
+        // 512 three-hop paths exist, but only the best few into each token survive each hop
+        let ranked = router.ranked_routes(&pools, &usdc, &dai, Decimal::from(1000)).unwrap();
+        assert_eq!(ranked.len(), PATHS_PER_TOKEN);
+        assert_eq!(ranked[0].1.pools, vec!["0xa8".to_string(), "0xb8".to_string(), "0xc8".to_string()]);
+
+        // Each pool sits on its edge once although it is listed for both orientations
+        let edge = &router.graph.edges[&usdc.address][&weth.address];
+        assert_eq!(edge.pools.len(), 8);
+    }
+}
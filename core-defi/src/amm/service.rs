@@ -0,0 +1,593 @@
+// =====================================================================================
+// File: core-defi/src/amm/service.rs
+// Description: AMM service backed by simulated on-chain pool math
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use rust_decimal::MathematicalOps;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+use super::{
+    AMMConfig, AMMHealthStatus, AMMService, AmmPool, LiquidityRequest, LiquidityResult,
+    RemoveLiquidityRequest, SwapQuote, SwapRequest, SwapResult, SwapRoute,
+};
+use super::router::{self, RouteSplit, Router, SplitRoute};
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::{AMMProtocol, LiquidityPool, Token},
+};
+
+/// How long a quote stays valid
+const QUOTE_VALIDITY_SECONDS: i64 = 30;
+
+/// Pool registered with the service
+#[derive(Debug, Clone)]
+struct PoolEntry {
+    id: Uuid,
+    pool: AmmPool,
+    created_at: DateTime<Utc>,
+}
+
+/// AMM service quoting and executing swaps against in-memory pool state
+pub struct AMMServiceImpl {
+    config: AMMConfig,
+    pools: Arc<RwLock<HashMap<String, PoolEntry>>>,
+}
+
+impl AMMServiceImpl {
+    pub fn new(config: AMMConfig) -> Self {
+        Self {
+            config,
+            pools: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Register a pool with the service
+    pub async fn add_pool(&self, pool: AmmPool) -> DeFiResult<Uuid> {
+        pool.validate()?;
+        let mut pools = self.pools.write().await;
+        let address = pool.address().to_string();
+        if pools.contains_key(&address) {
+            return Err(DeFiError::validation_error("pool_address", "Pool already registered"));
+        }
+
+        let id = Uuid::new_v4();
+        pools.insert(address, PoolEntry { id, pool, created_at: Utc::now() });
+        Ok(id)
+    }
+
+    /// Current state of a registered pool
+    pub async fn pool_state(&self, pool_address: &str) -> Option<AmmPool> {
+        self.pools.read().await.get(pool_address).map(|entry| entry.pool.clone())
+    }
+
+    /// Independent copy of the service and its pool state
+    ///
+    /// Swaps against the fork leave the original pools untouched.
+    pub async fn fork(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            pools: Arc::new(RwLock::new(self.pools.read().await.clone())),
+        }
+    }
+
+    /// Split the input of a swap across the best routes
+    pub async fn find_split_route(&self, token_in: &Token, token_out: &Token, amount_in: Decimal) -> DeFiResult<SplitRoute> {
+        let router = self.router().await?;
+        let pools = Self::snapshot(&*self.pools.read().await);
+        router.split_route(&pools, token_in, token_out, amount_in)
+    }
+
+    /// Router over the pools listed for every pair of registered tokens
+    async fn router(&self) -> DeFiResult<Router> {
+        let tokens: BTreeMap<String, Token> = self
+            .pools
+            .read()
+            .await
+            .values()
+            .flat_map(|entry| entry.pool.tokens())
+            .map(|token| (token.address.clone(), token))
+            .collect();
+        let tokens: Vec<Token> = tokens.into_values().collect();
+
+        let mut pair_pools = Vec::new();
+        for (index, token_a) in tokens.iter().enumerate() {
+            for token_b in &tokens[index + 1..] {
+                pair_pools.extend(self.get_pools_for_pair(token_a, token_b).await?);
+            }
+        }
+        Ok(Router::new(self.config.max_routing_hops, &pair_pools))
+    }
+
+    fn snapshot(pools: &HashMap<String, PoolEntry>) -> HashMap<String, AmmPool> {
+        pools
+            .iter()
+            .map(|(address, entry)| (address.clone(), entry.pool.clone()))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl AMMService for AMMServiceImpl {
+    async fn get_quote(
+        &self,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<SwapQuote> {
+        let route = self.find_best_route(token_in, token_out, amount_in).await?;
+
+        Ok(SwapQuote {
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount_in,
+            amount_out: route.expected_amount_out,
+            price_impact: route.price_impact,
+            gas_estimate: route.gas_estimate,
+            route,
+            valid_until: Utc::now() + chrono::Duration::seconds(QUOTE_VALIDITY_SECONDS),
+        })
+    }
+
+    async fn swap(&self, request: SwapRequest) -> DeFiResult<SwapResult> {
+        request.validate(&self.config)?;
+
+        let plan = {
+            let router = self.router().await?;
+            let pools = Self::snapshot(&*self.pools.read().await);
+            match request.route.as_deref() {
+                Some(route) => {
+                    let route = router.pinned_route(
+                        &pools, route, &request.token_in, &request.token_out, request.amount_in,
+                    )?;
+                    SplitRoute {
+                        amount_in: request.amount_in,
+                        expected_amount_out: route.expected_amount_out,
+                        price_impact: route.price_impact,
+                        gas_estimate: route.gas_estimate,
+                        splits: vec![RouteSplit { amount_in: request.amount_in, route }],
+                    }
+                }
+                None => router.split_route(&pools, &request.token_in, &request.token_out, request.amount_in)?,
+            }
+        };
+
+        if plan.price_impact > self.config.max_price_impact {
+            return Err(DeFiError::price_impact_too_high(
+                plan.price_impact.to_string(),
+                self.config.max_price_impact.to_string(),
+            ));
+        }
+        if plan.expected_amount_out < request.min_amount_out {
+            return Err(DeFiError::slippage_exceeded(
+                request.min_amount_out.to_string(),
+                plan.expected_amount_out.to_string(),
+            ));
+        }
+
+        // Execute on a copy so a failing split leaves every pool untouched
+        let mut pools = self.pools.write().await;
+        if request.deadline <= Utc::now() {
+            return Err(DeFiError::validation_error("deadline", "Deadline must be in the future"));
+        }
+        let mut state = Self::snapshot(&pools);
+        let mut amount_out = Decimal::ZERO;
+        for split in &plan.splits {
+            amount_out += router::execute_route(&mut state, &split.route, split.amount_in)?;
+        }
+
+        // Other swaps may have moved the pools since the plan was quoted
+        let min_amount_out = request
+            .min_amount_out
+            .max(plan.expected_amount_out * (Decimal::ONE - request.slippage_tolerance));
+        if amount_out < min_amount_out {
+            return Err(DeFiError::slippage_exceeded(min_amount_out.to_string(), amount_out.to_string()));
+        }
+
+        let route = plan.pools();
+        for address in &route {
+            if let (Some(entry), Some(pool)) = (pools.get_mut(address), state.remove(address)) {
+                entry.pool = pool;
+            }
+        }
+
+        Ok(SwapResult {
+            request_id: request.id,
+            transaction_hash: format!("0x{:x}", rand::random::<u64>()),
+            amount_in: request.amount_in,
+            amount_out,
+            price_impact: plan.price_impact,
+            gas_used: plan.gas_estimate,
+            gas_price: request.gas_price.unwrap_or_default(),
+            route,
+            executed_at: Utc::now(),
+        })
+    }
+
+    async fn add_liquidity(&self, request: LiquidityRequest) -> DeFiResult<LiquidityResult> {
+        if request.deadline <= Utc::now() {
+            return Err(DeFiError::validation_error("deadline", "Deadline must be in the future"));
+        }
+        if request.amount_a <= Decimal::ZERO || request.amount_b <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amounts must be positive"));
+        }
+
+        let mut pools = self.pools.write().await;
+        let mut candidates = pools
+            .values_mut()
+            .filter(|e| e.pool.supports_pair(&request.token_a, &request.token_b))
+            .peekable();
+        let protocol = match candidates.peek() {
+            Some(entry) => entry.pool.protocol(),
+            None => {
+                return Err(DeFiError::not_found(
+                    "Pool".to_string(),
+                    format!("{}/{}", request.token_a.symbol, request.token_b.symbol),
+                ));
+            }
+        };
+
+        let pool = candidates
+            .find_map(|entry| match &mut entry.pool {
+                AmmPool::UniswapV2(pool) => Some(pool),
+                _ => None,
+            })
+            .ok_or_else(|| DeFiError::protocol_error(
+                protocol.name(),
+                "Liquidity management is not supported for this pool type",
+            ))?;
+
+        let flipped = request.token_a.address != pool.token_pair.token_a.address;
+        let (desired_a, desired_b, min_a, min_b) = if flipped {
+            (request.amount_b, request.amount_a, request.min_amount_b, request.min_amount_a)
+        } else {
+            (request.amount_a, request.amount_b, request.min_amount_a, request.min_amount_b)
+        };
+
+        // Deposit at the current reserve ratio, as the V2 router does
+        let (amount_a, amount_b, liquidity) = if pool.total_supply.is_zero() {
+            let liquidity = (desired_a * desired_b)
+                .sqrt()
+                .ok_or_else(|| DeFiError::internal_error("Liquidity calculation failed"))?;
+            (desired_a, desired_b, liquidity)
+        } else {
+            let optimal_b = desired_a * pool.reserve_b / pool.reserve_a;
+            let (amount_a, amount_b) = if optimal_b <= desired_b {
+                (desired_a, optimal_b)
+            } else {
+                (desired_b * pool.reserve_a / pool.reserve_b, desired_b)
+            };
+            let liquidity = (amount_a * pool.total_supply / pool.reserve_a)
+                .min(amount_b * pool.total_supply / pool.reserve_b);
+            (amount_a, amount_b, liquidity)
+        };
+
+        if amount_a < min_a || amount_b < min_b {
+            return Err(DeFiError::slippage_exceeded(
+                format!("{}/{}", min_a, min_b),
+                format!("{}/{}", amount_a, amount_b),
+            ));
+        }
+
+        pool.reserve_a += amount_a;
+        pool.reserve_b += amount_b;
+        pool.total_supply += liquidity;
+        pool.last_updated = Utc::now();
+
+        let (amount_a, amount_b) = if flipped { (amount_b, amount_a) } else { (amount_a, amount_b) };
+
+        Ok(LiquidityResult {
+            request_id: request.id,
+            transaction_hash: format!("0x{:x}", rand::random::<u64>()),
+            liquidity_tokens: liquidity,
+            amount_a,
+            amount_b,
+            pool_address: pool.address.clone(),
+            executed_at: Utc::now(),
+        })
+    }
+
+    async fn remove_liquidity(&self, request: RemoveLiquidityRequest) -> DeFiResult<LiquidityResult> {
+        if request.deadline <= Utc::now() {
+            return Err(DeFiError::validation_error("deadline", "Deadline must be in the future"));
+        }
+
+        let mut pools = self.pools.write().await;
+        let entry = pools
+            .get_mut(&request.pool_address)
+            .ok_or_else(|| DeFiError::not_found("Pool", request.pool_address.as_str()))?;
+
+        let protocol = entry.pool.protocol();
+        let pool = match &mut entry.pool {
+            AmmPool::UniswapV2(pool) => pool,
+            _ => {
+                return Err(DeFiError::protocol_error(
+                    protocol.name(),
+                    "Liquidity management is not supported for this pool type",
+                ));
+            }
+        };
+
+        if request.liquidity_tokens <= Decimal::ZERO || request.liquidity_tokens > pool.total_supply {
+            return Err(DeFiError::validation_error("liquidity_tokens", "Invalid liquidity amount"));
+        }
+
+        let share = request.liquidity_tokens / pool.total_supply;
+        let amount_a = pool.reserve_a * share;
+        let amount_b = pool.reserve_b * share;
+        if amount_a < request.min_amount_a || amount_b < request.min_amount_b {
+            return Err(DeFiError::slippage_exceeded(
+                format!("{}/{}", request.min_amount_a, request.min_amount_b),
+                format!("{}/{}", amount_a, amount_b),
+            ));
+        }
+
+        pool.reserve_a -= amount_a;
+        pool.reserve_b -= amount_b;
+        pool.total_supply -= request.liquidity_tokens;
+        pool.last_updated = Utc::now();
+
+        Ok(LiquidityResult {
+            request_id: request.id,
+            transaction_hash: format!("0x{:x}", rand::random::<u64>()),
+            liquidity_tokens: request.liquidity_tokens,
+            amount_a,
+            amount_b,
+            pool_address: request.pool_address,
+            executed_at: Utc::now(),
+        })
+    }
+
+    async fn get_pool(&self, pool_address: &str) -> DeFiResult<Option<LiquidityPool>> {
+        let pools = self.pools.read().await;
+        Ok(pools.get(pool_address).and_then(|entry| {
+            let tokens = entry.pool.tokens();
+            match (tokens.first(), tokens.get(1)) {
+                (Some(token_a), Some(token_b)) => {
+                    Some(entry.pool.to_liquidity_pool(entry.id, token_a, token_b, entry.created_at))
+                }
+                _ => None,
+            }
+        }))
+    }
+
+    async fn get_pools_for_pair(&self, token_a: &Token, token_b: &Token) -> DeFiResult<Vec<LiquidityPool>> {
+        let pools = self.pools.read().await;
+        Ok(pools
+            .values()
+            .filter(|entry| entry.pool.supports_pair(token_a, token_b))
+            .map(|entry| entry.pool.to_liquidity_pool(entry.id, token_a, token_b, entry.created_at))
+            .collect())
+    }
+
+    async fn find_best_route(
+        &self,
+        token_in: &Token,
+        token_out: &Token,
+        amount_in: Decimal,
+    ) -> DeFiResult<SwapRoute> {
+        let router = self.router().await?;
+        let pools = Self::snapshot(&*self.pools.read().await);
+        router.best_route(&pools, token_in, token_out, amount_in)
+    }
+
+    async fn get_supported_protocols(&self) -> DeFiResult<Vec<AMMProtocol>> {
+        Ok(vec![
+            AMMProtocol::UniswapV2,
+            AMMProtocol::UniswapV3,
+            AMMProtocol::Curve,
+            AMMProtocol::Balancer,
+        ])
+    }
+
+    async fn health_check(&self) -> DeFiResult<AMMHealthStatus> {
+        let pools = self.pools.read().await;
+
+        let mut protocol_statuses = HashMap::new();
+        for entry in pools.values() {
+            protocol_statuses.insert(entry.pool.protocol().name().to_string(), "healthy".to_string());
+        }
+
+        Ok(AMMHealthStatus {
+            status: "healthy".to_string(),
+            total_pools: pools.len() as u64,
+            active_pools: pools.len() as u64,
+            // Valuing pools needs a price oracle, which this service does not hold
+            total_tvl: Decimal::ZERO,
+            volume_24h: Decimal::ZERO,
+            protocol_statuses,
+            last_check: Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::{CurvePool, UniswapV2Pool};
+    use crate::types::TokenPair;
+
+    fn create_tokens() -> (Token, Token) {
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+        let dai = Token::new("0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), "DAI".to_string(), "Dai Stablecoin".to_string(), 18, 1);
+        (usdc, dai)
+    }
+
+    async fn create_service() -> AMMServiceImpl {
+        let (usdc, dai) = create_tokens();
+        let service = AMMServiceImpl::new(AMMConfig::default());
+
+        service.add_pool(AmmPool::UniswapV2(UniswapV2Pool {
+            address: "0xv2".to_string(),
+            token_pair: TokenPair::new(usdc.clone(), dai.clone()),
+            reserve_a: Decimal::from(1_000_000),
+            reserve_b: Decimal::from(1_000_000),
+            total_supply: Decimal::from(1_000_000),
+            fee_rate: Decimal::new(3, 3),
+            last_updated: Utc::now(),
+        })).await.unwrap();
+
+        service.add_pool(AmmPool::Curve(CurvePool {
+            address: "0xcurve".to_string(),
+            tokens: vec![usdc, dai],
+            balances: vec![Decimal::from(1_000_000), Decimal::from(1_000_000)],
+            amplification_parameter: 100,
+            fee_rate: Decimal::new(4, 4),
+            admin_fee_rate: Decimal::new(5, 1),
+            last_updated: Utc::now(),
+        })).await.unwrap();
+
+        service
+    }
+
+    #[tokio::test]
+    async fn test_get_quote_picks_best_pool() {
+        let service = create_service().await;
+        let (usdc, dai) = create_tokens();
+
+        // The StableSwap curve beats constant product for a like-priced pair
+        let quote = service.get_quote(&usdc, &dai, Decimal::from(1000)).await.unwrap();
+        assert_eq!(quote.route.pools, vec!["0xcurve".to_string()]);
+        assert_eq!(quote.amount_out, Decimal::from_i128_with_scale(999590103058584712249, 18));
+
+        assert_eq!(service.get_pools_for_pair(&usdc, &dai).await.unwrap().len(), 2);
+        assert!(service.get_quote(&usdc, &dai, Decimal::ZERO).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_add_pool_rejects_zero_amplification() {
+        let service = create_service().await;
+        let (usdc, dai) = create_tokens();
+
+        let result = service.add_pool(AmmPool::Curve(CurvePool {
+            address: "0xcurve-flat".to_string(),
+            tokens: vec![usdc, dai],
+            balances: vec![Decimal::from(1_000_000), Decimal::from(1_000_000)],
+            amplification_parameter: 0,
+            fee_rate: Decimal::new(4, 4),
+            admin_fee_rate: Decimal::new(5, 1),
+            last_updated: Utc::now(),
+        })).await;
+
+        assert!(matches!(result, Err(DeFiError::ValidationError { .. })));
+        assert!(service.pool_state("0xcurve-flat").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_swap_enforces_limits_and_updates_state() {
+        let service = create_service().await;
+        let (usdc, dai) = create_tokens();
+
+        let mut request = SwapRequest::new("user123".to_string(), usdc.clone(), dai.clone(), Decimal::from(1000), Decimal::new(50, 4));
+        request.min_amount_out = Decimal::from(1000);
+        assert!(service.swap(request.clone()).await.is_err());
+
+        request.min_amount_out = Decimal::from(999);
+        let result = service.swap(request).await.unwrap();
+        assert_eq!(result.route, vec!["0xcurve".to_string()]);
+
+        match service.pool_state("0xcurve").await.unwrap() {
+            AmmPool::Curve(pool) => assert_eq!(pool.balances[0], Decimal::from(1_001_000)),
+            _ => panic!("unexpected pool type"),
+        }
+
+        // Large trades through the V2 pool exceed the price impact limit
+        let mut request = SwapRequest::new("user123".to_string(), usdc, dai, Decimal::from(200_000), Decimal::new(50, 4));
+        request.route = Some(vec!["0xv2".to_string()]);
+        assert!(service.swap(request).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_swap_splits_across_multi_hop_routes() {
+        let service = create_service().await;
+        let (usdc, dai) = create_tokens();
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        for (address, token_a, token_b, reserve_a, reserve_b) in [
+            ("0xusdc-weth", &usdc, &weth, 20_000_000, 10_000),
+            ("0xweth-dai", &weth, &dai, 10_000, 20_000_000),
+        ] {
+            service.add_pool(AmmPool::UniswapV2(UniswapV2Pool {
+                address: address.to_string(),
+                token_pair: TokenPair::new(token_a.clone(), token_b.clone()),
+                reserve_a: Decimal::from(reserve_a),
+                reserve_b: Decimal::from(reserve_b),
+                total_supply: Decimal::from(1_000),
+                fee_rate: Decimal::new(3, 3),
+                last_updated: Utc::now(),
+            })).await.unwrap();
+        }
+
+        let route = service.find_best_route(&usdc, &dai, Decimal::from(1000)).await.unwrap();
+        assert_eq!(route.pools, vec!["0xcurve".to_string()]);
+
+        let plan = service.find_split_route(&usdc, &dai, Decimal::from(400_000)).await.unwrap();
+        assert!(plan.splits.len() >= 2);
+        assert!(plan.pools().contains(&"0xweth-dai".to_string()));
+
+        // An unreachable minimum rejects the swap without touching any pool
+        let mut request = SwapRequest::new("user123".to_string(), usdc.clone(), dai.clone(), Decimal::from(400_000), Decimal::new(50, 4));
+        request.min_amount_out = plan.expected_amount_out + Decimal::ONE;
+        assert!(service.swap(request.clone()).await.is_err());
+        match service.pool_state("0xcurve").await.unwrap() {
+            AmmPool::Curve(pool) => assert_eq!(pool.balances[0], Decimal::from(1_000_000)),
+            _ => panic!("unexpected pool type"),
+        }
+
+        request.min_amount_out = plan.expected_amount_out;
+        let result = service.swap(request).await.unwrap();
+        assert_eq!(result.amount_out, plan.expected_amount_out);
+        assert_eq!(result.route, plan.pools());
+
+        // A pinned two-hop route must follow the given pool order
+        let mut request = SwapRequest::new("user123".to_string(), usdc.clone(), dai.clone(), Decimal::from(100), Decimal::new(50, 4));
+        request.route = Some(vec!["0xweth-dai".to_string(), "0xusdc-weth".to_string()]);
+        assert!(service.swap(request.clone()).await.is_err());
+        request.route = Some(vec!["0xusdc-weth".to_string(), "0xweth-dai".to_string()]);
+        assert_eq!(service.swap(request).await.unwrap().route.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_liquidity_management_for_v2_pools() {
+        let service = create_service().await;
+        let (usdc, dai) = create_tokens();
+
+        let request = LiquidityRequest {
+            id: Uuid::new_v4(),
+            user_id: "user123".to_string(),
+            token_a: dai.clone(),
+            token_b: usdc.clone(),
+            amount_a: Decimal::from(1000),
+            amount_b: Decimal::from(2000),
+            min_amount_a: Decimal::from(1000),
+            min_amount_b: Decimal::from(900),
+            deadline: Utc::now() + chrono::Duration::minutes(5),
+            fee_tier: None,
+            tick_lower: None,
+            tick_upper: None,
+            created_at: Utc::now(),
+        };
+        let result = service.add_liquidity(request).await.unwrap();
+        assert_eq!(result.pool_address, "0xv2");
+        assert_eq!(result.amount_a, Decimal::from(1000));
+        assert_eq!(result.amount_b, Decimal::from(1000));
+        assert_eq!(result.liquidity_tokens, Decimal::from(1000));
+
+        let request = RemoveLiquidityRequest {
+            id: Uuid::new_v4(),
+            user_id: "user123".to_string(),
+            pool_address: "0xcurve".to_string(),
+            liquidity_tokens: Decimal::from(1000),
+            min_amount_a: Decimal::ZERO,
+            min_amount_b: Decimal::ZERO,
+            deadline: Utc::now() + chrono::Duration::minutes(5),
+            created_at: Utc::now(),
+        };
+        assert!(service.remove_liquidity(request).await.is_err());
+    }
+}
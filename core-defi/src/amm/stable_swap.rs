@@ -0,0 +1,333 @@
+// =====================================================================================
+// File: core-defi/src/amm/stable_swap.rs
+// Description: Curve StableSwap invariant math with amplification
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Port of the StableSwap invariant used by the classic Curve pools.
+//!
+//! Balances are normalized to 18 decimals before solving the invariant, the
+//! same precision the on-chain contracts use via their `rates` table, and
+//! results are truncated back to the output token's decimals.
+
+use chrono::Utc;
+use ethers::types::U256;
+use rust_decimal::Decimal;
+
+use super::fixed_point::{from_fixed, to_fixed};
+use super::CurvePool;
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Token,
+};
+
+/// Precision of normalized balances
+const PRECISION_DECIMALS: u32 = 18;
+
+/// Fees are expressed with 10 decimals on-chain
+const FEE_DECIMALS: u32 = 10;
+
+/// Newton iteration limit used by the contracts
+const MAX_ITERATIONS: usize = 255;
+
+/// Solve the StableSwap invariant `D` for normalized balances
+pub fn get_d(xp: &[U256], amp: u64) -> DeFiResult<U256> {
+    // `ann - 1` and `d / ann` below are undefined without amplification
+    if amp == 0 {
+        return Err(DeFiError::validation_error(
+            "amplification_parameter",
+            "Amplification must be positive",
+        ));
+    }
+    let n = U256::from(xp.len());
+    let sum = xp.iter().fold(U256::zero(), |acc, x| acc + *x);
+    if sum.is_zero() {
+        return Ok(U256::zero());
+    }
+    if xp.iter().any(|x| x.is_zero()) {
+        return Err(DeFiError::insufficient_liquidity("Pool has an empty balance"));
+    }
+
+    let ann = U256::from(amp) * n;
+    let mut d = sum;
+
+    for _ in 0..MAX_ITERATIONS {
+        let mut d_p = d;
+        for x in xp {
+            d_p = d_p * d / (*x * n);
+        }
+
+        let d_prev = d;
+        let numerator = (ann * sum + d_p * n) * d;
+        let denominator = (ann - 1) * d + (n + 1) * d_p;
+        d = numerator / denominator;
+
+        if abs_diff(d, d_prev) <= U256::one() {
+            return Ok(d);
+        }
+    }
+
+    Err(DeFiError::internal_error("StableSwap invariant did not converge"))
+}
+
+/// Solve for the new balance of token `j` when token `i` has balance `x`
+pub fn get_y(i: usize, j: usize, x: U256, xp: &[U256], amp: u64) -> DeFiResult<U256> {
+    if i == j || i >= xp.len() || j >= xp.len() {
+        return Err(DeFiError::validation_error("tokens", "Invalid token indexes"));
+    }
+
+    let n = U256::from(xp.len());
+    let d = get_d(xp, amp)?;
+    let ann = U256::from(amp) * n;
+
+    let mut c = d;
+    let mut sum = U256::zero();
+    for (k, balance) in xp.iter().enumerate() {
+        let x_k = if k == i {
+            x
+        } else if k != j {
+            *balance
+        } else {
+            continue;
+        };
+        sum += x_k;
+        c = c * d / (x_k * n);
+    }
+    c = c * d / (ann * n);
+    let b = sum + d / ann;
+
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let y_prev = y;
+        let denominator = (y + y + b)
+            .checked_sub(d)
+            .ok_or_else(|| DeFiError::internal_error("StableSwap solver underflow"))?;
+        y = (y * y + c) / denominator;
+
+        if abs_diff(y, y_prev) <= U256::one() {
+            return Ok(y);
+        }
+    }
+
+    Err(DeFiError::internal_error("StableSwap solver did not converge"))
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+    if a > b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+/// Output of a StableSwap exchange in normalized units
+struct Exchange {
+    dy: U256,
+    fee: U256,
+}
+
+impl CurvePool {
+    /// Check the pool parameters before it is registered
+    pub fn validate(&self) -> DeFiResult<()> {
+        if self.amplification_parameter == 0 {
+            return Err(DeFiError::validation_error(
+                "amplification_parameter",
+                "Amplification must be positive",
+            ));
+        }
+        if self.tokens.len() < 2 || self.tokens.len() != self.balances.len() {
+            return Err(DeFiError::validation_error("tokens", "Pool needs a balance for each of at least two tokens"));
+        }
+        Ok(())
+    }
+
+    /// Index of a token within the pool
+    pub fn token_index(&self, token: &Token) -> DeFiResult<usize> {
+        self.tokens
+            .iter()
+            .position(|t| t.address == token.address)
+            .ok_or_else(|| DeFiError::validation_error("token", "Token not in pool"))
+    }
+
+    /// Current invariant of the pool in normalized units
+    pub fn invariant(&self) -> DeFiResult<U256> {
+        get_d(&self.normalized_balances()?, self.amplification_parameter)
+    }
+
+    /// Calculate output amount for a given input
+    pub fn get_amount_out(&self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let (i, j) = (self.token_index(token_in)?, self.token_index(token_out)?);
+        let exchange = self.exchange(i, j, amount_in)?;
+        self.denormalize(exchange.dy - exchange.fee, j)
+    }
+
+    /// Marginal fee-free price of `token_in` denominated in `token_out`
+    ///
+    /// Estimated from a swap of one thousandth of the smaller balance.
+    pub fn spot_price(&self, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let (i, j) = (self.token_index(token_in)?, self.token_index(token_out)?);
+        let probe = self
+            .balances
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or(Decimal::ZERO)
+            / Decimal::from(1000);
+        if probe <= Decimal::ZERO {
+            return Err(DeFiError::insufficient_liquidity("Pool has no liquidity"));
+        }
+
+        let exchange = self.exchange(i, j, probe)?;
+        Ok(self.denormalize(exchange.dy, j)? / probe)
+    }
+
+    /// Calculate price impact for a swap
+    pub fn calculate_price_impact(&self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let amount_out = self.get_amount_out(amount_in, token_in, token_out)?;
+        let current_price = self.spot_price(token_in, token_out)?;
+        let execution_price = amount_out / amount_in;
+
+        Ok(((current_price - execution_price) / current_price).abs())
+    }
+
+    /// Execute a swap against the simulated pool state and return the output amount
+    ///
+    /// The admin share of the fee leaves the pool, the rest stays with liquidity providers.
+    pub fn apply_swap(&mut self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let (i, j) = (self.token_index(token_in)?, self.token_index(token_out)?);
+        let exchange = self.exchange(i, j, amount_in)?;
+
+        let amount_out = self.denormalize(exchange.dy - exchange.fee, j)?;
+        let admin_fee = self.denormalize(exchange.fee, j)? * self.admin_fee_rate;
+
+        self.balances[i] += amount_in;
+        self.balances[j] -= amount_out + admin_fee;
+        self.last_updated = Utc::now();
+
+        Ok(amount_out)
+    }
+
+    fn exchange(&self, i: usize, j: usize, amount_in: Decimal) -> DeFiResult<Exchange> {
+        if amount_in <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount_in", "Amount must be positive"));
+        }
+        if self.balances.len() != self.tokens.len() {
+            return Err(DeFiError::validation_error("balances", "Balances do not match pool tokens"));
+        }
+
+        let xp = self.normalized_balances()?;
+        let x = xp[i] + to_fixed(amount_in, PRECISION_DECIMALS)?;
+        let y = get_y(i, j, x, &xp, self.amplification_parameter)?;
+
+        let dy = xp[j]
+            .checked_sub(y + 1)
+            .ok_or_else(|| DeFiError::insufficient_liquidity("Swap exceeds pool balance"))?;
+        let fee = dy * to_fixed(self.fee_rate, FEE_DECIMALS)? / U256::exp10(FEE_DECIMALS as usize);
+
+        Ok(Exchange { dy, fee })
+    }
+
+    fn normalized_balances(&self) -> DeFiResult<Vec<U256>> {
+        self.balances
+            .iter()
+            .map(|balance| to_fixed(*balance, PRECISION_DECIMALS))
+            .collect()
+    }
+
+    /// Convert a normalized amount back to token `j` units, truncating dust
+    fn denormalize(&self, amount: U256, j: usize) -> DeFiResult<Decimal> {
+        let decimals = self.tokens[j].decimals as u32;
+        let amount = if decimals < PRECISION_DECIMALS {
+            amount / U256::exp10((PRECISION_DECIMALS - decimals) as usize)
+        } else {
+            amount
+        };
+        from_fixed(amount, decimals.min(PRECISION_DECIMALS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_pool(usdc_balance: i64, dai_balance: i64) -> CurvePool {
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+        let dai = Token::new("0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), "DAI".to_string(), "Dai Stablecoin".to_string(), 18, 1);
+
+        CurvePool {
+            address: "0xcurve".to_string(),
+            tokens: vec![usdc, dai],
+            balances: vec![Decimal::from(usdc_balance), Decimal::from(dai_balance)],
+            amplification_parameter: 100,
+            fee_rate: Decimal::new(4, 4), // 0.04%
+            admin_fee_rate: Decimal::new(5, 1), // 50%
+            last_updated: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_invariant_of_balanced_pool() {
+        let pool = create_pool(1_000_000, 1_000_000);
+        let d = pool.invariant().unwrap();
+
+        // A balanced pool's invariant equals the sum of its balances
+        assert_eq!(d, to_fixed(Decimal::from(2_000_000), 18).unwrap());
+        assert_eq!(get_d(&[U256::zero(), U256::zero()], 100).unwrap(), U256::zero());
+    }
+
+    #[test]
+    fn test_get_amount_out_reference_values() {
+        let pool = create_pool(1_000_000, 1_000_000);
+        let (usdc, dai) = (pool.tokens[0].clone(), pool.tokens[1].clone());
+
+        assert_eq!(
+            pool.get_amount_out(Decimal::from(1000), &usdc, &dai).unwrap(),
+            Decimal::from_i128_with_scale(999590103058584712249, 18)
+        );
+        assert_eq!(
+            pool.get_amount_out(Decimal::from(1000), &dai, &usdc).unwrap(),
+            Decimal::new(999590103, 6)
+        );
+
+        // Imbalanced pools quote the scarce token at a premium
+        let pool = create_pool(1_500_000, 500_000);
+        assert_eq!(
+            pool.get_amount_out(Decimal::from(100_000), &usdc, &dai).unwrap(),
+            Decimal::from_i128_with_scale(97765357257049223509463, 18)
+        );
+        let impact = pool.calculate_price_impact(Decimal::from(100_000), &usdc, &dai).unwrap();
+        assert!(impact > Decimal::ZERO && impact < Decimal::new(1, 2));
+    }
+
+    #[test]
+    fn test_apply_swap_keeps_admin_fee_out_of_pool() {
+        let mut pool = create_pool(1_000_000, 1_000_000);
+        let (usdc, dai) = (pool.tokens[0].clone(), pool.tokens[1].clone());
+        let d_before = pool.invariant().unwrap();
+
+        let amount_out = pool.apply_swap(Decimal::from(1000), &usdc, &dai).unwrap();
+        assert_eq!(amount_out, Decimal::from_i128_with_scale(999590103058584712249, 18));
+        assert_eq!(pool.balances[0], Decimal::from(1_001_000));
+        // Half of the 0.399996.. DAI fee is paid out to the admin
+        assert_eq!(
+            pool.balances[1],
+            Decimal::from(1_000_000) - amount_out - Decimal::new(1999980198196448, 16)
+        );
+
+        // The LP share of the fee grows the invariant
+        assert!(pool.invariant().unwrap() > d_before);
+    }
+
+    #[test]
+    fn test_zero_amplification_is_rejected() {
+        let mut pool = create_pool(1_000_000, 1_000_000);
+        pool.amplification_parameter = 0;
+        let (usdc, dai) = (pool.tokens[0].clone(), pool.tokens[1].clone());
+
+        assert!(pool.validate().is_err());
+        assert!(pool.invariant().is_err());
+        assert!(pool.get_amount_out(Decimal::from(1000), &usdc, &dai).is_err());
+        assert!(get_d(&[U256::zero(), U256::zero()], 0).is_err());
+        assert!(get_y(0, 1, U256::one(), &[U256::one(), U256::one()], 0).is_err());
+    }
+}
@@ -0,0 +1,592 @@
+// =====================================================================================
+// File: core-defi/src/amm/uniswap_v3.rs
+// Description: Uniswap V3 concentrated liquidity math and tick-based swap simulation
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Integer ports of the v3-core `TickMath`, `SqrtPriceMath` and `SwapMath`
+//! libraries, so simulated swaps match on-chain results to the wei.
+//!
+//! Pool orientation follows the core contracts: `token_pair.token_a` is
+//! `token0`, prices are `sqrt(token1 / token0)` in Q64.96 and `fee_tier` is
+//! expressed in hundredths of a basis point (3000 = 0.3%).
+
+use chrono::Utc;
+use ethers::types::U256;
+use rust_decimal::prelude::*;
+
+use super::fixed_point::{div_rounding_up, from_fixed, mul_div, mul_div_rounding_up, to_f64, to_fixed};
+use super::UniswapV3Pool;
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Token,
+};
+
+/// Lowest tick supported by the protocol
+pub const MIN_TICK: i32 = -887272;
+/// Highest tick supported by the protocol
+pub const MAX_TICK: i32 = 887272;
+
+/// Fee denominator, fees are expressed in pips
+const FEE_DENOMINATOR: u32 = 1_000_000;
+
+/// `1.0001^(-2^i / 2)` in Q128.128 for each bit of the absolute tick
+const TICK_RATIOS: [u128; 20] = [
+    0xfffcb933bd6fad37aa2d162d1a594001,
+    0xfff97272373d413259a46990580e213a,
+    0xfff2e50f5f656932ef12357cf3c7fdcc,
+    0xffe5caca7e10e4e61c3624eaa0941cd0,
+    0xffcb9843d60f6159c9db58835c926644,
+    0xff973b41fa98c081472e6896dfb254c0,
+    0xff2ea16466c96a3843ec78b326b52861,
+    0xfe5dee046a99a2a811c461f1969c3053,
+    0xfcbe86c7900a88aedcffc83b479aa3a4,
+    0xf987a7253ac413176f2b074cf7815e54,
+    0xf3392b0822b70005940c7a398e4b70f3,
+    0xe7159475a2c29b7443b29c7fa6e889d9,
+    0xd097f3bdfd2022b8845ad8f792aa5825,
+    0xa9f746462d870fdf8a65dc1f90e061e5,
+    0x70d869a156d2a1b890bb3df62baf32f7,
+    0x31be135f97d08fd981231505542fcfa6,
+    0x9aa508b5b7a84e1c677de54f3e99bc9,
+    0x5d6af8dedb81196699c329225ee604,
+    0x2216e584f5fa1ea926041bedfe98,
+    0x48a170391f7dc42444e8fa2,
+];
+
+/// Square root price at `MIN_TICK`
+pub fn min_sqrt_ratio() -> U256 {
+    U256::from(4_295_128_739u64)
+}
+
+/// Square root price at `MAX_TICK`
+pub fn max_sqrt_ratio() -> U256 {
+    U256::from_dec_str("1461446703485210103287273052203988822378723970342")
+        .expect("valid constant")
+}
+
+fn q96() -> U256 {
+    U256::one() << 96
+}
+
+fn overflow() -> DeFiError {
+    DeFiError::internal_error("Arithmetic overflow in pool math")
+}
+
+/// Calculate `sqrt(1.0001^tick) * 2^96`
+pub fn get_sqrt_ratio_at_tick(tick: i32) -> DeFiResult<U256> {
+    let abs_tick = tick.unsigned_abs();
+    if abs_tick > MAX_TICK as u32 {
+        return Err(DeFiError::validation_error("tick", "Tick out of range"));
+    }
+
+    let mut ratio = if abs_tick & 1 != 0 {
+        U256::from(TICK_RATIOS[0])
+    } else {
+        U256::one() << 128
+    };
+    for (bit, constant) in TICK_RATIOS.iter().enumerate().skip(1) {
+        if abs_tick & (1 << bit) != 0 {
+            ratio = (ratio * U256::from(*constant)) >> 128;
+        }
+    }
+
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    // Round up from Q128.128 to Q64.96 so the tick lookup stays consistent
+    let remainder = ratio & ((U256::one() << 32) - 1);
+    Ok((ratio >> 32) + if remainder.is_zero() { U256::zero() } else { U256::one() })
+}
+
+/// Calculate the greatest tick whose square root price is at or below `sqrt_price_x96`
+pub fn get_tick_at_sqrt_ratio(sqrt_price_x96: U256) -> DeFiResult<i32> {
+    if sqrt_price_x96 < min_sqrt_ratio() || sqrt_price_x96 >= max_sqrt_ratio() {
+        return Err(DeFiError::validation_error("sqrt_price_x96", "Price out of range"));
+    }
+
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if get_sqrt_ratio_at_tick(mid)? <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+/// Amount of token0 between two prices for the given liquidity
+pub fn get_amount0_delta(
+    sqrt_ratio_a: U256,
+    sqrt_ratio_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> DeFiResult<U256> {
+    let (lower, upper) = if sqrt_ratio_a > sqrt_ratio_b {
+        (sqrt_ratio_b, sqrt_ratio_a)
+    } else {
+        (sqrt_ratio_a, sqrt_ratio_b)
+    };
+    if lower.is_zero() {
+        return Err(DeFiError::validation_error("sqrt_price_x96", "Price must be positive"));
+    }
+
+    let numerator1 = U256::from(liquidity) << 96;
+    let numerator2 = upper - lower;
+
+    if round_up {
+        div_rounding_up(mul_div_rounding_up(numerator1, numerator2, upper)?, lower)
+    } else {
+        Ok(mul_div(numerator1, numerator2, upper)? / lower)
+    }
+}
+
+/// Amount of token1 between two prices for the given liquidity
+pub fn get_amount1_delta(
+    sqrt_ratio_a: U256,
+    sqrt_ratio_b: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> DeFiResult<U256> {
+    let (lower, upper) = if sqrt_ratio_a > sqrt_ratio_b {
+        (sqrt_ratio_b, sqrt_ratio_a)
+    } else {
+        (sqrt_ratio_a, sqrt_ratio_b)
+    };
+
+    if round_up {
+        mul_div_rounding_up(U256::from(liquidity), upper - lower, q96())
+    } else {
+        mul_div(U256::from(liquidity), upper - lower, q96())
+    }
+}
+
+/// Price reached after adding `amount_in` of the input token
+pub fn get_next_sqrt_price_from_input(
+    sqrt_price_x96: U256,
+    liquidity: u128,
+    amount_in: U256,
+    zero_for_one: bool,
+) -> DeFiResult<U256> {
+    if sqrt_price_x96.is_zero() || liquidity == 0 {
+        return Err(DeFiError::insufficient_liquidity("Pool has no active liquidity"));
+    }
+    if amount_in.is_zero() {
+        return Ok(sqrt_price_x96);
+    }
+
+    let numerator1 = U256::from(liquidity) << 96;
+
+    if zero_for_one {
+        // Token0 in: price moves down, rounding up keeps the pool solvent
+        if let Some(product) = amount_in.checked_mul(sqrt_price_x96) {
+            if let Some(denominator) = numerator1.checked_add(product) {
+                return mul_div_rounding_up(numerator1, sqrt_price_x96, denominator);
+            }
+        }
+        let denominator = (numerator1 / sqrt_price_x96)
+            .checked_add(amount_in)
+            .ok_or_else(overflow)?;
+        div_rounding_up(numerator1, denominator)
+    } else {
+        // Token1 in: price moves up, rounding down keeps the pool solvent
+        let quotient = mul_div(amount_in, q96(), U256::from(liquidity))?;
+        let next = sqrt_price_x96.checked_add(quotient).ok_or_else(overflow)?;
+        if next.bits() > 160 {
+            return Err(overflow());
+        }
+        Ok(next)
+    }
+}
+
+/// Outcome of swapping within a single initialized tick range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: U256,
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+}
+
+/// Compute an exact-input swap step towards `sqrt_price_target`
+pub fn compute_swap_step(
+    sqrt_price_current: U256,
+    sqrt_price_target: U256,
+    liquidity: u128,
+    amount_remaining: U256,
+    fee_pips: u32,
+) -> DeFiResult<SwapStep> {
+    if fee_pips >= FEE_DENOMINATOR {
+        return Err(DeFiError::validation_error("fee_tier", "Fee must be below 100%"));
+    }
+
+    let zero_for_one = sqrt_price_current >= sqrt_price_target;
+    let fee = U256::from(fee_pips);
+    let denominator = U256::from(FEE_DENOMINATOR);
+
+    let remaining_less_fee = mul_div(amount_remaining, denominator - fee, denominator)?;
+    let amount_to_target = if zero_for_one {
+        get_amount0_delta(sqrt_price_target, sqrt_price_current, liquidity, true)?
+    } else {
+        get_amount1_delta(sqrt_price_current, sqrt_price_target, liquidity, true)?
+    };
+
+    let sqrt_price_next = if remaining_less_fee >= amount_to_target {
+        sqrt_price_target
+    } else {
+        get_next_sqrt_price_from_input(sqrt_price_current, liquidity, remaining_less_fee, zero_for_one)?
+    };
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_in = if reached_target {
+            amount_to_target
+        } else {
+            get_amount0_delta(sqrt_price_next, sqrt_price_current, liquidity, true)?
+        };
+        (amount_in, get_amount1_delta(sqrt_price_next, sqrt_price_current, liquidity, false)?)
+    } else {
+        let amount_in = if reached_target {
+            amount_to_target
+        } else {
+            get_amount1_delta(sqrt_price_current, sqrt_price_next, liquidity, true)?
+        };
+        (amount_in, get_amount0_delta(sqrt_price_current, sqrt_price_next, liquidity, false)?)
+    };
+
+    // When the target is not reached the whole remainder is consumed and the
+    // rounding dust goes to the fee
+    let fee_amount = if reached_target {
+        mul_div_rounding_up(amount_in, fee, denominator - fee)?
+    } else {
+        amount_remaining - amount_in
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Result of simulating an exact-input swap against a V3 pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct V3SwapResult {
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    pub ticks_crossed: Vec<i32>,
+}
+
+impl UniswapV3Pool {
+    /// Current square root price in Q64.96
+    pub fn sqrt_price(&self) -> DeFiResult<U256> {
+        U256::from_dec_str(&self.sqrt_price_x96)
+            .map_err(|_| DeFiError::validation_error("sqrt_price_x96", "Invalid Q64.96 price"))
+    }
+
+    /// Active liquidity as an integer
+    pub fn active_liquidity(&self) -> DeFiResult<u128> {
+        self.liquidity
+            .trunc()
+            .to_u128()
+            .ok_or_else(|| DeFiError::validation_error("liquidity", "Invalid pool liquidity"))
+    }
+
+    /// Simulate an exact-input swap, crossing initialized ticks as needed
+    pub fn simulate_swap(&self, zero_for_one: bool, amount_in: U256) -> DeFiResult<V3SwapResult> {
+        if amount_in.is_zero() {
+            return Err(DeFiError::validation_error("amount_in", "Amount must be positive"));
+        }
+
+        let limit = if zero_for_one {
+            min_sqrt_ratio() + 1
+        } else {
+            max_sqrt_ratio() - 1
+        };
+
+        let mut sqrt_price = self.sqrt_price()?;
+        let mut tick = self.current_tick;
+        let mut liquidity = self.active_liquidity()?;
+        let mut remaining = amount_in;
+        let mut amount_out = U256::zero();
+        let mut fee_amount = U256::zero();
+        let mut ticks_crossed = Vec::new();
+
+        while !remaining.is_zero() && sqrt_price != limit {
+            let start = sqrt_price;
+            let (tick_next, initialized) = self.next_initialized_tick(tick, zero_for_one);
+            let sqrt_price_next_tick = get_sqrt_ratio_at_tick(tick_next)?;
+            let target = if zero_for_one {
+                sqrt_price_next_tick.max(limit)
+            } else {
+                sqrt_price_next_tick.min(limit)
+            };
+
+            let step = compute_swap_step(sqrt_price, target, liquidity, remaining, self.fee_tier)?;
+            sqrt_price = step.sqrt_price_next;
+            remaining -= step.amount_in + step.fee_amount;
+            amount_out += step.amount_out;
+            fee_amount += step.fee_amount;
+
+            if sqrt_price == sqrt_price_next_tick {
+                if initialized {
+                    let mut liquidity_net = self.liquidity_net(tick_next)?;
+                    if zero_for_one {
+                        liquidity_net = -liquidity_net;
+                    }
+                    liquidity = apply_liquidity_delta(liquidity, liquidity_net)?;
+                    ticks_crossed.push(tick_next);
+                }
+                tick = if zero_for_one { tick_next - 1 } else { tick_next };
+            } else if sqrt_price != start {
+                tick = get_tick_at_sqrt_ratio(sqrt_price)?;
+            }
+        }
+
+        if !remaining.is_zero() {
+            return Err(DeFiError::insufficient_liquidity(
+                "Swap exhausts all liquidity in the price range",
+            ));
+        }
+
+        Ok(V3SwapResult {
+            amount_in,
+            amount_out,
+            fee_amount,
+            sqrt_price_x96: sqrt_price,
+            tick,
+            liquidity,
+            ticks_crossed,
+        })
+    }
+
+    /// Calculate output amount for a given input
+    pub fn get_amount_out(&self, amount_in: Decimal, token_in: &Token) -> DeFiResult<Decimal> {
+        let (zero_for_one, decimals_in, decimals_out) = self.direction(token_in)?;
+        let result = self.simulate_swap(zero_for_one, to_fixed(amount_in, decimals_in)?)?;
+        from_fixed(result.amount_out, decimals_out)
+    }
+
+    /// Current fee-free price of `token_in` denominated in the other token
+    pub fn spot_price(&self, token_in: &Token) -> DeFiResult<Decimal> {
+        let (zero_for_one, decimals_in, decimals_out) = self.direction(token_in)?;
+        let sqrt_price = to_f64(self.sqrt_price()?) / 2f64.powi(96);
+        let raw_price = if zero_for_one {
+            sqrt_price * sqrt_price
+        } else {
+            1.0 / (sqrt_price * sqrt_price)
+        };
+        let price = raw_price * 10f64.powi(decimals_in as i32 - decimals_out as i32);
+
+        Decimal::from_f64(price)
+            .ok_or_else(|| DeFiError::internal_error("Spot price out of range"))
+    }
+
+    /// Calculate price impact for a swap
+    pub fn calculate_price_impact(&self, amount_in: Decimal, token_in: &Token) -> DeFiResult<Decimal> {
+        let amount_out = self.get_amount_out(amount_in, token_in)?;
+        let current_price = self.spot_price(token_in)?;
+        if current_price.is_zero() {
+            return Err(DeFiError::insufficient_liquidity("Pool has no price"));
+        }
+
+        let execution_price = amount_out / amount_in;
+        Ok(((current_price - execution_price) / current_price).abs())
+    }
+
+    /// Execute a swap against the simulated pool state and return the output amount
+    pub fn apply_swap(&mut self, amount_in: Decimal, token_in: &Token) -> DeFiResult<Decimal> {
+        let (zero_for_one, decimals_in, decimals_out) = self.direction(token_in)?;
+        let result = self.simulate_swap(zero_for_one, to_fixed(amount_in, decimals_in)?)?;
+
+        self.sqrt_price_x96 = result.sqrt_price_x96.to_string();
+        self.current_tick = result.tick;
+        self.liquidity = Decimal::from_u128(result.liquidity)
+            .ok_or_else(|| DeFiError::internal_error("Liquidity exceeds decimal range"))?;
+        self.last_updated = Utc::now();
+
+        from_fixed(result.amount_out, decimals_out)
+    }
+
+    /// Swap direction and token decimals for `token_in`
+    fn direction(&self, token_in: &Token) -> DeFiResult<(bool, u32, u32)> {
+        let token0 = &self.token_pair.token_a;
+        let token1 = &self.token_pair.token_b;
+
+        if token_in.address == token0.address {
+            Ok((true, token0.decimals as u32, token1.decimals as u32))
+        } else if token_in.address == token1.address {
+            Ok((false, token1.decimals as u32, token0.decimals as u32))
+        } else {
+            Err(DeFiError::validation_error("token_in", "Token not in pool"))
+        }
+    }
+
+    /// Next initialized tick in the swap direction, or the price bound if none remain
+    fn next_initialized_tick(&self, tick: i32, zero_for_one: bool) -> (i32, bool) {
+        let next = if zero_for_one {
+            self.ticks.range(..=tick).next_back()
+        } else {
+            self.ticks.range(tick + 1..).next()
+        };
+
+        match next {
+            Some((tick, _)) => (*tick, true),
+            None if zero_for_one => (MIN_TICK, false),
+            None => (MAX_TICK, false),
+        }
+    }
+
+    fn liquidity_net(&self, tick: i32) -> DeFiResult<i128> {
+        self.ticks
+            .get(&tick)
+            .and_then(|net| net.trunc().to_i128())
+            .ok_or_else(|| DeFiError::validation_error("ticks", "Invalid liquidity net"))
+    }
+}
+
+fn apply_liquidity_delta(liquidity: u128, delta: i128) -> DeFiResult<u128> {
+    let updated = if delta < 0 {
+        liquidity.checked_sub(delta.unsigned_abs())
+    } else {
+        liquidity.checked_add(delta as u128)
+    };
+
+    updated.ok_or_else(|| DeFiError::internal_error("Tick liquidity underflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TokenPair;
+    use std::collections::BTreeMap;
+
+    const E18: u128 = 1_000_000_000_000_000_000;
+
+    fn u(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    fn create_pool() -> UniswapV3Pool {
+        let token0 = Token::new("0x0000000000000000000000000000000000000001".to_string(), "TK0".to_string(), "Token 0".to_string(), 18, 1);
+        let token1 = Token::new("0x0000000000000000000000000000000000000002".to_string(), "TK1".to_string(), "Token 1".to_string(), 18, 1);
+
+        let mut ticks = BTreeMap::new();
+        ticks.insert(-1200, Decimal::from(E18));
+        ticks.insert(-120, Decimal::from(2 * E18));
+        ticks.insert(120, -Decimal::from(2 * E18));
+        ticks.insert(1200, -Decimal::from(E18));
+
+        UniswapV3Pool {
+            address: "0xv3pool".to_string(),
+            token_pair: TokenPair::with_fee_tier(token0, token1, 3000),
+            fee_tier: 3000,
+            tick_spacing: 60,
+            current_tick: 0,
+            sqrt_price_x96: q96().to_string(),
+            liquidity: Decimal::from(3 * E18),
+            ticks,
+            last_updated: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_tick_math_reference_values() {
+        assert_eq!(get_sqrt_ratio_at_tick(0).unwrap(), q96());
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK).unwrap(), min_sqrt_ratio());
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK).unwrap(), max_sqrt_ratio());
+        assert!(get_sqrt_ratio_at_tick(MAX_TICK + 1).is_err());
+
+        assert_eq!(get_tick_at_sqrt_ratio(min_sqrt_ratio()).unwrap(), MIN_TICK);
+        assert_eq!(get_tick_at_sqrt_ratio(q96()).unwrap(), 0);
+        assert_eq!(get_tick_at_sqrt_ratio(q96() - 1).unwrap(), -1);
+    }
+
+    #[test]
+    fn test_sqrt_price_math_reference_values() {
+        let price_1_1 = q96();
+        let price_121_100 = u("87150978765690771352898345369");
+
+        assert_eq!(
+            get_next_sqrt_price_from_input(price_1_1, E18, U256::from(E18 / 10), false).unwrap(),
+            price_121_100
+        );
+        assert_eq!(
+            get_next_sqrt_price_from_input(price_1_1, E18, U256::from(E18 / 10), true).unwrap(),
+            u("72025602285694852357767227579")
+        );
+
+        assert_eq!(get_amount0_delta(price_1_1, price_121_100, E18, true).unwrap(), u("90909090909090910"));
+        assert_eq!(get_amount0_delta(price_1_1, price_121_100, E18, false).unwrap(), u("90909090909090909"));
+        assert_eq!(get_amount1_delta(price_1_1, price_121_100, E18, true).unwrap(), u("100000000000000000"));
+    }
+
+    #[test]
+    fn test_compute_swap_step_reference_values() {
+        // Exact input that is capped by the price target
+        let step = compute_swap_step(q96(), u("79623317895830914510639640423"), 2 * E18, U256::from(E18), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, u("79623317895830914510639640423"));
+        assert_eq!(step.amount_in, u("9975124224178055"));
+        assert_eq!(step.amount_out, u("9925619580021728"));
+        assert_eq!(step.fee_amount, u("5988667735148"));
+
+        // Exact input fully consumed before the target
+        let step = compute_swap_step(q96(), u("250541448375047931186413801569"), 2 * E18, U256::from(E18), 600).unwrap();
+        assert_eq!(step.sqrt_price_next, u("118818475322642227089037862318"));
+        assert_eq!(step.amount_in, u("999400000000000000"));
+        assert_eq!(step.amount_out, u("666399946655997866"));
+        assert_eq!(step.fee_amount, u("600000000000000"));
+    }
+
+    #[test]
+    fn test_swap_crosses_initialized_ticks() {
+        let pool = create_pool();
+
+        // Small swap stays inside the current range
+        let result = pool.simulate_swap(true, U256::from(E18 / 1000)).unwrap();
+        assert_eq!(result.amount_out, u("996668773744192"));
+        assert_eq!(result.tick, -7);
+        assert!(result.ticks_crossed.is_empty());
+
+        // Larger swaps cross the inner range boundary in either direction
+        let result = pool.simulate_swap(true, U256::from(E18 / 20)).unwrap();
+        assert_eq!(result.amount_out, u("48400170437094520"));
+        assert_eq!(result.sqrt_price_x96, u("76341350127970862754586582308"));
+        assert_eq!(result.tick, -743);
+        assert_eq!(result.liquidity, E18);
+        assert_eq!(result.ticks_crossed, vec![-120]);
+
+        let result = pool.simulate_swap(false, U256::from(E18 / 20)).unwrap();
+        assert_eq!(result.amount_out, u("48400170437094520"));
+        assert_eq!(result.sqrt_price_x96, u("82224138358365248176293609639"));
+        assert_eq!(result.tick, 742);
+        assert_eq!(result.ticks_crossed, vec![120]);
+    }
+
+    #[test]
+    fn test_apply_swap_updates_pool_state() {
+        let mut pool = create_pool();
+        let token0 = pool.token_pair.token_a.clone();
+
+        let quoted = pool.get_amount_out(Decimal::new(5, 2), &token0).unwrap();
+        assert_eq!(quoted, Decimal::new(48400170437094520, 18));
+        assert!(pool.calculate_price_impact(Decimal::new(5, 2), &token0).unwrap() > Decimal::ZERO);
+
+        let executed = pool.apply_swap(Decimal::new(5, 2), &token0).unwrap();
+        assert_eq!(executed, quoted);
+        assert_eq!(pool.current_tick, -743);
+        assert_eq!(pool.liquidity, Decimal::from(E18));
+
+        // Draining beyond the last initialized tick fails instead of filling partially
+        assert!(pool.get_amount_out(Decimal::from(1_000_000), &token0).is_err());
+    }
+}
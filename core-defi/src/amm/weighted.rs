@@ -0,0 +1,211 @@
+// =====================================================================================
+// File: core-defi/src/amm/weighted.rs
+// Description: Balancer weighted-product pool math
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Weighted constant-product math used by Balancer pools, where the invariant
+//! is `prod(balance_i ^ weight_i)`.
+
+use chrono::Utc;
+use rust_decimal::prelude::*;
+use rust_decimal::MathematicalOps;
+
+use super::BalancerPool;
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Token,
+};
+
+/// Maximum share of the input balance a single swap may add (Balancer `MAX_IN_RATIO`)
+pub const MAX_IN_RATIO: Decimal = Decimal::from_parts(3, 0, 0, false, 1);
+
+/// Calculate `out_given_in` for a weighted pool
+///
+/// `balance_out * (1 - (balance_in / (balance_in + amount_in_after_fee)) ^ (weight_in / weight_out))`
+pub fn calc_out_given_in(
+    balance_in: Decimal,
+    weight_in: Decimal,
+    balance_out: Decimal,
+    weight_out: Decimal,
+    amount_in: Decimal,
+    swap_fee: Decimal,
+) -> DeFiResult<Decimal> {
+    if balance_in <= Decimal::ZERO || balance_out <= Decimal::ZERO {
+        return Err(DeFiError::insufficient_liquidity("Pool has no liquidity"));
+    }
+    if weight_in <= Decimal::ZERO || weight_out <= Decimal::ZERO {
+        return Err(DeFiError::validation_error("weights", "Weights must be positive"));
+    }
+    if amount_in > balance_in * MAX_IN_RATIO {
+        return Err(DeFiError::insufficient_liquidity("Swap exceeds maximum input ratio"));
+    }
+
+    let amount_in_after_fee = amount_in * (Decimal::ONE - swap_fee);
+    let base = balance_in / (balance_in + amount_in_after_fee);
+    let exponent = weight_in / weight_out;
+
+    let power = if exponent == Decimal::ONE {
+        base
+    } else {
+        base.checked_powd(exponent)
+            .ok_or_else(|| DeFiError::internal_error("Weighted pool power overflow"))?
+    };
+
+    Ok(balance_out * (Decimal::ONE - power))
+}
+
+/// Fee-free spot price of the input token denominated in the output token
+pub fn calc_spot_price(
+    balance_in: Decimal,
+    weight_in: Decimal,
+    balance_out: Decimal,
+    weight_out: Decimal,
+) -> DeFiResult<Decimal> {
+    if balance_in <= Decimal::ZERO || weight_out <= Decimal::ZERO {
+        return Err(DeFiError::insufficient_liquidity("Pool has no liquidity"));
+    }
+
+    Ok((balance_out / weight_out) / (balance_in / weight_in))
+}
+
+impl BalancerPool {
+    /// Index of a token within the pool
+    pub fn token_index(&self, token: &Token) -> DeFiResult<usize> {
+        self.tokens
+            .iter()
+            .position(|t| t.address == token.address)
+            .ok_or_else(|| DeFiError::validation_error("token", "Token not in pool"))
+    }
+
+    /// Calculate output amount for a given input
+    pub fn get_amount_out(&self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        if amount_in <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount_in", "Amount must be positive"));
+        }
+
+        let (i, j) = self.indexes(token_in, token_out)?;
+        let amount_out = calc_out_given_in(
+            self.balances[i],
+            self.weights[i],
+            self.balances[j],
+            self.weights[j],
+            amount_in,
+            self.swap_fee,
+        )?;
+
+        Ok(amount_out.round_dp_with_strategy(self.tokens[j].decimals as u32, RoundingStrategy::ToZero))
+    }
+
+    /// Fee-free price of `token_in` denominated in `token_out`
+    pub fn spot_price(&self, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let (i, j) = self.indexes(token_in, token_out)?;
+        calc_spot_price(self.balances[i], self.weights[i], self.balances[j], self.weights[j])
+    }
+
+    /// Calculate price impact for a swap
+    pub fn calculate_price_impact(&self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let amount_out = self.get_amount_out(amount_in, token_in, token_out)?;
+        let current_price = self.spot_price(token_in, token_out)?;
+        let execution_price = amount_out / amount_in;
+
+        Ok(((current_price - execution_price) / current_price).abs())
+    }
+
+    /// Execute a swap against the simulated pool state and return the output amount
+    pub fn apply_swap(&mut self, amount_in: Decimal, token_in: &Token, token_out: &Token) -> DeFiResult<Decimal> {
+        let amount_out = self.get_amount_out(amount_in, token_in, token_out)?;
+        let (i, j) = self.indexes(token_in, token_out)?;
+
+        self.balances[i] += amount_in;
+        self.balances[j] -= amount_out;
+        self.last_updated = Utc::now();
+
+        Ok(amount_out)
+    }
+
+    fn indexes(&self, token_in: &Token, token_out: &Token) -> DeFiResult<(usize, usize)> {
+        if self.balances.len() != self.tokens.len() || self.weights.len() != self.tokens.len() {
+            return Err(DeFiError::validation_error("balances", "Balances and weights do not match pool tokens"));
+        }
+
+        let (i, j) = (self.token_index(token_in)?, self.token_index(token_out)?);
+        if i == j {
+            return Err(DeFiError::validation_error("tokens", "Cannot swap same token"));
+        }
+
+        Ok((i, j))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_pool() -> BalancerPool {
+        let bal = Token::new("0xba100000625a3754423978a60c9317c58a424e3D".to_string(), "BAL".to_string(), "Balancer".to_string(), 18, 1);
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+
+        BalancerPool {
+            address: "0xbalancer".to_string(),
+            tokens: vec![bal, usdc],
+            balances: vec![Decimal::from(1000), Decimal::from(4_000_000)],
+            weights: vec![Decimal::new(8, 1), Decimal::new(2, 1)],
+            swap_fee: Decimal::new(3, 3), // 0.3%
+            last_updated: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_out_given_in_reference_values() {
+        // 80/20 pool, 10 in at 0.3% fee
+        let out = calc_out_given_in(
+            Decimal::from(1000), Decimal::new(8, 1),
+            Decimal::from(4_000_000), Decimal::new(2, 1),
+            Decimal::from(10), Decimal::new(3, 3),
+        ).unwrap();
+        assert!((out - Decimal::new(155621884623040, 9)).abs() < Decimal::new(1, 6));
+
+        // Equal weights reduce to the constant product formula
+        let out = calc_out_given_in(
+            Decimal::from(1000), Decimal::new(5, 1),
+            Decimal::from(2_000_000), Decimal::new(5, 1),
+            Decimal::from(10), Decimal::new(3, 3),
+        ).unwrap();
+        assert!((out - Decimal::from_i128_with_scale(19743160687941225977, 15)).abs() < Decimal::new(1, 9));
+
+        // Inputs above 30% of the balance are rejected
+        assert!(calc_out_given_in(
+            Decimal::from(1000), Decimal::new(5, 1),
+            Decimal::from(1000), Decimal::new(5, 1),
+            Decimal::from(301), Decimal::ZERO,
+        ).is_err());
+    }
+
+    #[test]
+    fn test_pool_quote_and_spot_price() {
+        let pool = create_pool();
+        let (bal, usdc) = (pool.tokens[0].clone(), pool.tokens[1].clone());
+
+        // 80/20 weighting prices BAL at 16,000 USDC
+        assert_eq!(pool.spot_price(&bal, &usdc).unwrap(), Decimal::from(16_000));
+
+        let out = pool.get_amount_out(Decimal::from(20_000), &usdc, &bal).unwrap();
+        assert!((out - Decimal::new(1242381, 6)).abs() <= Decimal::new(1, 6));
+        assert!(pool.calculate_price_impact(Decimal::from(20_000), &usdc, &bal).unwrap() > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_apply_swap_updates_balances() {
+        let mut pool = create_pool();
+        let (bal, usdc) = (pool.tokens[0].clone(), pool.tokens[1].clone());
+
+        let out = pool.apply_swap(Decimal::from(10), &bal, &usdc).unwrap();
+        assert_eq!(out.scale(), 6);
+        assert_eq!(pool.balances[0], Decimal::from(1010));
+        assert_eq!(pool.balances[1], Decimal::from(4_000_000) - out);
+
+        // Selling BAL lowers its price
+        assert!(pool.spot_price(&bal, &usdc).unwrap() < Decimal::from(16_000));
+    }
+}
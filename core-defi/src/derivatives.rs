@@ -1,17 +1,50 @@
 // =====================================================================================
 // File: core-defi/src/derivatives.rs
-// Description: Derivatives trading implementation (placeholder)
+// Description: Options and perpetual futures engine with margin accounts
 // Author: arkSong (arksong2018@gmail.com)
 // =====================================================================================
 
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use rust_decimal::MathematicalOps;
 use serde::{Deserialize, Serialize};
-use rust_decimal::Decimal;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Price,
+};
+
+/// Seconds per year used to annualize option expiries
+const SECONDS_PER_YEAR: i64 = 31_536_000;
 
 /// Derivatives configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DerivativesConfig {
     pub enable_options: bool,
     pub enable_futures: bool,
+    /// Margin required to open or increase a position, as a share of notional
+    pub initial_margin_ratio: Decimal,
+    /// Margin below which an account can be liquidated
+    pub maintenance_margin_ratio: Decimal,
+    /// Penalty charged on the notional closed by a liquidation
+    pub liquidation_fee_ratio: Decimal,
+    /// Share of the liquidation penalty paid to the liquidator, the rest funds insurance
+    pub liquidator_reward_share: Decimal,
+    /// Fee charged on perpetual trade notional
+    pub trading_fee_rate: Decimal,
+    /// Length of one funding period
+    pub funding_interval_seconds: u64,
+    /// Maximum funding rate per funding period
+    pub max_funding_rate: Decimal,
+    /// Annual risk-free rate used for option pricing
+    pub risk_free_rate: Decimal,
+    /// Maximum index price age before trading is halted
+    pub max_price_age_seconds: u64,
 }
 
 impl Default for DerivativesConfig {
@@ -19,6 +52,1204 @@ impl Default for DerivativesConfig {
         Self {
             enable_options: true,
             enable_futures: true,
+            initial_margin_ratio: Decimal::new(10, 2), // 10%
+            maintenance_margin_ratio: Decimal::new(5, 2), // 5%
+            liquidation_fee_ratio: Decimal::new(1, 2), // 1%
+            liquidator_reward_share: Decimal::new(5, 1), // 50%
+            trading_fee_rate: Decimal::new(5, 4), // 0.05%
+            funding_interval_seconds: 3600,
+            max_funding_rate: Decimal::new(1, 3), // 0.1% per hour
+            risk_free_rate: Decimal::new(5, 2), // 5%
+            max_price_age_seconds: 300,
+        }
+    }
+}
+
+/// Option type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OptionType {
+    Call,
+    Put,
+}
+
+impl OptionType {
+    /// Payoff at expiry for one contract
+    pub fn intrinsic_value(&self, spot: Decimal, strike: Decimal) -> Decimal {
+        match self {
+            OptionType::Call => (spot - strike).max(Decimal::ZERO),
+            OptionType::Put => (strike - spot).max(Decimal::ZERO),
+        }
+    }
+}
+
+/// Option sensitivities
+///
+/// Theta is per year, vega and rho are per unit change (1.00 = 100%) of
+/// volatility and rate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Greeks {
+    pub delta: Decimal,
+    pub gamma: Decimal,
+    pub vega: Decimal,
+    pub theta: Decimal,
+    pub rho: Decimal,
+}
+
+/// Option price with its greeks
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OptionQuote {
+    pub price: Decimal,
+    pub greeks: Greeks,
+}
+
+/// Price a European option with Black-Scholes
+pub fn black_scholes(
+    option_type: OptionType,
+    spot: Decimal,
+    strike: Decimal,
+    time_to_expiry: Decimal,
+    rate: Decimal,
+    volatility: Decimal,
+) -> DeFiResult<OptionQuote> {
+    if spot <= Decimal::ZERO || strike <= Decimal::ZERO {
+        return Err(DeFiError::validation_error("price", "Spot and strike must be positive"));
+    }
+    if volatility <= Decimal::ZERO {
+        return Err(DeFiError::validation_error("volatility", "Volatility must be positive"));
+    }
+
+    // Expired options are worth their intrinsic value
+    if time_to_expiry <= Decimal::ZERO {
+        let price = option_type.intrinsic_value(spot, strike);
+        let delta = match option_type {
+            OptionType::Call if spot > strike => Decimal::ONE,
+            OptionType::Put if spot < strike => Decimal::NEGATIVE_ONE,
+            _ => Decimal::ZERO,
+        };
+        return Ok(OptionQuote {
+            price,
+            greeks: Greeks {
+                delta,
+                gamma: Decimal::ZERO,
+                vega: Decimal::ZERO,
+                theta: Decimal::ZERO,
+                rho: Decimal::ZERO,
+            },
+        });
+    }
+
+    let sqrt_t = time_to_expiry
+        .sqrt()
+        .ok_or_else(|| DeFiError::internal_error("Invalid time to expiry"))?;
+    let vol_sqrt_t = volatility * sqrt_t;
+    let d1 = ((spot / strike).ln() + (rate + volatility * volatility / Decimal::TWO) * time_to_expiry) / vol_sqrt_t;
+    let d2 = d1 - vol_sqrt_t;
+
+    let discount = (-rate * time_to_expiry)
+        .checked_exp()
+        .ok_or_else(|| DeFiError::internal_error("Discount factor overflowed"))?;
+    let pdf_d1 = bounded_norm_pdf(d1)?;
+    let gamma = pdf_d1 / (spot * vol_sqrt_t);
+    let vega = spot * pdf_d1 * sqrt_t;
+    let time_decay = -spot * pdf_d1 * volatility / (Decimal::TWO * sqrt_t);
+
+    let (price, delta, theta, rho) = match option_type {
+        OptionType::Call => (
+            spot * bounded_norm_cdf(d1) - strike * discount * bounded_norm_cdf(d2),
+            bounded_norm_cdf(d1),
+            time_decay - rate * strike * discount * bounded_norm_cdf(d2),
+            strike * time_to_expiry * discount * bounded_norm_cdf(d2),
+        ),
+        OptionType::Put => (
+            strike * discount * bounded_norm_cdf(-d2) - spot * bounded_norm_cdf(-d1),
+            bounded_norm_cdf(d1) - Decimal::ONE,
+            time_decay + rate * strike * discount * bounded_norm_cdf(-d2),
+            -strike * time_to_expiry * discount * bounded_norm_cdf(-d2),
+        ),
+    };
+
+    Ok(OptionQuote {
+        price: price.max(Decimal::ZERO),
+        greeks: Greeks { delta, gamma, vega, theta, rho },
+    })
+}
+
+/// Beyond this many standard deviations the normal distribution is treated as saturated
+const NORM_SATURATION: i64 = 8;
+
+/// Standard normal CDF, saturating far in the tails where the series overflows
+fn bounded_norm_cdf(x: Decimal) -> Decimal {
+    if x > Decimal::from(NORM_SATURATION) {
+        Decimal::ONE
+    } else if x < -Decimal::from(NORM_SATURATION) {
+        Decimal::ZERO
+    } else {
+        x.norm_cdf()
+    }
+}
+
+/// Standard normal PDF, zero far in the tails where `exp` overflows
+fn bounded_norm_pdf(x: Decimal) -> DeFiResult<Decimal> {
+    if x.abs() > Decimal::from(NORM_SATURATION) {
+        return Ok(Decimal::ZERO);
+    }
+    x.checked_norm_pdf()
+        .ok_or_else(|| DeFiError::internal_error("Normal density overflowed"))
+}
+
+/// Years between two timestamps
+fn years_between(from: DateTime<Utc>, to: DateTime<Utc>) -> Decimal {
+    Decimal::from((to - from).num_seconds()) / Decimal::from(SECONDS_PER_YEAR)
+}
+
+/// Derivatives market for one underlying, shared by its options and perpetual
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivativesMarket {
+    pub symbol: String,
+    pub index_price: Decimal,
+    pub index_updated_at: DateTime<Utc>,
+    /// Annualized implied volatility used to price options
+    pub volatility: Decimal,
+    /// Open interest skew at which the perpetual trades at a 100% premium
+    pub skew_scale: Decimal,
+    pub long_open_interest: Decimal,
+    pub short_open_interest: Decimal,
+    /// Funding paid per unit of long exposure since listing
+    pub cumulative_funding: Decimal,
+    pub last_funding_time: DateTime<Utc>,
+    /// Index fixed for each option expiry that open positions still settle against
+    pub expiry_fixings: BTreeMap<DateTime<Utc>, Decimal>,
+}
+
+impl DerivativesMarket {
+    /// Net long exposure
+    pub fn skew(&self) -> Decimal {
+        self.long_open_interest - self.short_open_interest
+    }
+
+    /// Premium of the perpetual over the index implied by the skew
+    pub fn premium(&self) -> Decimal {
+        if self.skew_scale.is_zero() {
+            Decimal::ZERO
+        } else {
+            self.skew() / self.skew_scale
+        }
+    }
+
+    /// Perpetual mark price
+    pub fn mark_price(&self) -> Decimal {
+        self.index_price * (Decimal::ONE + self.premium())
+    }
+
+    /// Price at which an order of `size_delta` fills, averaging the premium before and after the trade
+    pub fn fill_price(&self, size_delta: Decimal) -> Decimal {
+        if self.skew_scale.is_zero() {
+            return self.index_price;
+        }
+        let premium = (self.skew() + size_delta / Decimal::TWO) / self.skew_scale;
+        self.index_price * (Decimal::ONE + premium)
+    }
+
+    /// Current funding rate per funding period, positive when longs pay shorts
+    pub fn funding_rate(&self, max_funding_rate: Decimal) -> Decimal {
+        self.premium().max(-max_funding_rate).min(max_funding_rate)
+    }
+
+    /// Accrue funding up to `now` and return the rate applied
+    fn accrue_funding(&mut self, now: DateTime<Utc>, config: &DerivativesConfig) -> Decimal {
+        let rate = self.funding_rate(config.max_funding_rate);
+        if now <= self.last_funding_time || config.funding_interval_seconds == 0 {
+            return rate;
+        }
+
+        let elapsed = Decimal::from((now - self.last_funding_time).num_milliseconds());
+        let interval = Decimal::from(config.funding_interval_seconds) * Decimal::from(1000);
+        self.cumulative_funding += rate * self.index_price * elapsed / interval;
+        self.last_funding_time = now;
+
+        rate
+    }
+
+    fn update_open_interest(&mut self, old_size: Decimal, new_size: Decimal) {
+        self.long_open_interest += new_size.max(Decimal::ZERO) - old_size.max(Decimal::ZERO);
+        self.short_open_interest += old_size.min(Decimal::ZERO) - new_size.min(Decimal::ZERO);
+    }
+
+    fn check_price_fresh(&self, config: &DerivativesConfig) -> DeFiResult<()> {
+        let age = (Utc::now() - self.index_updated_at).num_seconds();
+        if age > config.max_price_age_seconds as i64 {
+            return Err(DeFiError::price_feed_error(self.symbol.clone(), "Index price is stale".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Index at `expiry`: the recorded fixing, or the current index if it was
+    /// observed at or before expiry and was no older than the staleness limit then
+    fn fixing_at(&self, expiry: DateTime<Utc>, config: &DerivativesConfig) -> Option<Decimal> {
+        if let Some(fixing) = self.expiry_fixings.get(&expiry) {
+            return Some(*fixing);
+        }
+        let age = (expiry - self.index_updated_at).num_seconds();
+        (age >= 0 && age <= config.max_price_age_seconds as i64).then_some(self.index_price)
+    }
+
+    fn option_price(&self, position: &OptionPosition, now: DateTime<Utc>, config: &DerivativesConfig) -> DeFiResult<Decimal> {
+        Ok(black_scholes(
+            position.option_type,
+            self.index_price,
+            position.strike,
+            years_between(now, position.expiry),
+            config.risk_free_rate,
+            self.volatility,
+        )?
+        .price)
+    }
+}
+
+/// Perpetual futures position, positive size is long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerpetualPosition {
+    pub id: Uuid,
+    pub market: String,
+    pub size: Decimal,
+    pub entry_price: Decimal,
+    /// Market cumulative funding when funding was last settled
+    pub entry_funding: Decimal,
+    pub realized_pnl: Decimal,
+    pub opened_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PerpetualPosition {
+    /// Unrealized profit at the given mark price
+    pub fn unrealized_pnl(&self, mark_price: Decimal) -> Decimal {
+        self.size * (mark_price - self.entry_price)
+    }
+
+    /// Funding owed since the last settlement, negative when the position receives funding
+    pub fn accrued_funding(&self, cumulative_funding: Decimal) -> Decimal {
+        self.size * (cumulative_funding - self.entry_funding)
+    }
+}
+
+/// European option position, positive quantity is long
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionPosition {
+    pub id: Uuid,
+    pub market: String,
+    pub option_type: OptionType,
+    pub strike: Decimal,
+    pub expiry: DateTime<Utc>,
+    pub quantity: Decimal,
+    pub entry_premium: Decimal,
+    pub opened_at: DateTime<Utc>,
+}
+
+/// Cross-margined account holding collateral and positions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginAccount {
+    pub user_id: String,
+    pub collateral: Decimal,
+    pub perpetuals: HashMap<String, PerpetualPosition>,
+    pub options: Vec<OptionPosition>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl MarginAccount {
+    pub fn new(user_id: String) -> Self {
+        Self {
+            user_id,
+            collateral: Decimal::ZERO,
+            perpetuals: HashMap::new(),
+            options: Vec::new(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+/// Margin health of an account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarginSummary {
+    pub user_id: String,
+    pub collateral: Decimal,
+    pub unrealized_pnl: Decimal,
+    pub accrued_funding: Decimal,
+    pub option_value: Decimal,
+    pub equity: Decimal,
+    pub initial_margin: Decimal,
+    pub maintenance_margin: Decimal,
+    pub free_margin: Decimal,
+    pub is_liquidatable: bool,
+}
+
+/// Perpetual order, positive size buys
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerpetualOrder {
+    pub user_id: String,
+    pub market: String,
+    pub size_delta: Decimal,
+}
+
+/// Option order, positive quantity buys and negative quantity writes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionOrder {
+    pub user_id: String,
+    pub market: String,
+    pub option_type: OptionType,
+    pub strike: Decimal,
+    pub expiry: DateTime<Utc>,
+    pub quantity: Decimal,
+}
+
+/// Settlement of an expired option position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptionSettlement {
+    pub user_id: String,
+    pub position_id: Uuid,
+    pub settlement_price: Decimal,
+    /// Amount credited to the account, negative when a writer pays
+    pub payout: Decimal,
+    /// Part of a writer's obligation beyond its collateral, absorbed by the insurance fund
+    pub shortfall: Decimal,
+    pub settled_at: DateTime<Utc>,
+}
+
+/// Result of liquidating a margin account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivativesLiquidation {
+    pub user_id: String,
+    pub liquidator_id: String,
+    pub closed_notional: Decimal,
+    pub penalty: Decimal,
+    pub liquidator_reward: Decimal,
+    /// Losses beyond the account's collateral, absorbed by the insurance fund
+    pub shortfall: Decimal,
+    pub remaining_collateral: Decimal,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Derivatives service trait
+#[async_trait]
+pub trait DerivativesService: Send + Sync {
+    /// List an underlying for options and perpetual trading
+    async fn list_market(&self, symbol: &str, index_price: Price, volatility: Decimal, skew_scale: Decimal) -> DeFiResult<DerivativesMarket>;
+
+    /// Update the index price from the oracle, accruing funding up to the price timestamp
+    async fn update_index_price(&self, symbol: &str, price: Price) -> DeFiResult<()>;
+
+    /// Get market data
+    async fn get_market(&self, symbol: &str) -> DeFiResult<DerivativesMarket>;
+
+    /// Accrue perpetual funding up to `now` and return the current funding rate
+    async fn accrue_funding(&self, symbol: &str, now: DateTime<Utc>) -> DeFiResult<Decimal>;
+
+    /// Price an option against the current index
+    async fn quote_option(&self, symbol: &str, option_type: OptionType, strike: Decimal, expiry: DateTime<Utc>) -> DeFiResult<OptionQuote>;
+
+    /// Buy or write options
+    async fn trade_option(&self, order: OptionOrder) -> DeFiResult<OptionPosition>;
+
+    /// Open, increase, reduce or flip a perpetual position
+    async fn trade_perpetual(&self, order: PerpetualOrder) -> DeFiResult<PerpetualPosition>;
+
+    /// Deposit margin collateral
+    async fn deposit_margin(&self, user_id: &str, amount: Decimal) -> DeFiResult<Decimal>;
+
+    /// Withdraw margin collateral not needed for initial margin
+    async fn withdraw_margin(&self, user_id: &str, amount: Decimal) -> DeFiResult<Decimal>;
+
+    /// Get margin health of an account
+    async fn get_margin_summary(&self, user_id: &str) -> DeFiResult<MarginSummary>;
+
+    /// Cash-settle options expired at `now` against their expiry fixing.
+    /// Positions without a fresh index at expiry stay open.
+    async fn settle_expired_options(&self, now: DateTime<Utc>) -> DeFiResult<Vec<OptionSettlement>>;
+
+    /// Close out an account below maintenance margin
+    async fn liquidate(&self, user_id: &str, liquidator_id: &str) -> DeFiResult<DerivativesLiquidation>;
+}
+
+/// Mutable engine state, kept behind a single lock so trades are atomic
+#[derive(Debug, Default)]
+struct EngineState {
+    markets: HashMap<String, DerivativesMarket>,
+    accounts: HashMap<String, MarginAccount>,
+    insurance_fund: Decimal,
+}
+
+/// In-memory options and perpetuals engine
+pub struct DerivativesEngine {
+    config: DerivativesConfig,
+    state: Arc<RwLock<EngineState>>,
+}
+
+impl DerivativesEngine {
+    pub fn new(config: DerivativesConfig) -> Self {
+        Self {
+            config,
+            state: Arc::new(RwLock::new(EngineState::default())),
+        }
+    }
+
+    /// Balance of the insurance fund
+    pub async fn insurance_fund(&self) -> Decimal {
+        self.state.read().await.insurance_fund
+    }
+
+    /// Compute margin health of an account against current market state
+    fn summarize(
+        &self,
+        markets: &HashMap<String, DerivativesMarket>,
+        account: &MarginAccount,
+        now: DateTime<Utc>,
+    ) -> DeFiResult<MarginSummary> {
+        let mut unrealized_pnl = Decimal::ZERO;
+        let mut accrued_funding = Decimal::ZERO;
+        let mut option_value = Decimal::ZERO;
+        let mut exposure = Decimal::ZERO;
+
+        for position in account.perpetuals.values() {
+            let market = markets
+                .get(&position.market)
+                .ok_or_else(|| DeFiError::not_found("Market", position.market.as_str()))?;
+            let mark = market.mark_price();
+            unrealized_pnl += position.unrealized_pnl(mark);
+            accrued_funding += position.accrued_funding(market.cumulative_funding);
+            exposure += position.size.abs() * mark;
+        }
+
+        for position in &account.options {
+            let market = markets
+                .get(&position.market)
+                .ok_or_else(|| DeFiError::not_found("Market", position.market.as_str()))?;
+            option_value += position.quantity * market.option_price(position, now, &self.config)?;
+            // Written options carry the risk of the underlying
+            if position.quantity < Decimal::ZERO {
+                exposure += position.quantity.abs() * market.index_price;
+            }
+        }
+
+        let equity = account.collateral + unrealized_pnl - accrued_funding + option_value;
+        let initial_margin = exposure * self.config.initial_margin_ratio;
+        let maintenance_margin = exposure * self.config.maintenance_margin_ratio;
+
+        Ok(MarginSummary {
+            user_id: account.user_id.clone(),
+            collateral: account.collateral,
+            unrealized_pnl,
+            accrued_funding,
+            option_value,
+            equity,
+            initial_margin,
+            maintenance_margin,
+            free_margin: (equity - initial_margin).max(Decimal::ZERO),
+            is_liquidatable: maintenance_margin > Decimal::ZERO && equity < maintenance_margin,
+        })
+    }
+
+    fn check_initial_margin(&self, summary: &MarginSummary) -> DeFiResult<()> {
+        if summary.equity < summary.initial_margin {
+            return Err(DeFiError::insufficient_balance(
+                summary.initial_margin.to_string(),
+                summary.equity.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DerivativesService for DerivativesEngine {
+    async fn list_market(&self, symbol: &str, index_price: Price, volatility: Decimal, skew_scale: Decimal) -> DeFiResult<DerivativesMarket> {
+        if index_price.price_usd <= Decimal::ZERO {
+            return Err(DeFiError::oracle_error("Index price must be positive"));
+        }
+        if volatility <= Decimal::ZERO || skew_scale <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("market", "Volatility and skew scale must be positive"));
+        }
+
+        let mut state = self.state.write().await;
+        if state.markets.contains_key(symbol) {
+            return Err(DeFiError::AlreadyExists {
+                resource_type: "Market".to_string(),
+                id: symbol.to_string(),
+            });
+        }
+
+        let market = DerivativesMarket {
+            symbol: symbol.to_string(),
+            index_price: index_price.price_usd,
+            index_updated_at: index_price.timestamp,
+            volatility,
+            skew_scale,
+            long_open_interest: Decimal::ZERO,
+            short_open_interest: Decimal::ZERO,
+            cumulative_funding: Decimal::ZERO,
+            last_funding_time: index_price.timestamp,
+            expiry_fixings: BTreeMap::new(),
+        };
+        state.markets.insert(symbol.to_string(), market.clone());
+
+        Ok(market)
+    }
+
+    async fn update_index_price(&self, symbol: &str, price: Price) -> DeFiResult<()> {
+        if price.price_usd <= Decimal::ZERO {
+            return Err(DeFiError::oracle_error("Index price must be positive"));
+        }
+
+        let mut guard = self.state.write().await;
+        let state = &mut *guard;
+        let expiries: BTreeSet<DateTime<Utc>> = state
+            .accounts
+            .values()
+            .flat_map(|account| &account.options)
+            .filter(|position| position.market == symbol)
+            .map(|position| position.expiry)
+            .collect();
+        let market = state
+            .markets
+            .get_mut(symbol)
+            .ok_or_else(|| DeFiError::not_found("Market", symbol))?;
+        if price.timestamp < market.index_updated_at {
+            return Err(DeFiError::oracle_error("Index price is older than the current one"));
+        }
+
+        // Fix expiries this observation moves past, falling back to the new price
+        // when the previous one was already stale at expiry
+        for expiry in expiries.range(market.index_updated_at..price.timestamp) {
+            if market.expiry_fixings.contains_key(expiry) {
+                continue;
+            }
+            let late_by = (price.timestamp - *expiry).num_seconds();
+            let fixing = market
+                .fixing_at(*expiry, &self.config)
+                .or_else(|| (late_by <= self.config.max_price_age_seconds as i64).then_some(price.price_usd));
+            if let Some(fixing) = fixing {
+                market.expiry_fixings.insert(*expiry, fixing);
+            }
+        }
+
+        // Funding up to the new observation accrues at the previous index
+        market.accrue_funding(price.timestamp, &self.config);
+        market.index_price = price.price_usd;
+        market.index_updated_at = price.timestamp;
+
+        Ok(())
+    }
+
+    async fn get_market(&self, symbol: &str) -> DeFiResult<DerivativesMarket> {
+        self.state
+            .read()
+            .await
+            .markets
+            .get(symbol)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("Market", symbol))
+    }
+
+    async fn accrue_funding(&self, symbol: &str, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        let mut state = self.state.write().await;
+        let market = state
+            .markets
+            .get_mut(symbol)
+            .ok_or_else(|| DeFiError::not_found("Market", symbol))?;
+
+        Ok(market.accrue_funding(now, &self.config))
+    }
+
+    async fn quote_option(&self, symbol: &str, option_type: OptionType, strike: Decimal, expiry: DateTime<Utc>) -> DeFiResult<OptionQuote> {
+        let state = self.state.read().await;
+        let market = state
+            .markets
+            .get(symbol)
+            .ok_or_else(|| DeFiError::not_found("Market", symbol))?;
+        market.check_price_fresh(&self.config)?;
+
+        black_scholes(
+            option_type,
+            market.index_price,
+            strike,
+            years_between(Utc::now(), expiry),
+            self.config.risk_free_rate,
+            market.volatility,
+        )
+    }
+
+    async fn trade_option(&self, order: OptionOrder) -> DeFiResult<OptionPosition> {
+        if !self.config.enable_options {
+            return Err(DeFiError::ConfigurationError { message: "Options trading is disabled".to_string() });
+        }
+        if order.quantity.is_zero() {
+            return Err(DeFiError::validation_error("quantity", "Quantity must be non-zero"));
+        }
+
+        let now = Utc::now();
+        if order.expiry <= now {
+            return Err(DeFiError::validation_error("expiry", "Expiry must be in the future"));
+        }
+
+        let mut state = self.state.write().await;
+        let market = state
+            .markets
+            .get(&order.market)
+            .ok_or_else(|| DeFiError::not_found("Market", order.market.as_str()))?;
+        market.check_price_fresh(&self.config)?;
+
+        let premium = black_scholes(
+            order.option_type,
+            market.index_price,
+            order.strike,
+            years_between(now, order.expiry),
+            self.config.risk_free_rate,
+            market.volatility,
+        )?
+        .price;
+
+        let mut account = state
+            .accounts
+            .get(&order.user_id)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("MarginAccount", order.user_id.as_str()))?;
+
+        let cost = premium * order.quantity;
+        if cost > account.collateral {
+            return Err(DeFiError::insufficient_balance(cost.to_string(), account.collateral.to_string()));
+        }
+        account.collateral -= cost;
+
+        let position = OptionPosition {
+            id: Uuid::new_v4(),
+            market: order.market,
+            option_type: order.option_type,
+            strike: order.strike,
+            expiry: order.expiry,
+            quantity: order.quantity,
+            entry_premium: premium,
+            opened_at: now,
+        };
+        account.options.push(position.clone());
+        account.updated_at = now;
+
+        if order.quantity < Decimal::ZERO {
+            self.check_initial_margin(&self.summarize(&state.markets, &account, now)?)?;
+        }
+
+        state.accounts.insert(account.user_id.clone(), account);
+        Ok(position)
+    }
+
+    async fn trade_perpetual(&self, order: PerpetualOrder) -> DeFiResult<PerpetualPosition> {
+        if !self.config.enable_futures {
+            return Err(DeFiError::ConfigurationError { message: "Futures trading is disabled".to_string() });
+        }
+        if order.size_delta.is_zero() {
+            return Err(DeFiError::validation_error("size_delta", "Size must be non-zero"));
+        }
+
+        let now = Utc::now();
+        let mut state = self.state.write().await;
+        let mut account = state
+            .accounts
+            .get(&order.user_id)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("MarginAccount", order.user_id.as_str()))?;
+        let mut market = state
+            .markets
+            .get(&order.market)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("Market", order.market.as_str()))?;
+        market.check_price_fresh(&self.config)?;
+        market.accrue_funding(now, &self.config);
+
+        let price = market.fill_price(order.size_delta);
+        let mut position = account.perpetuals.remove(&order.market).unwrap_or_else(|| PerpetualPosition {
+            id: Uuid::new_v4(),
+            market: order.market.clone(),
+            size: Decimal::ZERO,
+            entry_price: price,
+            entry_funding: market.cumulative_funding,
+            realized_pnl: Decimal::ZERO,
+            opened_at: now,
+            updated_at: now,
+        });
+
+        // Settle funding before the size changes
+        account.collateral -= position.accrued_funding(market.cumulative_funding);
+        position.entry_funding = market.cumulative_funding;
+
+        let old_size = position.size;
+        let new_size = old_size + order.size_delta;
+        let increasing = old_size.is_zero() || old_size.is_sign_positive() == order.size_delta.is_sign_positive();
+
+        let flipped = !increasing && order.size_delta.abs() > old_size.abs();
+
+        if increasing {
+            position.entry_price = (old_size.abs() * position.entry_price + order.size_delta.abs() * price) / new_size.abs();
+        } else {
+            let closed = order.size_delta.abs().min(old_size.abs());
+            let pnl = closed * old_size.signum() * (price - position.entry_price);
+            account.collateral += pnl;
+            position.realized_pnl += pnl;
+            if flipped {
+                position.entry_price = price;
+            }
         }
+
+        let fee = order.size_delta.abs() * price * self.config.trading_fee_rate;
+        account.collateral -= fee;
+
+        position.size = new_size;
+        position.updated_at = now;
+        market.update_open_interest(old_size, new_size);
+
+        if !new_size.is_zero() {
+            account.perpetuals.insert(order.market.clone(), position.clone());
+        }
+        account.updated_at = now;
+
+        let mut markets = state.markets.clone();
+        markets.insert(order.market.clone(), market.clone());
+
+        // Trades that reduce risk are always allowed
+        if increasing || flipped {
+            self.check_initial_margin(&self.summarize(&markets, &account, now)?)?;
+        }
+
+        state.insurance_fund += fee;
+        state.markets.insert(order.market, market);
+        state.accounts.insert(account.user_id.clone(), account);
+
+        Ok(position)
+    }
+
+    async fn deposit_margin(&self, user_id: &str, amount: Decimal) -> DeFiResult<Decimal> {
+        if amount <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+        }
+
+        let mut state = self.state.write().await;
+        let account = state
+            .accounts
+            .entry(user_id.to_string())
+            .or_insert_with(|| MarginAccount::new(user_id.to_string()));
+        account.collateral += amount;
+        account.updated_at = Utc::now();
+
+        Ok(account.collateral)
+    }
+
+    async fn withdraw_margin(&self, user_id: &str, amount: Decimal) -> DeFiResult<Decimal> {
+        if amount <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+        }
+
+        let mut state = self.state.write().await;
+        let mut account = state
+            .accounts
+            .get(user_id)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("MarginAccount", user_id))?;
+        if amount > account.collateral {
+            return Err(DeFiError::insufficient_balance(amount.to_string(), account.collateral.to_string()));
+        }
+
+        account.collateral -= amount;
+        account.updated_at = Utc::now();
+        self.check_initial_margin(&self.summarize(&state.markets, &account, Utc::now())?)?;
+
+        let collateral = account.collateral;
+        state.accounts.insert(user_id.to_string(), account);
+        Ok(collateral)
+    }
+
+    async fn get_margin_summary(&self, user_id: &str) -> DeFiResult<MarginSummary> {
+        let state = self.state.read().await;
+        let account = state
+            .accounts
+            .get(user_id)
+            .ok_or_else(|| DeFiError::not_found("MarginAccount", user_id))?;
+
+        self.summarize(&state.markets, account, Utc::now())
+    }
+
+    async fn settle_expired_options(&self, now: DateTime<Utc>) -> DeFiResult<Vec<OptionSettlement>> {
+        let mut guard = self.state.write().await;
+        let state = &mut *guard;
+        let mut settlements = Vec::new();
+        let mut shortfall_total = Decimal::ZERO;
+
+        for account in state.accounts.values_mut() {
+            let (expired, open): (Vec<_>, Vec<_>) = account
+                .options
+                .drain(..)
+                .partition(|position| position.expiry <= now);
+            account.options = open;
+
+            for position in expired {
+                let market = state
+                    .markets
+                    .get(&position.market)
+                    .ok_or_else(|| DeFiError::not_found("Market", position.market.as_str()))?;
+                let settlement_price = match market.fixing_at(position.expiry, &self.config) {
+                    Some(price) => price,
+                    None => {
+                        // Refuse to settle on a stale index; wait for a fixing
+                        account.options.push(position);
+                        continue;
+                    }
+                };
+
+                // Writers pay at most their collateral, the rest falls to insurance
+                let owed = position.option_type.intrinsic_value(settlement_price, position.strike) * position.quantity;
+                let payout = owed.max(-account.collateral.max(Decimal::ZERO));
+                let shortfall = payout - owed;
+                account.collateral += payout;
+                account.updated_at = now;
+                shortfall_total += shortfall;
+
+                settlements.push(OptionSettlement {
+                    user_id: account.user_id.clone(),
+                    position_id: position.id,
+                    settlement_price,
+                    payout,
+                    shortfall,
+                    settled_at: now,
+                });
+            }
+        }
+        state.insurance_fund -= shortfall_total;
+
+        // Drop fixings no open position settles against
+        let open_expiries: BTreeSet<(String, DateTime<Utc>)> = state
+            .accounts
+            .values()
+            .flat_map(|account| &account.options)
+            .map(|position| (position.market.clone(), position.expiry))
+            .collect();
+        for market in state.markets.values_mut() {
+            market
+                .expiry_fixings
+                .retain(|expiry, _| open_expiries.contains(&(market.symbol.clone(), *expiry)));
+        }
+
+        Ok(settlements)
+    }
+
+    async fn liquidate(&self, user_id: &str, liquidator_id: &str) -> DeFiResult<DerivativesLiquidation> {
+        let now = Utc::now();
+        let mut state = self.state.write().await;
+        let mut account = state
+            .accounts
+            .get(user_id)
+            .cloned()
+            .ok_or_else(|| DeFiError::not_found("MarginAccount", user_id))?;
+
+        for market in account.perpetuals.keys() {
+            if let Some(market) = state.markets.get_mut(market) {
+                market.accrue_funding(now, &self.config);
+            }
+        }
+
+        let summary = self.summarize(&state.markets, &account, now)?;
+        if !summary.is_liquidatable {
+            return Err(DeFiError::LiquidationError {
+                message: "Account is above maintenance margin".to_string(),
+            });
+        }
+
+        // Close everything at mark
+        let mut closed_notional = Decimal::ZERO;
+        for (symbol, position) in account.perpetuals.drain() {
+            let market = state
+                .markets
+                .get_mut(&symbol)
+                .ok_or_else(|| DeFiError::not_found("Market", symbol.as_str()))?;
+            let mark = market.mark_price();
+            account.collateral += position.unrealized_pnl(mark) - position.accrued_funding(market.cumulative_funding);
+            closed_notional += position.size.abs() * mark;
+            market.update_open_interest(position.size, Decimal::ZERO);
+        }
+        for position in account.options.drain(..) {
+            let market = state
+                .markets
+                .get(&position.market)
+                .ok_or_else(|| DeFiError::not_found("Market", position.market.as_str()))?;
+            let value = position.quantity * market.option_price(&position, now, &self.config)?;
+            account.collateral += value;
+            closed_notional += value.abs();
+        }
+
+        let penalty = (closed_notional * self.config.liquidation_fee_ratio).min(account.collateral.max(Decimal::ZERO));
+        let liquidator_reward = penalty * self.config.liquidator_reward_share;
+        account.collateral -= penalty;
+
+        let shortfall = (-account.collateral).max(Decimal::ZERO);
+        account.collateral = account.collateral.max(Decimal::ZERO);
+        account.updated_at = now;
+
+        state.insurance_fund += penalty - liquidator_reward - shortfall;
+        let remaining_collateral = account.collateral;
+        state.accounts.insert(user_id.to_string(), account);
+        state
+            .accounts
+            .entry(liquidator_id.to_string())
+            .or_insert_with(|| MarginAccount::new(liquidator_id.to_string()))
+            .collateral += liquidator_reward;
+
+        Ok(DerivativesLiquidation {
+            user_id: user_id.to_string(),
+            liquidator_id: liquidator_id.to_string(),
+            closed_notional,
+            penalty,
+            liquidator_reward,
+            shortfall,
+            remaining_collateral,
+            executed_at: now,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_price(price: i64) -> Price {
+        index_price_at(price, Utc::now())
+    }
+
+    fn index_price_at(price: i64, timestamp: DateTime<Utc>) -> Price {
+        Price {
+            token_address: "0x2260FAC5E5542a773Aa44fBCfeDf7C193bc2C599".to_string(),
+            price_usd: Decimal::from(price),
+            price_change_24h: Decimal::ZERO,
+            volume_24h: Decimal::ZERO,
+            market_cap: None,
+            timestamp,
+            source: "oracle".to_string(),
+        }
+    }
+
+    fn assert_close(actual: Decimal, expected: Decimal, tolerance: Decimal) {
+        assert!((actual - expected).abs() <= tolerance, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn test_black_scholes_reference_values() {
+        let (spot, strike, t, r, vol) = (Decimal::from(100), Decimal::from(100), Decimal::ONE, Decimal::new(5, 2), Decimal::new(2, 1));
+        let tolerance = Decimal::new(1, 3);
+
+        let call = black_scholes(OptionType::Call, spot, strike, t, r, vol).unwrap();
+        assert_close(call.price, Decimal::new(104506, 4), tolerance);
+        assert_close(call.greeks.delta, Decimal::new(6368, 4), tolerance);
+        assert_close(call.greeks.gamma, Decimal::new(18762, 6), tolerance);
+        assert_close(call.greeks.vega, Decimal::new(37524, 3), tolerance);
+        assert_close(call.greeks.theta, Decimal::new(-6414, 3), tolerance);
+        assert_close(call.greeks.rho, Decimal::new(53232, 3), tolerance);
+
+        let put = black_scholes(OptionType::Put, spot, strike, t, r, vol).unwrap();
+        assert_close(put.price, Decimal::new(55735, 4), tolerance);
+        assert_close(put.greeks.delta, Decimal::new(-3632, 4), tolerance);
+
+        // Put-call parity: C - P = S - K e^(-rT)
+        assert_close(call.price - put.price, spot - strike * (-r * t).exp(), tolerance);
+
+        // Expired options are worth intrinsic value
+        let expired = black_scholes(OptionType::Put, Decimal::from(90), strike, Decimal::ZERO, r, vol).unwrap();
+        assert_eq!(expired.price, Decimal::from(10));
+        assert_eq!(expired.greeks.delta, Decimal::NEGATIVE_ONE);
+    }
+
+    #[tokio::test]
+    async fn test_funding_accrues_from_skew() {
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("BTC", index_price(50_000), Decimal::new(6, 1), Decimal::from(1000)).await.unwrap();
+
+        engine.deposit_margin("long", Decimal::from(200_000)).await.unwrap();
+        engine.deposit_margin("short", Decimal::from(200_000)).await.unwrap();
+        engine.trade_perpetual(PerpetualOrder { user_id: "long".to_string(), market: "BTC".to_string(), size_delta: Decimal::from(15) }).await.unwrap();
+        engine.trade_perpetual(PerpetualOrder { user_id: "short".to_string(), market: "BTC".to_string(), size_delta: Decimal::from(-5) }).await.unwrap();
+
+        // Net long skew of 10 trades at a 1% premium, capped at the 0.1% max funding rate
+        let market = engine.get_market("BTC").await.unwrap();
+        assert_eq!(market.skew(), Decimal::from(10));
+        assert_eq!(market.mark_price(), Decimal::from(50_500));
+        assert_eq!(engine.accrue_funding("BTC", Utc::now() + chrono::Duration::hours(8)).await.unwrap(), Decimal::new(1, 3));
+
+        // 8 hours at 0.1% of 50,000 is 400 per contract, longs pay and shorts receive
+        let long = engine.get_margin_summary("long").await.unwrap();
+        let short = engine.get_margin_summary("short").await.unwrap();
+        assert_close(long.accrued_funding, Decimal::from(6000), Decimal::ONE);
+        assert_close(short.accrued_funding, Decimal::from(-2000), Decimal::ONE);
+    }
+
+    #[tokio::test]
+    async fn test_margin_checks_and_liquidation() {
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("ETH", index_price(2000), Decimal::new(7, 1), Decimal::from(1_000_000)).await.unwrap();
+        engine.deposit_margin("trader", Decimal::from(10_000)).await.unwrap();
+
+        // 100k notional needs 10k initial margin plus fees
+        let order = PerpetualOrder { user_id: "trader".to_string(), market: "ETH".to_string(), size_delta: Decimal::from(50) };
+        assert!(engine.trade_perpetual(order).await.is_err());
+
+        let order = PerpetualOrder { user_id: "trader".to_string(), market: "ETH".to_string(), size_delta: Decimal::from(35) };
+        engine.trade_perpetual(order).await.unwrap();
+        assert!(engine.withdraw_margin("trader", Decimal::from(5000)).await.is_err());
+        assert!(engine.liquidate("trader", "keeper").await.is_err());
+
+        // A 10% drop leaves equity below the 5% maintenance margin
+        engine.update_index_price("ETH", index_price(1800)).await.unwrap();
+        let summary = engine.get_margin_summary("trader").await.unwrap();
+        assert!(summary.is_liquidatable);
+
+        let insurance_before = engine.insurance_fund().await;
+        let liquidation = engine.liquidate("trader", "keeper").await.unwrap();
+        assert!(liquidation.penalty > Decimal::ZERO);
+        assert_eq!(liquidation.shortfall, Decimal::ZERO);
+        assert_eq!(liquidation.liquidator_reward, liquidation.penalty / Decimal::TWO);
+        assert_eq!(engine.insurance_fund().await - insurance_before, liquidation.penalty - liquidation.liquidator_reward);
+
+        let summary = engine.get_margin_summary("trader").await.unwrap();
+        assert_eq!(summary.maintenance_margin, Decimal::ZERO);
+        assert_eq!(summary.collateral, liquidation.remaining_collateral);
+        assert_eq!(engine.get_market("ETH").await.unwrap().long_open_interest, Decimal::ZERO);
+        assert_eq!(engine.get_margin_summary("keeper").await.unwrap().collateral, liquidation.liquidator_reward);
+    }
+
+    #[tokio::test]
+    async fn test_option_trading_and_settlement() {
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("ETH", index_price(2000), Decimal::new(7, 1), Decimal::from(1_000_000)).await.unwrap();
+        engine.deposit_margin("buyer", Decimal::from(10_000)).await.unwrap();
+        engine.deposit_margin("writer", Decimal::from(900)).await.unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::days(30);
+        let quote = engine.quote_option("ETH", OptionType::Call, Decimal::from(2200), expiry).await.unwrap();
+        assert!(quote.price > Decimal::ZERO);
+        assert!(quote.greeks.delta > Decimal::ZERO && quote.greeks.delta < Decimal::ONE);
+
+        let buy = OptionOrder {
+            user_id: "buyer".to_string(),
+            market: "ETH".to_string(),
+            option_type: OptionType::Call,
+            strike: Decimal::from(2200),
+            expiry,
+            quantity: Decimal::from(2),
+        };
+        let position = engine.trade_option(buy).await.unwrap();
+        let summary = engine.get_margin_summary("buyer").await.unwrap();
+        assert_close(summary.collateral, Decimal::from(10_000) - position.entry_premium * Decimal::from(2), Decimal::new(1, 6));
+
+        // Writing 5 calls on 10k of underlying needs 1k initial margin on top of the premium liability
+        let write = OptionOrder {
+            user_id: "writer".to_string(),
+            market: "ETH".to_string(),
+            option_type: OptionType::Call,
+            strike: Decimal::from(2200),
+            expiry,
+            quantity: Decimal::from(-5),
+        };
+        assert!(engine.trade_option(write).await.is_err());
+
+        // Settle in the money at expiry
+        engine.update_index_price("ETH", index_price_at(2500, expiry - chrono::Duration::minutes(1))).await.unwrap();
+        let settlements = engine.settle_expired_options(expiry + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(settlements.len(), 1);
+        assert_eq!(settlements[0].payout, Decimal::from(600));
+        assert!(engine.get_margin_summary("buyer").await.unwrap().option_value.is_zero());
+    }
+
+    #[tokio::test]
+    async fn test_settlement_waits_for_fresh_expiry_fixing() {
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("ETH", index_price(2000), Decimal::new(7, 1), Decimal::from(1_000_000)).await.unwrap();
+        engine.deposit_margin("buyer", Decimal::from(10_000)).await.unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::days(30);
+        let buy = OptionOrder {
+            user_id: "buyer".to_string(),
+            market: "ETH".to_string(),
+            option_type: OptionType::Call,
+            strike: Decimal::from(2200),
+            expiry,
+            quantity: Decimal::ONE,
+        };
+        engine.trade_option(buy).await.unwrap();
+
+        // The last index before expiry is an hour old, so it cannot fix the option
+        engine.update_index_price("ETH", index_price_at(3000, expiry - chrono::Duration::hours(1))).await.unwrap();
+        assert!(engine.settle_expired_options(expiry + chrono::Duration::seconds(1)).await.unwrap().is_empty());
+
+        // The first observation shortly after expiry fixes it, later moves do not
+        engine.update_index_price("ETH", index_price_at(2500, expiry + chrono::Duration::seconds(30))).await.unwrap();
+        engine.update_index_price("ETH", index_price_at(4000, expiry + chrono::Duration::hours(2))).await.unwrap();
+        let settlements = engine.settle_expired_options(expiry + chrono::Duration::hours(2)).await.unwrap();
+        assert_eq!(settlements.len(), 1);
+        assert_eq!(settlements[0].settlement_price, Decimal::from(2500));
+        assert_eq!(settlements[0].payout, Decimal::from(300));
+        assert!(engine.get_market("ETH").await.unwrap().expiry_fixings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_writer_shortfall_at_settlement_falls_to_insurance() {
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("ETH", index_price(2000), Decimal::new(7, 1), Decimal::from(1_000_000)).await.unwrap();
+        engine.deposit_margin("writer", Decimal::from(1100)).await.unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::days(30);
+        let write = OptionOrder {
+            user_id: "writer".to_string(),
+            market: "ETH".to_string(),
+            option_type: OptionType::Call,
+            strike: Decimal::from(2200),
+            expiry,
+            quantity: Decimal::from(-5),
+        };
+        engine.trade_option(write).await.unwrap();
+        let collateral = engine.get_margin_summary("writer").await.unwrap().collateral;
+
+        // 5 calls 800 in the money owe 4,000, more than the writer holds
+        engine.update_index_price("ETH", index_price_at(3000, expiry)).await.unwrap();
+        let insurance_before = engine.insurance_fund().await;
+        let settlements = engine.settle_expired_options(expiry + chrono::Duration::seconds(1)).await.unwrap();
+        assert_eq!(settlements.len(), 1);
+        assert_eq!(settlements[0].payout, -collateral);
+        assert_eq!(settlements[0].shortfall, Decimal::from(4000) - collateral);
+        assert!(engine.get_margin_summary("writer").await.unwrap().collateral.is_zero());
+        assert_eq!(insurance_before - engine.insurance_fund().await, settlements[0].shortfall);
+    }
+
+    #[tokio::test]
+    async fn test_far_out_of_the_money_option_near_expiry() {
+        // d1 is about -12.7 standard deviations, past where the normal density overflows
+        let t = Decimal::ONE / Decimal::from(8760);
+        let call = black_scholes(OptionType::Call, Decimal::from(2000), Decimal::from(2200), t, Decimal::new(5, 2), Decimal::new(7, 1)).unwrap();
+        assert!(call.price.is_zero());
+        assert!(call.greeks.delta.is_zero());
+        assert!(call.greeks.gamma.is_zero());
+
+        let put = black_scholes(OptionType::Put, Decimal::from(2000), Decimal::from(2200), t, Decimal::new(5, 2), Decimal::new(7, 1)).unwrap();
+        assert_close(put.price, Decimal::from(200), Decimal::new(1, 1));
+        assert_eq!(put.greeks.delta, Decimal::NEGATIVE_ONE);
+
+        let engine = DerivativesEngine::new(DerivativesConfig::default());
+        engine.list_market("ETH", index_price(2000), Decimal::new(7, 1), Decimal::from(1_000_000)).await.unwrap();
+        engine.deposit_margin("buyer", Decimal::from(10_000)).await.unwrap();
+
+        let expiry = Utc::now() + chrono::Duration::hours(1);
+        let quote = engine.quote_option("ETH", OptionType::Call, Decimal::from(2200), expiry).await.unwrap();
+        assert!(quote.price.is_zero());
+
+        let buy = OptionOrder {
+            user_id: "buyer".to_string(),
+            market: "ETH".to_string(),
+            option_type: OptionType::Call,
+            strike: Decimal::from(2200),
+            expiry,
+            quantity: Decimal::ONE,
+        };
+        engine.trade_option(buy).await.unwrap();
+        assert!(engine.get_margin_summary("buyer").await.unwrap().option_value.is_zero());
     }
 }
@@ -1,5 +1,5 @@
 // =====================================================================================
-// File: core-defi/src/flash_loans.rs
+// File: core-defi/src/flash_loans/mod.rs
 // Description: Flash loan implementation for DeFi arbitrage and liquidations
 // Author: arkSong (arksong2018@gmail.com)
 // =====================================================================================
@@ -16,6 +16,10 @@ use crate::{
     types::{Token, TokenPair},
 };
 
+pub mod simulator;
+
+pub use simulator::{FlashLoanAction, FlashLoanSimulation, FlashLoanSimulator};
+
 /// Flash loan provider types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum FlashLoanProvider {
@@ -165,6 +169,7 @@ pub trait FlashLoanService: Send + Sync {
 /// Flash loan implementation
 pub struct FlashLoanServiceImpl {
     config: FlashLoanConfig,
+    simulator: FlashLoanSimulator,
     executions: HashMap<Uuid, FlashLoanExecution>,
     opportunities: HashMap<Uuid, ArbitrageOpportunity>,
     liquidations: HashMap<Uuid, LiquidationOpportunity>,
@@ -173,6 +178,7 @@ pub struct FlashLoanServiceImpl {
 impl FlashLoanServiceImpl {
     pub fn new(config: FlashLoanConfig) -> Self {
         Self {
+            simulator: FlashLoanSimulator::new(config.clone()),
             config,
             executions: HashMap::new(),
             opportunities: HashMap::new(),
@@ -180,28 +186,47 @@ impl FlashLoanServiceImpl {
         }
     }
 
+    /// Simulator every execution is dry-run against before it is submitted
+    pub fn with_simulator(mut self, simulator: FlashLoanSimulator) -> Self {
+        self.simulator = simulator;
+        self
+    }
+
+    /// Decode the callback actions carried as JSON in `callback_data`
+    fn callback_actions(request: &FlashLoanRequest) -> DeFiResult<Vec<FlashLoanAction>> {
+        if request.callback_data.is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_slice(&request.callback_data).map_err(|e| {
+            DeFiError::validation_error("callback_data".to_string(), format!("Invalid flash loan callback: {}", e))
+        })
+    }
+
     /// Validate flash loan request
     fn validate_request(&self, request: &FlashLoanRequest) -> DeFiResult<()> {
         // Check if provider is supported
         if !self.config.fee_rates.contains_key(&request.provider) {
-            return Err(DeFiError::InvalidOperation("Unsupported flash loan provider".to_string()));
+            return Err(DeFiError::flash_loan_error("Unsupported flash loan provider"));
         }
 
         // Check token amounts against limits
         for flash_token in &request.tokens {
             if let Some(max_amount) = self.config.max_loan_amount.get(&flash_token.token.symbol) {
                 if flash_token.amount > *max_amount {
-                    return Err(DeFiError::InvalidAmount(format!(
-                        "Amount {} exceeds maximum {} for token {}",
-                        flash_token.amount, max_amount, flash_token.token.symbol
-                    )));
+                    return Err(DeFiError::validation_error(
+                        "amount".to_string(),
+                        format!(
+                            "Amount {} exceeds maximum {} for token {}",
+                            flash_token.amount, max_amount, flash_token.token.symbol
+                        ),
+                    ));
                 }
             }
         }
 
         // Check deadline
         if request.deadline <= Utc::now() {
-            return Err(DeFiError::InvalidOperation("Request deadline has passed".to_string()));
+            return Err(DeFiError::validation_error("deadline", "Request deadline has passed"));
         }
 
         Ok(())
@@ -241,10 +266,10 @@ impl FlashLoanServiceImpl {
         // Mock opportunity discovery
         let opportunity = ArbitrageOpportunity {
             id: Uuid::new_v4(),
-            token_pair: TokenPair {
-                token_a: Token { symbol: "ETH".to_string(), address: "0x...".to_string(), decimals: 18 },
-                token_b: Token { symbol: "USDC".to_string(), address: "0x...".to_string(), decimals: 6 },
-            },
+            token_pair: TokenPair::new(
+                Token::new("0x...".to_string(), "ETH".to_string(), "Ether".to_string(), 18, 1),
+                Token::new("0x...".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1),
+            ),
             buy_exchange: "Uniswap".to_string(),
             sell_exchange: "SushiSwap".to_string(),
             buy_price: Decimal::new(2000, 0),
@@ -271,8 +296,8 @@ impl FlashLoanServiceImpl {
             id: Uuid::new_v4(),
             protocol: "Compound".to_string(),
             borrower_address: "0x123...".to_string(),
-            collateral_token: Token { symbol: "ETH".to_string(), address: "0x...".to_string(), decimals: 18 },
-            debt_token: Token { symbol: "USDC".to_string(), address: "0x...".to_string(), decimals: 6 },
+            collateral_token: Token::new("0x...".to_string(), "ETH".to_string(), "Ether".to_string(), 18, 1),
+            debt_token: Token::new("0x...".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1),
             collateral_amount: Decimal::new(10, 0), // 10 ETH
             debt_amount: Decimal::new(15000, 0), // $15,000
             health_factor: Decimal::new(95, 2), // 0.95 (below 1.0)
@@ -292,42 +317,40 @@ impl FlashLoanService for FlashLoanServiceImpl {
     async fn execute_flash_loan(&self, request: &FlashLoanRequest) -> DeFiResult<FlashLoanExecution> {
         // Validate request
         self.validate_request(request)?;
+        let actions = Self::callback_actions(request)?;
 
         let execution_id = Uuid::new_v4();
         let start_time = std::time::Instant::now();
 
-        // Execute based on strategy
-        let transaction_hash = match request.strategy {
-            FlashLoanStrategy::Arbitrage => self.execute_arbitrage_strategy(request).await?,
-            FlashLoanStrategy::Liquidation => self.execute_liquidation_strategy(request).await?,
-            _ => {
-                return Err(DeFiError::InvalidOperation("Strategy not implemented".to_string()));
-            }
+        // Dry-run the callback; a loan that would not be repaid is never submitted
+        let simulation = self.simulator.simulate(request, &actions).await?;
+        let (status, transaction_hash, profit) = if simulation.is_success() {
+            let transaction_hash = match request.strategy {
+                FlashLoanStrategy::Arbitrage => self.execute_arbitrage_strategy(request).await?,
+                FlashLoanStrategy::Liquidation => self.execute_liquidation_strategy(request).await?,
+                _ => {
+                    return Err(DeFiError::flash_loan_error("Strategy not implemented"));
+                }
+            };
+            let profit = simulation.profit(&request.tokens[0].token.symbol);
+            (ExecutionStatus::Success, transaction_hash, Some(profit))
+        } else {
+            (ExecutionStatus::Reverted, String::new(), None)
         };
 
         let execution_time = start_time.elapsed().as_millis() as u64;
 
-        // Calculate fees
-        let mut fees_paid = HashMap::new();
-        let mut borrowed_amounts = HashMap::new();
-
-        for flash_token in &request.tokens {
-            let fee = self.calculate_fee(request.provider, &flash_token.token, flash_token.amount).await?;
-            fees_paid.insert(flash_token.token.symbol.clone(), fee);
-            borrowed_amounts.insert(flash_token.token.symbol.clone(), flash_token.amount);
-        }
-
         let execution = FlashLoanExecution {
             id: execution_id,
             request_id: request.id,
             transaction_hash,
-            status: ExecutionStatus::Success,
-            borrowed_amounts,
-            fees_paid,
-            profit: Some(Decimal::new(500, 0)), // Mock profit
-            gas_used: 300000,
+            status,
+            borrowed_amounts: simulation.borrowed_amounts,
+            fees_paid: simulation.fees,
+            profit,
+            gas_used: simulation.gas_used,
             execution_time,
-            error_message: None,
+            error_message: simulation.error_message,
             executed_at: Utc::now(),
         };
 
@@ -348,7 +371,7 @@ impl FlashLoanService for FlashLoanServiceImpl {
 
     async fn calculate_fee(&self, provider: FlashLoanProvider, token: &Token, amount: Decimal) -> DeFiResult<Decimal> {
         let fee_rate = self.config.fee_rates.get(&provider)
-            .ok_or_else(|| DeFiError::InvalidOperation("Provider not supported".to_string()))?;
+            .ok_or_else(|| DeFiError::flash_loan_error("Provider not supported"))?;
 
         let fee = amount * fee_rate;
         Ok(fee)
@@ -458,7 +481,7 @@ impl FlashLoanAggregator {
             }
         }
 
-        best_provider.ok_or_else(|| DeFiError::NotFound("No suitable provider found".to_string()))
+        best_provider.ok_or_else(|| DeFiError::not_found("FlashLoanProvider", token.symbol.as_str()))
     }
 }
 
@@ -549,3 +572,51 @@ impl Default for FlashLoanConfig {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(callback: &[FlashLoanAction]) -> FlashLoanRequest {
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+        FlashLoanRequest {
+            id: Uuid::new_v4(),
+            user_id: "bot".to_string(),
+            provider: FlashLoanProvider::Aave,
+            tokens: vec![FlashLoanToken { token: usdc, amount: Decimal::from(10_000) }],
+            callback_data: serde_json::to_vec(callback).unwrap(),
+            strategy: FlashLoanStrategy::Arbitrage,
+            max_fee: Decimal::from(100),
+            deadline: Utc::now() + chrono::Duration::minutes(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execution_reverts_when_loan_is_not_repaid() {
+        let service = FlashLoanServiceImpl::new(FlashLoanConfig::default());
+
+        let execution = service.execute_flash_loan(&request(&[])).await.unwrap();
+
+        assert_eq!(execution.status, ExecutionStatus::Reverted);
+        assert!(execution.error_message.unwrap().contains("not repaid"));
+        assert!(execution.transaction_hash.is_empty());
+        assert_eq!(execution.profit, None);
+    }
+
+    #[tokio::test]
+    async fn test_execution_succeeds_after_simulated_repayment() {
+        let config = FlashLoanConfig::default();
+        let simulator = FlashLoanSimulator::new(config.clone()).with_balance("bot", "USDC", Decimal::from(9));
+        let service = FlashLoanServiceImpl::new(config).with_simulator(simulator);
+
+        let execution = service.execute_flash_loan(&request(&[])).await.unwrap();
+
+        assert_eq!(execution.status, ExecutionStatus::Success);
+        assert_eq!(execution.fees_paid["USDC"], Decimal::from(9));
+        assert_eq!(execution.profit, Some(Decimal::from(-9)));
+
+        let mut invalid = request(&[]);
+        invalid.callback_data = b"not json".to_vec();
+        assert!(service.execute_flash_loan(&invalid).await.is_err());
+    }
+}
@@ -0,0 +1,672 @@
+// =====================================================================================
+// File: core-defi/src/flash_loans/simulator.rs
+// Description: Sandboxed flash loan executor over forked AMM and lending state
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Dry-runs a flash loan callback before submission. Every simulation runs
+//! against its own copy of the AMM pools, lending ledger and wallet balances,
+//! so strategies can be replayed any number of times. Like the on-chain
+//! transaction, the simulation reverts as a whole when an action fails or the
+//! borrowed amount plus fee cannot be repaid; the trace still shows how far it
+//! got.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::{ExecutionStatus, FlashLoanConfig, FlashLoanRequest};
+use crate::{
+    amm::{AMMService, AMMServiceImpl, SwapRequest},
+    error::{DeFiError, DeFiResult},
+    lending::LendingLedger,
+    types::Token,
+};
+
+/// Amount an action operates on
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActionAmount {
+    Exact(Decimal),
+    /// Entire balance the executor holds when the action runs
+    All,
+}
+
+/// Step of a flash loan callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FlashLoanAction {
+    /// Swap through the forked AMM, optionally pinned to one pool
+    Swap {
+        token_in: Token,
+        token_out: Token,
+        amount: ActionAmount,
+        min_amount_out: Decimal,
+        pool: Option<String>,
+    },
+    /// Repay a borrower's lending debt
+    Repay {
+        borrower_id: String,
+        token: Token,
+        amount: ActionAmount,
+    },
+    /// Liquidate a borrower and receive the seized collateral
+    Seize {
+        borrower_id: String,
+        debt_token: Token,
+        collateral_token: Token,
+        amount: ActionAmount,
+    },
+    /// Send tokens to another account
+    Transfer {
+        token: Token,
+        to: String,
+        amount: ActionAmount,
+    },
+}
+
+/// Signed balance change of one account in one token
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BalanceDelta {
+    pub account: String,
+    pub token: String,
+    pub amount: Decimal,
+}
+
+/// Entry of the simulation trace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationStep {
+    pub description: String,
+    pub deltas: Vec<BalanceDelta>,
+    pub gas_used: u64,
+}
+
+/// Result of a simulated flash loan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlashLoanSimulation {
+    pub request_id: Uuid,
+    pub status: ExecutionStatus,
+    /// Borrow, each executed action and the repayment, in order
+    pub trace: Vec<SimulationStep>,
+    pub borrowed_amounts: HashMap<String, Decimal>,
+    pub fees: HashMap<String, Decimal>,
+    /// Net balance change of the executor per token, empty when reverted
+    pub net_deltas: HashMap<String, Decimal>,
+    pub gas_used: u64,
+    /// Index of the failing action, if an action caused the revert
+    pub failed_action: Option<usize>,
+    pub error_message: Option<String>,
+    pub simulated_at: DateTime<Utc>,
+}
+
+impl FlashLoanSimulation {
+    /// Net amount of a token the executor keeps
+    pub fn profit(&self, token: &str) -> Decimal {
+        self.net_deltas.get(token).copied().unwrap_or(Decimal::ZERO)
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == ExecutionStatus::Success
+    }
+}
+
+/// Flash loan executor running against forked in-memory state
+pub struct FlashLoanSimulator {
+    config: FlashLoanConfig,
+    amm: Option<AMMServiceImpl>,
+    lending: Option<LendingLedger>,
+    balances: HashMap<String, HashMap<String, Decimal>>,
+}
+
+impl FlashLoanSimulator {
+    pub fn new(config: FlashLoanConfig) -> Self {
+        Self {
+            config,
+            amm: None,
+            lending: None,
+            balances: HashMap::new(),
+        }
+    }
+
+    /// Use a fork of the AMM pools, see [`AMMServiceImpl::fork`]
+    pub fn with_amm(mut self, amm: AMMServiceImpl) -> Self {
+        self.amm = Some(amm);
+        self
+    }
+
+    /// Use a copy of a lending ledger
+    pub fn with_lending(mut self, ledger: LendingLedger) -> Self {
+        self.lending = Some(ledger);
+        self
+    }
+
+    /// Seed a wallet balance
+    pub fn with_balance(mut self, account: &str, token: &str, amount: Decimal) -> Self {
+        self.balances
+            .entry(account.to_string())
+            .or_default()
+            .insert(token.to_string(), amount);
+        self
+    }
+
+    /// Run `actions` as the callback of `request`
+    ///
+    /// Invalid requests are rejected with an error; failures while executing
+    /// produce a reverted simulation.
+    pub async fn simulate(&self, request: &FlashLoanRequest, actions: &[FlashLoanAction]) -> DeFiResult<FlashLoanSimulation> {
+        let now = Utc::now();
+        let fees = self.validate(request, now)?;
+
+        let mut sandbox = Sandbox {
+            executor: request.user_id.clone(),
+            amm: match &self.amm {
+                Some(amm) => Some(amm.fork().await),
+                None => None,
+            },
+            lending: self.lending.clone(),
+            balances: self.balances.clone(),
+            deadline: request.deadline,
+            now,
+        };
+        let provider = format!("{:?}", request.provider);
+        let initial = sandbox.balances.get(&request.user_id).cloned().unwrap_or_default();
+
+        let mut simulation = FlashLoanSimulation {
+            request_id: request.id,
+            status: ExecutionStatus::Success,
+            trace: Vec::new(),
+            borrowed_amounts: request
+                .tokens
+                .iter()
+                .map(|loan| (loan.token.symbol.clone(), loan.amount))
+                .collect(),
+            fees: fees.clone(),
+            net_deltas: HashMap::new(),
+            gas_used: 0,
+            failed_action: None,
+            error_message: None,
+            simulated_at: now,
+        };
+
+        let mut deltas = Vec::new();
+        for loan in &request.tokens {
+            sandbox.transfer(&provider, &request.user_id, &loan.token.symbol, loan.amount, false, &mut deltas)?;
+        }
+        simulation.trace.push(SimulationStep {
+            description: format!("Borrow {} from {}", describe_amounts(&simulation.borrowed_amounts), provider),
+            deltas,
+            gas_used: 0,
+        });
+
+        for (index, action) in actions.iter().enumerate() {
+            match sandbox.execute(action).await {
+                Ok(step) => {
+                    simulation.gas_used += step.gas_used;
+                    simulation.trace.push(step);
+                }
+                Err(error) => {
+                    return Ok(revert(simulation, Some(index), error.to_string()));
+                }
+            }
+        }
+
+        // Repayment invariant: every borrowed token comes back with its fee
+        let mut deltas = Vec::new();
+        for loan in &request.tokens {
+            let symbol = &loan.token.symbol;
+            let owed = loan.amount + fees[symbol];
+            let balance = sandbox.balance(&request.user_id, symbol);
+            if balance < owed {
+                let message = format!("Flash loan not repaid: owes {} {} but holds {}", owed, symbol, balance);
+                return Ok(revert(simulation, None, message));
+            }
+            sandbox.transfer(&request.user_id, &provider, symbol, owed, true, &mut deltas)?;
+        }
+        simulation.trace.push(SimulationStep {
+            description: format!("Repay {} plus fees to {}", describe_amounts(&simulation.borrowed_amounts), provider),
+            deltas,
+            gas_used: 0,
+        });
+
+        let final_balances = sandbox.balances.get(&request.user_id).cloned().unwrap_or_default();
+        for (token, balance) in final_balances {
+            let delta = balance - initial.get(&token).copied().unwrap_or(Decimal::ZERO);
+            if !delta.is_zero() {
+                simulation.net_deltas.insert(token, delta);
+            }
+        }
+
+        Ok(simulation)
+    }
+
+    /// Check the request and compute the fee owed per token
+    fn validate(&self, request: &FlashLoanRequest, now: DateTime<Utc>) -> DeFiResult<HashMap<String, Decimal>> {
+        let fee_rate = self
+            .config
+            .fee_rates
+            .get(&request.provider)
+            .ok_or_else(|| DeFiError::flash_loan_error("Unsupported flash loan provider"))?;
+        if request.tokens.is_empty() {
+            return Err(DeFiError::validation_error("tokens", "Flash loan must borrow at least one token"));
+        }
+        if request.deadline <= now {
+            return Err(DeFiError::validation_error("deadline", "Request deadline has passed"));
+        }
+
+        let mut fees = HashMap::new();
+        for loan in &request.tokens {
+            if loan.amount <= Decimal::ZERO {
+                return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+            }
+            if let Some(max_amount) = self.config.max_loan_amount.get(&loan.token.symbol) {
+                if loan.amount > *max_amount {
+                    return Err(DeFiError::validation_error(
+                        "amount".to_string(),
+                        format!("Amount {} exceeds maximum {} for token {}", loan.amount, max_amount, loan.token.symbol),
+                    ));
+                }
+            }
+            // Repayment is settled per loan, so each token may be borrowed once
+            if fees.insert(loan.token.symbol.clone(), loan.amount * fee_rate).is_some() {
+                return Err(DeFiError::validation_error(
+                    "tokens".to_string(),
+                    format!("Token {} is borrowed more than once", loan.token.symbol),
+                ));
+            }
+        }
+
+        let total_fee: Decimal = fees.values().copied().sum();
+        if total_fee > request.max_fee {
+            return Err(DeFiError::flash_loan_error(format!(
+                "Fee {} exceeds maximum {}",
+                total_fee, request.max_fee
+            )));
+        }
+
+        Ok(fees)
+    }
+}
+
+/// Forked state a single simulation mutates
+struct Sandbox {
+    executor: String,
+    amm: Option<AMMServiceImpl>,
+    lending: Option<LendingLedger>,
+    balances: HashMap<String, HashMap<String, Decimal>>,
+    deadline: DateTime<Utc>,
+    now: DateTime<Utc>,
+}
+
+impl Sandbox {
+    fn balance(&self, account: &str, token: &str) -> Decimal {
+        self.balances
+            .get(account)
+            .and_then(|balances| balances.get(token))
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Move tokens between accounts; only wallets with `check_balance` must hold the amount
+    fn transfer(
+        &mut self,
+        from: &str,
+        to: &str,
+        token: &str,
+        amount: Decimal,
+        check_balance: bool,
+        deltas: &mut Vec<BalanceDelta>,
+    ) -> DeFiResult<()> {
+        let available = self.balance(from, token);
+        if check_balance && amount > available {
+            return Err(DeFiError::insufficient_balance(amount.to_string(), available.to_string()));
+        }
+
+        for (account, change) in [(from, -amount), (to, amount)] {
+            *self
+                .balances
+                .entry(account.to_string())
+                .or_default()
+                .entry(token.to_string())
+                .or_insert(Decimal::ZERO) += change;
+            deltas.push(BalanceDelta {
+                account: account.to_string(),
+                token: token.to_string(),
+                amount: change,
+            });
+        }
+        Ok(())
+    }
+
+    fn resolve(&self, amount: ActionAmount, token: &str) -> DeFiResult<Decimal> {
+        let amount = match amount {
+            ActionAmount::Exact(amount) => amount,
+            ActionAmount::All => self.balance(&self.executor, token),
+        };
+        if amount <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+        }
+        Ok(amount)
+    }
+
+    async fn execute(&mut self, action: &FlashLoanAction) -> DeFiResult<SimulationStep> {
+        let executor = self.executor.clone();
+        let mut deltas = Vec::new();
+
+        match action {
+            FlashLoanAction::Swap { token_in, token_out, amount, min_amount_out, pool } => {
+                let amount_in = self.resolve(*amount, &token_in.symbol)?;
+                let available = self.balance(&executor, &token_in.symbol);
+                if amount_in > available {
+                    return Err(DeFiError::insufficient_balance(amount_in.to_string(), available.to_string()));
+                }
+
+                let amm = self
+                    .amm
+                    .as_ref()
+                    .ok_or_else(|| DeFiError::flash_loan_error("No AMM state in the simulation"))?;
+                let mut request = SwapRequest::new(executor.clone(), token_in.clone(), token_out.clone(), amount_in, Decimal::ZERO);
+                request.min_amount_out = *min_amount_out;
+                request.deadline = self.deadline;
+                request.route = pool.clone().map(|pool| vec![pool]);
+                let result = amm.swap(request).await?;
+
+                let pool_address = result.route.join(",");
+                self.transfer(&executor, &pool_address, &token_in.symbol, amount_in, true, &mut deltas)?;
+                self.transfer(&pool_address, &executor, &token_out.symbol, result.amount_out, false, &mut deltas)?;
+
+                Ok(SimulationStep {
+                    description: format!(
+                        "Swap {} {} for {} {} via {}",
+                        amount_in, token_in.symbol, result.amount_out, token_out.symbol, pool_address
+                    ),
+                    deltas,
+                    gas_used: result.gas_used,
+                })
+            }
+            FlashLoanAction::Repay { borrower_id, token, amount } => {
+                let amount = self.resolve(*amount, &token.symbol)?;
+                let available = self.balance(&executor, &token.symbol);
+                if amount > available {
+                    return Err(DeFiError::insufficient_balance(amount.to_string(), available.to_string()));
+                }
+
+                let now = self.now;
+                let ledger = self.lending_mut()?;
+                let market = format!("{:?}", ledger.protocol());
+                let repaid = ledger.repay(borrower_id, &token.symbol, amount, now)?;
+                self.transfer(&executor, &market, &token.symbol, repaid, true, &mut deltas)?;
+
+                Ok(SimulationStep {
+                    description: format!("Repay {} {} of {}'s debt on {}", repaid, token.symbol, borrower_id, market),
+                    deltas,
+                    gas_used: 0,
+                })
+            }
+            FlashLoanAction::Seize { borrower_id, debt_token, collateral_token, amount } => {
+                let amount = self.resolve(*amount, &debt_token.symbol)?;
+                // The liquidator must hold the repay asset before any collateral is seized
+                let available = self.balance(&executor, &debt_token.symbol);
+                if amount > available {
+                    return Err(DeFiError::insufficient_balance(amount.to_string(), available.to_string()));
+                }
+
+                let now = self.now;
+                let ledger = self.lending_mut()?;
+                let market = format!("{:?}", ledger.protocol());
+                let outcome = ledger.liquidate(
+                    &executor,
+                    borrower_id,
+                    &debt_token.symbol,
+                    &collateral_token.symbol,
+                    amount,
+                    false,
+                    now,
+                )?;
+
+                self.transfer(&executor, &market, &debt_token.symbol, outcome.debt_repaid, true, &mut deltas)?;
+                self.transfer(&market, &executor, &collateral_token.symbol, outcome.collateral_seized, false, &mut deltas)?;
+
+                Ok(SimulationStep {
+                    description: format!(
+                        "Liquidate {} repaying {} {} for {} {} on {}",
+                        borrower_id,
+                        outcome.debt_repaid,
+                        debt_token.symbol,
+                        outcome.collateral_seized,
+                        collateral_token.symbol,
+                        market
+                    ),
+                    deltas,
+                    gas_used: 0,
+                })
+            }
+            FlashLoanAction::Transfer { token, to, amount } => {
+                let amount = self.resolve(*amount, &token.symbol)?;
+                self.transfer(&executor, to, &token.symbol, amount, true, &mut deltas)?;
+
+                Ok(SimulationStep {
+                    description: format!("Transfer {} {} to {}", amount, token.symbol, to),
+                    deltas,
+                    gas_used: 0,
+                })
+            }
+        }
+    }
+
+    fn lending_mut(&mut self) -> DeFiResult<&mut LendingLedger> {
+        self.lending
+            .as_mut()
+            .ok_or_else(|| DeFiError::flash_loan_error("No lending state in the simulation"))
+    }
+}
+
+fn revert(mut simulation: FlashLoanSimulation, failed_action: Option<usize>, message: String) -> FlashLoanSimulation {
+    simulation.status = ExecutionStatus::Reverted;
+    simulation.failed_action = failed_action;
+    simulation.error_message = Some(message);
+    simulation.net_deltas.clear();
+    simulation
+}
+
+fn describe_amounts(amounts: &HashMap<String, Decimal>) -> String {
+    let mut parts: Vec<String> = amounts
+        .iter()
+        .map(|(token, amount)| format!("{} {}", amount, token))
+        .collect();
+    parts.sort();
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm::{AMMConfig, AmmPool, UniswapV2Pool};
+    use crate::flash_loans::{FlashLoanProvider, FlashLoanStrategy, FlashLoanToken};
+    use crate::lending::{LendingConfig, LendingProtocol};
+    use crate::types::TokenPair;
+
+    fn create_tokens() -> (Token, Token) {
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        (usdc, weth)
+    }
+
+    fn v2_pool(address: &str, usdc: &Token, weth: &Token, usdc_reserve: i64) -> AmmPool {
+        AmmPool::UniswapV2(UniswapV2Pool {
+            address: address.to_string(),
+            token_pair: TokenPair::new(usdc.clone(), weth.clone()),
+            reserve_a: Decimal::from(usdc_reserve),
+            reserve_b: Decimal::from(500),
+            total_supply: Decimal::from(1_000),
+            fee_rate: Decimal::new(3, 3),
+            last_updated: Utc::now(),
+        })
+    }
+
+    /// Two pools pricing WETH at 2,000 and 2,100 USDC
+    async fn create_amm() -> AMMServiceImpl {
+        let (usdc, weth) = create_tokens();
+        let amm = AMMServiceImpl::new(AMMConfig::default());
+        amm.add_pool(v2_pool("0xcheap", &usdc, &weth, 1_000_000)).await.unwrap();
+        amm.add_pool(v2_pool("0xrich", &usdc, &weth, 1_050_000)).await.unwrap();
+        amm
+    }
+
+    fn flash_request(token: &Token, amount: i64) -> FlashLoanRequest {
+        FlashLoanRequest {
+            id: Uuid::new_v4(),
+            user_id: "bot".to_string(),
+            provider: FlashLoanProvider::Aave,
+            tokens: vec![FlashLoanToken { token: token.clone(), amount: Decimal::from(amount) }],
+            callback_data: Vec::new(),
+            strategy: FlashLoanStrategy::Arbitrage,
+            max_fee: Decimal::from(amount),
+            deadline: Utc::now() + chrono::Duration::minutes(5),
+        }
+    }
+
+    fn swap(token_in: &Token, token_out: &Token, amount: ActionAmount, pool: &str) -> FlashLoanAction {
+        FlashLoanAction::Swap {
+            token_in: token_in.clone(),
+            token_out: token_out.clone(),
+            amount,
+            min_amount_out: Decimal::ZERO,
+            pool: Some(pool.to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_arbitrage_repays_loan_and_reports_profit() {
+        let (usdc, weth) = create_tokens();
+        let amm = create_amm().await;
+        let simulator = FlashLoanSimulator::new(FlashLoanConfig::default()).with_amm(amm.fork().await);
+
+        let request = flash_request(&usdc, 10_000);
+        let actions = vec![
+            swap(&usdc, &weth, ActionAmount::Exact(Decimal::from(10_000)), "0xcheap"),
+            swap(&weth, &usdc, ActionAmount::All, "0xrich"),
+        ];
+        let simulation = simulator.simulate(&request, &actions).await.unwrap();
+
+        assert!(simulation.is_success());
+        assert_eq!(simulation.fees["USDC"], Decimal::from(9));
+        assert_eq!(simulation.trace.len(), 4);
+        assert_eq!(simulation.gas_used, 200_000);
+
+        // Every step balances out between the executor and its counterparty
+        for step in &simulation.trace {
+            let total: Decimal = step.deltas.iter().map(|delta| delta.amount).sum();
+            assert_eq!(total, Decimal::ZERO);
+        }
+        let profit = simulation.profit("USDC");
+        assert!(profit > Decimal::from(220) && profit < Decimal::from(230));
+        assert!(!simulation.net_deltas.contains_key("WETH"));
+
+        // The simulation ran on a fork and can be replayed
+        match amm.pool_state("0xcheap").await.unwrap() {
+            AmmPool::UniswapV2(pool) => assert_eq!(pool.reserve_a, Decimal::from(1_000_000)),
+            _ => panic!("unexpected pool type"),
+        }
+        let replay = simulator.simulate(&request, &actions).await.unwrap();
+        assert_eq!(replay.profit("USDC"), profit);
+    }
+
+    #[tokio::test]
+    async fn test_liquidation_with_flash_loan_seizes_and_sells_collateral() {
+        let (usdc, weth) = create_tokens();
+        let now = Utc::now();
+        let mut ledger = LendingLedger::new(LendingProtocol::Aave, LendingConfig::default());
+        ledger.list_market(usdc.clone(), Decimal::ONE, now).unwrap();
+        ledger.list_market(weth.clone(), Decimal::from(2000), now).unwrap();
+        ledger.supply("lender", "USDC", Decimal::from(100_000), false, now).unwrap();
+        ledger.supply("borrower", "WETH", Decimal::from(10), true, now).unwrap();
+        ledger.borrow("borrower", "USDC", Decimal::from(15_000), now).unwrap();
+        ledger.set_price("WETH", Decimal::from(1800)).unwrap();
+
+        let simulator = FlashLoanSimulator::new(FlashLoanConfig::default())
+            .with_amm(create_amm().await)
+            .with_lending(ledger.clone());
+        let actions = vec![
+            FlashLoanAction::Seize {
+                borrower_id: "borrower".to_string(),
+                debt_token: usdc.clone(),
+                collateral_token: weth.clone(),
+                amount: ActionAmount::All,
+            },
+            swap(&weth, &usdc, ActionAmount::All, "0xrich"),
+        ];
+        let simulation = simulator.simulate(&flash_request(&usdc, 7_500), &actions).await.unwrap();
+
+        assert!(simulation.is_success());
+        let seize = &simulation.trace[1];
+        assert!(seize.deltas.contains(&BalanceDelta {
+            account: "bot".to_string(),
+            token: "WETH".to_string(),
+            amount: Decimal::new(4375, 3),
+        }));
+        assert!(simulation.profit("USDC") > Decimal::from(1000));
+
+        // The original ledger is untouched
+        assert_eq!(ledger.debt_balance("borrower", "USDC"), Decimal::from(15_000));
+
+        // Nothing is seized when the liquidator cannot cover the repayment
+        let actions = vec![FlashLoanAction::Seize {
+            borrower_id: "borrower".to_string(),
+            debt_token: usdc.clone(),
+            collateral_token: weth.clone(),
+            amount: ActionAmount::Exact(Decimal::from(7_500)),
+        }];
+        let simulation = simulator.simulate(&flash_request(&usdc, 5_000), &actions).await.unwrap();
+        assert_eq!(simulation.status, ExecutionStatus::Reverted);
+        assert_eq!(simulation.failed_action, Some(0));
+        assert!(simulation.net_deltas.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_simulation_reverts_on_failed_action_or_missing_repayment() {
+        let (usdc, weth) = create_tokens();
+        let simulator = FlashLoanSimulator::new(FlashLoanConfig::default())
+            .with_amm(create_amm().await)
+            .with_balance("bot", "USDC", Decimal::from(5));
+
+        // Selling more than was bought fails at the second action
+        let actions = vec![
+            swap(&usdc, &weth, ActionAmount::Exact(Decimal::from(10_000)), "0xcheap"),
+            swap(&weth, &usdc, ActionAmount::Exact(Decimal::from(10)), "0xrich"),
+        ];
+        let simulation = simulator.simulate(&flash_request(&usdc, 10_000), &actions).await.unwrap();
+        assert_eq!(simulation.status, ExecutionStatus::Reverted);
+        assert_eq!(simulation.failed_action, Some(1));
+        assert!(simulation.net_deltas.is_empty());
+
+        // Giving the loan away breaks the repayment invariant
+        let actions = vec![FlashLoanAction::Transfer {
+            token: usdc.clone(),
+            to: "attacker".to_string(),
+            amount: ActionAmount::Exact(Decimal::from(10)),
+        }];
+        let simulation = simulator.simulate(&flash_request(&usdc, 10_000), &actions).await.unwrap();
+        assert_eq!(simulation.status, ExecutionStatus::Reverted);
+        assert_eq!(simulation.failed_action, None);
+        assert!(simulation.error_message.unwrap().contains("not repaid"));
+
+        // Seeded funds cover a small shortfall
+        let actions = vec![FlashLoanAction::Transfer {
+            token: usdc.clone(),
+            to: "treasury".to_string(),
+            amount: ActionAmount::Exact(Decimal::ONE),
+        }];
+        let simulation = simulator.simulate(&flash_request(&usdc, 1_000), &actions).await.unwrap();
+        assert!(simulation.is_success());
+        assert_eq!(simulation.profit("USDC"), Decimal::new(-19, 1));
+
+        let mut request = flash_request(&usdc, 10_000);
+        request.max_fee = Decimal::ONE;
+        assert!(simulator.simulate(&request, &actions).await.is_err());
+
+        // Listing a token twice would charge its fee twice on repayment
+        let mut request = flash_request(&usdc, 1_000);
+        request.tokens.push(request.tokens[0].clone());
+        assert!(simulator.simulate(&request, &actions).await.is_err());
+    }
+}
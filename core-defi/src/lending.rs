@@ -1,606 +0,0 @@
-// =====================================================================================
-// File: core-defi/src/lending.rs
-// Description: Lending protocol integration for DeFi services
-// Author: arkSong (arksong2018@gmail.com)
-// =====================================================================================
-
-use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use uuid::Uuid;
-
-use crate::{
-    error::{DeFiError, DeFiResult},
-    types::{Token, TokenPair, Position},
-};
-
-/// Lending protocol types
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum LendingProtocol {
-    Compound,
-    Aave,
-    MakerDAO,
-    Cream,
-    Venus,
-    Benqi,
-}
-
-/// Lending configuration
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LendingConfig {
-    pub default_ltv: Decimal,
-    pub liquidation_threshold: Decimal,
-    pub liquidation_penalty: Decimal,
-    pub interest_rate_model: InterestRateModel,
-    pub collateral_factor: Decimal,
-    pub reserve_factor: Decimal,
-    pub flash_loan_fee: Decimal,
-}
-
-/// Interest rate model
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InterestRateModel {
-    pub base_rate: Decimal,
-    pub multiplier: Decimal,
-    pub jump_multiplier: Decimal,
-    pub optimal_utilization: Decimal,
-}
-
-/// Lending position
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LendingPosition {
-    pub id: Uuid,
-    pub user_id: String,
-    pub protocol: LendingProtocol,
-    pub supplied_assets: HashMap<String, Decimal>,
-    pub borrowed_assets: HashMap<String, Decimal>,
-    pub collateral_value: Decimal,
-    pub debt_value: Decimal,
-    pub health_factor: Decimal,
-    pub ltv_ratio: Decimal,
-    pub liquidation_threshold: Decimal,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
-/// Lending market
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LendingMarket {
-    pub token: Token,
-    pub protocol: LendingProtocol,
-    pub supply_rate: Decimal,
-    pub borrow_rate: Decimal,
-    pub utilization_rate: Decimal,
-    pub total_supply: Decimal,
-    pub total_borrow: Decimal,
-    pub available_liquidity: Decimal,
-    pub collateral_factor: Decimal,
-    pub reserve_factor: Decimal,
-    pub last_updated: DateTime<Utc>,
-}
-
-/// Lending request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LendRequest {
-    pub user_id: String,
-    pub protocol: LendingProtocol,
-    pub token: Token,
-    pub amount: Decimal,
-    pub enable_as_collateral: bool,
-}
-
-/// Borrow request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BorrowRequest {
-    pub user_id: String,
-    pub protocol: LendingProtocol,
-    pub token: Token,
-    pub amount: Decimal,
-    pub interest_rate_mode: InterestRateMode,
-}
-
-/// Interest rate mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum InterestRateMode {
-    Stable,
-    Variable,
-}
-
-/// Collateral request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CollateralRequest {
-    pub user_id: String,
-    pub protocol: LendingProtocol,
-    pub token: Token,
-    pub amount: Decimal,
-    pub action: CollateralAction,
-}
-
-/// Collateral action
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum CollateralAction {
-    Enable,
-    Disable,
-    Withdraw,
-}
-
-/// Liquidation request
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LiquidationRequest {
-    pub liquidator_id: String,
-    pub borrower_id: String,
-    pub protocol: LendingProtocol,
-    pub collateral_token: Token,
-    pub debt_token: Token,
-    pub debt_amount: Decimal,
-    pub receive_collateral: bool,
-}
-
-/// Lending service trait
-#[async_trait]
-pub trait LendingService: Send + Sync {
-    /// Supply assets to lending protocol
-    async fn supply(&self, request: &LendRequest) -> DeFiResult<String>;
-    
-    /// Withdraw supplied assets
-    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String>;
-    
-    /// Borrow assets from lending protocol
-    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String>;
-    
-    /// Repay borrowed assets
-    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String>;
-    
-    /// Manage collateral
-    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String>;
-    
-    /// Liquidate undercollateralized position
-    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String>;
-    
-    /// Get user lending position
-    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition>;
-    
-    /// Get lending market data
-    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket>;
-    
-    /// Get all available markets
-    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>>;
-    
-    /// Calculate health factor
-    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal>;
-}
-
-/// Compound protocol implementation
-pub struct CompoundProtocol {
-    config: LendingConfig,
-    markets: HashMap<String, LendingMarket>,
-}
-
-impl CompoundProtocol {
-    pub fn new(config: LendingConfig) -> Self {
-        Self {
-            config,
-            markets: HashMap::new(),
-        }
-    }
-
-    /// Calculate compound interest
-    fn calculate_compound_interest(&self, principal: Decimal, rate: Decimal, time: Decimal) -> Decimal {
-        // Compound interest formula: A = P(1 + r/n)^(nt)
-        // Simplified for continuous compounding: A = Pe^(rt)
-        let e_rt = (rate * time).exp();
-        principal * e_rt
-    }
-
-    /// Update market rates
-    async fn update_market_rates(&mut self, token: &Token) -> DeFiResult<()> {
-        if let Some(market) = self.markets.get_mut(&token.symbol) {
-            // Mock rate calculation based on utilization
-            let utilization = market.utilization_rate;
-            
-            // Calculate supply rate
-            let borrow_rate = if utilization <= self.config.interest_rate_model.optimal_utilization {
-                self.config.interest_rate_model.base_rate + 
-                (utilization * self.config.interest_rate_model.multiplier / self.config.interest_rate_model.optimal_utilization)
-            } else {
-                let excess_utilization = utilization - self.config.interest_rate_model.optimal_utilization;
-                self.config.interest_rate_model.base_rate + 
-                self.config.interest_rate_model.multiplier +
-                (excess_utilization * self.config.interest_rate_model.jump_multiplier / 
-                 (Decimal::ONE - self.config.interest_rate_model.optimal_utilization))
-            };
-            
-            let supply_rate = borrow_rate * utilization * (Decimal::ONE - self.config.reserve_factor);
-            
-            market.supply_rate = supply_rate;
-            market.borrow_rate = borrow_rate;
-            market.last_updated = Utc::now();
-        }
-        
-        Ok(())
-    }
-}
-
-#[async_trait]
-impl LendingService for CompoundProtocol {
-    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        // In real implementation, this would:
-        // 1. Validate the request
-        // 2. Check user's token balance
-        // 3. Approve token transfer
-        // 4. Call compound's supply function
-        // 5. Update user's position
-        
-        Ok(transaction_hash)
-    }
-
-    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        // In real implementation, this would:
-        // 1. Check user's supplied balance
-        // 2. Verify withdrawal doesn't break health factor
-        // 3. Call compound's redeem function
-        // 4. Update user's position
-        
-        Ok(transaction_hash)
-    }
-
-    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        // In real implementation, this would:
-        // 1. Check user's collateral
-        // 2. Verify borrow capacity
-        // 3. Check market liquidity
-        // 4. Call compound's borrow function
-        // 5. Update user's position
-        
-        Ok(transaction_hash)
-    }
-
-    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        // In real implementation, this would:
-        // 1. Check user's debt balance
-        // 2. Approve token transfer
-        // 3. Call compound's repay function
-        // 4. Update user's position
-        
-        Ok(transaction_hash)
-    }
-
-    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        match request.action {
-            CollateralAction::Enable => {
-                // Enable token as collateral
-            },
-            CollateralAction::Disable => {
-                // Disable token as collateral (if safe)
-            },
-            CollateralAction::Withdraw => {
-                // Withdraw collateral (if safe)
-            },
-        }
-        
-        Ok(transaction_hash)
-    }
-
-    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
-        // Mock implementation
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        
-        // In real implementation, this would:
-        // 1. Check if position is liquidatable
-        // 2. Calculate liquidation amounts
-        // 3. Execute liquidation
-        // 4. Transfer collateral to liquidator
-        
-        Ok(transaction_hash)
-    }
-
-    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
-        // Mock implementation
-        Ok(LendingPosition {
-            id: Uuid::new_v4(),
-            user_id: user_id.to_string(),
-            protocol,
-            supplied_assets: HashMap::new(),
-            borrowed_assets: HashMap::new(),
-            collateral_value: Decimal::ZERO,
-            debt_value: Decimal::ZERO,
-            health_factor: Decimal::ONE,
-            ltv_ratio: Decimal::ZERO,
-            liquidation_threshold: self.config.liquidation_threshold,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
-
-    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
-        // Mock implementation
-        Ok(LendingMarket {
-            token: token.clone(),
-            protocol,
-            supply_rate: Decimal::new(5, 2), // 5%
-            borrow_rate: Decimal::new(8, 2), // 8%
-            utilization_rate: Decimal::new(75, 2), // 75%
-            total_supply: Decimal::new(1000000, 0),
-            total_borrow: Decimal::new(750000, 0),
-            available_liquidity: Decimal::new(250000, 0),
-            collateral_factor: self.config.collateral_factor,
-            reserve_factor: self.config.reserve_factor,
-            last_updated: Utc::now(),
-        })
-    }
-
-    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
-        // Mock implementation - return sample markets
-        let tokens = vec![
-            Token { symbol: "ETH".to_string(), address: "0x...".to_string(), decimals: 18 },
-            Token { symbol: "USDC".to_string(), address: "0x...".to_string(), decimals: 6 },
-            Token { symbol: "DAI".to_string(), address: "0x...".to_string(), decimals: 18 },
-        ];
-
-        let mut markets = Vec::new();
-        for token in tokens {
-            markets.push(self.get_market(protocol, &token).await?);
-        }
-
-        Ok(markets)
-    }
-
-    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
-        let position = self.get_position(user_id, protocol).await?;
-        
-        if position.debt_value == Decimal::ZERO {
-            return Ok(Decimal::MAX); // No debt means infinite health factor
-        }
-        
-        // Health factor = (collateral * liquidation threshold) / debt
-        let health_factor = (position.collateral_value * position.liquidation_threshold) / position.debt_value;
-        
-        Ok(health_factor)
-    }
-}
-
-/// Aave protocol implementation
-pub struct AaveProtocol {
-    config: LendingConfig,
-}
-
-impl AaveProtocol {
-    pub fn new(config: LendingConfig) -> Self {
-        Self { config }
-    }
-}
-
-#[async_trait]
-impl LendingService for AaveProtocol {
-    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
-        // Similar to Compound but with Aave-specific logic
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
-        Ok(LendingPosition {
-            id: Uuid::new_v4(),
-            user_id: user_id.to_string(),
-            protocol,
-            supplied_assets: HashMap::new(),
-            borrowed_assets: HashMap::new(),
-            collateral_value: Decimal::ZERO,
-            debt_value: Decimal::ZERO,
-            health_factor: Decimal::ONE,
-            ltv_ratio: Decimal::ZERO,
-            liquidation_threshold: self.config.liquidation_threshold,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
-
-    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
-        Ok(LendingMarket {
-            token: token.clone(),
-            protocol,
-            supply_rate: Decimal::new(4, 2), // 4%
-            borrow_rate: Decimal::new(7, 2), // 7%
-            utilization_rate: Decimal::new(80, 2), // 80%
-            total_supply: Decimal::new(2000000, 0),
-            total_borrow: Decimal::new(1600000, 0),
-            available_liquidity: Decimal::new(400000, 0),
-            collateral_factor: self.config.collateral_factor,
-            reserve_factor: self.config.reserve_factor,
-            last_updated: Utc::now(),
-        })
-    }
-
-    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
-        let tokens = vec![
-            Token { symbol: "ETH".to_string(), address: "0x...".to_string(), decimals: 18 },
-            Token { symbol: "USDC".to_string(), address: "0x...".to_string(), decimals: 6 },
-            Token { symbol: "DAI".to_string(), address: "0x...".to_string(), decimals: 18 },
-        ];
-
-        let mut markets = Vec::new();
-        for token in tokens {
-            markets.push(self.get_market(protocol, &token).await?);
-        }
-
-        Ok(markets)
-    }
-
-    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
-        let position = self.get_position(user_id, protocol).await?;
-        
-        if position.debt_value == Decimal::ZERO {
-            return Ok(Decimal::MAX);
-        }
-        
-        let health_factor = (position.collateral_value * position.liquidation_threshold) / position.debt_value;
-        Ok(health_factor)
-    }
-}
-
-/// MakerDAO protocol implementation (simplified)
-pub struct MakerDAOProtocol {
-    config: LendingConfig,
-}
-
-impl MakerDAOProtocol {
-    pub fn new(config: LendingConfig) -> Self {
-        Self { config }
-    }
-}
-
-#[async_trait]
-impl LendingService for MakerDAOProtocol {
-    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
-        // MakerDAO uses CDP (Collateralized Debt Position) model
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
-        // In MakerDAO, borrowing means minting DAI against collateral
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
-        // Repaying means burning DAI to reduce debt
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
-        let transaction_hash = format!("0x{:x}", rand::random::<u64>());
-        Ok(transaction_hash)
-    }
-
-    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
-        Ok(LendingPosition {
-            id: Uuid::new_v4(),
-            user_id: user_id.to_string(),
-            protocol,
-            supplied_assets: HashMap::new(),
-            borrowed_assets: HashMap::new(),
-            collateral_value: Decimal::ZERO,
-            debt_value: Decimal::ZERO,
-            health_factor: Decimal::ONE,
-            ltv_ratio: Decimal::ZERO,
-            liquidation_threshold: self.config.liquidation_threshold,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        })
-    }
-
-    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
-        Ok(LendingMarket {
-            token: token.clone(),
-            protocol,
-            supply_rate: Decimal::ZERO, // MakerDAO doesn't pay supply interest
-            borrow_rate: Decimal::new(6, 2), // 6% stability fee
-            utilization_rate: Decimal::new(90, 2), // 90%
-            total_supply: Decimal::new(5000000, 0),
-            total_borrow: Decimal::new(4500000, 0),
-            available_liquidity: Decimal::new(500000, 0),
-            collateral_factor: self.config.collateral_factor,
-            reserve_factor: self.config.reserve_factor,
-            last_updated: Utc::now(),
-        })
-    }
-
-    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
-        // MakerDAO primarily deals with ETH and other collateral types
-        let tokens = vec![
-            Token { symbol: "ETH".to_string(), address: "0x...".to_string(), decimals: 18 },
-            Token { symbol: "WBTC".to_string(), address: "0x...".to_string(), decimals: 8 },
-        ];
-
-        let mut markets = Vec::new();
-        for token in tokens {
-            markets.push(self.get_market(protocol, &token).await?);
-        }
-
-        Ok(markets)
-    }
-
-    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
-        let position = self.get_position(user_id, protocol).await?;
-        
-        if position.debt_value == Decimal::ZERO {
-            return Ok(Decimal::MAX);
-        }
-        
-        let health_factor = (position.collateral_value * position.liquidation_threshold) / position.debt_value;
-        Ok(health_factor)
-    }
-}
-
-impl Default for LendingConfig {
-    fn default() -> Self {
-        Self {
-            default_ltv: Decimal::new(75, 2), // 75%
-            liquidation_threshold: Decimal::new(80, 2), // 80%
-            liquidation_penalty: Decimal::new(5, 2), // 5%
-            interest_rate_model: InterestRateModel {
-                base_rate: Decimal::new(2, 2), // 2%
-                multiplier: Decimal::new(10, 2), // 10%
-                jump_multiplier: Decimal::new(100, 2), // 100%
-                optimal_utilization: Decimal::new(80, 2), // 80%
-            },
-            collateral_factor: Decimal::new(75, 2), // 75%
-            reserve_factor: Decimal::new(10, 2), // 10%
-            flash_loan_fee: Decimal::new(9, 4), // 0.09%
-        }
-    }
-}
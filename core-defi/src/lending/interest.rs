@@ -0,0 +1,117 @@
+// =====================================================================================
+// File: core-defi/src/lending/interest.rs
+// Description: Kinked utilization interest rate model and index compounding
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Rates are annual fractions. Borrow interest compounds every second, the
+//! same way Aave's variable borrow index grows between interactions.
+
+use rust_decimal::prelude::*;
+use rust_decimal::MathematicalOps;
+
+use super::InterestRateModel;
+use crate::error::{DeFiError, DeFiResult};
+
+/// Seconds in a 365 day year
+pub const SECONDS_PER_YEAR: i64 = 31_536_000;
+
+impl InterestRateModel {
+    /// Utilization of a market: `borrows / (cash + borrows - reserves)`
+    pub fn utilization(cash: Decimal, borrows: Decimal, reserves: Decimal) -> Decimal {
+        let supplied = cash + borrows - reserves;
+        if borrows <= Decimal::ZERO || supplied <= Decimal::ZERO {
+            return Decimal::ZERO;
+        }
+
+        (borrows / supplied).min(Decimal::ONE)
+    }
+
+    /// Annual borrow rate at the given utilization
+    ///
+    /// Rates grow along `multiplier` up to the optimal utilization and along
+    /// the much steeper `jump_multiplier` past the kink.
+    pub fn borrow_rate(&self, utilization: Decimal) -> Decimal {
+        if utilization <= self.optimal_utilization {
+            if self.optimal_utilization.is_zero() {
+                return self.base_rate;
+            }
+            self.base_rate + utilization * self.multiplier / self.optimal_utilization
+        } else {
+            let excess_utilization = utilization - self.optimal_utilization;
+            self.base_rate
+                + self.multiplier
+                + excess_utilization * self.jump_multiplier / (Decimal::ONE - self.optimal_utilization)
+        }
+    }
+
+    /// Annual supply rate after the protocol takes its reserve factor
+    pub fn supply_rate(&self, utilization: Decimal, reserve_factor: Decimal) -> Decimal {
+        self.borrow_rate(utilization) * utilization * (Decimal::ONE - reserve_factor)
+    }
+}
+
+/// Growth factor of an annual rate compounded every second over `elapsed_seconds`
+pub fn compounded_growth(annual_rate: Decimal, elapsed_seconds: i64) -> DeFiResult<Decimal> {
+    if elapsed_seconds <= 0 || annual_rate.is_zero() {
+        return Ok(Decimal::ONE);
+    }
+    if annual_rate < Decimal::ZERO {
+        return Err(DeFiError::validation_error("annual_rate", "Interest rate cannot be negative"));
+    }
+
+    let rate_per_second = annual_rate / Decimal::from(SECONDS_PER_YEAR);
+    (Decimal::ONE + rate_per_second)
+        .checked_powu(elapsed_seconds as u64)
+        .ok_or_else(|| DeFiError::internal_error("Interest index overflow"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_model() -> InterestRateModel {
+        InterestRateModel {
+            base_rate: Decimal::new(2, 2),
+            multiplier: Decimal::new(10, 2),
+            jump_multiplier: Decimal::new(100, 2),
+            optimal_utilization: Decimal::new(80, 2),
+        }
+    }
+
+    #[test]
+    fn test_borrow_rate_kinks_at_optimal_utilization() {
+        let model = create_model();
+
+        assert_eq!(model.borrow_rate(Decimal::ZERO), Decimal::new(2, 2));
+        assert_eq!(model.borrow_rate(Decimal::new(40, 2)), Decimal::new(7, 2));
+        assert_eq!(model.borrow_rate(Decimal::new(80, 2)), Decimal::new(12, 2));
+        // 10% past the kink adds half of the jump multiplier
+        assert_eq!(model.borrow_rate(Decimal::new(90, 2)), Decimal::new(62, 2));
+        assert_eq!(model.borrow_rate(Decimal::ONE), Decimal::new(112, 2));
+    }
+
+    #[test]
+    fn test_utilization_and_supply_rate() {
+        let model = create_model();
+
+        let utilization = InterestRateModel::utilization(
+            Decimal::from(300), Decimal::from(800), Decimal::from(100),
+        );
+        assert_eq!(utilization, Decimal::new(8, 1));
+        assert_eq!(InterestRateModel::utilization(Decimal::from(100), Decimal::ZERO, Decimal::ZERO), Decimal::ZERO);
+
+        // 12% borrow rate * 80% utilization * 90% after reserves
+        assert_eq!(model.supply_rate(utilization, Decimal::new(10, 2)), Decimal::new(864, 4));
+    }
+
+    #[test]
+    fn test_compounded_growth_approaches_continuous_compounding() {
+        let growth = compounded_growth(Decimal::new(10, 2), SECONDS_PER_YEAR).unwrap();
+        // e^0.1 = 1.105170918..
+        assert!((growth - Decimal::new(1105170918, 9)).abs() < Decimal::new(1, 8));
+
+        assert_eq!(compounded_growth(Decimal::new(10, 2), 0).unwrap(), Decimal::ONE);
+        assert!(compounded_growth(Decimal::new(-1, 2), 60).is_err());
+    }
+}
@@ -0,0 +1,891 @@
+// =====================================================================================
+// File: core-defi/src/lending/ledger.rs
+// Description: Index-based lending ledger with scaled balances and reserve accrual
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Balances are stored scaled by the market's supply or borrow index at the
+//! time of the last interaction, so accruing interest only touches the two
+//! indexes and every user's balance grows with them.
+//!
+//! Each market keeps `cash + total_debt == total_supply + reserves`: interest
+//! added to the debt is split between suppliers and the protocol reserve
+//! according to the reserve factor.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use super::interest::compounded_growth;
+use super::{InterestRateModel, LendingConfig, LendingMarket, LendingPosition, LendingProtocol};
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Token,
+};
+
+/// Interest bearing state of a single token market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketState {
+    pub token: Token,
+    /// USD price used to value positions
+    pub price: Decimal,
+    /// Underlying held by the market, including reserves
+    pub cash: Decimal,
+    pub scaled_supply: Decimal,
+    pub scaled_debt: Decimal,
+    pub supply_index: Decimal,
+    pub borrow_index: Decimal,
+    /// Interest collected for the protocol, net of written-off debt
+    pub reserves: Decimal,
+    /// Debt written off without being recovered
+    #[serde(default)]
+    pub bad_debt: Decimal,
+    pub last_accrual: DateTime<Utc>,
+}
+
+impl MarketState {
+    pub fn new(token: Token, price: Decimal, now: DateTime<Utc>) -> Self {
+        Self {
+            token,
+            price,
+            cash: Decimal::ZERO,
+            scaled_supply: Decimal::ZERO,
+            scaled_debt: Decimal::ZERO,
+            supply_index: Decimal::ONE,
+            borrow_index: Decimal::ONE,
+            reserves: Decimal::ZERO,
+            bad_debt: Decimal::ZERO,
+            last_accrual: now,
+        }
+    }
+
+    /// Total supplied including accrued interest
+    pub fn total_supply(&self) -> Decimal {
+        self.scaled_supply * self.supply_index
+    }
+
+    /// Total borrowed including accrued interest
+    pub fn total_debt(&self) -> Decimal {
+        self.scaled_debt * self.borrow_index
+    }
+
+    pub fn utilization(&self) -> Decimal {
+        InterestRateModel::utilization(self.cash, self.total_debt(), self.reserves)
+    }
+
+    /// Accrue interest up to `now`
+    ///
+    /// The borrow index compounds at the rate implied by the utilization at
+    /// the start of the period; the reserve factor share of the new interest
+    /// goes to reserves and the rest raises the supply index.
+    pub fn accrue(&mut self, model: &InterestRateModel, reserve_factor: Decimal, now: DateTime<Utc>) -> DeFiResult<()> {
+        let elapsed = (now - self.last_accrual).num_seconds();
+        if elapsed <= 0 {
+            return Ok(());
+        }
+
+        let debt_before = self.total_debt();
+        if debt_before > Decimal::ZERO {
+            let rate = model.borrow_rate(self.utilization());
+            self.borrow_index *= compounded_growth(rate, elapsed)?;
+
+            let interest = self.total_debt() - debt_before;
+            let to_reserves = interest * reserve_factor;
+            let supply_before = self.total_supply();
+            if supply_before > Decimal::ZERO {
+                self.supply_index *= Decimal::ONE + (interest - to_reserves) / supply_before;
+                self.reserves += to_reserves;
+            } else {
+                self.reserves += interest;
+            }
+        }
+
+        self.last_accrual = now;
+        Ok(())
+    }
+}
+
+/// Scaled balances of a single user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserAccount {
+    pub id: Uuid,
+    pub scaled_supply: HashMap<String, Decimal>,
+    pub scaled_debt: HashMap<String, Decimal>,
+    /// Supplied tokens counted as collateral
+    pub collateral: HashSet<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserAccount {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            scaled_supply: HashMap::new(),
+            scaled_debt: HashMap::new(),
+            collateral: HashSet::new(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+/// USD valuation of an account
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccountValues {
+    pub collateral_value: Decimal,
+    /// Maximum debt value allowed by the collateral factor
+    pub borrow_capacity: Decimal,
+    /// Debt value at which the account becomes liquidatable
+    pub liquidation_value: Decimal,
+    pub debt_value: Decimal,
+}
+
+impl AccountValues {
+    /// `liquidation_value / debt_value`, or `Decimal::MAX` without debt
+    pub fn health_factor(&self) -> Decimal {
+        if self.debt_value <= Decimal::ZERO {
+            return Decimal::MAX;
+        }
+        self.liquidation_value / self.debt_value
+    }
+}
+
+/// Result of liquidating part of a position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationOutcome {
+    pub borrower_id: String,
+    pub liquidator_id: String,
+    pub debt_symbol: String,
+    pub collateral_symbol: String,
+    pub debt_repaid: Decimal,
+    pub collateral_seized: Decimal,
+    /// Collateral paid to the liquidator on top of the repaid value
+    pub bonus_collateral: Decimal,
+    /// Debt written off per token once the borrower ran out of collateral
+    pub bad_debt: HashMap<String, Decimal>,
+    pub health_factor_after: Decimal,
+}
+
+/// In-memory ledger shared by the lending protocol implementations
+#[derive(Debug, Clone)]
+pub struct LendingLedger {
+    protocol: LendingProtocol,
+    config: LendingConfig,
+    markets: HashMap<String, MarketState>,
+    accounts: HashMap<String, UserAccount>,
+}
+
+impl LendingLedger {
+    pub fn new(protocol: LendingProtocol, config: LendingConfig) -> Self {
+        Self {
+            protocol,
+            config,
+            markets: HashMap::new(),
+            accounts: HashMap::new(),
+        }
+    }
+
+    pub fn protocol(&self) -> LendingProtocol {
+        self.protocol
+    }
+
+    pub fn config(&self) -> &LendingConfig {
+        &self.config
+    }
+
+    /// Open a market for a token
+    pub fn list_market(&mut self, token: Token, price: Decimal, now: DateTime<Utc>) -> DeFiResult<()> {
+        if price <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("price", "Price must be positive"));
+        }
+        if self.markets.contains_key(&token.symbol) {
+            return Err(DeFiError::AlreadyExists {
+                resource_type: "LendingMarket".to_string(),
+                id: token.symbol,
+            });
+        }
+
+        self.markets.insert(token.symbol.clone(), MarketState::new(token, price, now));
+        Ok(())
+    }
+
+    /// Update the USD price of a market's token
+    pub fn set_price(&mut self, symbol: &str, price: Decimal) -> DeFiResult<()> {
+        if price <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("price", "Price must be positive"));
+        }
+        self.market_mut(symbol)?.price = price;
+        Ok(())
+    }
+
+    /// Accrue interest in every market up to `now`
+    pub fn accrue(&mut self, now: DateTime<Utc>) -> DeFiResult<()> {
+        let model = &self.config.interest_rate_model;
+        for market in self.markets.values_mut() {
+            market.accrue(model, self.config.reserve_factor, now)?;
+        }
+        Ok(())
+    }
+
+    pub fn market_state(&self, symbol: &str) -> DeFiResult<&MarketState> {
+        self.markets
+            .get(symbol)
+            .ok_or_else(|| DeFiError::not_found("LendingMarket", symbol))
+    }
+
+    pub fn account(&self, user_id: &str) -> Option<&UserAccount> {
+        self.accounts.get(user_id)
+    }
+
+    /// Users with an account in the ledger
+    pub fn users(&self) -> impl Iterator<Item = &String> {
+        self.accounts.keys()
+    }
+
+    /// Supplied balance of a user including interest
+    pub fn supplied_balance(&self, user_id: &str, symbol: &str) -> Decimal {
+        match (self.accounts.get(user_id), self.markets.get(symbol)) {
+            (Some(account), Some(market)) => account
+                .scaled_supply
+                .get(symbol)
+                .map(|scaled| *scaled * market.supply_index)
+                .unwrap_or(Decimal::ZERO),
+            _ => Decimal::ZERO,
+        }
+    }
+
+    /// Debt of a user including interest
+    pub fn debt_balance(&self, user_id: &str, symbol: &str) -> Decimal {
+        match (self.accounts.get(user_id), self.markets.get(symbol)) {
+            (Some(account), Some(market)) => account
+                .scaled_debt
+                .get(symbol)
+                .map(|scaled| *scaled * market.borrow_index)
+                .unwrap_or(Decimal::ZERO),
+            _ => Decimal::ZERO,
+        }
+    }
+
+    /// Deposit tokens into a market
+    pub fn supply(
+        &mut self,
+        user_id: &str,
+        symbol: &str,
+        amount: Decimal,
+        as_collateral: bool,
+        now: DateTime<Utc>,
+    ) -> DeFiResult<()> {
+        validate_amount(amount)?;
+        self.accrue(now)?;
+
+        let market = self.market_mut(symbol)?;
+        let scaled = amount / market.supply_index;
+        market.scaled_supply += scaled;
+        market.cash += amount;
+
+        let account = self
+            .accounts
+            .entry(user_id.to_string())
+            .or_insert_with(|| UserAccount::new(now));
+        *account.scaled_supply.entry(symbol.to_string()).or_insert(Decimal::ZERO) += scaled;
+        if as_collateral {
+            account.collateral.insert(symbol.to_string());
+        }
+        account.updated_at = now;
+
+        Ok(())
+    }
+
+    /// Withdraw supplied tokens, keeping the account within its borrow capacity
+    pub fn withdraw(&mut self, user_id: &str, symbol: &str, amount: Decimal, now: DateTime<Utc>) -> DeFiResult<()> {
+        validate_amount(amount)?;
+        self.accrue(now)?;
+
+        let balance = self.supplied_balance(user_id, symbol);
+        if amount > balance {
+            return Err(DeFiError::insufficient_balance(amount.to_string(), balance.to_string()));
+        }
+        let market = self.market_state(symbol)?;
+        if amount > market.cash {
+            return Err(DeFiError::insufficient_liquidity(format!("{} market has {} available", symbol, market.cash)));
+        }
+
+        self.with_solvency_check(user_id, |ledger| {
+            let scaled = ledger.debit_supply(user_id, symbol, amount, now)?;
+            let market = ledger.market_mut(symbol)?;
+            market.scaled_supply -= scaled;
+            market.cash -= amount;
+            Ok(())
+        })
+    }
+
+    /// Borrow tokens against the account's collateral
+    pub fn borrow(&mut self, user_id: &str, symbol: &str, amount: Decimal, now: DateTime<Utc>) -> DeFiResult<()> {
+        validate_amount(amount)?;
+        self.accrue(now)?;
+
+        let market = self.market_state(symbol)?;
+        if amount > market.cash {
+            return Err(DeFiError::insufficient_liquidity(format!("{} market has {} available", symbol, market.cash)));
+        }
+        self.account_mut(user_id)?;
+
+        self.with_solvency_check(user_id, |ledger| {
+            let market = ledger.market_mut(symbol)?;
+            let scaled = amount / market.borrow_index;
+            market.scaled_debt += scaled;
+            market.cash -= amount;
+
+            let account = ledger.account_mut(user_id)?;
+            *account.scaled_debt.entry(symbol.to_string()).or_insert(Decimal::ZERO) += scaled;
+            account.updated_at = now;
+            Ok(())
+        })
+    }
+
+    /// Repay debt, capped at the outstanding balance
+    ///
+    /// Returns the amount actually repaid.
+    pub fn repay(&mut self, user_id: &str, symbol: &str, amount: Decimal, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        validate_amount(amount)?;
+        self.accrue(now)?;
+
+        let debt = self.debt_balance(user_id, symbol);
+        if debt <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "No outstanding debt to repay"));
+        }
+
+        let repaid = amount.min(debt);
+        let scaled = self.debit_debt(user_id, symbol, repaid, now)?;
+        let market = self.market_mut(symbol)?;
+        market.scaled_debt -= scaled;
+        market.cash += repaid;
+
+        Ok(repaid)
+    }
+
+    /// Enable or disable a supplied token as collateral
+    pub fn set_collateral(&mut self, user_id: &str, symbol: &str, enabled: bool, now: DateTime<Utc>) -> DeFiResult<()> {
+        self.accrue(now)?;
+        self.market_state(symbol)?;
+
+        if enabled {
+            if self.supplied_balance(user_id, symbol) <= Decimal::ZERO {
+                return Err(DeFiError::CollateralError {
+                    message: format!("No {} supplied to enable as collateral", symbol),
+                });
+            }
+            let account = self.account_mut(user_id)?;
+            account.collateral.insert(symbol.to_string());
+            account.updated_at = now;
+            return Ok(());
+        }
+
+        self.account_mut(user_id)?;
+        self.with_solvency_check(user_id, |ledger| {
+            let account = ledger.account_mut(user_id)?;
+            account.collateral.remove(symbol);
+            account.updated_at = now;
+            Ok(())
+        })
+    }
+
+    /// Repay part of an unhealthy borrower's debt in exchange for collateral plus the liquidation bonus
+    ///
+    /// Repayment is capped by the close factor and by the collateral available.
+    /// With `receive_collateral` the liquidator is credited the supplied
+    /// balance, otherwise the underlying leaves the market. Debt left once the
+    /// borrower's collateral is exhausted is written off against reserves.
+    #[allow(clippy::too_many_arguments)]
+    pub fn liquidate(
+        &mut self,
+        liquidator_id: &str,
+        borrower_id: &str,
+        debt_symbol: &str,
+        collateral_symbol: &str,
+        amount: Decimal,
+        receive_collateral: bool,
+        now: DateTime<Utc>,
+    ) -> DeFiResult<LiquidationOutcome> {
+        validate_amount(amount)?;
+        if liquidator_id == borrower_id {
+            return Err(DeFiError::LiquidationError {
+                message: "Borrowers cannot liquidate themselves".to_string(),
+            });
+        }
+        self.accrue(now)?;
+
+        let health_factor = self.health_factor(borrower_id);
+        if health_factor >= Decimal::ONE {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Position is healthy with health factor {}", health_factor),
+            });
+        }
+
+        let debt = self.debt_balance(borrower_id, debt_symbol);
+        if debt <= Decimal::ZERO {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Borrower has no {} debt", debt_symbol),
+            });
+        }
+        let is_collateral = self
+            .accounts
+            .get(borrower_id)
+            .map(|account| account.collateral.contains(collateral_symbol))
+            .unwrap_or(false);
+        let collateral_balance = self.supplied_balance(borrower_id, collateral_symbol);
+        if !is_collateral || collateral_balance <= Decimal::ZERO {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Borrower has no {} collateral", collateral_symbol),
+            });
+        }
+
+        let debt_price = self.market_state(debt_symbol)?.price;
+        let collateral_price = self.market_state(collateral_symbol)?.price;
+        let bonus = Decimal::ONE + self.config.liquidation_penalty;
+
+        let mut repaid = amount.min(debt * self.config.close_factor);
+        let mut seized = repaid * debt_price * bonus / collateral_price;
+        if seized > collateral_balance {
+            seized = collateral_balance;
+            repaid = seized * collateral_price / (debt_price * bonus);
+        }
+        let collateral_market = self.market_state(collateral_symbol)?;
+        if !receive_collateral && seized > collateral_market.cash {
+            return Err(DeFiError::insufficient_liquidity(format!(
+                "{} market has {} available",
+                collateral_symbol, collateral_market.cash
+            )));
+        }
+
+        let scaled_debt = self.debit_debt(borrower_id, debt_symbol, repaid, now)?;
+        let market = self.market_mut(debt_symbol)?;
+        market.scaled_debt -= scaled_debt;
+        market.cash += repaid;
+
+        let scaled_collateral = self.debit_supply(borrower_id, collateral_symbol, seized, now)?;
+        if receive_collateral {
+            let account = self
+                .accounts
+                .entry(liquidator_id.to_string())
+                .or_insert_with(|| UserAccount::new(now));
+            *account.scaled_supply.entry(collateral_symbol.to_string()).or_insert(Decimal::ZERO) += scaled_collateral;
+            account.updated_at = now;
+        } else {
+            let market = self.market_mut(collateral_symbol)?;
+            market.scaled_supply -= scaled_collateral;
+            market.cash -= seized;
+        }
+
+        let mut bad_debt = HashMap::new();
+        if self.account_values(borrower_id).collateral_value <= Decimal::ZERO {
+            let symbols: Vec<String> = self
+                .accounts
+                .get(borrower_id)
+                .map(|account| account.scaled_debt.keys().cloned().collect())
+                .unwrap_or_default();
+            for symbol in symbols {
+                let written_off = self.write_off_debt(borrower_id, &symbol, now)?;
+                self.market_mut(&symbol)?.bad_debt += written_off;
+                bad_debt.insert(symbol, written_off);
+            }
+        }
+
+        Ok(LiquidationOutcome {
+            borrower_id: borrower_id.to_string(),
+            liquidator_id: liquidator_id.to_string(),
+            debt_symbol: debt_symbol.to_string(),
+            collateral_symbol: collateral_symbol.to_string(),
+            debt_repaid: repaid,
+            collateral_seized: seized,
+            bonus_collateral: seized - repaid * debt_price / collateral_price,
+            bad_debt,
+            health_factor_after: self.health_factor(borrower_id),
+        })
+    }
+
+    /// Remove all of a borrower's supplied balance in a market so it can be auctioned
+    ///
+    /// Returns the amount of underlying taken out of the market.
+    pub fn seize_collateral(&mut self, borrower_id: &str, symbol: &str, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        self.accrue(now)?;
+
+        let balance = self.supplied_balance(borrower_id, symbol);
+        if balance <= Decimal::ZERO {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Borrower has no {} collateral", symbol),
+            });
+        }
+        let market = self.market_state(symbol)?;
+        if balance > market.cash {
+            return Err(DeFiError::insufficient_liquidity(format!("{} market has {} available", symbol, market.cash)));
+        }
+
+        let scaled = self.debit_supply(borrower_id, symbol, balance, now)?;
+        let market = self.market_mut(symbol)?;
+        market.scaled_supply -= scaled;
+        market.cash -= balance;
+
+        Ok(balance)
+    }
+
+    /// Remove a borrower's debt in a market and charge it to the market's reserves
+    ///
+    /// Reserves may go negative, recording a deficit until the debt is
+    /// recovered with [`LendingLedger::recover_debt`].
+    pub fn write_off_debt(&mut self, borrower_id: &str, symbol: &str, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        self.accrue(now)?;
+
+        let debt = self.debt_balance(borrower_id, symbol);
+        if debt <= Decimal::ZERO {
+            return Ok(Decimal::ZERO);
+        }
+
+        let scaled = self.debit_debt(borrower_id, symbol, debt, now)?;
+        let market = self.market_mut(symbol)?;
+        market.scaled_debt -= scaled;
+        market.reserves -= debt;
+
+        Ok(debt)
+    }
+
+    /// Credit repayment of written-off debt raised outside the market
+    pub fn recover_debt(&mut self, symbol: &str, amount: Decimal) -> DeFiResult<()> {
+        validate_amount(amount)?;
+        let market = self.market_mut(symbol)?;
+        market.cash += amount;
+        market.reserves += amount;
+        Ok(())
+    }
+
+    /// Record written-off debt that was never recovered
+    pub fn record_bad_debt(&mut self, symbol: &str, amount: Decimal) -> DeFiResult<()> {
+        self.market_mut(symbol)?.bad_debt += amount;
+        Ok(())
+    }
+
+    /// USD valuation of a user's collateral and debt
+    pub fn account_values(&self, user_id: &str) -> AccountValues {
+        let mut values = AccountValues {
+            collateral_value: Decimal::ZERO,
+            borrow_capacity: Decimal::ZERO,
+            liquidation_value: Decimal::ZERO,
+            debt_value: Decimal::ZERO,
+        };
+
+        let Some(account) = self.accounts.get(user_id) else {
+            return values;
+        };
+
+        for symbol in &account.collateral {
+            if let Some(market) = self.markets.get(symbol) {
+                values.collateral_value += self.supplied_balance(user_id, symbol) * market.price;
+            }
+        }
+        for symbol in account.scaled_debt.keys() {
+            if let Some(market) = self.markets.get(symbol) {
+                values.debt_value += self.debt_balance(user_id, symbol) * market.price;
+            }
+        }
+        values.borrow_capacity = values.collateral_value * self.config.collateral_factor;
+        values.liquidation_value = values.collateral_value * self.config.liquidation_threshold;
+
+        values
+    }
+
+    pub fn health_factor(&self, user_id: &str) -> Decimal {
+        self.account_values(user_id).health_factor()
+    }
+
+    /// Snapshot of a user's position
+    pub fn position(&self, user_id: &str) -> DeFiResult<LendingPosition> {
+        let account = self
+            .accounts
+            .get(user_id)
+            .ok_or_else(|| DeFiError::not_found("LendingPosition", user_id))?;
+
+        let supplied_assets = account
+            .scaled_supply
+            .keys()
+            .map(|symbol| (symbol.clone(), self.supplied_balance(user_id, symbol)))
+            .collect();
+        let borrowed_assets = account
+            .scaled_debt
+            .keys()
+            .map(|symbol| (symbol.clone(), self.debt_balance(user_id, symbol)))
+            .collect();
+
+        let values = self.account_values(user_id);
+        let ltv_ratio = if values.collateral_value > Decimal::ZERO {
+            values.debt_value / values.collateral_value
+        } else {
+            Decimal::ZERO
+        };
+
+        Ok(LendingPosition {
+            id: account.id,
+            user_id: user_id.to_string(),
+            protocol: self.protocol,
+            supplied_assets,
+            borrowed_assets,
+            collateral_value: values.collateral_value,
+            debt_value: values.debt_value,
+            health_factor: values.health_factor(),
+            ltv_ratio,
+            liquidation_threshold: self.config.liquidation_threshold,
+            created_at: account.created_at,
+            updated_at: account.updated_at,
+        })
+    }
+
+    /// Public market data for a token
+    pub fn market(&self, symbol: &str) -> DeFiResult<LendingMarket> {
+        let market = self.market_state(symbol)?;
+        let model = &self.config.interest_rate_model;
+        let utilization = market.utilization();
+
+        Ok(LendingMarket {
+            token: market.token.clone(),
+            protocol: self.protocol,
+            supply_rate: model.supply_rate(utilization, self.config.reserve_factor),
+            borrow_rate: model.borrow_rate(utilization),
+            utilization_rate: utilization,
+            total_supply: market.total_supply(),
+            total_borrow: market.total_debt(),
+            available_liquidity: market.cash,
+            collateral_factor: self.config.collateral_factor,
+            reserve_factor: self.config.reserve_factor,
+            last_updated: market.last_accrual,
+        })
+    }
+
+    pub fn markets(&self) -> Vec<LendingMarket> {
+        let mut symbols: Vec<&String> = self.markets.keys().collect();
+        symbols.sort();
+        symbols.into_iter().filter_map(|symbol| self.market(symbol).ok()).collect()
+    }
+
+    /// Apply `change` and roll it back if it leaves the account above its borrow capacity
+    fn with_solvency_check<F>(&mut self, user_id: &str, change: F) -> DeFiResult<()>
+    where
+        F: FnOnce(&mut Self) -> DeFiResult<()>,
+    {
+        let markets = self.markets.clone();
+        let account = self.accounts.get(user_id).cloned();
+
+        let result = change(self).and_then(|_| {
+            let values = self.account_values(user_id);
+            if values.debt_value > values.borrow_capacity {
+                return Err(DeFiError::CollateralError {
+                    message: format!(
+                        "Debt value {} would exceed borrow capacity {}",
+                        values.debt_value, values.borrow_capacity
+                    ),
+                });
+            }
+            Ok(())
+        });
+
+        if result.is_err() {
+            self.markets = markets;
+            if let Some(account) = account {
+                self.accounts.insert(user_id.to_string(), account);
+            }
+        }
+        result
+    }
+
+    /// Remove `amount` from a user's supplied balance and return the scaled amount removed
+    ///
+    /// Taking the whole balance clears the entry so no dust is left behind.
+    fn debit_supply(&mut self, user_id: &str, symbol: &str, amount: Decimal, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        let balance = self.supplied_balance(user_id, symbol);
+        let supply_index = self.market_state(symbol)?.supply_index;
+        let account = self.account_mut(user_id)?;
+
+        let scaled = if amount >= balance {
+            account.collateral.remove(symbol);
+            account.scaled_supply.remove(symbol).unwrap_or(Decimal::ZERO)
+        } else {
+            let scaled = amount / supply_index;
+            *account.scaled_supply.entry(symbol.to_string()).or_insert(Decimal::ZERO) -= scaled;
+            scaled
+        };
+        account.updated_at = now;
+
+        Ok(scaled)
+    }
+
+    /// Remove `amount` from a user's debt and return the scaled amount removed
+    fn debit_debt(&mut self, user_id: &str, symbol: &str, amount: Decimal, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        let debt = self.debt_balance(user_id, symbol);
+        let borrow_index = self.market_state(symbol)?.borrow_index;
+        let account = self.account_mut(user_id)?;
+
+        let scaled = if amount >= debt {
+            account.scaled_debt.remove(symbol).unwrap_or(Decimal::ZERO)
+        } else {
+            let scaled = amount / borrow_index;
+            *account.scaled_debt.entry(symbol.to_string()).or_insert(Decimal::ZERO) -= scaled;
+            scaled
+        };
+        account.updated_at = now;
+
+        Ok(scaled)
+    }
+
+    fn market_mut(&mut self, symbol: &str) -> DeFiResult<&mut MarketState> {
+        self.markets
+            .get_mut(symbol)
+            .ok_or_else(|| DeFiError::not_found("LendingMarket", symbol))
+    }
+
+    fn account_mut(&mut self, user_id: &str) -> DeFiResult<&mut UserAccount> {
+        self.accounts
+            .get_mut(user_id)
+            .ok_or_else(|| DeFiError::not_found("LendingPosition", user_id))
+    }
+}
+
+fn validate_amount(amount: Decimal) -> DeFiResult<()> {
+    if amount <= Decimal::ZERO {
+        return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lending::interest::SECONDS_PER_YEAR;
+    use chrono::Duration;
+    use proptest::prelude::*;
+
+    fn create_ledger(now: DateTime<Utc>) -> LendingLedger {
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        let usdc = Token::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(), "USDC".to_string(), "USD Coin".to_string(), 6, 1);
+
+        let mut ledger = LendingLedger::new(LendingProtocol::Compound, LendingConfig::default());
+        ledger.list_market(weth, Decimal::from(2000), now).unwrap();
+        ledger.list_market(usdc, Decimal::ONE, now).unwrap();
+        ledger
+    }
+
+    #[test]
+    fn test_interest_accrues_to_borrowers_suppliers_and_reserves() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+
+        ledger.supply("lender", "USDC", Decimal::from(1_000_000), false, now).unwrap();
+        ledger.supply("borrower", "WETH", Decimal::from(1000), true, now).unwrap();
+        ledger.borrow("borrower", "USDC", Decimal::from(800_000), now).unwrap();
+
+        // 80% utilization sits on the kink: 12% borrow rate
+        let market = ledger.market("USDC").unwrap();
+        assert_eq!(market.borrow_rate, Decimal::new(12, 2));
+        assert_eq!(market.supply_rate, Decimal::new(864, 4));
+
+        ledger.accrue(now + Duration::seconds(SECONDS_PER_YEAR)).unwrap();
+
+        // One year at 12% compounded every second: e^0.12 = 1.127496851..
+        let debt = ledger.debt_balance("borrower", "USDC");
+        assert!((debt - Decimal::new(901997481, 3)).abs() < Decimal::ONE);
+
+        let state = ledger.market_state("USDC").unwrap();
+        let interest = debt - Decimal::from(800_000);
+        assert!((state.reserves - interest / Decimal::from(10)).abs() < Decimal::new(1, 12));
+        assert!((ledger.supplied_balance("lender", "USDC") - Decimal::from(1_000_000) - interest * Decimal::new(9, 1)).abs() < Decimal::new(1, 12));
+        assert!((state.cash + state.total_debt() - state.total_supply() - state.reserves).abs() < Decimal::new(1, 12));
+    }
+
+    #[test]
+    fn test_borrow_and_withdraw_respect_collateral() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+
+        ledger.supply("lender", "USDC", Decimal::from(1_000_000), false, now).unwrap();
+        ledger.supply("borrower", "WETH", Decimal::from(10), true, now).unwrap();
+
+        // 20,000 USD of collateral at a 75% collateral factor
+        assert!(ledger.borrow("borrower", "USDC", Decimal::from(15_001), now).is_err());
+        ledger.borrow("borrower", "USDC", Decimal::from(15_000), now).unwrap();
+        assert!(ledger.withdraw("borrower", "WETH", Decimal::ONE, now).is_err());
+        assert!(ledger.set_collateral("borrower", "WETH", false, now).is_err());
+        assert_eq!(ledger.supplied_balance("borrower", "WETH"), Decimal::from(10));
+
+        // 16,000 * 0.8 / 15,000
+        let position = ledger.position("borrower").unwrap();
+        assert_eq!(position.collateral_value, Decimal::from(20_000));
+        assert_eq!(position.ltv_ratio, Decimal::new(75, 2));
+        assert!((position.health_factor - Decimal::from(16) / Decimal::from(15)).abs() < Decimal::new(1, 20));
+
+        // A price drop pushes the health factor below one
+        ledger.set_price("WETH", Decimal::from(1800)).unwrap();
+        assert!(ledger.health_factor("borrower") < Decimal::ONE);
+
+        // Repaying more than owed only clears the debt
+        let repaid = ledger.repay("borrower", "USDC", Decimal::from(20_000), now).unwrap();
+        assert_eq!(repaid, Decimal::from(15_000));
+        assert_eq!(ledger.health_factor("borrower"), Decimal::MAX);
+        ledger.withdraw("borrower", "WETH", Decimal::from(10), now).unwrap();
+        assert_eq!(ledger.market_state("WETH").unwrap().scaled_supply, Decimal::ZERO);
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Supply { user: usize, amount: i64 },
+        Withdraw { user: usize, amount: i64 },
+        Borrow { user: usize, amount: i64 },
+        Repay { user: usize, amount: i64 },
+        Advance { seconds: i64 },
+    }
+
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            (0usize..4, 1i64..1_000_000).prop_map(|(user, amount)| Op::Supply { user, amount }),
+            (0usize..4, 1i64..1_000_000).prop_map(|(user, amount)| Op::Withdraw { user, amount }),
+            (0usize..4, 1i64..1_000_000).prop_map(|(user, amount)| Op::Borrow { user, amount }),
+            (0usize..4, 1i64..1_000_000).prop_map(|(user, amount)| Op::Repay { user, amount }),
+            (1i64..30 * 86_400).prop_map(|seconds| Op::Advance { seconds }),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn prop_total_debt_matches_user_debts(ops in prop::collection::vec(op_strategy(), 1..80)) {
+            let start = Utc::now();
+            let mut now = start;
+            let mut ledger = create_ledger(start);
+            for user in 0..4 {
+                ledger.supply(&format!("user{}", user), "WETH", Decimal::from(500), true, start).unwrap();
+            }
+
+            for op in ops {
+                // Rejected operations must leave the ledger unchanged, so errors are ignored
+                let _ = match op {
+                    Op::Supply { user, amount } => ledger.supply(&format!("user{}", user), "USDC", Decimal::new(amount, 2), false, now),
+                    Op::Withdraw { user, amount } => ledger.withdraw(&format!("user{}", user), "USDC", Decimal::new(amount, 2), now),
+                    Op::Borrow { user, amount } => ledger.borrow(&format!("user{}", user), "USDC", Decimal::new(amount, 2), now),
+                    Op::Repay { user, amount } => ledger.repay(&format!("user{}", user), "USDC", Decimal::new(amount, 2), now).map(|_| ()),
+                    Op::Advance { seconds } => {
+                        now += Duration::seconds(seconds);
+                        ledger.accrue(now)
+                    }
+                };
+            }
+
+            let state = ledger.market_state("USDC").unwrap();
+            let user_debt: Decimal = (0..4).map(|user| ledger.debt_balance(&format!("user{}", user), "USDC")).sum();
+            let user_supply: Decimal = (0..4).map(|user| ledger.supplied_balance(&format!("user{}", user), "USDC")).sum();
+            let tolerance = Decimal::new(1, 12);
+
+            prop_assert!((state.total_debt() - user_debt).abs() < tolerance);
+            prop_assert!((state.total_supply() - user_supply).abs() < tolerance);
+            prop_assert!((state.cash + state.total_debt() - state.total_supply() - state.reserves).abs() < tolerance);
+            prop_assert!(state.cash >= Decimal::ZERO);
+        }
+    }
+}
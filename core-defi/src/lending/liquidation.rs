@@ -0,0 +1,639 @@
+// =====================================================================================
+// File: core-defi/src/lending/liquidation.rs
+// Description: Liquidation keeper with fixed-bonus and Dutch auction liquidations
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! The keeper scans a [`LendingLedger`] for positions with a health factor
+//! below one, most unhealthy first. Liquid collateral is liquidated at the
+//! close factor and liquidation bonus; illiquid collateral such as tokenized
+//! real-world assets is sold through a descending-price auction modelled on
+//! MakerDAO's clipper, where the borrower's debt is written off against
+//! reserves when the auction starts and recovered from the proceeds.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::*;
+use rust_decimal::MathematicalOps;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+use super::ledger::{LendingLedger, LiquidationOutcome};
+use crate::error::{DeFiError, DeFiResult};
+
+/// Dutch auction parameters, named after their clipper counterparts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionConfig {
+    /// Starting price as a multiple of the oracle price (`buf`)
+    pub start_multiplier: Decimal,
+    /// Price multiplier applied at every step (`cut`)
+    pub step_decay: Decimal,
+    /// Seconds between price steps (`step`)
+    pub step_seconds: i64,
+    /// Age after which an auction must be reset (`tail`)
+    pub max_duration_seconds: i64,
+    /// Share of the starting price below which an auction must be reset (`cusp`)
+    pub reset_threshold: Decimal,
+}
+
+/// Liquidation keeper configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeeperConfig {
+    /// Collateral tokens sold through auctions instead of at a fixed bonus
+    pub auction_collateral: HashSet<String>,
+    /// Maximum number of positions liquidated per run
+    pub max_liquidations_per_run: usize,
+    pub auction: AuctionConfig,
+}
+
+impl KeeperConfig {
+    pub fn with_auction_collateral<S: Into<String>>(mut self, symbol: S) -> Self {
+        self.auction_collateral.insert(symbol.into());
+        self
+    }
+}
+
+/// Position eligible for liquidation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationCandidate {
+    pub user_id: String,
+    pub health_factor: Decimal,
+    pub collateral_value: Decimal,
+    pub debt_value: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuctionStatus {
+    Active,
+    Completed,
+}
+
+/// Collateral auction for an unhealthy position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Auction {
+    pub id: Uuid,
+    pub borrower_id: String,
+    pub collateral_symbol: String,
+    pub debt_symbol: String,
+    /// Collateral left to sell
+    pub lot: Decimal,
+    /// Debt plus liquidation penalty left to raise
+    pub tab: Decimal,
+    /// Debt written off when the auction started
+    pub debt: Decimal,
+    pub raised: Decimal,
+    /// Starting price in debt tokens per unit of collateral
+    pub top: Decimal,
+    pub started_at: DateTime<Utc>,
+    pub reset_count: u32,
+    pub status: AuctionStatus,
+}
+
+impl Auction {
+    /// Current price following the stairstep exponential decrease
+    pub fn price(&self, config: &AuctionConfig, now: DateTime<Utc>) -> DeFiResult<Decimal> {
+        let elapsed = (now - self.started_at).num_seconds().max(0);
+        let steps = if config.step_seconds > 0 { elapsed / config.step_seconds } else { 0 };
+        let decay = config
+            .step_decay
+            .checked_powu(steps as u64)
+            .ok_or_else(|| DeFiError::internal_error("Auction price overflow"))?;
+        Ok(self.top * decay)
+    }
+
+    /// Whether the auction is too old or too cheap and must restart from the oracle price
+    pub fn needs_reset(&self, config: &AuctionConfig, now: DateTime<Utc>) -> DeFiResult<bool> {
+        if (now - self.started_at).num_seconds() > config.max_duration_seconds {
+            return Ok(true);
+        }
+        Ok(self.price(config, now)? < self.top * config.reset_threshold)
+    }
+}
+
+/// Result of buying collateral from an auction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionTake {
+    pub auction_id: Uuid,
+    pub buyer_id: String,
+    pub price: Decimal,
+    pub collateral_bought: Decimal,
+    pub paid: Decimal,
+    /// Unsold collateral returned to the borrower once the tab is covered
+    pub collateral_returned: Decimal,
+    /// Written-off debt the auction failed to recover
+    pub bad_debt: Decimal,
+    pub completed: bool,
+}
+
+/// Outcome of a keeper run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeeperReport {
+    pub liquidations: Vec<LiquidationOutcome>,
+    pub auctions_started: Vec<Uuid>,
+    pub auctions_reset: Vec<Uuid>,
+    /// Candidates that could not be liquidated, with the reason
+    pub skipped: HashMap<String, String>,
+}
+
+/// Liquidation keeper and auction house for a lending ledger
+pub struct LiquidationKeeper {
+    config: KeeperConfig,
+    auctions: HashMap<Uuid, Auction>,
+    /// Liquidator balances available to repay borrowers' debt, by symbol
+    funds: HashMap<String, Decimal>,
+    /// Bidder balances available to pay for auctioned collateral, by bidder and symbol
+    bidder_funds: HashMap<(String, String), Decimal>,
+}
+
+impl LiquidationKeeper {
+    pub fn new(config: KeeperConfig) -> Self {
+        Self {
+            config,
+            auctions: HashMap::new(),
+            funds: HashMap::new(),
+            bidder_funds: HashMap::new(),
+        }
+    }
+
+    /// Add repay funds for fixed-bonus liquidations
+    pub fn fund(&mut self, symbol: &str, amount: Decimal) -> DeFiResult<()> {
+        if amount <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+        }
+        *self.funds.entry(symbol.to_string()).or_insert(Decimal::ZERO) += amount;
+        Ok(())
+    }
+
+    pub fn available_funds(&self, symbol: &str) -> Decimal {
+        self.funds.get(symbol).copied().unwrap_or(Decimal::ZERO)
+    }
+
+    /// Add a bidder's funds for buying auctioned collateral
+    pub fn fund_bidder(&mut self, bidder_id: &str, symbol: &str, amount: Decimal) -> DeFiResult<()> {
+        if amount <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("amount", "Amount must be positive"));
+        }
+        *self
+            .bidder_funds
+            .entry((bidder_id.to_string(), symbol.to_string()))
+            .or_insert(Decimal::ZERO) += amount;
+        Ok(())
+    }
+
+    pub fn available_bidder_funds(&self, bidder_id: &str, symbol: &str) -> Decimal {
+        self.bidder_funds
+            .get(&(bidder_id.to_string(), symbol.to_string()))
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    pub fn auction(&self, id: &Uuid) -> Option<&Auction> {
+        self.auctions.get(id)
+    }
+
+    pub fn active_auctions(&self) -> Vec<&Auction> {
+        self.auctions
+            .values()
+            .filter(|auction| auction.status == AuctionStatus::Active)
+            .collect()
+    }
+
+    /// Positions with a health factor below one, most unhealthy first
+    pub fn scan(&self, ledger: &LendingLedger) -> Vec<LiquidationCandidate> {
+        let mut candidates: Vec<LiquidationCandidate> = ledger
+            .users()
+            .filter_map(|user_id| {
+                let values = ledger.account_values(user_id);
+                let health_factor = values.health_factor();
+                (health_factor < Decimal::ONE).then(|| LiquidationCandidate {
+                    user_id: user_id.clone(),
+                    health_factor,
+                    collateral_value: values.collateral_value,
+                    debt_value: values.debt_value,
+                })
+            })
+            .collect();
+
+        candidates.sort_by(|a, b| a.health_factor.cmp(&b.health_factor).then_with(|| a.user_id.cmp(&b.user_id)));
+        candidates
+    }
+
+    /// Reset stale auctions, then liquidate unhealthy positions
+    ///
+    /// Each position is liquidated against its largest debt and largest
+    /// collateral; the liquidator is credited the seized supplied balance.
+    /// Repayments are capped by and debited from the keeper's funds, so a
+    /// position is skipped when there is nothing to repay its debt with.
+    pub fn run(&mut self, ledger: &mut LendingLedger, liquidator_id: &str, now: DateTime<Utc>) -> DeFiResult<KeeperReport> {
+        ledger.accrue(now)?;
+        let mut report = KeeperReport::default();
+
+        let stale: Vec<Uuid> = self
+            .auctions
+            .values()
+            .filter(|auction| auction.status == AuctionStatus::Active)
+            .filter(|auction| auction.needs_reset(&self.config.auction, now).unwrap_or(false))
+            .map(|auction| auction.id)
+            .collect();
+        for id in stale {
+            self.reset(ledger, &id, now)?;
+            report.auctions_reset.push(id);
+        }
+
+        let candidates = self.scan(ledger);
+        for candidate in candidates.into_iter().take(self.config.max_liquidations_per_run) {
+            let Some((debt_symbol, collateral_symbol)) = largest_exposures(ledger, &candidate.user_id) else {
+                report.skipped.insert(candidate.user_id, "No liquidatable collateral".to_string());
+                continue;
+            };
+
+            if self.config.auction_collateral.contains(&collateral_symbol) {
+                match self.start_auction(ledger, &candidate.user_id, &collateral_symbol, &debt_symbol, now) {
+                    Ok(id) => report.auctions_started.push(id),
+                    Err(error) => {
+                        report.skipped.insert(candidate.user_id, error.to_string());
+                    }
+                }
+                continue;
+            }
+
+            let available = self.available_funds(&debt_symbol);
+            if available <= Decimal::ZERO {
+                report.skipped.insert(candidate.user_id, format!("No {} funds to repay with", debt_symbol));
+                continue;
+            }
+
+            let amount = ledger.debt_balance(&candidate.user_id, &debt_symbol).min(available);
+            match ledger.liquidate(liquidator_id, &candidate.user_id, &debt_symbol, &collateral_symbol, amount, true, now) {
+                Ok(outcome) => {
+                    self.funds.insert(debt_symbol, available - outcome.debt_repaid);
+                    report.liquidations.push(outcome);
+                }
+                Err(error) => {
+                    report.skipped.insert(candidate.user_id, error.to_string());
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Seize a borrower's collateral and write off their debt into a new auction
+    pub fn start_auction(
+        &mut self,
+        ledger: &mut LendingLedger,
+        borrower_id: &str,
+        collateral_symbol: &str,
+        debt_symbol: &str,
+        now: DateTime<Utc>,
+    ) -> DeFiResult<Uuid> {
+        ledger.accrue(now)?;
+
+        let health_factor = ledger.health_factor(borrower_id);
+        if health_factor >= Decimal::ONE {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Position is healthy with health factor {}", health_factor),
+            });
+        }
+        if ledger.debt_balance(borrower_id, debt_symbol) <= Decimal::ZERO {
+            return Err(DeFiError::LiquidationError {
+                message: format!("Borrower has no {} debt", debt_symbol),
+            });
+        }
+
+        let top = oracle_price(ledger, collateral_symbol, debt_symbol)? * self.config.auction.start_multiplier;
+        let lot = ledger.seize_collateral(borrower_id, collateral_symbol, now)?;
+        let debt = ledger.write_off_debt(borrower_id, debt_symbol, now)?;
+
+        let auction = Auction {
+            id: Uuid::new_v4(),
+            borrower_id: borrower_id.to_string(),
+            collateral_symbol: collateral_symbol.to_string(),
+            debt_symbol: debt_symbol.to_string(),
+            lot,
+            tab: debt * (Decimal::ONE + ledger.config().liquidation_penalty),
+            debt,
+            raised: Decimal::ZERO,
+            top,
+            started_at: now,
+            reset_count: 0,
+            status: AuctionStatus::Active,
+        };
+        let id = auction.id;
+        self.auctions.insert(id, auction);
+
+        Ok(id)
+    }
+
+    /// Restart an auction from the current oracle price
+    pub fn reset(&mut self, ledger: &LendingLedger, auction_id: &Uuid, now: DateTime<Utc>) -> DeFiResult<()> {
+        let start_multiplier = self.config.auction.start_multiplier;
+        let auction = self.active_auction_mut(auction_id)?;
+        let price = oracle_price(ledger, &auction.collateral_symbol, &auction.debt_symbol)?;
+
+        auction.top = price * start_multiplier;
+        auction.started_at = now;
+        auction.reset_count += 1;
+        Ok(())
+    }
+
+    /// Buy up to `max_collateral` from an auction at no more than `max_price`
+    ///
+    /// The buyer pays from their bidder funds and is credited the collateral
+    /// as a supplied balance; the payment recovers the written-off debt.
+    pub fn take(
+        &mut self,
+        ledger: &mut LendingLedger,
+        auction_id: &Uuid,
+        buyer_id: &str,
+        max_collateral: Decimal,
+        max_price: Decimal,
+        now: DateTime<Utc>,
+    ) -> DeFiResult<AuctionTake> {
+        if max_collateral <= Decimal::ZERO {
+            return Err(DeFiError::validation_error("max_collateral", "Amount must be positive"));
+        }
+
+        let config = self.config.auction.clone();
+        let debt_symbol = self.active_auction_mut(auction_id)?.debt_symbol.clone();
+        let available = self.available_bidder_funds(buyer_id, &debt_symbol);
+        let auction = self.active_auction_mut(auction_id)?;
+        if auction.needs_reset(&config, now)? {
+            return Err(DeFiError::LiquidationError {
+                message: "Auction must be reset before it can be taken".to_string(),
+            });
+        }
+        let price = auction.price(&config, now)?;
+        if price > max_price {
+            return Err(DeFiError::slippage_exceeded(max_price.to_string(), price.to_string()));
+        }
+
+        let mut bought = max_collateral.min(auction.lot);
+        let mut paid = bought * price;
+        if paid >= auction.tab {
+            paid = auction.tab;
+            bought = (paid / price).min(auction.lot);
+        }
+        if paid > available {
+            return Err(DeFiError::insufficient_balance(paid.to_string(), available.to_string()));
+        }
+
+        auction.lot -= bought;
+        auction.tab -= paid;
+        auction.raised += paid;
+        let (collateral_symbol, borrower_id) = (auction.collateral_symbol.clone(), auction.borrower_id.clone());
+
+        let mut collateral_returned = Decimal::ZERO;
+        let mut bad_debt = Decimal::ZERO;
+        if auction.tab <= Decimal::ZERO {
+            collateral_returned = auction.lot;
+            auction.lot = Decimal::ZERO;
+            auction.status = AuctionStatus::Completed;
+        } else if auction.lot <= Decimal::ZERO {
+            bad_debt = (auction.debt - auction.raised).max(Decimal::ZERO);
+            auction.status = AuctionStatus::Completed;
+        }
+        let completed = auction.status == AuctionStatus::Completed;
+
+        self.bidder_funds
+            .insert((buyer_id.to_string(), debt_symbol.clone()), available - paid);
+        if paid > Decimal::ZERO {
+            ledger.recover_debt(&debt_symbol, paid)?;
+        }
+        if bought > Decimal::ZERO {
+            ledger.supply(buyer_id, &collateral_symbol, bought, false, now)?;
+        }
+        if collateral_returned > Decimal::ZERO {
+            ledger.supply(&borrower_id, &collateral_symbol, collateral_returned, true, now)?;
+        }
+        if bad_debt > Decimal::ZERO {
+            ledger.record_bad_debt(&debt_symbol, bad_debt)?;
+        }
+
+        Ok(AuctionTake {
+            auction_id: *auction_id,
+            buyer_id: buyer_id.to_string(),
+            price,
+            collateral_bought: bought,
+            paid,
+            collateral_returned,
+            bad_debt,
+            completed,
+        })
+    }
+
+    fn active_auction_mut(&mut self, auction_id: &Uuid) -> DeFiResult<&mut Auction> {
+        match self.auctions.get_mut(auction_id) {
+            Some(auction) if auction.status == AuctionStatus::Active => Ok(auction),
+            Some(_) => Err(DeFiError::LiquidationError {
+                message: format!("Auction {} is completed", auction_id),
+            }),
+            None => Err(DeFiError::not_found("Auction".to_string(), auction_id.to_string())),
+        }
+    }
+}
+
+/// Oracle price of the collateral in units of the debt token
+fn oracle_price(ledger: &LendingLedger, collateral_symbol: &str, debt_symbol: &str) -> DeFiResult<Decimal> {
+    Ok(ledger.market_state(collateral_symbol)?.price / ledger.market_state(debt_symbol)?.price)
+}
+
+/// Largest debt and largest collateral of a user by value
+fn largest_exposures(ledger: &LendingLedger, user_id: &str) -> Option<(String, String)> {
+    let account = ledger.account(user_id)?;
+    let value_of = |symbol: &String, balance: Decimal| {
+        ledger
+            .market_state(symbol)
+            .map(|market| balance * market.price)
+            .unwrap_or(Decimal::ZERO)
+    };
+
+    let debt = account
+        .scaled_debt
+        .keys()
+        .map(|symbol| (value_of(symbol, ledger.debt_balance(user_id, symbol)), symbol))
+        .max()?;
+    let collateral = account
+        .collateral
+        .iter()
+        .map(|symbol| (value_of(symbol, ledger.supplied_balance(user_id, symbol)), symbol))
+        .filter(|(value, _)| *value > Decimal::ZERO)
+        .max()?;
+
+    Some((debt.1.clone(), collateral.1.clone()))
+}
+
+impl Default for AuctionConfig {
+    fn default() -> Self {
+        Self {
+            start_multiplier: Decimal::new(120, 2), // 120%
+            step_decay: Decimal::new(99, 2), // 1% per step
+            step_seconds: 90,
+            max_duration_seconds: 8400,
+            reset_threshold: Decimal::new(40, 2), // 40%
+        }
+    }
+}
+
+impl Default for KeeperConfig {
+    fn default() -> Self {
+        Self {
+            auction_collateral: HashSet::new(),
+            max_liquidations_per_run: 20,
+            auction: AuctionConfig::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lending::{LendingConfig, LendingProtocol};
+    use crate::types::Token;
+    use chrono::Duration;
+
+    fn create_ledger(now: DateTime<Utc>) -> LendingLedger {
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        let dai = Token::new("0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), "DAI".to_string(), "Dai Stablecoin".to_string(), 18, 1);
+        let rwa = Token::new("0x0000000000000000000000000000000000000b0d".to_string(), "TBILL".to_string(), "Tokenized T-Bill".to_string(), 18, 1);
+
+        let mut ledger = LendingLedger::new(LendingProtocol::Aave, LendingConfig::default());
+        ledger.list_market(weth, Decimal::from(2000), now).unwrap();
+        ledger.list_market(dai, Decimal::ONE, now).unwrap();
+        ledger.list_market(rwa, Decimal::from(100), now).unwrap();
+        ledger.supply("lender", "DAI", Decimal::from(1_000_000), false, now).unwrap();
+        ledger
+    }
+
+    #[test]
+    fn test_liquidation_applies_close_factor_and_bonus() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+        ledger.supply("borrower", "WETH", Decimal::from(10), true, now).unwrap();
+        ledger.borrow("borrower", "DAI", Decimal::from(15_000), now).unwrap();
+
+        assert!(ledger.liquidate("keeper", "borrower", "DAI", "WETH", Decimal::from(1000), true, now).is_err());
+
+        // 18,000 * 0.8 / 15,000 = 0.96
+        ledger.set_price("WETH", Decimal::from(1800)).unwrap();
+        let outcome = ledger.liquidate("keeper", "borrower", "DAI", "WETH", Decimal::from(10_000), false, now).unwrap();
+
+        // Half the debt at most, paid for with a 5% bonus
+        assert_eq!(outcome.debt_repaid, Decimal::from(7500));
+        assert_eq!(outcome.collateral_seized, Decimal::new(4375, 3));
+        assert!((outcome.bonus_collateral - Decimal::new(208333, 6)).abs() < Decimal::new(1, 6));
+        assert!(outcome.bad_debt.is_empty());
+        assert_eq!(outcome.health_factor_after, Decimal::new(108, 2));
+        assert_eq!(ledger.debt_balance("borrower", "DAI"), Decimal::from(7500));
+        assert_eq!(ledger.market_state("WETH").unwrap().cash, Decimal::new(5625, 3));
+    }
+
+    #[test]
+    fn test_keeper_liquidates_worst_positions_and_records_bad_debt() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+        ledger.supply("healthy", "WETH", Decimal::from(100), true, now).unwrap();
+        ledger.borrow("healthy", "DAI", Decimal::from(10_000), now).unwrap();
+        ledger.supply("risky", "WETH", Decimal::from(10), true, now).unwrap();
+        ledger.borrow("risky", "DAI", Decimal::from(15_000), now).unwrap();
+
+        let mut keeper = LiquidationKeeper::new(KeeperConfig::default());
+        keeper.fund("DAI", Decimal::from(20_000)).unwrap();
+        assert!(keeper.scan(&ledger).is_empty());
+
+        ledger.set_price("WETH", Decimal::from(1000)).unwrap();
+        let candidates = keeper.scan(&ledger);
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].user_id, "risky");
+
+        let report = keeper.run(&mut ledger, "keeper", now).unwrap();
+        assert_eq!(report.liquidations.len(), 1);
+        assert_eq!(report.liquidations[0].collateral_seized, Decimal::new(7875, 3));
+        assert_eq!(ledger.supplied_balance("keeper", "WETH"), Decimal::new(7875, 3));
+
+        // The second pass runs out of collateral and writes off the rest
+        let report = keeper.run(&mut ledger, "keeper", now).unwrap();
+        let outcome = &report.liquidations[0];
+        assert_eq!(outcome.collateral_seized, Decimal::new(2125, 3));
+        let bad_debt = Decimal::from(7500) - outcome.debt_repaid;
+        assert!((outcome.bad_debt["DAI"] - bad_debt).abs() < Decimal::new(1, 20));
+
+        let market = ledger.market_state("DAI").unwrap();
+        assert_eq!(market.bad_debt, outcome.bad_debt["DAI"]);
+        assert_eq!(market.reserves, -outcome.bad_debt["DAI"]);
+        assert_eq!(ledger.debt_balance("risky", "DAI"), Decimal::ZERO);
+        assert!(keeper.scan(&ledger).is_empty());
+        assert_eq!(keeper.available_funds("DAI"), Decimal::from(12_500) - outcome.debt_repaid);
+    }
+
+    #[test]
+    fn test_keeper_repays_only_from_its_funds() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+        ledger.supply("risky", "WETH", Decimal::from(10), true, now).unwrap();
+        ledger.borrow("risky", "DAI", Decimal::from(15_000), now).unwrap();
+        ledger.set_price("WETH", Decimal::from(1000)).unwrap();
+
+        // Without funds nothing is seized
+        let mut keeper = LiquidationKeeper::new(KeeperConfig::default());
+        let report = keeper.run(&mut ledger, "keeper", now).unwrap();
+        assert!(report.liquidations.is_empty());
+        assert!(report.skipped["risky"].contains("No DAI funds"));
+        assert_eq!(ledger.supplied_balance("keeper", "WETH"), Decimal::ZERO);
+
+        // Repayment is capped at what the keeper holds
+        keeper.fund("DAI", Decimal::from(3000)).unwrap();
+        let report = keeper.run(&mut ledger, "keeper", now).unwrap();
+        assert_eq!(report.liquidations[0].debt_repaid, Decimal::from(3000));
+        assert_eq!(ledger.supplied_balance("keeper", "WETH"), Decimal::new(315, 2));
+        assert_eq!(ledger.debt_balance("risky", "DAI"), Decimal::from(12_000));
+        assert_eq!(keeper.available_funds("DAI"), Decimal::ZERO);
+        assert!(keeper.fund("DAI", Decimal::ZERO).is_err());
+    }
+
+    #[test]
+    fn test_dutch_auction_for_illiquid_collateral() {
+        let now = Utc::now();
+        let mut ledger = create_ledger(now);
+        ledger.supply("fund", "TBILL", Decimal::from(200), true, now).unwrap();
+        ledger.borrow("fund", "DAI", Decimal::from(15_000), now).unwrap();
+        ledger.set_price("TBILL", Decimal::from(90)).unwrap();
+
+        let mut keeper = LiquidationKeeper::new(KeeperConfig::default().with_auction_collateral("TBILL"));
+        let report = keeper.run(&mut ledger, "keeper", now).unwrap();
+        assert!(report.liquidations.is_empty());
+        let id = report.auctions_started[0];
+
+        let auction = keeper.auction(&id).unwrap().clone();
+        assert_eq!(auction.lot, Decimal::from(200));
+        assert_eq!(auction.tab, Decimal::from(15_750));
+        assert_eq!(auction.top, Decimal::from(108));
+        assert!(auction.needs_reset(&AuctionConfig::default(), now + Duration::seconds(9000)).unwrap());
+        assert_eq!(ledger.market_state("DAI").unwrap().reserves, Decimal::from(-15_000));
+
+        // Buyers pay from their bidder funds
+        assert!(keeper.take(&mut ledger, &id, "buyer", Decimal::from(100), Decimal::from(100), now + Duration::seconds(900)).is_err());
+        keeper.fund_bidder("buyer", "DAI", Decimal::from(20_000)).unwrap();
+
+        // Ten 1% steps later the price is below what the buyer is willing to pay
+        assert!(keeper.take(&mut ledger, &id, "buyer", Decimal::from(100), Decimal::from(95), now + Duration::seconds(900)).is_err());
+        let first = keeper.take(&mut ledger, &id, "buyer", Decimal::from(100), Decimal::from(100), now + Duration::seconds(900)).unwrap();
+        assert!((first.price - Decimal::new(97673264, 6)).abs() < Decimal::new(1, 4));
+        assert_eq!(first.collateral_bought, Decimal::from(100));
+        assert!(!first.completed);
+
+        // The second buyer covers the tab and the rest goes back to the borrower
+        let second = keeper.take(&mut ledger, &id, "buyer", Decimal::from(200), Decimal::from(100), now + Duration::seconds(1800)).unwrap();
+        assert!(second.completed);
+        assert_eq!(first.paid + second.paid, Decimal::from(15_750));
+        assert_eq!(second.bad_debt, Decimal::ZERO);
+        assert!(second.collateral_returned > Decimal::from(32) && second.collateral_returned < Decimal::from(33));
+        assert_eq!(ledger.supplied_balance("fund", "TBILL"), second.collateral_returned);
+        assert_eq!(ledger.supplied_balance("buyer", "TBILL"), first.collateral_bought + second.collateral_bought);
+        assert_eq!(keeper.available_bidder_funds("buyer", "DAI"), Decimal::from(4250));
+
+        // The penalty ends up in reserves
+        assert_eq!(ledger.market_state("DAI").unwrap().reserves, Decimal::from(750));
+        assert!(keeper.active_auctions().is_empty());
+    }
+}
@@ -0,0 +1,667 @@
+// =====================================================================================
+// File: core-defi/src/lending/mod.rs
+// Description: Lending protocol integration for DeFi services
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{
+    error::{DeFiError, DeFiResult},
+    types::Token,
+};
+
+pub mod interest;
+pub mod ledger;
+pub mod liquidation;
+
+pub use ledger::{LendingLedger, LiquidationOutcome};
+pub use liquidation::{AuctionConfig, KeeperConfig, LiquidationKeeper};
+
+/// Lending protocol types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LendingProtocol {
+    Compound,
+    Aave,
+    MakerDAO,
+    Cream,
+    Venus,
+    Benqi,
+}
+
+/// Lending configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingConfig {
+    pub default_ltv: Decimal,
+    pub liquidation_threshold: Decimal,
+    pub liquidation_penalty: Decimal,
+    /// Maximum share of a borrower's debt repaid in one liquidation
+    pub close_factor: Decimal,
+    pub interest_rate_model: InterestRateModel,
+    pub collateral_factor: Decimal,
+    pub reserve_factor: Decimal,
+    pub flash_loan_fee: Decimal,
+}
+
+/// Interest rate model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterestRateModel {
+    pub base_rate: Decimal,
+    pub multiplier: Decimal,
+    pub jump_multiplier: Decimal,
+    pub optimal_utilization: Decimal,
+}
+
+/// Lending position
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingPosition {
+    pub id: Uuid,
+    pub user_id: String,
+    pub protocol: LendingProtocol,
+    pub supplied_assets: HashMap<String, Decimal>,
+    pub borrowed_assets: HashMap<String, Decimal>,
+    pub collateral_value: Decimal,
+    pub debt_value: Decimal,
+    pub health_factor: Decimal,
+    pub ltv_ratio: Decimal,
+    pub liquidation_threshold: Decimal,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lending market
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendingMarket {
+    pub token: Token,
+    pub protocol: LendingProtocol,
+    pub supply_rate: Decimal,
+    pub borrow_rate: Decimal,
+    pub utilization_rate: Decimal,
+    pub total_supply: Decimal,
+    pub total_borrow: Decimal,
+    pub available_liquidity: Decimal,
+    pub collateral_factor: Decimal,
+    pub reserve_factor: Decimal,
+    pub last_updated: DateTime<Utc>,
+}
+
+/// Lending request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LendRequest {
+    pub user_id: String,
+    pub protocol: LendingProtocol,
+    pub token: Token,
+    pub amount: Decimal,
+    pub enable_as_collateral: bool,
+}
+
+/// Borrow request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BorrowRequest {
+    pub user_id: String,
+    pub protocol: LendingProtocol,
+    pub token: Token,
+    pub amount: Decimal,
+    pub interest_rate_mode: InterestRateMode,
+}
+
+/// Interest rate mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InterestRateMode {
+    Stable,
+    Variable,
+}
+
+/// Collateral request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollateralRequest {
+    pub user_id: String,
+    pub protocol: LendingProtocol,
+    pub token: Token,
+    pub amount: Decimal,
+    pub action: CollateralAction,
+}
+
+/// Collateral action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CollateralAction {
+    Enable,
+    Disable,
+    Withdraw,
+}
+
+/// Liquidation request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LiquidationRequest {
+    pub liquidator_id: String,
+    pub borrower_id: String,
+    pub protocol: LendingProtocol,
+    pub collateral_token: Token,
+    pub debt_token: Token,
+    pub debt_amount: Decimal,
+    pub receive_collateral: bool,
+}
+
+/// Lending service trait
+#[async_trait]
+pub trait LendingService: Send + Sync {
+    /// Supply assets to lending protocol
+    async fn supply(&self, request: &LendRequest) -> DeFiResult<String>;
+    
+    /// Withdraw supplied assets
+    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String>;
+    
+    /// Borrow assets from lending protocol
+    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String>;
+    
+    /// Repay borrowed assets
+    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String>;
+    
+    /// Manage collateral
+    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String>;
+    
+    /// Liquidate undercollateralized position
+    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String>;
+    
+    /// Get user lending position
+    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition>;
+    
+    /// Get lending market data
+    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket>;
+    
+    /// Get all available markets
+    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>>;
+    
+    /// Calculate health factor
+    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal>;
+}
+
+/// Market administration shared by protocols backed by a [`LendingLedger`]
+#[async_trait]
+pub trait LedgerProtocol: Send + Sync {
+    /// Ledger holding markets and user balances
+    fn ledger(&self) -> Arc<RwLock<LendingLedger>>;
+
+    /// List a token market at the given USD price
+    async fn add_market(&self, token: Token, price: Decimal) -> DeFiResult<()> {
+        self.ledger().write().await.list_market(token, price, Utc::now())
+    }
+
+    /// Update the USD price of a listed token
+    async fn set_price(&self, token: &Token, price: Decimal) -> DeFiResult<()> {
+        self.ledger().write().await.set_price(&token.symbol, price)
+    }
+
+    /// Accrue interest, or MakerDAO stability fees, in every market up to `now`
+    async fn accrue_interest(&self, now: DateTime<Utc>) -> DeFiResult<()> {
+        self.ledger().write().await.accrue(now)
+    }
+}
+
+/// Compound protocol implementation
+pub struct CompoundProtocol {
+    ledger: Arc<RwLock<LendingLedger>>,
+}
+
+impl CompoundProtocol {
+    pub fn new(config: LendingConfig) -> Self {
+        Self {
+            ledger: Arc::new(RwLock::new(LendingLedger::new(LendingProtocol::Compound, config))),
+        }
+    }
+}
+
+impl LedgerProtocol for CompoundProtocol {
+    fn ledger(&self) -> Arc<RwLock<LendingLedger>> {
+        self.ledger.clone()
+    }
+}
+
+#[async_trait]
+impl LendingService for CompoundProtocol {
+    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, request.protocol)?;
+        self.ledger.write().await.supply(
+            &request.user_id,
+            &request.token.symbol,
+            request.amount,
+            request.enable_as_collateral,
+            Utc::now(),
+        )?;
+        Ok(transaction_hash())
+    }
+
+    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, protocol)?;
+        self.ledger.write().await.withdraw(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, request.protocol)?;
+        check_rate_mode(request.interest_rate_mode)?;
+        self.ledger.write().await.borrow(&request.user_id, &request.token.symbol, request.amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, protocol)?;
+        self.ledger.write().await.repay(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, request.protocol)?;
+        apply_collateral_action(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Compound, request.protocol)?;
+        apply_liquidation(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
+        check_protocol(LendingProtocol::Compound, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.position(user_id)
+    }
+
+    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
+        check_protocol(LendingProtocol::Compound, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.market(&token.symbol)
+    }
+
+    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
+        check_protocol(LendingProtocol::Compound, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        Ok(ledger.markets())
+    }
+
+    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
+        // Health factor = (collateral * liquidation threshold) / debt
+        Ok(self.get_position(user_id, protocol).await?.health_factor)
+    }
+}
+
+/// Aave protocol implementation
+pub struct AaveProtocol {
+    ledger: Arc<RwLock<LendingLedger>>,
+}
+
+impl AaveProtocol {
+    pub fn new(config: LendingConfig) -> Self {
+        Self {
+            ledger: Arc::new(RwLock::new(LendingLedger::new(LendingProtocol::Aave, config))),
+        }
+    }
+}
+
+impl LedgerProtocol for AaveProtocol {
+    fn ledger(&self) -> Arc<RwLock<LendingLedger>> {
+        self.ledger.clone()
+    }
+}
+
+#[async_trait]
+impl LendingService for AaveProtocol {
+    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, request.protocol)?;
+        self.ledger.write().await.supply(
+            &request.user_id,
+            &request.token.symbol,
+            request.amount,
+            request.enable_as_collateral,
+            Utc::now(),
+        )?;
+        Ok(transaction_hash())
+    }
+
+    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, protocol)?;
+        self.ledger.write().await.withdraw(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, request.protocol)?;
+        check_rate_mode(request.interest_rate_mode)?;
+        self.ledger.write().await.borrow(&request.user_id, &request.token.symbol, request.amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, protocol)?;
+        self.ledger.write().await.repay(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, request.protocol)?;
+        apply_collateral_action(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::Aave, request.protocol)?;
+        apply_liquidation(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
+        check_protocol(LendingProtocol::Aave, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.position(user_id)
+    }
+
+    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
+        check_protocol(LendingProtocol::Aave, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.market(&token.symbol)
+    }
+
+    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
+        check_protocol(LendingProtocol::Aave, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        Ok(ledger.markets())
+    }
+
+    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
+        Ok(self.get_position(user_id, protocol).await?.health_factor)
+    }
+}
+
+/// MakerDAO protocol implementation (simplified)
+///
+/// Vaults pay no supply interest: the whole stability fee goes to the
+/// protocol surplus, so the ledger runs with a 100% reserve factor.
+pub struct MakerDAOProtocol {
+    ledger: Arc<RwLock<LendingLedger>>,
+}
+
+impl MakerDAOProtocol {
+    pub fn new(config: LendingConfig) -> Self {
+        let config = LendingConfig {
+            reserve_factor: Decimal::ONE,
+            ..config
+        };
+        Self {
+            ledger: Arc::new(RwLock::new(LendingLedger::new(LendingProtocol::MakerDAO, config))),
+        }
+    }
+}
+
+impl LedgerProtocol for MakerDAOProtocol {
+    fn ledger(&self) -> Arc<RwLock<LendingLedger>> {
+        self.ledger.clone()
+    }
+}
+
+#[async_trait]
+impl LendingService for MakerDAOProtocol {
+    async fn supply(&self, request: &LendRequest) -> DeFiResult<String> {
+        // MakerDAO uses CDP (Collateralized Debt Position) model
+        check_protocol(LendingProtocol::MakerDAO, request.protocol)?;
+        self.ledger.write().await.supply(
+            &request.user_id,
+            &request.token.symbol,
+            request.amount,
+            request.enable_as_collateral,
+            Utc::now(),
+        )?;
+        Ok(transaction_hash())
+    }
+
+    async fn withdraw(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::MakerDAO, protocol)?;
+        self.ledger.write().await.withdraw(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn borrow(&self, request: &BorrowRequest) -> DeFiResult<String> {
+        // In MakerDAO, borrowing means minting DAI against collateral
+        check_protocol(LendingProtocol::MakerDAO, request.protocol)?;
+        check_rate_mode(request.interest_rate_mode)?;
+        self.ledger.write().await.borrow(&request.user_id, &request.token.symbol, request.amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn repay(&self, user_id: &str, protocol: LendingProtocol, token: &Token, amount: Decimal) -> DeFiResult<String> {
+        // Repaying means burning DAI to reduce debt
+        check_protocol(LendingProtocol::MakerDAO, protocol)?;
+        self.ledger.write().await.repay(user_id, &token.symbol, amount, Utc::now())?;
+        Ok(transaction_hash())
+    }
+
+    async fn manage_collateral(&self, request: &CollateralRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::MakerDAO, request.protocol)?;
+        apply_collateral_action(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn liquidate(&self, request: &LiquidationRequest) -> DeFiResult<String> {
+        check_protocol(LendingProtocol::MakerDAO, request.protocol)?;
+        apply_liquidation(&mut *self.ledger.write().await, request)?;
+        Ok(transaction_hash())
+    }
+
+    async fn get_position(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<LendingPosition> {
+        check_protocol(LendingProtocol::MakerDAO, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.position(user_id)
+    }
+
+    async fn get_market(&self, protocol: LendingProtocol, token: &Token) -> DeFiResult<LendingMarket> {
+        check_protocol(LendingProtocol::MakerDAO, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        ledger.market(&token.symbol)
+    }
+
+    async fn get_markets(&self, protocol: LendingProtocol) -> DeFiResult<Vec<LendingMarket>> {
+        check_protocol(LendingProtocol::MakerDAO, protocol)?;
+        let mut ledger = self.ledger.write().await;
+        ledger.accrue(Utc::now())?;
+        Ok(ledger.markets())
+    }
+
+    async fn calculate_health_factor(&self, user_id: &str, protocol: LendingProtocol) -> DeFiResult<Decimal> {
+        Ok(self.get_position(user_id, protocol).await?.health_factor)
+    }
+}
+
+/// Reject requests addressed to a different protocol
+fn check_protocol(expected: LendingProtocol, actual: LendingProtocol) -> DeFiResult<()> {
+    if expected != actual {
+        return Err(DeFiError::protocol_error(
+            format!("{:?}", expected),
+            format!("Request targets {:?}", actual),
+        ));
+    }
+    Ok(())
+}
+
+/// Only variable rate borrowing is modelled by the ledger
+fn check_rate_mode(mode: InterestRateMode) -> DeFiResult<()> {
+    if mode != InterestRateMode::Variable {
+        return Err(DeFiError::validation_error("interest_rate_mode", "Only variable rate borrowing is supported"));
+    }
+    Ok(())
+}
+
+fn apply_collateral_action(ledger: &mut LendingLedger, request: &CollateralRequest) -> DeFiResult<()> {
+    let now = Utc::now();
+    match request.action {
+        CollateralAction::Enable => ledger.set_collateral(&request.user_id, &request.token.symbol, true, now),
+        CollateralAction::Disable => ledger.set_collateral(&request.user_id, &request.token.symbol, false, now),
+        CollateralAction::Withdraw => ledger.withdraw(&request.user_id, &request.token.symbol, request.amount, now),
+    }
+}
+
+fn apply_liquidation(ledger: &mut LendingLedger, request: &LiquidationRequest) -> DeFiResult<LiquidationOutcome> {
+    ledger.liquidate(
+        &request.liquidator_id,
+        &request.borrower_id,
+        &request.debt_token.symbol,
+        &request.collateral_token.symbol,
+        request.debt_amount,
+        request.receive_collateral,
+        Utc::now(),
+    )
+}
+
+fn transaction_hash() -> String {
+    format!("0x{:x}", rand::random::<u64>())
+}
+
+impl Default for LendingConfig {
+    fn default() -> Self {
+        Self {
+            default_ltv: Decimal::new(75, 2), // 75%
+            liquidation_threshold: Decimal::new(80, 2), // 80%
+            liquidation_penalty: Decimal::new(5, 2), // 5%
+            close_factor: Decimal::new(50, 2), // 50%
+            interest_rate_model: InterestRateModel {
+                base_rate: Decimal::new(2, 2), // 2%
+                multiplier: Decimal::new(10, 2), // 10%
+                jump_multiplier: Decimal::new(100, 2), // 100%
+                optimal_utilization: Decimal::new(80, 2), // 80%
+            },
+            collateral_factor: Decimal::new(75, 2), // 75%
+            reserve_factor: Decimal::new(10, 2), // 10%
+            flash_loan_fee: Decimal::new(9, 4), // 0.09%
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lending::interest::SECONDS_PER_YEAR;
+
+    fn create_tokens() -> (Token, Token) {
+        let weth = Token::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2".to_string(), "WETH".to_string(), "Wrapped Ether".to_string(), 18, 1);
+        let dai = Token::new("0x6B175474E89094C44Da98b954EedeAC495271d0F".to_string(), "DAI".to_string(), "Dai Stablecoin".to_string(), 18, 1);
+        (weth, dai)
+    }
+
+    fn lend_request(user_id: &str, protocol: LendingProtocol, token: &Token, amount: i64, collateral: bool) -> LendRequest {
+        LendRequest {
+            user_id: user_id.to_string(),
+            protocol,
+            token: token.clone(),
+            amount: Decimal::from(amount),
+            enable_as_collateral: collateral,
+        }
+    }
+
+    fn borrow_request(user_id: &str, protocol: LendingProtocol, token: &Token, amount: i64) -> BorrowRequest {
+        BorrowRequest {
+            user_id: user_id.to_string(),
+            protocol,
+            token: token.clone(),
+            amount: Decimal::from(amount),
+            interest_rate_mode: InterestRateMode::Variable,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_position_and_health_factor_after_time_passes() {
+        let (weth, dai) = create_tokens();
+        let compound = CompoundProtocol::new(LendingConfig::default());
+        compound.add_market(weth.clone(), Decimal::from(2000)).await.unwrap();
+        compound.add_market(dai.clone(), Decimal::ONE).await.unwrap();
+
+        compound.supply(&lend_request("lender", LendingProtocol::Compound, &dai, 100_000, false)).await.unwrap();
+        compound.supply(&lend_request("borrower", LendingProtocol::Compound, &weth, 50, true)).await.unwrap();
+        compound.borrow(&borrow_request("borrower", LendingProtocol::Compound, &dai, 50_000)).await.unwrap();
+
+        let before = compound.get_position("borrower", LendingProtocol::Compound).await.unwrap();
+        assert_eq!(before.collateral_value, Decimal::from(100_000));
+        assert!((before.health_factor - Decimal::new(16, 1)).abs() < Decimal::new(1, 6));
+
+        // 50% utilization borrows at 8.25% a year
+        compound.accrue_interest(Utc::now() + chrono::Duration::seconds(SECONDS_PER_YEAR)).await.unwrap();
+
+        let after = compound.get_position("borrower", LendingProtocol::Compound).await.unwrap();
+        let debt = after.borrowed_assets["DAI"];
+        assert!(debt > Decimal::from(54_290) && debt < Decimal::from(54_310));
+        assert_eq!(after.debt_value, debt);
+        assert!(after.health_factor < before.health_factor);
+        assert_eq!(
+            compound.calculate_health_factor("borrower", LendingProtocol::Compound).await.unwrap(),
+            after.health_factor
+        );
+
+        let market = compound.get_market(LendingProtocol::Compound, &dai).await.unwrap();
+        assert_eq!(market.total_borrow, debt);
+        assert!(market.total_supply > Decimal::from(100_000));
+        assert_eq!(compound.get_markets(LendingProtocol::Compound).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_maker_stability_fee_goes_to_surplus() {
+        let (weth, dai) = create_tokens();
+        let maker = MakerDAOProtocol::new(LendingConfig::default());
+        maker.add_market(weth.clone(), Decimal::from(2000)).await.unwrap();
+        maker.add_market(dai.clone(), Decimal::ONE).await.unwrap();
+
+        maker.supply(&lend_request("dss", LendingProtocol::MakerDAO, &dai, 1_000_000, false)).await.unwrap();
+        maker.supply(&lend_request("vault", LendingProtocol::MakerDAO, &weth, 100, true)).await.unwrap();
+        maker.borrow(&borrow_request("vault", LendingProtocol::MakerDAO, &dai, 100_000)).await.unwrap();
+        maker.accrue_interest(Utc::now() + chrono::Duration::seconds(SECONDS_PER_YEAR)).await.unwrap();
+
+        let market = maker.get_market(LendingProtocol::MakerDAO, &dai).await.unwrap();
+        assert_eq!(market.supply_rate, Decimal::ZERO);
+
+        let ledger = maker.ledger();
+        let ledger = ledger.read().await;
+        let state = ledger.market_state("DAI").unwrap();
+        assert_eq!(ledger.supplied_balance("dss", "DAI"), Decimal::from(1_000_000));
+        assert!((state.reserves - (state.total_debt() - Decimal::from(100_000))).abs() < Decimal::new(1, 12));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_requests_are_rejected() {
+        let (weth, dai) = create_tokens();
+        let aave = AaveProtocol::new(LendingConfig::default());
+        aave.add_market(weth.clone(), Decimal::from(2000)).await.unwrap();
+
+        assert!(aave.add_market(weth.clone(), Decimal::from(2000)).await.is_err());
+        assert!(aave.supply(&lend_request("user", LendingProtocol::Compound, &weth, 1, true)).await.is_err());
+        assert!(aave.supply(&lend_request("user", LendingProtocol::Aave, &dai, 1, true)).await.is_err());
+        assert!(aave.get_position("nobody", LendingProtocol::Aave).await.is_err());
+
+        aave.supply(&lend_request("user", LendingProtocol::Aave, &weth, 1, true)).await.unwrap();
+        let mut request = borrow_request("user", LendingProtocol::Aave, &weth, 1);
+        request.interest_rate_mode = InterestRateMode::Stable;
+        assert!(aave.borrow(&request).await.is_err());
+
+        // Disabling collateral without debt is allowed, withdrawing too much is not
+        aave.manage_collateral(&CollateralRequest {
+            user_id: "user".to_string(),
+            protocol: LendingProtocol::Aave,
+            token: weth.clone(),
+            amount: Decimal::ZERO,
+            action: CollateralAction::Disable,
+        }).await.unwrap();
+        assert!(aave.withdraw("user", LendingProtocol::Aave, &weth, Decimal::from(2)).await.is_err());
+        assert_eq!(aave.calculate_health_factor("user", LendingProtocol::Aave).await.unwrap(), Decimal::MAX);
+    }
+}
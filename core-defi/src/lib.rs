@@ -23,16 +23,17 @@ pub mod governance;
 pub mod service;
 
 // Re-export main types and traits
-pub use lending::{LendingService, LendingConfig, LendingPosition, LendingMarket, CompoundProtocol, AaveProtocol, MakerDAOProtocol};
+pub use lending::{LendingService, LedgerProtocol, LendingConfig, LendingPosition, LendingMarket, LendingLedger, LiquidationKeeper, CompoundProtocol, AaveProtocol, MakerDAOProtocol};
 pub use staking::{StakingService, StakingConfig, StakingPosition, StakingPool, StakingReward, UnstakingRequest, ValidatorStaking, LiquidStaking};
 pub use yield_farming::{YieldFarmingService, YieldFarmConfig, FarmingPosition, YieldFarm, HarvestRequest};
-pub use flash_loans::{FlashLoanService, FlashLoanConfig, FlashLoanRequest, FlashLoanExecution, ArbitrageOpportunity, LiquidationOpportunity};
+pub use flash_loans::{FlashLoanService, FlashLoanConfig, FlashLoanRequest, FlashLoanExecution, ArbitrageOpportunity, LiquidationOpportunity, FlashLoanSimulator, FlashLoanAction, FlashLoanSimulation};
+pub use derivatives::{DerivativesService, DerivativesConfig, DerivativesEngine, DerivativesMarket, OptionType, OptionQuote, Greeks, PerpetualPosition, OptionPosition, MarginSummary};
 
 
 // Additional re-exports
 pub use error::{DeFiError, DeFiResult};
 pub use types::{Token, TokenPair, LiquidityPool, Position, Strategy, AMMProtocol};
-pub use amm::{AMMService, SwapRequest, SwapResult, LiquidityRequest, LiquidityResult};
+pub use amm::{AMMService, AMMServiceImpl, AmmPool, Router, SplitRoute, SwapRequest, SwapResult, LiquidityRequest, LiquidityResult};
 pub use service::DeFiService;
 
 use serde::{Deserialize, Serialize};
@@ -18,6 +18,7 @@ use crate::{
     error::{DeFiError, DeFiResult},
     types::{Token, TokenPair, Position, Strategy, Price},
     amm::{AMMService, SwapRequest, SwapResult, LiquidityRequest, LiquidityResult},
+    derivatives::DerivativesService,
     DeFiServiceConfig, DeFiMetrics, DeFiHealthStatus, DeFiTransaction,
 };
 
@@ -25,6 +26,7 @@ use crate::{
 pub struct DeFiService {
     config: Arc<RwLock<DeFiServiceConfig>>,
     amm_service: Arc<dyn AMMService>,
+    derivatives_service: Option<Arc<dyn DerivativesService>>,
     positions: Arc<RwLock<HashMap<Uuid, Position>>>,
     strategies: Arc<RwLock<HashMap<Uuid, Strategy>>>,
     price_cache: Arc<RwLock<HashMap<String, Price>>>,
@@ -39,12 +41,26 @@ impl DeFiService {
         Self {
             config: Arc::new(RwLock::new(config)),
             amm_service,
+            derivatives_service: None,
             positions: Arc::new(RwLock::new(HashMap::new())),
             strategies: Arc::new(RwLock::new(HashMap::new())),
             price_cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Register the options and perpetuals service
+    pub fn with_derivatives_service(mut self, derivatives_service: Arc<dyn DerivativesService>) -> Self {
+        self.derivatives_service = Some(derivatives_service);
+        self
+    }
+
+    /// Get the registered derivatives service
+    pub fn derivatives(&self) -> DeFiResult<Arc<dyn DerivativesService>> {
+        self.derivatives_service.clone().ok_or_else(|| DeFiError::ConfigurationError {
+            message: "Derivatives service is not registered".to_string(),
+        })
+    }
+
     /// Execute a swap transaction
     pub async fn execute_swap(&self, request: SwapRequest) -> DeFiResult<SwapResult> {
         // Validate request
@@ -178,6 +194,11 @@ impl DeFiService {
     /// Health check
     pub async fn health_check(&self) -> DeFiResult<DeFiHealthStatus> {
         let amm_health = self.amm_service.health_check().await?;
+
+        let mut protocol_statuses = HashMap::new();
+        if self.derivatives_service.is_some() {
+            protocol_statuses.insert("derivatives".to_string(), "healthy".to_string());
+        }
         
         Ok(DeFiHealthStatus {
             overall_status: "healthy".to_string(),
@@ -187,7 +208,7 @@ impl DeFiService {
             yield_farming_status: "healthy".to_string(),
             oracle_status: "healthy".to_string(),
             governance_status: "healthy".to_string(),
-            protocol_statuses: HashMap::new(),
+            protocol_statuses,
             last_check: Utc::now(),
         })
     }
@@ -533,6 +554,21 @@ mod tests {
         assert_eq!(health.overall_status, "healthy");
     }
 
+    #[tokio::test]
+    async fn test_derivatives_service_registration() {
+        let config = DeFiServiceConfig::default();
+        let amm_service = Arc::new(MockAMMService);
+        let service = DeFiService::new(config.clone(), amm_service.clone());
+        assert!(service.derivatives().is_err());
+
+        let derivatives = Arc::new(crate::derivatives::DerivativesEngine::new(config.derivatives_config.clone()));
+        let service = DeFiService::new(config, amm_service).with_derivatives_service(derivatives);
+
+        service.derivatives().unwrap().deposit_margin("user123", Decimal::from(1000)).await.unwrap();
+        let health = service.health_check().await.unwrap();
+        assert_eq!(health.protocol_statuses.get("derivatives"), Some(&"healthy".to_string()));
+    }
+
     #[tokio::test]
     async fn test_swap_execution() {
         let config = DeFiServiceConfig::default();
@@ -9,17 +9,23 @@ use chrono::Utc;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use uuid::Uuid;
 
 use crate::{
     error::{OracleError, OracleResult},
     service::ProviderClient,
     types::{
-        AggregationConfig, AggregationMethod, OracleProvider, PriceData, ValidationRule,
-        ValidationRuleType, ValidationSeverity,
+        AggregationConfig, AggregationMethod, OracleProvider, PriceData, PricePoint,
+        ValidationRule, ValidationRuleType, ValidationSeverity,
     },
+    window::{time_weighted_average, volume_weighted_average, ObservationStore, WindowConfig},
 };
 
+/// User-supplied aggregation function used by `AggregationMethod::Custom`
+pub type CustomAggregationFn = Arc<dyn Fn(&[PriceData]) -> OracleResult<Decimal> + Send + Sync>;
+
 /// Price aggregator trait
 #[async_trait]
 pub trait PriceAggregator: Send + Sync {
@@ -54,6 +60,9 @@ pub struct MultiSourceAggregator {
     config: AggregationConfig,
     providers: HashMap<OracleProvider, Box<dyn ProviderClient>>,
     validation_rules: Vec<ValidationRule>,
+    observations: Arc<RwLock<ObservationStore>>,
+    mode_precision: u32,
+    custom_aggregation: Option<CustomAggregationFn>,
 }
 
 /// Aggregation result with metadata
@@ -145,9 +154,120 @@ impl MultiSourceAggregator {
             config,
             providers,
             validation_rules,
+            observations: Arc::new(RwLock::new(ObservationStore::new(WindowConfig::default()))),
+            mode_precision: 2,
+            custom_aggregation: None,
+        }
+    }
+
+    /// Set the per-feed observation window used by TWAP and VWAP
+    pub fn with_observation_window(mut self, config: WindowConfig) -> Self {
+        self.observations = Arc::new(RwLock::new(ObservationStore::new(config)));
+        self
+    }
+
+    /// Set the number of decimal places prices are bucketed to for `Mode`
+    pub fn with_mode_precision(mut self, decimals: u32) -> Self {
+        self.mode_precision = decimals;
+        self
+    }
+
+    /// Register the aggregation function used by `AggregationMethod::Custom`
+    pub fn with_custom_aggregation<F>(mut self, aggregation: F) -> Self
+    where
+        F: Fn(&[PriceData]) -> OracleResult<Decimal> + Send + Sync + 'static,
+    {
+        self.custom_aggregation = Some(Arc::new(aggregation));
+        self
+    }
+
+    /// Record source observations in a feed's rolling window
+    pub async fn record_observations(&self, feed_id: &str, prices: &[PriceData]) {
+        let mut observations = self.observations.write().await;
+        for price_data in prices {
+            observations.record(feed_id, PricePoint::from(price_data));
+        }
+    }
+
+    /// Aggregate a feed's rolling window with a time or volume weighted method.
+    ///
+    /// Other methods aggregate the observations in the window as a price set.
+    pub async fn aggregate_window(
+        &self,
+        feed_id: &str,
+        method: AggregationMethod,
+    ) -> OracleResult<PriceData> {
+        let observations = self.observations.read().await;
+        let window = observations
+            .window(feed_id)
+            .filter(|window| !window.is_empty())
+            .ok_or_else(|| OracleError::insufficient_data(feed_id, 1, 0))?;
+
+        let now = Utc::now();
+        let price = match method {
+            AggregationMethod::TWAP => window.twap(now)?,
+            AggregationMethod::VWAP => window.vwap()?,
+            _ => {
+                let prices: Vec<PriceData> = window
+                    .points()
+                    .map(Self::observation_price)
+                    .collect();
+                drop(observations);
+                return self.aggregate_prices(prices, method).await;
+            }
+        };
+
+        let latest = window.latest().map_or(now, |point| point.timestamp);
+        Ok(PriceData {
+            price,
+            timestamp: latest,
+            source: OracleProvider::Custom(0), // Aggregated source
+            confidence: 1.0,
+            volume: Some(window.points().map(|point| point.volume).sum()),
+            market_cap: None,
+            deviation: None,
+            round_id: None,
+        })
+    }
+
+    fn observation_price(point: &PricePoint) -> PriceData {
+        PriceData {
+            price: point.close,
+            timestamp: point.timestamp,
+            source: OracleProvider::Custom(0),
+            confidence: 1.0,
+            volume: Some(point.volume),
+            market_cap: None,
+            deviation: None,
+            round_id: None,
         }
     }
 
+    /// Most frequent price after bucketing to `mode_precision` decimal places.
+    ///
+    /// Ties go to the bucket with the higher total confidence, then the lower price.
+    fn mode_price(&self, prices: &[PriceData]) -> Decimal {
+        let mut buckets: HashMap<Decimal, (usize, f64)> = HashMap::new();
+        for price_data in prices {
+            let bucket = buckets
+                .entry(price_data.price.round_dp(self.mode_precision).normalize())
+                .or_insert((0, 0.0));
+            bucket.0 += 1;
+            bucket.1 += price_data.confidence;
+        }
+
+        buckets
+            .into_iter()
+            .max_by(|(price_a, (count_a, conf_a)), (price_b, (count_b, conf_b))| {
+                count_a
+                    .cmp(count_b)
+                    .then(conf_a.partial_cmp(conf_b).unwrap_or(std::cmp::Ordering::Equal))
+                    .then(price_b.cmp(price_a))
+            })
+            .map(|(price, _)| price)
+            .unwrap_or(Decimal::ZERO)
+    }
+
     /// Get prices from all available providers
     pub async fn get_all_prices(&self, feed_id: &str) -> OracleResult<Vec<PriceData>> {
         let mut prices = Vec::new();
@@ -201,10 +321,17 @@ impl MultiSourceAggregator {
 
         let outliers_removed = (source_prices.len() - filtered_prices.len()) as u32;
 
+        // Keep the rolling window that time and volume weighted methods need
+        self.record_observations(feed_id, &filtered_prices).await;
+
         // Aggregate prices
-        let aggregated_price = self
-            .aggregate_prices(filtered_prices.clone(), self.config.default_method)
-            .await?;
+        let aggregated_price = match self.config.default_method {
+            AggregationMethod::TWAP | AggregationMethod::VWAP => {
+                self.aggregate_window(feed_id, self.config.default_method)
+                    .await?
+            }
+            method => self.aggregate_prices(filtered_prices.clone(), method).await?,
+        };
 
         // Calculate consensus score
         let consensus_score = self.calculate_consensus(&filtered_prices).await?;
@@ -551,12 +678,22 @@ impl PriceAggregator for MultiSourceAggregator {
                     .sum();
                 weighted_sum / Decimal::from_f64_retain(total_weight).unwrap_or(Decimal::ONE)
             }
-            _ => {
-                // Default to median for other methods
-                let mut sorted_prices: Vec<Decimal> = prices.iter().map(|p| p.price).collect();
-                sorted_prices.sort();
-                let mid = sorted_prices.len() / 2;
-                sorted_prices[mid]
+            AggregationMethod::TWAP => {
+                // A snapshot is weighted up to its latest observation
+                let points: Vec<PricePoint> = prices.iter().map(PricePoint::from).collect();
+                let end = prices.iter().map(|p| p.timestamp).max().unwrap_or(Utc::now());
+                time_weighted_average(&points, end)?
+            }
+            AggregationMethod::VWAP => {
+                let points: Vec<PricePoint> = prices.iter().map(PricePoint::from).collect();
+                volume_weighted_average(&points)?
+            }
+            AggregationMethod::Mode => self.mode_price(&prices),
+            AggregationMethod::Custom => {
+                let aggregation = self.custom_aggregation.as_ref().ok_or_else(|| {
+                    OracleError::configuration_error("No custom aggregation function registered")
+                })?;
+                aggregation(&prices)?
             }
         };
 
@@ -775,4 +912,123 @@ mod tests {
         assert_eq!(result.outliers.len(), 1);
         assert_eq!(result.outliers[0].price, Decimal::new(200, 0));
     }
+
+    fn price_at(price: i64, seconds: i64, volume: i64, confidence: f64) -> PriceData {
+        PriceData {
+            price: Decimal::new(price, 2),
+            timestamp: chrono::DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            source: OracleProvider::Chainlink,
+            confidence,
+            volume: Some(Decimal::new(volume, 0)),
+            market_cap: None,
+            deviation: None,
+            round_id: None,
+        }
+    }
+
+    fn aggregator() -> MultiSourceAggregator {
+        let config = AggregationConfig {
+            default_method: AggregationMethod::TWAP,
+            outlier_detection: false,
+            outlier_threshold_percent: 10.0,
+            min_sources_for_consensus: 1,
+            confidence_threshold: 0.8,
+        };
+        MultiSourceAggregator::new(config, HashMap::new())
+    }
+
+    #[tokio::test]
+    async fn test_price_aggregation_twap_vwap() {
+        let prices = vec![
+            price_at(10000, 0, 5, 0.9),
+            price_at(10200, 30, 1, 0.9),
+            price_at(10100, 40, 4, 0.9),
+            price_at(9900, 60, 10, 0.9),
+        ];
+
+        // (100 * 30 + 102 * 10 + 101 * 20) / 60, weighted up to the latest observation
+        let twap = aggregator()
+            .aggregate_prices(prices.clone(), AggregationMethod::TWAP)
+            .await
+            .unwrap();
+        assert_eq!(twap.price.round_dp(6), Decimal::new(100_666_667, 6));
+
+        // (100 * 5 + 102 * 1 + 101 * 4 + 99 * 10) / 20
+        let vwap = aggregator()
+            .aggregate_prices(prices, AggregationMethod::VWAP)
+            .await
+            .unwrap();
+        assert_eq!(vwap.price, Decimal::new(9980, 2));
+
+        let no_volume = vec![PriceData { volume: None, ..price_at(10000, 0, 0, 0.9) }];
+        assert!(aggregator()
+            .aggregate_prices(no_volume, AggregationMethod::VWAP)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_price_aggregation_mode_and_custom() {
+        let prices = vec![
+            price_at(10001, 0, 1, 0.5),
+            price_at(10004, 0, 1, 0.5),
+            price_at(10120, 0, 1, 0.9),
+            price_at(10080, 0, 1, 0.9),
+            price_at(9000, 0, 1, 0.9),
+        ];
+
+        // Bucketed to whole units, 100 and 101 tie on count; 101 has more confidence
+        let mode = aggregator()
+            .with_mode_precision(0)
+            .aggregate_prices(prices.clone(), AggregationMethod::Mode)
+            .await
+            .unwrap();
+        assert_eq!(mode.price, Decimal::new(101, 0));
+
+        assert!(aggregator()
+            .aggregate_prices(prices.clone(), AggregationMethod::Custom)
+            .await
+            .is_err());
+
+        let max_price = aggregator().with_custom_aggregation(|prices| {
+            prices
+                .iter()
+                .map(|p| p.price)
+                .max()
+                .ok_or_else(|| OracleError::aggregation_error("empty"))
+        });
+        let custom = max_price
+            .aggregate_prices(prices, AggregationMethod::Custom)
+            .await
+            .unwrap();
+        assert_eq!(custom.price, Decimal::new(10120, 2));
+    }
+
+    #[tokio::test]
+    async fn test_rolling_window_vwap() {
+        let aggregator = aggregator().with_observation_window(crate::window::WindowConfig {
+            capacity: 3,
+            max_age_seconds: 3600,
+        });
+        let prices = [
+            price_at(10000, 0, 5, 0.9),
+            price_at(10200, 30, 1, 0.9),
+            price_at(10100, 40, 4, 0.9),
+            price_at(9900, 60, 10, 0.9),
+        ];
+        aggregator.record_observations("RWA/USD", &prices[..2]).await;
+        aggregator.record_observations("RWA/USD", &prices[2..]).await;
+
+        // Capacity 3 evicts the first observation: (102 * 1 + 101 * 4 + 99 * 10) / 15
+        let vwap = aggregator
+            .aggregate_window("RWA/USD", AggregationMethod::VWAP)
+            .await
+            .unwrap();
+        assert_eq!(vwap.price.round_dp(6), Decimal::new(99_733_333, 6));
+        assert_eq!(vwap.volume, Some(Decimal::new(15, 0)));
+        assert!(aggregator
+            .aggregate_window("BTC/USD", AggregationMethod::TWAP)
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,728 @@
+// =====================================================================================
+// File: core-oracle/src/attestation.rs
+// Description: Signed oracle reports and offline report verification
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use chrono::{DateTime, Duration, Utc};
+use ethers::signers::{LocalWallet, Signer};
+use ethers::types::{Address, RecoveryMessage, Signature, H256};
+use ethers::utils::keccak256;
+use ring::rand::{SecureRandom, SystemRandom};
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{
+    error::{OracleError, OracleResult},
+    types::{AggregationMethod, OracleProvider, PriceData},
+};
+
+/// Domain separator prefixed to every signed report payload
+const REPORT_DOMAIN: &str = "stablerwa-oracle-report-v1";
+
+/// Signature schemes supported for oracle node keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    Ed25519,
+    /// Ethereum-style recoverable ECDSA over the keccak256 report digest
+    Secp256k1,
+}
+
+/// A single provider's contribution to an aggregated price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProviderObservation {
+    pub provider: OracleProvider,
+    pub price: Decimal,
+    pub timestamp: DateTime<Utc>,
+    pub round_id: Option<u64>,
+}
+
+impl From<&PriceData> for ProviderObservation {
+    fn from(price_data: &PriceData) -> Self {
+        Self {
+            provider: price_data.source,
+            price: price_data.price,
+            timestamp: price_data.timestamp,
+            round_id: price_data.round_id,
+        }
+    }
+}
+
+/// Aggregated price together with everything needed to reproduce it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OracleReport {
+    pub report_id: Uuid,
+    pub feed_id: String,
+    pub node_id: String,
+    pub price: Decimal,
+    pub method: AggregationMethod,
+    pub observations: Vec<ProviderObservation>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl OracleReport {
+    /// Canonical byte encoding covered by the signature.
+    ///
+    /// Every field is written as a 4-byte big-endian length followed by its
+    /// UTF-8 bytes, with decimals normalized, timestamps in milliseconds and
+    /// enums by fixed codes, so no field value can be mistaken for another
+    /// and the payload can be rebuilt in any language.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        push_field(&mut payload, REPORT_DOMAIN);
+        push_field(&mut payload, &self.report_id.to_string());
+        push_field(&mut payload, &self.feed_id);
+        push_field(&mut payload, &self.node_id);
+        push_field(&mut payload, &self.price.normalize().to_string());
+        push_field(&mut payload, method_code(self.method));
+        push_field(&mut payload, &self.timestamp.timestamp_millis().to_string());
+        push_field(&mut payload, &self.observations.len().to_string());
+        for observation in &self.observations {
+            push_field(&mut payload, &provider_code(observation.provider));
+            push_field(&mut payload, &observation.price.normalize().to_string());
+            push_field(&mut payload, &observation.timestamp.timestamp_millis().to_string());
+            push_field(
+                &mut payload,
+                &observation.round_id.map_or(String::new(), |round| round.to_string()),
+            );
+        }
+        payload
+    }
+
+    /// keccak256 of the signing payload
+    pub fn digest(&self) -> [u8; 32] {
+        keccak256(self.signing_payload())
+    }
+
+    /// Number of distinct providers contributing to the report
+    pub fn distinct_providers(&self) -> usize {
+        self.observations
+            .iter()
+            .map(|observation| observation.provider)
+            .collect::<HashSet<_>>()
+            .len()
+    }
+}
+
+/// Append a length-prefixed field to a signing payload
+fn push_field(payload: &mut Vec<u8>, value: &str) {
+    payload.extend_from_slice(&(value.len() as u32).to_be_bytes());
+    payload.extend_from_slice(value.as_bytes());
+}
+
+/// Stable code for an aggregation method in signing payloads
+fn method_code(method: AggregationMethod) -> &'static str {
+    match method {
+        AggregationMethod::Mean => "mean",
+        AggregationMethod::Median => "median",
+        AggregationMethod::WeightedMean => "weighted_mean",
+        AggregationMethod::TWAP => "twap",
+        AggregationMethod::VWAP => "vwap",
+        AggregationMethod::Mode => "mode",
+        AggregationMethod::Custom => "custom",
+    }
+}
+
+/// Stable code for a provider in signing payloads
+fn provider_code(provider: OracleProvider) -> String {
+    match provider {
+        OracleProvider::Chainlink => "chainlink".to_string(),
+        OracleProvider::BandProtocol => "band_protocol".to_string(),
+        OracleProvider::PythNetwork => "pyth_network".to_string(),
+        OracleProvider::UMA => "uma".to_string(),
+        OracleProvider::Tellor => "tellor".to_string(),
+        OracleProvider::API3 => "api3".to_string(),
+        OracleProvider::DIA => "dia".to_string(),
+        OracleProvider::Custom(id) => format!("custom:{}", id),
+    }
+}
+
+/// Oracle report signed by a node key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedOracleReport {
+    pub report: OracleReport,
+    pub scheme: SignatureScheme,
+    /// Hex public key for Ed25519, checksummed address for Secp256k1
+    pub signer: String,
+    /// Hex encoded signature
+    pub signature: String,
+}
+
+/// Oracle node signing key
+pub trait ReportSigner: Send + Sync {
+    fn scheme(&self) -> SignatureScheme;
+
+    /// Identity verifiers pin: hex public key or address
+    fn signer_id(&self) -> String;
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> OracleResult<Vec<u8>>;
+}
+
+/// Ed25519 node key
+pub struct Ed25519Signer {
+    key_pair: Ed25519KeyPair,
+}
+
+impl Ed25519Signer {
+    /// Generate a fresh key, returning the signer and its PKCS#8 document for storage
+    pub fn generate() -> OracleResult<(Self, Vec<u8>)> {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+            .map_err(|_| OracleError::attestation_error("Failed to generate Ed25519 key"))?;
+        let signer = Self::from_pkcs8(pkcs8.as_ref())?;
+        Ok((signer, pkcs8.as_ref().to_vec()))
+    }
+
+    pub fn from_pkcs8(pkcs8: &[u8]) -> OracleResult<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| OracleError::attestation_error(format!("Invalid Ed25519 key: {}", e)))?;
+        Ok(Self { key_pair })
+    }
+}
+
+impl ReportSigner for Ed25519Signer {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Ed25519
+    }
+
+    fn signer_id(&self) -> String {
+        hex::encode(self.key_pair.public_key().as_ref())
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> OracleResult<Vec<u8>> {
+        Ok(self.key_pair.sign(digest).as_ref().to_vec())
+    }
+}
+
+/// secp256k1 node key, verifiable on-chain with `ecrecover`
+pub struct Secp256k1Signer {
+    wallet: LocalWallet,
+}
+
+impl Secp256k1Signer {
+    /// Generate a fresh key, returning the signer and its raw private key for storage
+    pub fn generate() -> OracleResult<(Self, Vec<u8>)> {
+        let mut secret = [0u8; 32];
+        SystemRandom::new()
+            .fill(&mut secret)
+            .map_err(|_| OracleError::attestation_error("Failed to generate secp256k1 key"))?;
+        Ok((Self::from_bytes(&secret)?, secret.to_vec()))
+    }
+
+    pub fn from_bytes(secret: &[u8]) -> OracleResult<Self> {
+        let wallet = LocalWallet::from_bytes(secret).map_err(|e| {
+            OracleError::attestation_error(format!("Invalid secp256k1 key: {}", e))
+        })?;
+        Ok(Self { wallet })
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+}
+
+impl ReportSigner for Secp256k1Signer {
+    fn scheme(&self) -> SignatureScheme {
+        SignatureScheme::Secp256k1
+    }
+
+    fn signer_id(&self) -> String {
+        ethers::utils::to_checksum(&self.wallet.address(), None)
+    }
+
+    fn sign_digest(&self, digest: &[u8; 32]) -> OracleResult<Vec<u8>> {
+        let signature = self
+            .wallet
+            .sign_hash(H256::from(*digest))
+            .map_err(|e| OracleError::attestation_error(format!("Signing failed: {}", e)))?;
+        Ok(signature.to_vec())
+    }
+}
+
+/// Oracle node that attests to aggregated prices
+pub struct OracleNode {
+    node_id: String,
+    signer: Box<dyn ReportSigner>,
+}
+
+impl OracleNode {
+    pub fn new(node_id: impl Into<String>, signer: Box<dyn ReportSigner>) -> Self {
+        Self {
+            node_id: node_id.into(),
+            signer,
+        }
+    }
+
+    pub fn node_id(&self) -> &str {
+        &self.node_id
+    }
+
+    pub fn signer_id(&self) -> String {
+        self.signer.signer_id()
+    }
+
+    /// Sign an aggregated price along with the source observations it was built from
+    pub fn sign_report(
+        &self,
+        feed_id: &str,
+        method: AggregationMethod,
+        aggregated: &PriceData,
+        source_prices: &[PriceData],
+    ) -> OracleResult<SignedOracleReport> {
+        let report = OracleReport {
+            report_id: Uuid::new_v4(),
+            feed_id: feed_id.to_string(),
+            node_id: self.node_id.clone(),
+            price: aggregated.price,
+            method,
+            observations: source_prices.iter().map(ProviderObservation::from).collect(),
+            timestamp: aggregated.timestamp,
+        };
+        let signature = self.signer.sign_digest(&report.digest())?;
+
+        Ok(SignedOracleReport {
+            report,
+            scheme: self.signer.scheme(),
+            signer: self.signer.signer_id(),
+            signature: hex::encode(signature),
+        })
+    }
+}
+
+/// Verify a hex signature over a 32-byte digest against a signer ID
+/// (hex Ed25519 public key or secp256k1 address)
+pub fn verify_digest(
+    scheme: SignatureScheme,
+    signer: &str,
+    digest: &[u8; 32],
+    signature: &str,
+) -> OracleResult<()> {
+    let signature = hex::decode(signature.trim_start_matches("0x"))
+        .map_err(|e| OracleError::attestation_error(format!("Malformed signature: {}", e)))?;
+
+    let valid = match scheme {
+        SignatureScheme::Ed25519 => {
+            let public_key = hex::decode(signer).map_err(|e| {
+                OracleError::attestation_error(format!("Malformed public key: {}", e))
+            })?;
+            UnparsedPublicKey::new(&ED25519, public_key)
+                .verify(digest, &signature)
+                .is_ok()
+        }
+        SignatureScheme::Secp256k1 => {
+            let address = Address::from_str(signer).map_err(|e| {
+                OracleError::attestation_error(format!("Malformed address: {}", e))
+            })?;
+            let signature = Signature::try_from(signature.as_slice()).map_err(|e| {
+                OracleError::attestation_error(format!("Malformed signature: {}", e))
+            })?;
+            signature
+                .verify(RecoveryMessage::Hash(H256::from(*digest)), address)
+                .is_ok()
+        }
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(OracleError::attestation_error(format!(
+            "Signature does not match signer {}",
+            signer
+        )))
+    }
+}
+
+/// Verifier configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifierConfig {
+    /// Minimum distinct providers behind each report
+    pub min_observations: usize,
+    /// Minimum distinct trusted nodes that must attest to a price
+    pub min_signers: usize,
+    /// Maximum spread between node prices, as a percentage of the median
+    pub max_report_deviation_percent: Decimal,
+    /// Reject reports older than this, so captured reports cannot be replayed later
+    pub max_age_seconds: u64,
+    /// How far a report timestamp may run ahead of the verifier's clock
+    pub max_clock_skew_seconds: u64,
+}
+
+impl Default for VerifierConfig {
+    fn default() -> Self {
+        Self {
+            min_observations: 1,
+            min_signers: 1,
+            max_report_deviation_percent: Decimal::new(1, 0), // 1%
+            max_age_seconds: 300,        // 5 minutes
+            max_clock_skew_seconds: 5,
+        }
+    }
+}
+
+/// Price attested by a quorum of oracle nodes
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedPrice {
+    pub feed_id: String,
+    /// Median of the attested prices
+    pub price: Decimal,
+    pub signers: Vec<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Verifies signed oracle reports offline against a set of trusted node keys
+pub struct ReportVerifier {
+    config: VerifierConfig,
+    /// Trusted signer ID -> node ID
+    trusted_signers: HashMap<String, String>,
+}
+
+impl ReportVerifier {
+    pub fn new(config: VerifierConfig) -> Self {
+        Self {
+            config,
+            trusted_signers: HashMap::new(),
+        }
+    }
+
+    /// Trust a node's signer ID (hex public key or address)
+    pub fn with_trusted_signer(
+        mut self,
+        node_id: impl Into<String>,
+        signer_id: impl Into<String>,
+    ) -> Self {
+        self.trusted_signers
+            .insert(Self::normalize_signer(&signer_id.into()), node_id.into());
+        self
+    }
+
+    /// Verify a single report's signature, signer and observation quorum
+    pub fn verify(&self, signed: &SignedOracleReport, now: DateTime<Utc>) -> OracleResult<()> {
+        let report = &signed.report;
+        let node_id = self
+            .trusted_signers
+            .get(&Self::normalize_signer(&signed.signer))
+            .ok_or_else(|| {
+                OracleError::attestation_error(format!("Untrusted signer {}", signed.signer))
+            })?;
+        if *node_id != report.node_id {
+            return Err(OracleError::attestation_error(format!(
+                "Signer {} belongs to node {}, not {}",
+                signed.signer, node_id, report.node_id
+            )));
+        }
+
+        Self::verify_signature(signed)?;
+
+        let providers = report.distinct_providers();
+        if providers < self.config.min_observations {
+            return Err(OracleError::insufficient_data(
+                report.feed_id.clone(),
+                self.config.min_observations as u32,
+                providers as u32,
+            ));
+        }
+
+        let age = now - report.timestamp;
+        if age > Duration::seconds(self.config.max_age_seconds as i64) {
+            return Err(OracleError::stale_data(
+                report.feed_id.clone(),
+                age.num_seconds() as u64,
+                self.config.max_age_seconds,
+            ));
+        }
+        if -age > Duration::seconds(self.config.max_clock_skew_seconds as i64) {
+            return Err(OracleError::attestation_error(format!(
+                "Report {} is timestamped {}s in the future",
+                report.report_id,
+                (-age).num_seconds()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Verify a set of reports for one feed and require a quorum of distinct trusted nodes
+    pub fn verify_quorum(
+        &self,
+        reports: &[SignedOracleReport],
+        now: DateTime<Utc>,
+    ) -> OracleResult<VerifiedPrice> {
+        let feed_id = reports
+            .first()
+            .map(|signed| signed.report.feed_id.clone())
+            .ok_or_else(|| OracleError::attestation_error("No reports to verify"))?;
+
+        let mut nodes = HashSet::new();
+        let mut signers = Vec::new();
+        let mut prices = Vec::new();
+        let mut timestamp = reports[0].report.timestamp;
+        for signed in reports {
+            if signed.report.feed_id != feed_id {
+                return Err(OracleError::attestation_error(format!(
+                    "Report for {} mixed into quorum for {}",
+                    signed.report.feed_id, feed_id
+                )));
+            }
+            self.verify(signed, now)?;
+
+            // Each node counts once, even if several of its keys are trusted
+            if nodes.insert(signed.report.node_id.clone()) {
+                signers.push(Self::normalize_signer(&signed.signer));
+                prices.push(signed.report.price);
+                timestamp = timestamp.min(signed.report.timestamp);
+            }
+        }
+
+        if nodes.len() < self.config.min_signers {
+            return Err(OracleError::consensus_error(format!(
+                "{} of {} required signers attested to {}",
+                nodes.len(),
+                self.config.min_signers,
+                feed_id
+            )));
+        }
+
+        prices.sort();
+        let mid = prices.len() / 2;
+        let median = if prices.len() % 2 == 0 {
+            (prices[mid - 1] + prices[mid]) / Decimal::TWO
+        } else {
+            prices[mid]
+        };
+
+        if median > Decimal::ZERO {
+            let spread = (prices[prices.len() - 1] - prices[0]) / median * Decimal::ONE_HUNDRED;
+            if spread > self.config.max_report_deviation_percent {
+                return Err(OracleError::consensus_error(format!(
+                    "Attested prices for {} spread {}%, above {}%",
+                    feed_id,
+                    spread.round_dp(4),
+                    self.config.max_report_deviation_percent
+                )));
+            }
+        }
+
+        Ok(VerifiedPrice {
+            feed_id,
+            price: median,
+            signers,
+            timestamp,
+        })
+    }
+
+    /// Check a report's signature against its declared signer
+    pub fn verify_signature(signed: &SignedOracleReport) -> OracleResult<()> {
+        verify_digest(
+            signed.scheme,
+            &signed.signer,
+            &signed.report.digest(),
+            &signed.signature,
+        )
+        .map_err(|_| {
+            OracleError::attestation_error(format!(
+                "Invalid signature on report {} from {}",
+                signed.report.report_id, signed.signer
+            ))
+        })
+    }
+
+    fn normalize_signer(signer: &str) -> String {
+        signer.trim_start_matches("0x").to_lowercase()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(provider: OracleProvider, price: i64) -> PriceData {
+        PriceData {
+            price: Decimal::new(price, 2),
+            timestamp: Utc::now(),
+            source: provider,
+            confidence: 0.95,
+            volume: None,
+            market_cap: None,
+            deviation: None,
+            round_id: Some(7),
+        }
+    }
+
+    fn sources() -> Vec<PriceData> {
+        vec![
+            source(OracleProvider::Chainlink, 10000),
+            source(OracleProvider::PythNetwork, 10010),
+        ]
+    }
+
+    fn aggregated(price: i64) -> PriceData {
+        source(OracleProvider::Custom(0), price)
+    }
+
+    fn nodes() -> (OracleNode, OracleNode) {
+        let (ed25519, _) = Ed25519Signer::generate().unwrap();
+        let (secp256k1, _) = Secp256k1Signer::generate().unwrap();
+        (
+            OracleNode::new("node-a", Box::new(ed25519)),
+            OracleNode::new("node-b", Box::new(secp256k1)),
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify_both_schemes() {
+        let (node_a, node_b) = nodes();
+        let verifier = ReportVerifier::new(VerifierConfig {
+            min_observations: 2,
+            ..VerifierConfig::default()
+        })
+        .with_trusted_signer("node-a", node_a.signer_id())
+        .with_trusted_signer("node-b", node_b.signer_id());
+
+        for node in [&node_a, &node_b] {
+            let signed = node
+                .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+                .unwrap();
+            assert_eq!(signed.report.observations.len(), 2);
+            verifier.verify(&signed, Utc::now()).unwrap();
+
+            // Any change to the report invalidates the signature
+            let mut tampered = signed.clone();
+            tampered.report.price = Decimal::new(20000, 2);
+            assert!(verifier.verify(&tampered, Utc::now()).is_err());
+
+            let mut tampered = signed.clone();
+            tampered.report.observations[0].price = Decimal::new(5000, 2);
+            assert!(verifier.verify(&tampered, Utc::now()).is_err());
+        }
+
+        // Serialized reports verify offline
+        let signed = node_b
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+            .unwrap();
+        let json = serde_json::to_string(&signed).unwrap();
+        let restored: SignedOracleReport = serde_json::from_str(&json).unwrap();
+        assert!(ReportVerifier::verify_signature(&restored).is_ok());
+    }
+
+    #[test]
+    fn test_signing_payload_fields_are_unambiguous() {
+        let (node_a, _) = nodes();
+        let signed = node_a
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+            .unwrap();
+
+        // Moving text across a field boundary must change the payload
+        let mut shifted = signed.report.clone();
+        shifted.feed_id = "RWA/USD\nnode".to_string();
+        shifted.node_id = "-a".to_string();
+        assert_ne!(shifted.signing_payload(), signed.report.signing_payload());
+
+        let payload = signed.report.signing_payload();
+        assert_eq!(&payload[..4], &(REPORT_DOMAIN.len() as u32).to_be_bytes());
+        assert!(payload.windows(6).any(|window| window == b"median"));
+        assert!(payload.windows(9).any(|window| window == b"chainlink"));
+    }
+
+    #[test]
+    fn test_untrusted_signer_and_observation_quorum() {
+        let (node_a, node_b) = nodes();
+        let verifier = ReportVerifier::new(VerifierConfig {
+            min_observations: 3,
+            ..VerifierConfig::default()
+        })
+        .with_trusted_signer("node-a", node_a.signer_id());
+
+        let signed = node_a
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+            .unwrap();
+        assert!(matches!(
+            verifier.verify(&signed, Utc::now()),
+            Err(OracleError::InsufficientData { required: 3, available: 2, .. })
+        ));
+
+        let signed = node_b
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+            .unwrap();
+        assert!(matches!(
+            verifier.verify(&signed, Utc::now()),
+            Err(OracleError::AttestationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_stale_and_future_reports_are_rejected() {
+        let (node_a, _) = nodes();
+        let verifier = ReportVerifier::new(VerifierConfig::default())
+            .with_trusted_signer("node-a", node_a.signer_id());
+        let signed = node_a
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10005), &sources())
+            .unwrap();
+        let signed_at = signed.report.timestamp;
+
+        verifier.verify(&signed, signed_at + Duration::seconds(60)).unwrap();
+        verifier.verify(&signed, signed_at - Duration::seconds(2)).unwrap();
+
+        // Replaying the report later fails, as does a verifier clock far behind it
+        assert!(matches!(
+            verifier.verify(&signed, signed_at + Duration::minutes(10)),
+            Err(OracleError::StaleData { .. })
+        ));
+        assert!(matches!(
+            verifier.verify(&signed, signed_at - Duration::minutes(1)),
+            Err(OracleError::AttestationError { .. })
+        ));
+    }
+
+    #[test]
+    fn test_signer_quorum() {
+        let (node_a, node_b) = nodes();
+        let verifier = ReportVerifier::new(VerifierConfig {
+            min_signers: 2,
+            ..VerifierConfig::default()
+        })
+        .with_trusted_signer("node-a", node_a.signer_id())
+        .with_trusted_signer("node-b", node_b.signer_id());
+
+        let report_a = node_a
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10000), &sources())
+            .unwrap();
+        let report_b = node_b
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10050), &sources())
+            .unwrap();
+
+        // The same node twice does not make a quorum
+        assert!(verifier
+            .verify_quorum(&[report_a.clone(), report_a.clone()], Utc::now())
+            .is_err());
+
+        let verified = verifier
+            .verify_quorum(&[report_a.clone(), report_b], Utc::now())
+            .unwrap();
+        assert_eq!(verified.price, Decimal::new(10025, 2));
+        assert_eq!(verified.signers.len(), 2);
+
+        // A node with a rotated key still counts once
+        let (rotated, _) = Ed25519Signer::generate().unwrap();
+        let rotated = OracleNode::new("node-a", Box::new(rotated));
+        let verifier = verifier.with_trusted_signer("node-a", rotated.signer_id());
+        let report_rotated = rotated
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10000), &sources())
+            .unwrap();
+        assert!(matches!(
+            verifier.verify_quorum(&[report_a.clone(), report_rotated], Utc::now()),
+            Err(OracleError::ConsensusError { .. })
+        ));
+
+        // Nodes attesting to prices too far apart are rejected
+        let far = node_b
+            .sign_report("RWA/USD", AggregationMethod::Median, &aggregated(10500), &sources())
+            .unwrap();
+        assert!(matches!(
+            verifier.verify_quorum(&[report_a, far], Utc::now()),
+            Err(OracleError::ConsensusError { .. })
+        ));
+    }
+}
@@ -0,0 +1,564 @@
+// =====================================================================================
+// File: core-oracle/src/circuit_breaker.rs
+// Description: Per-feed price circuit breakers that freeze feeds on abnormal moves
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::broadcast;
+
+use crate::error::{OracleError, OracleResult};
+use crate::types::{CircuitBreakerConfig, FeedStatus, PriceData};
+
+/// Circuit breaker states
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BreakerState {
+    /// Prices flow through
+    Closed,
+    /// Feed is frozen at its last good price
+    Open,
+    /// Probing whether prices have stabilized
+    HalfOpen,
+}
+
+/// Why a breaker opened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TripReason {
+    /// Providers disagree by more than the configured spread
+    CrossProviderDeviation { deviation_percent: f64 },
+    /// The aggregated price moved too far in a single update
+    TickJump { change_percent: f64 },
+    /// Consecutive update failures reached the threshold
+    ProviderFailures { consecutive_failures: u32 },
+    /// Opened by an operator
+    Manual { reason: String },
+}
+
+/// Breaker state transition, published so downstream consumers can pause
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakerEvent {
+    pub feed_id: String,
+    pub from: BreakerState,
+    pub to: BreakerState,
+    pub reason: Option<TripReason>,
+    pub last_good_price: Option<Decimal>,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Price served after passing through a breaker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardedPrice {
+    pub price: PriceData,
+    /// `FeedStatus::Frozen` when the last good price is served in place of fresh data
+    pub status: FeedStatus,
+    pub breaker_state: BreakerState,
+}
+
+impl GuardedPrice {
+    pub fn is_frozen(&self) -> bool {
+        self.status == FeedStatus::Frozen
+    }
+}
+
+/// Breaker for a single feed
+#[derive(Debug, Clone)]
+struct FeedBreaker {
+    state: BreakerState,
+    opened_at: Option<DateTime<Utc>>,
+    last_good: Option<PriceData>,
+    last_probe: Option<Decimal>,
+    half_open_successes: u32,
+    consecutive_failures: u32,
+    /// Opened by an operator; only `reset` closes it
+    manual: bool,
+}
+
+impl FeedBreaker {
+    fn new() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            opened_at: None,
+            last_good: None,
+            last_probe: None,
+            half_open_successes: 0,
+            consecutive_failures: 0,
+            manual: false,
+        }
+    }
+}
+
+/// Per-feed circuit breakers.
+///
+/// A closed breaker opens when providers disagree beyond
+/// `max_cross_provider_deviation_percent`, when the aggregated price moves
+/// more than `max_tick_change_percent` from the last good price, or after
+/// `failure_threshold` consecutive failed updates. While open the last good
+/// price is served as frozen, or an error when there is none yet. Apart from
+/// manual trips, which stay open until `reset`, after
+/// `recovery_timeout_seconds` the breaker goes half-open and requires `half_open_max_calls` consecutive stable
+/// updates before it closes. The first probe must be within the tick bound
+/// of the last good price and each later probe within the bound of the one
+/// before it. A failed probe reopens the breaker.
+pub struct CircuitBreakerRegistry {
+    config: CircuitBreakerConfig,
+    breakers: HashMap<String, FeedBreaker>,
+    events: broadcast::Sender<BreakerEvent>,
+}
+
+impl CircuitBreakerRegistry {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        let (events, _) = broadcast::channel(256);
+        Self {
+            config,
+            breakers: HashMap::new(),
+            events,
+        }
+    }
+
+    /// Subscribe to breaker transitions for all feeds
+    pub fn subscribe(&self) -> broadcast::Receiver<BreakerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Current state of a feed's breaker
+    pub fn state(&self, feed_id: &str) -> BreakerState {
+        self.breakers
+            .get(feed_id)
+            .map_or(BreakerState::Closed, |breaker| breaker.state)
+    }
+
+    /// Last price that passed the breaker
+    pub fn last_good_price(&self, feed_id: &str) -> Option<&PriceData> {
+        self.breakers
+            .get(feed_id)
+            .and_then(|breaker| breaker.last_good.as_ref())
+    }
+
+    /// Run a fresh aggregated price and its source prices through the feed's breaker.
+    ///
+    /// Fails when the breaker is not closed and no good price was ever seen.
+    pub fn evaluate(
+        &mut self,
+        feed_id: &str,
+        source_prices: &[PriceData],
+        aggregated: PriceData,
+        now: DateTime<Utc>,
+    ) -> OracleResult<GuardedPrice> {
+        if !self.config.enabled {
+            return Ok(Self::live(aggregated, BreakerState::Closed));
+        }
+
+        self.recover_if_due(feed_id, now);
+        let trip = self.check_bounds(feed_id, source_prices, &aggregated);
+        let required_probes = self.config.half_open_max_calls;
+
+        let breaker = self.breaker(feed_id);
+        breaker.consecutive_failures = 0;
+
+        match (breaker.state, trip) {
+            (BreakerState::Closed, None) => {
+                breaker.last_good = Some(aggregated.clone());
+                Ok(Self::live(aggregated, BreakerState::Closed))
+            }
+            (BreakerState::Closed, Some(reason)) | (BreakerState::HalfOpen, Some(reason)) => {
+                self.open(feed_id, reason, now);
+                self.frozen(feed_id)
+            }
+            (BreakerState::Open, _) => self.frozen(feed_id),
+            (BreakerState::HalfOpen, None) => {
+                breaker.half_open_successes += 1;
+                breaker.last_probe = Some(aggregated.price);
+                if breaker.half_open_successes < required_probes {
+                    return self.frozen(feed_id);
+                }
+
+                breaker.last_good = Some(aggregated.clone());
+                self.transition(feed_id, BreakerState::Closed, None, now);
+                Ok(Self::live(aggregated, BreakerState::Closed))
+            }
+        }
+    }
+
+    /// Record a failed update; serves the last good price once the breaker is open
+    pub fn record_failure(&mut self, feed_id: &str, now: DateTime<Utc>) -> Option<GuardedPrice> {
+        if !self.config.enabled {
+            return None;
+        }
+
+        self.recover_if_due(feed_id, now);
+        let threshold = self.config.failure_threshold;
+        let breaker = self.breaker(feed_id);
+        breaker.consecutive_failures += 1;
+        let failures = breaker.consecutive_failures;
+
+        if breaker.state != BreakerState::Open && failures >= threshold {
+            self.open(
+                feed_id,
+                TripReason::ProviderFailures {
+                    consecutive_failures: failures,
+                },
+                now,
+            );
+        }
+
+        let breaker = self.breaker(feed_id);
+        match (breaker.state, breaker.last_good.clone()) {
+            (BreakerState::Open, Some(last_good)) => Some(GuardedPrice {
+                price: last_good,
+                status: FeedStatus::Frozen,
+                breaker_state: BreakerState::Open,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Open a feed's breaker manually; it stays open until `reset`
+    pub fn trip(&mut self, feed_id: &str, reason: impl Into<String>, now: DateTime<Utc>) {
+        self.open(
+            feed_id,
+            TripReason::Manual {
+                reason: reason.into(),
+            },
+            now,
+        );
+    }
+
+    /// Close a feed's breaker manually, accepting the next price as good
+    pub fn reset(&mut self, feed_id: &str, now: DateTime<Utc>) {
+        if self.state(feed_id) != BreakerState::Closed {
+            self.transition(feed_id, BreakerState::Closed, None, now);
+        }
+        let breaker = self.breaker(feed_id);
+        breaker.last_good = None;
+        breaker.consecutive_failures = 0;
+        breaker.manual = false;
+    }
+
+    fn breaker(&mut self, feed_id: &str) -> &mut FeedBreaker {
+        self.breakers
+            .entry(feed_id.to_string())
+            .or_insert_with(FeedBreaker::new)
+    }
+
+    fn check_bounds(
+        &self,
+        feed_id: &str,
+        source_prices: &[PriceData],
+        aggregated: &PriceData,
+    ) -> Option<TripReason> {
+        let deviation_percent = cross_provider_deviation_percent(source_prices);
+        if deviation_percent > self.config.max_cross_provider_deviation_percent {
+            return Some(TripReason::CrossProviderDeviation { deviation_percent });
+        }
+
+        // Half-open probes must be stable relative to each other; the first one
+        // and closed feeds compare with the last price accepted before the trip
+        let reference = self.breakers.get(feed_id).and_then(|breaker| {
+            let last_good = breaker.last_good.as_ref().map(|good| good.price);
+            match breaker.state {
+                BreakerState::HalfOpen => breaker.last_probe.or(last_good),
+                _ => last_good,
+            }
+        })?;
+
+        let change_percent = percent_change(reference, aggregated.price);
+        if change_percent > self.config.max_tick_change_percent {
+            return Some(TripReason::TickJump { change_percent });
+        }
+
+        None
+    }
+
+    fn recover_if_due(&mut self, feed_id: &str, now: DateTime<Utc>) {
+        let timeout = Duration::seconds(self.config.recovery_timeout_seconds as i64);
+        let due = self.breakers.get(feed_id).is_some_and(|breaker| {
+            breaker.state == BreakerState::Open
+                && !breaker.manual
+                && breaker.opened_at.is_none_or(|opened| now - opened >= timeout)
+        });
+
+        if due {
+            self.transition(feed_id, BreakerState::HalfOpen, None, now);
+        }
+    }
+
+    fn open(&mut self, feed_id: &str, reason: TripReason, now: DateTime<Utc>) {
+        let breaker = self.breaker(feed_id);
+        breaker.opened_at = Some(now);
+        if matches!(reason, TripReason::Manual { .. }) {
+            breaker.manual = true;
+        }
+        if breaker.state != BreakerState::Open {
+            self.transition(feed_id, BreakerState::Open, Some(reason), now);
+        }
+    }
+
+    fn transition(
+        &mut self,
+        feed_id: &str,
+        to: BreakerState,
+        reason: Option<TripReason>,
+        now: DateTime<Utc>,
+    ) {
+        let breaker = self.breaker(feed_id);
+        let from = breaker.state;
+        breaker.state = to;
+        breaker.half_open_successes = 0;
+        breaker.last_probe = None;
+
+        let event = BreakerEvent {
+            feed_id: feed_id.to_string(),
+            from,
+            to,
+            reason,
+            last_good_price: breaker.last_good.as_ref().map(|good| good.price),
+            timestamp: now,
+        };
+        // No subscribers is not an error
+        let _ = self.events.send(event);
+    }
+
+    fn live(price: PriceData, breaker_state: BreakerState) -> GuardedPrice {
+        GuardedPrice {
+            price,
+            status: FeedStatus::Active,
+            breaker_state,
+        }
+    }
+
+    /// Serve the last good price; with no history there is nothing safe to serve
+    fn frozen(&mut self, feed_id: &str) -> OracleResult<GuardedPrice> {
+        let breaker = self.breaker(feed_id);
+        let price = breaker.last_good.clone().ok_or_else(|| {
+            OracleError::circuit_breaker_triggered(
+                feed_id.to_string(),
+                format!("Breaker is {:?} with no last good price", breaker.state),
+            )
+        })?;
+        Ok(GuardedPrice {
+            price,
+            status: FeedStatus::Frozen,
+            breaker_state: breaker.state,
+        })
+    }
+}
+
+/// Spread between the highest and lowest source price, as a percentage of their mean
+pub fn cross_provider_deviation_percent(prices: &[PriceData]) -> f64 {
+    let min = prices.iter().map(|p| p.price).min();
+    let max = prices.iter().map(|p| p.price).max();
+    match (min, max) {
+        (Some(min), Some(max)) if min + max > Decimal::ZERO => {
+            let mean = (min + max) / Decimal::TWO;
+            ((max - min) / mean * Decimal::ONE_HUNDRED)
+                .to_f64()
+                .unwrap_or(f64::MAX)
+        }
+        _ => 0.0,
+    }
+}
+
+fn percent_change(reference: Decimal, price: Decimal) -> f64 {
+    if reference.is_zero() {
+        return 0.0;
+    }
+    (((price - reference) / reference).abs() * Decimal::ONE_HUNDRED)
+        .to_f64()
+        .unwrap_or(f64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OracleProvider;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 3,
+            recovery_timeout_seconds: 60,
+            half_open_max_calls: 2,
+            max_cross_provider_deviation_percent: 5.0,
+            max_tick_change_percent: 10.0,
+        }
+    }
+
+    fn price(value: i64, source: OracleProvider) -> PriceData {
+        PriceData {
+            price: Decimal::new(value, 0),
+            timestamp: Utc::now(),
+            source,
+            confidence: 0.95,
+            volume: None,
+            market_cap: None,
+            deviation: None,
+            round_id: None,
+        }
+    }
+
+    fn tick(
+        registry: &mut CircuitBreakerRegistry,
+        value: i64,
+        now: DateTime<Utc>,
+    ) -> GuardedPrice {
+        let aggregated = price(value, OracleProvider::Custom(0));
+        registry
+            .evaluate("RWA/USD", &[aggregated.clone()], aggregated, now)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_tick_jump_opens_and_recovers() {
+        let mut registry = CircuitBreakerRegistry::new(config());
+        let mut events = registry.subscribe();
+        let start = Utc::now();
+
+        assert!(!tick(&mut registry, 100, start).is_frozen());
+        let jumped = tick(&mut registry, 130, start);
+        assert!(jumped.is_frozen());
+        assert_eq!(jumped.price.price, Decimal::new(100, 0));
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Open);
+
+        let event = events.try_recv().unwrap();
+        assert_eq!((event.from, event.to), (BreakerState::Closed, BreakerState::Open));
+        assert!(matches!(event.reason, Some(TripReason::TickJump { .. })));
+        assert_eq!(event.last_good_price, Some(Decimal::new(100, 0)));
+
+        // Still frozen before the recovery timeout
+        assert!(tick(&mut registry, 131, start + Duration::seconds(30)).is_frozen());
+
+        // Half-open: the first probe is near the last good price, the next one jumps and reopens
+        let later = start + Duration::seconds(61);
+        assert!(tick(&mut registry, 105, later).is_frozen());
+        assert_eq!(registry.state("RWA/USD"), BreakerState::HalfOpen);
+        assert!(tick(&mut registry, 160, later).is_frozen());
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Open);
+
+        // After another timeout the market has settled near 101
+        let later = later + Duration::seconds(61);
+        assert!(tick(&mut registry, 101, later).is_frozen());
+        assert_eq!(registry.state("RWA/USD"), BreakerState::HalfOpen);
+        let closed = tick(&mut registry, 102, later);
+        assert!(!closed.is_frozen());
+        assert_eq!(closed.price.price, Decimal::new(102, 0));
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Closed);
+
+        let transitions: Vec<(BreakerState, BreakerState)> =
+            std::iter::from_fn(|| events.try_recv().ok())
+                .map(|event| (event.from, event.to))
+                .collect();
+        assert_eq!(
+            transitions,
+            vec![
+                (BreakerState::Open, BreakerState::HalfOpen),
+                (BreakerState::HalfOpen, BreakerState::Open),
+                (BreakerState::Open, BreakerState::HalfOpen),
+                (BreakerState::HalfOpen, BreakerState::Closed),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wild_first_probe_keeps_breaker_open() {
+        let mut registry = CircuitBreakerRegistry::new(CircuitBreakerConfig {
+            half_open_max_calls: 1,
+            ..config()
+        });
+        let start = Utc::now();
+
+        tick(&mut registry, 100, start);
+        assert!(tick(&mut registry, 130, start).is_frozen());
+
+        // A single probe would close the breaker, so it must be bounded by the last good price
+        let later = start + Duration::seconds(61);
+        let probe = tick(&mut registry, 500, later);
+        assert!(probe.is_frozen());
+        assert_eq!(probe.price.price, Decimal::new(100, 0));
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Open);
+
+        let later = later + Duration::seconds(61);
+        assert!(!tick(&mut registry, 104, later).is_frozen());
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Closed);
+    }
+
+    #[test]
+    fn test_cross_provider_deviation_opens() {
+        let mut registry = CircuitBreakerRegistry::new(config());
+        let now = Utc::now();
+        let sources = vec![
+            price(100, OracleProvider::Chainlink),
+            price(101, OracleProvider::BandProtocol),
+        ];
+        let guarded = registry
+            .evaluate("RWA/USD", &sources, price(100, OracleProvider::Custom(0)), now)
+            .unwrap();
+        assert!(!guarded.is_frozen());
+
+        let sources = vec![
+            price(100, OracleProvider::Chainlink),
+            price(112, OracleProvider::PythNetwork),
+        ];
+        let guarded = registry
+            .evaluate("RWA/USD", &sources, price(106, OracleProvider::Custom(0)), now)
+            .unwrap();
+        assert!(guarded.is_frozen());
+        assert_eq!(guarded.price.price, Decimal::new(100, 0));
+        assert!((cross_provider_deviation_percent(&sources) - 11.320754).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_consecutive_failures_open() {
+        let mut registry = CircuitBreakerRegistry::new(config());
+        let now = Utc::now();
+        tick(&mut registry, 100, now);
+
+        assert!(registry.record_failure("RWA/USD", now).is_none());
+        assert!(registry.record_failure("RWA/USD", now).is_none());
+        let frozen = registry.record_failure("RWA/USD", now).unwrap();
+        assert_eq!(frozen.status, FeedStatus::Frozen);
+        assert_eq!(frozen.price.price, Decimal::new(100, 0));
+
+        registry.reset("RWA/USD", now);
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Closed);
+        assert!(!tick(&mut registry, 150, now).is_frozen());
+    }
+
+    #[test]
+    fn test_manual_trip_stays_open_until_reset() {
+        let mut registry = CircuitBreakerRegistry::new(config());
+        let start = Utc::now();
+        tick(&mut registry, 100, start);
+
+        registry.trip("RWA/USD", "maintenance", start);
+        let later = start + Duration::seconds(600);
+        assert!(tick(&mut registry, 100, later).is_frozen());
+        assert!(tick(&mut registry, 100, later).is_frozen());
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Open);
+
+        registry.reset("RWA/USD", later);
+        assert!(!tick(&mut registry, 100, later).is_frozen());
+
+        // A later automatic trip recovers on its own again
+        tick(&mut registry, 130, later);
+        let recovered = later + Duration::seconds(61);
+        tick(&mut registry, 101, recovered);
+        assert_eq!(registry.state("RWA/USD"), BreakerState::HalfOpen);
+    }
+
+    #[test]
+    fn test_first_price_tripping_breaker_is_not_served() {
+        let mut registry = CircuitBreakerRegistry::new(config());
+        let now = Utc::now();
+        let sources = vec![
+            price(100, OracleProvider::Chainlink),
+            price(120, OracleProvider::PythNetwork),
+        ];
+        let result = registry.evaluate("RWA/USD", &sources, price(110, OracleProvider::Custom(0)), now);
+        assert!(matches!(result, Err(OracleError::CircuitBreakerTriggered { .. })));
+        assert_eq!(registry.state("RWA/USD"), BreakerState::Open);
+    }
+}
@@ -117,6 +117,10 @@ pub enum OracleError {
     #[error("Consensus error: {message}")]
     ConsensusError { message: String },
 
+    /// Report signing or verification errors
+    #[error("Attestation error: {message}")]
+    AttestationError { message: String },
+
     /// Internal errors
     #[error("Internal error: {message}")]
     InternalError { message: String },
@@ -278,6 +282,13 @@ impl OracleError {
         }
     }
 
+    /// Create an attestation error
+    pub fn attestation_error<S: Into<String>>(message: S) -> Self {
+        Self::AttestationError {
+            message: message.into(),
+        }
+    }
+
     /// Create an internal error
     pub fn internal_error<S: Into<String>>(message: S) -> Self {
         Self::InternalError {
@@ -308,6 +319,7 @@ impl OracleError {
             Self::FeedNotFound { .. } => "FEED_NOT_FOUND",
             Self::InvalidPrice { .. } => "INVALID_PRICE",
             Self::ConsensusError { .. } => "CONSENSUS_ERROR",
+            Self::AttestationError { .. } => "ATTESTATION_ERROR",
             Self::InternalError { .. } => "INTERNAL_ERROR",
         }
     }
@@ -343,6 +355,7 @@ impl OracleError {
                 | Self::PriceDeviation { .. }
                 | Self::StaleData { .. }
                 | Self::ConsensusError { .. }
+                | Self::AttestationError { .. }
         )
     }
 
@@ -11,34 +11,50 @@
 //! Pyth Network, and UMA.
 
 pub mod aggregator;
+pub mod attestation;
 pub mod band;
 pub mod chainlink;
+pub mod circuit_breaker;
 pub mod error;
+pub mod history;
 pub mod pyth;
 pub mod service;
 pub mod types;
+pub mod window;
 
 // Re-export main types and traits
 pub use aggregator::{
-    AggregationResult, ConsensusMethod, MultiSourceAggregator, OutlierDetectionResult,
-    PriceAggregator,
+    AggregationResult, ConsensusMethod, CustomAggregationFn, MultiSourceAggregator,
+    OutlierDetectionResult, PriceAggregator,
+};
+pub use attestation::{
+    Ed25519Signer, OracleNode, OracleReport, ProviderObservation, ReportSigner, ReportVerifier,
+    Secp256k1Signer, SignatureScheme, SignedOracleReport, VerifiedPrice, VerifierConfig,
+    verify_digest,
 };
 pub use band::{BandFeed, BandOracle, BandOracleScript, BandPriceResponse, BandRequest};
 pub use chainlink::{
     ChainlinkAggregator, ChainlinkFeed, ChainlinkNetwork, ChainlinkOracle, ChainlinkPriceResponse,
     ChainlinkRoundData,
 };
+pub use circuit_breaker::{
+    BreakerEvent, BreakerState, CircuitBreakerRegistry, GuardedPrice, TripReason,
+};
 pub use error::{OracleError, OracleResult};
+pub use history::{InMemoryPriceHistory, PriceHistoryStore, RetentionPolicy};
+#[cfg(feature = "database")]
+pub use history::PostgresPriceHistory;
 pub use pyth::{PythFeed, PythOracle, PythPrice, PythPriceData, PythPriceFeedResponse};
 pub use service::{
     HistoricalQuery, MockProviderClient, OracleService, OracleServiceImpl, PriceQuery,
     ProviderClient,
 };
 pub use types::{
-    AggregationMethod, AlertSeverity, AlertType, DataSource, FeedConfig, OracleConfig,
-    OracleHealthStatus, OracleProvider, PriceData, PriceFeed, PricePoint, ProviderConfig,
-    TimeSeriesData, ValidationRule,
+    AggregationMethod, AlertSeverity, AlertType, CircuitBreakerConfig, DataSource, FeedConfig,
+    FeedStatus, OracleConfig, OracleHealthStatus, OracleProvider, PriceData, PriceFeed,
+    PricePoint, ProviderConfig, TimeSeriesData, ValidationRule,
 };
+pub use window::{ObservationWindow, WindowConfig};
 
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
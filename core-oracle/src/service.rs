@@ -10,15 +10,19 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, RwLock};
+use tracing::warn;
 use uuid::Uuid;
 
 use crate::{
+    attestation::{OracleNode, SignedOracleReport},
+    circuit_breaker::{BreakerEvent, BreakerState, CircuitBreakerRegistry, GuardedPrice},
     error::{OracleError, OracleResult},
+    history::PriceHistoryStore,
     types::{
-        AggregationMethod, FeedConfig, FeedSubscription, OracleConfig, OracleHealthStatus,
-        OracleProvider, OracleRequest, OracleResponse, PriceAlert, PriceData, PriceFeed,
-        TimeSeriesData, ValidationRule,
+        AggregationMethod, FeedConfig, FeedStatus, FeedSubscription, OracleConfig,
+        OracleHealthStatus, OracleProvider, OracleRequest, OracleResponse, PriceAlert, PriceData,
+        PriceFeed, TimeSeriesData, ValidationRule,
     },
 };
 
@@ -35,7 +39,7 @@ pub trait OracleService: Send + Sync {
         provider: OracleProvider,
     ) -> OracleResult<PriceData>;
 
-    /// Get historical prices
+    /// Get hourly historical prices
     async fn get_historical_prices(
         &self,
         feed_id: &str,
@@ -43,6 +47,9 @@ pub trait OracleService: Send + Sync {
         end_time: chrono::DateTime<chrono::Utc>,
     ) -> OracleResult<TimeSeriesData>;
 
+    /// Get historical prices, downsampled to the query interval
+    async fn query_historical_prices(&self, query: &HistoricalQuery) -> OracleResult<TimeSeriesData>;
+
     /// Subscribe to price feed updates
     async fn subscribe_to_feed(&self, subscription: &FeedSubscription) -> OracleResult<Uuid>;
 
@@ -104,6 +111,12 @@ pub struct OracleServiceImpl {
     feeds: Arc<RwLock<HashMap<String, PriceFeed>>>,
     subscriptions: Arc<RwLock<HashMap<Uuid, FeedSubscription>>>,
     price_cache: Arc<RwLock<HashMap<String, PriceData>>>,
+    circuit_breakers: Arc<RwLock<CircuitBreakerRegistry>>,
+    history: Option<Arc<dyn PriceHistoryStore>>,
+    /// Node key that attests to every accepted aggregated price
+    node: Option<OracleNode>,
+    /// Latest signed report per feed
+    signed_reports: Arc<RwLock<HashMap<String, SignedOracleReport>>>,
     provider_clients: HashMap<OracleProvider, Box<dyn ProviderClient>>,
     start_time: chrono::DateTime<chrono::Utc>,
 }
@@ -190,16 +203,170 @@ impl OracleServiceImpl {
             }
         }
 
+        let circuit_breakers = CircuitBreakerRegistry::new(config.circuit_breaker.clone());
+
         Self {
             config,
             feeds: Arc::new(RwLock::new(HashMap::new())),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
             price_cache: Arc::new(RwLock::new(HashMap::new())),
+            circuit_breakers: Arc::new(RwLock::new(circuit_breakers)),
+            history: None,
+            node: None,
+            signed_reports: Arc::new(RwLock::new(HashMap::new())),
             provider_clients,
             start_time: Utc::now(),
         }
     }
 
+    /// Replace the provider clients, e.g. with custom or test clients
+    pub fn with_provider_clients(
+        mut self,
+        provider_clients: HashMap<OracleProvider, Box<dyn ProviderClient>>,
+    ) -> Self {
+        self.provider_clients = provider_clients;
+        self
+    }
+
+    /// Persist every accepted price to a history store
+    pub fn with_history_store(mut self, history: Arc<dyn PriceHistoryStore>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Sign every accepted aggregated price with this node's key
+    pub fn with_node(mut self, node: OracleNode) -> Self {
+        self.node = Some(node);
+        self
+    }
+
+    /// Signed report for the latest accepted price of a feed
+    pub async fn get_signed_report(&self, feed_id: &str) -> Option<SignedOracleReport> {
+        self.signed_reports.read().await.get(feed_id).cloned()
+    }
+
+    /// Subscribe to circuit breaker transitions for all feeds
+    pub async fn subscribe_breaker_events(&self) -> broadcast::Receiver<BreakerEvent> {
+        self.circuit_breakers.read().await.subscribe()
+    }
+
+    /// Per-feed circuit breakers, for manual trips and resets
+    pub fn circuit_breakers(&self) -> Arc<RwLock<CircuitBreakerRegistry>> {
+        self.circuit_breakers.clone()
+    }
+
+    /// Get the current price for a feed through its circuit breaker.
+    ///
+    /// While the breaker is open the last good price is returned with
+    /// `FeedStatus::Frozen`; frozen prices are never cached.
+    pub async fn get_guarded_price(&self, feed_id: &str) -> OracleResult<GuardedPrice> {
+        // Check cache first; a tripped breaker must re-evaluate instead
+        let breaker_state = self.circuit_breakers.read().await.state(feed_id);
+        if breaker_state == BreakerState::Closed {
+            let cache = self.price_cache.read().await;
+            if let Some(cached_price) = cache.get(feed_id) {
+                let age = Utc::now() - cached_price.timestamp;
+                if age.num_seconds() < 60 {
+                    // Cache for 1 minute
+                    return Ok(GuardedPrice {
+                        price: cached_price.clone(),
+                        status: FeedStatus::Active,
+                        breaker_state,
+                    });
+                }
+            }
+        }
+
+        // Get prices from all available providers
+        let mut prices = Vec::new();
+        for (provider, client) in &self.provider_clients {
+            match client.get_price(feed_id).await {
+                Ok(price_data) => {
+                    if self.validate_price(feed_id, &price_data).await.is_ok() {
+                        prices.push(price_data);
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to get price from {:?}: {}", provider, e);
+                }
+            }
+        }
+
+        if prices.is_empty() {
+            let frozen = self
+                .circuit_breakers
+                .write()
+                .await
+                .record_failure(feed_id, Utc::now());
+            return match frozen {
+                Some(frozen) => {
+                    self.set_feed_status(feed_id, frozen.status).await;
+                    Ok(frozen)
+                }
+                None => Err(OracleError::feed_not_found(feed_id.to_string())),
+            };
+        }
+
+        // Aggregate prices
+        let method = self.config.aggregation.default_method;
+        let aggregated_price = self.aggregate_prices(prices.clone(), method).await?;
+
+        let guarded = self.circuit_breakers.write().await.evaluate(
+            feed_id,
+            &prices,
+            aggregated_price,
+            Utc::now(),
+        );
+        let guarded = match guarded {
+            Ok(guarded) => guarded,
+            Err(e) => {
+                self.set_feed_status(feed_id, FeedStatus::Frozen).await;
+                return Err(e);
+            }
+        };
+        self.set_feed_status(feed_id, guarded.status).await;
+
+        // Update cache, attestation and history
+        if !guarded.is_frozen() {
+            self.price_cache
+                .write()
+                .await
+                .insert(feed_id.to_string(), guarded.price.clone());
+
+            if let Some(node) = &self.node {
+                let mut signed_reports = self.signed_reports.write().await;
+                match node.sign_report(feed_id, method, &guarded.price, &prices) {
+                    Ok(report) => {
+                        signed_reports.insert(feed_id.to_string(), report);
+                    }
+                    Err(e) => {
+                        // Never leave a report for an older price in place
+                        warn!("Failed to sign report for {}: {}", feed_id, e);
+                        signed_reports.remove(feed_id);
+                    }
+                }
+            }
+
+            // History is best effort; a failed write must not withhold a valid price
+            if let Some(history) = &self.history {
+                if let Err(e) = history.record(feed_id, &guarded.price).await {
+                    warn!("Failed to record price history for {}: {}", feed_id, e);
+                }
+            }
+        }
+
+        Ok(guarded)
+    }
+
+    async fn set_feed_status(&self, feed_id: &str, status: FeedStatus) {
+        let mut feeds = self.feeds.write().await;
+        if let Some(feed) = feeds.get_mut(feed_id) {
+            if feed.status == FeedStatus::Active || feed.status == FeedStatus::Frozen {
+                feed.status = status;
+            }
+        }
+    }
+
     /// Initialize service with default feeds
     pub async fn initialize(&self) -> OracleResult<()> {
         // Add some default feeds
@@ -304,7 +471,7 @@ impl OracleServiceImpl {
         if price_data.price <= Decimal::ZERO {
             return Err(OracleError::invalid_price(
                 feed_id,
-                price_data.price.to_string(),
+                &price_data.price.to_string(),
                 "Price must be positive",
             ));
         }
@@ -334,50 +501,16 @@ impl OracleServiceImpl {
 
 #[async_trait]
 impl OracleService for OracleServiceImpl {
+    /// Only live prices are returned; use `get_guarded_price` to read frozen ones
     async fn get_price(&self, feed_id: &str) -> OracleResult<PriceData> {
-        // Check cache first
-        {
-            let cache = self.price_cache.read().await;
-            if let Some(cached_price) = cache.get(feed_id) {
-                let age = Utc::now() - cached_price.timestamp;
-                if age.num_seconds() < 60 {
-                    // Cache for 1 minute
-                    return Ok(cached_price.clone());
-                }
-            }
-        }
-
-        // Get prices from all available providers
-        let mut prices = Vec::new();
-        for (provider, client) in &self.provider_clients {
-            match client.get_price(feed_id).await {
-                Ok(price_data) => {
-                    if self.validate_price(feed_id, &price_data).await.is_ok() {
-                        prices.push(price_data);
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to get price from {:?}: {}", provider, e);
-                }
-            }
-        }
-
-        if prices.is_empty() {
-            return Err(OracleError::feed_not_found(feed_id.to_string()));
-        }
-
-        // Aggregate prices
-        let aggregated_price = self
-            .aggregate_prices(prices, self.config.aggregation.default_method)
-            .await?;
-
-        // Update cache
-        {
-            let mut cache = self.price_cache.write().await;
-            cache.insert(feed_id.to_string(), aggregated_price.clone());
+        let guarded = self.get_guarded_price(feed_id).await?;
+        if guarded.breaker_state != BreakerState::Closed {
+            return Err(OracleError::circuit_breaker_triggered(
+                feed_id.to_string(),
+                format!("Breaker is {:?}, price is frozen", guarded.breaker_state),
+            ));
         }
-
-        Ok(aggregated_price)
+        Ok(guarded.price)
     }
 
     async fn get_price_from_provider(
@@ -401,12 +534,29 @@ impl OracleService for OracleServiceImpl {
         start_time: chrono::DateTime<chrono::Utc>,
         end_time: chrono::DateTime<chrono::Utc>,
     ) -> OracleResult<TimeSeriesData> {
+        self.query_historical_prices(&HistoricalQuery {
+            feed_id: feed_id.to_string(),
+            start_time,
+            end_time,
+            interval: crate::types::TimeInterval::OneHour,
+            providers: None,
+        })
+        .await
+    }
+
+    async fn query_historical_prices(&self, query: &HistoricalQuery) -> OracleResult<TimeSeriesData> {
+        if let Some(history) = &self.history {
+            return history
+                .query_range(&query.feed_id, query.start_time, query.end_time, query.interval)
+                .await;
+        }
+
         // Mock historical data generation
         let mut points = Vec::new();
-        let mut current_time = start_time;
-        let interval = chrono::Duration::hours(1);
+        let mut current_time = query.start_time;
+        let interval = chrono::Duration::seconds(query.interval.to_seconds() as i64);
 
-        while current_time <= end_time {
+        while current_time <= query.end_time {
             let base_price = Decimal::new(200000, 2); // $2000.00
             let variation = (current_time.timestamp() % 100) as i64;
             let price = base_price + Decimal::new(variation, 0);
@@ -425,11 +575,11 @@ impl OracleService for OracleServiceImpl {
         }
 
         Ok(TimeSeriesData {
-            symbol: feed_id.to_string(),
-            interval: crate::types::TimeInterval::OneHour,
+            symbol: query.feed_id.clone(),
+            interval: query.interval,
             points,
-            start_time,
-            end_time,
+            start_time: query.start_time,
+            end_time: query.end_time,
         })
     }
 
@@ -650,4 +800,217 @@ mod tests {
             .unwrap();
         assert_eq!(aggregated.price, Decimal::new(101, 0));
     }
+
+    /// Provider client whose price can be moved between calls
+    struct SteppingProviderClient {
+        price: Arc<RwLock<Decimal>>,
+    }
+
+    #[async_trait]
+    impl ProviderClient for SteppingProviderClient {
+        async fn get_price(&self, _feed_id: &str) -> OracleResult<PriceData> {
+            Ok(PriceData {
+                price: *self.price.read().await,
+                timestamp: Utc::now(),
+                source: OracleProvider::Chainlink,
+                confidence: 0.95,
+                volume: None,
+                market_cap: None,
+                deviation: None,
+                round_id: None,
+            })
+        }
+
+        async fn get_health(&self) -> OracleResult<crate::types::ProviderHealth> {
+            MockProviderClient::new(OracleProvider::Chainlink).get_health().await
+        }
+
+        fn provider(&self) -> OracleProvider {
+            OracleProvider::Chainlink
+        }
+    }
+
+    #[tokio::test]
+    async fn test_price_jump_freezes_feed() {
+        let price = Arc::new(RwLock::new(Decimal::new(100, 0)));
+        let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+        clients.insert(
+            OracleProvider::Chainlink,
+            Box::new(SteppingProviderClient {
+                price: price.clone(),
+            }),
+        );
+        let service = OracleServiceImpl::new(OracleConfig::default()).with_provider_clients(clients);
+        service.initialize().await.unwrap();
+        let mut events = service.subscribe_breaker_events().await;
+
+        assert_eq!(service.get_price("ETH/USD").await.unwrap().price, Decimal::new(100, 0));
+
+        // A 50% jump trips the breaker; the last good price is served frozen
+        *price.write().await = Decimal::new(150, 0);
+        service.price_cache.write().await.clear();
+        let guarded = service.get_guarded_price("ETH/USD").await.unwrap();
+        assert!(guarded.is_frozen());
+        assert_eq!(guarded.price.price, Decimal::new(100, 0));
+        assert_eq!(
+            service.get_feed_info("ETH/USD").await.unwrap().status,
+            FeedStatus::Frozen
+        );
+        // Plain price reads refuse the frozen price instead of passing it off as live
+        assert!(matches!(
+            service.get_price("ETH/USD").await,
+            Err(OracleError::CircuitBreakerTriggered { .. })
+        ));
+
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.feed_id, "ETH/USD");
+        assert_eq!(event.to, crate::circuit_breaker::BreakerState::Open);
+    }
+
+    #[tokio::test]
+    async fn test_accepted_prices_are_signed() {
+        let price = Arc::new(RwLock::new(Decimal::new(100, 0)));
+        let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+        clients.insert(
+            OracleProvider::Chainlink,
+            Box::new(SteppingProviderClient {
+                price: price.clone(),
+            }),
+        );
+        let (signer, _) = crate::attestation::Ed25519Signer::generate().unwrap();
+        let node = OracleNode::new("node-a", Box::new(signer));
+        let verifier = crate::attestation::ReportVerifier::new(Default::default())
+            .with_trusted_signer("node-a", node.signer_id());
+        let service = OracleServiceImpl::new(OracleConfig::default())
+            .with_provider_clients(clients)
+            .with_node(node);
+        service.initialize().await.unwrap();
+        assert!(service.get_signed_report("ETH/USD").await.is_none());
+
+        service.get_price("ETH/USD").await.unwrap();
+        let signed = service.get_signed_report("ETH/USD").await.unwrap();
+        assert_eq!(signed.report.price, Decimal::new(100, 0));
+        assert_eq!(signed.report.method, AggregationMethod::Median);
+        assert_eq!(signed.report.observations.len(), 1);
+        verifier.verify(&signed, Utc::now()).unwrap();
+
+        // A frozen price is not attested
+        *price.write().await = Decimal::new(150, 0);
+        service.price_cache.write().await.clear();
+        assert!(service.get_guarded_price("ETH/USD").await.unwrap().is_frozen());
+        let latest = service.get_signed_report("ETH/USD").await.unwrap();
+        assert_eq!(latest.report.report_id, signed.report.report_id);
+    }
+
+    #[tokio::test]
+    async fn test_open_breaker_bypasses_price_cache() {
+        let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+        clients.insert(
+            OracleProvider::Chainlink,
+            Box::new(MockProviderClient::new(OracleProvider::Chainlink)),
+        );
+        let service = OracleServiceImpl::new(OracleConfig::default()).with_provider_clients(clients);
+        service.initialize().await.unwrap();
+
+        let live = service.get_guarded_price("ETH/USD").await.unwrap();
+        assert_eq!(live.status, FeedStatus::Active);
+
+        // The cached price is still fresh, but the tripped feed must read as frozen
+        service
+            .circuit_breakers()
+            .write()
+            .await
+            .trip("ETH/USD", "maintenance", Utc::now());
+        let guarded = service.get_guarded_price("ETH/USD").await.unwrap();
+        assert!(guarded.is_frozen());
+        assert_eq!(guarded.breaker_state, BreakerState::Open);
+        assert_eq!(guarded.price.price, live.price.price);
+    }
+
+    #[tokio::test]
+    async fn test_accepted_prices_are_recorded() {
+        let history = Arc::new(crate::history::InMemoryPriceHistory::default());
+        let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+        clients.insert(
+            OracleProvider::Chainlink,
+            Box::new(MockProviderClient::new(OracleProvider::Chainlink)),
+        );
+        let service = OracleServiceImpl::new(OracleConfig::default())
+            .with_provider_clients(clients)
+            .with_history_store(history.clone());
+        service.initialize().await.unwrap();
+
+        let price = service.get_price("ETH/USD").await.unwrap();
+        let query = HistoricalQuery {
+            feed_id: "ETH/USD".to_string(),
+            start_time: price.timestamp - chrono::Duration::minutes(1),
+            end_time: price.timestamp + chrono::Duration::minutes(1),
+            interval: crate::types::TimeInterval::OneMinute,
+            providers: None,
+        };
+        let series = service.query_historical_prices(&query).await.unwrap();
+        assert_eq!(series.points.len(), 1);
+        assert_eq!(series.points[0].close, price.price);
+    }
+
+    /// History store whose writes always fail
+    struct FailingHistory;
+
+    #[async_trait]
+    impl PriceHistoryStore for FailingHistory {
+        async fn record(&self, _feed_id: &str, _price_data: &PriceData) -> OracleResult<()> {
+            Err(OracleError::database_error("connection refused"))
+        }
+
+        async fn query_range(
+            &self,
+            _feed_id: &str,
+            _start: chrono::DateTime<chrono::Utc>,
+            _end: chrono::DateTime<chrono::Utc>,
+            _interval: crate::types::TimeInterval,
+        ) -> OracleResult<TimeSeriesData> {
+            Err(OracleError::database_error("connection refused"))
+        }
+
+        async fn latest(&self, _feed_id: &str) -> OracleResult<Option<crate::types::PricePoint>> {
+            Ok(None)
+        }
+
+        async fn apply_retention(&self, _now: chrono::DateTime<chrono::Utc>) -> OracleResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_failure_still_serves_price() {
+        let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+        clients.insert(
+            OracleProvider::Chainlink,
+            Box::new(MockProviderClient::new(OracleProvider::Chainlink)),
+        );
+        let service = OracleServiceImpl::new(OracleConfig::default())
+            .with_provider_clients(clients)
+            .with_history_store(Arc::new(FailingHistory));
+        service.initialize().await.unwrap();
+
+        let price = service.get_price("ETH/USD").await.unwrap();
+        assert!(price.price > Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_historical_prices_use_query_interval() {
+        let service = OracleServiceImpl::new(OracleConfig::default());
+        let start_time = Utc::now() - chrono::Duration::hours(1);
+        let query = HistoricalQuery {
+            feed_id: "ETH/USD".to_string(),
+            start_time,
+            end_time: start_time + chrono::Duration::hours(1),
+            interval: crate::types::TimeInterval::FifteenMinutes,
+            providers: None,
+        };
+
+        let series = service.query_historical_prices(&query).await.unwrap();
+        assert_eq!(series.interval, crate::types::TimeInterval::FifteenMinutes);
+        assert_eq!(series.points.len(), 5);
+    }
 }
@@ -77,6 +77,10 @@ pub struct CircuitBreakerConfig {
     pub failure_threshold: u32,
     pub recovery_timeout_seconds: u64,
     pub half_open_max_calls: u32,
+    /// Maximum spread between provider prices, as a percentage of their mean
+    pub max_cross_provider_deviation_percent: f64,
+    /// Maximum change between consecutive aggregated prices
+    pub max_tick_change_percent: f64,
 }
 
 /// Oracle providers
@@ -192,6 +196,8 @@ pub enum FeedStatus {
     Deprecated,
     Maintenance,
     Error,
+    /// Circuit breaker open; the last good price is being served
+    Frozen,
 }
 
 /// Aggregation methods
@@ -415,6 +421,8 @@ impl Default for OracleConfig {
                 failure_threshold: 5,
                 recovery_timeout_seconds: 300,
                 half_open_max_calls: 3,
+                max_cross_provider_deviation_percent: 5.0,
+                max_tick_change_percent: 10.0,
             },
         }
     }
@@ -0,0 +1,268 @@
+// =====================================================================================
+// File: core-oracle/src/window.rs
+// Description: Rolling price observation windows and time/volume weighted averages
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+use crate::{
+    error::{OracleError, OracleResult},
+    types::{PriceData, PricePoint},
+};
+
+/// Observation window configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowConfig {
+    /// Maximum number of observations kept per feed
+    pub capacity: usize,
+    /// Observations older than this, relative to the newest, are evicted
+    pub max_age_seconds: u64,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 1024,
+            max_age_seconds: 3600, // 1 hour
+        }
+    }
+}
+
+/// Rolling window of price observations for a single feed.
+///
+/// Behaves as a ring buffer: once full, the oldest observation is dropped for
+/// each new one. Observations are kept in timestamp order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObservationWindow {
+    config: WindowConfig,
+    points: VecDeque<PricePoint>,
+}
+
+impl ObservationWindow {
+    pub fn new(config: WindowConfig) -> Self {
+        Self {
+            points: VecDeque::with_capacity(config.capacity.min(1024)),
+            config,
+        }
+    }
+
+    /// Record an observation, evicting by capacity and age
+    pub fn push(&mut self, point: PricePoint) {
+        let position = self
+            .points
+            .iter()
+            .rposition(|existing| existing.timestamp <= point.timestamp)
+            .map_or(0, |index| index + 1);
+        self.points.insert(position, point);
+
+        while self.points.len() > self.config.capacity {
+            self.points.pop_front();
+        }
+
+        if let Some(newest) = self.points.back().map(|p| p.timestamp) {
+            let cutoff = newest - Duration::seconds(self.config.max_age_seconds as i64);
+            while self.points.front().is_some_and(|p| p.timestamp < cutoff) {
+                self.points.pop_front();
+            }
+        }
+    }
+
+    /// Record a price observation
+    pub fn push_price(&mut self, price_data: &PriceData) {
+        self.push(PricePoint::from(price_data));
+    }
+
+    /// Observations from `since` onwards, oldest first
+    pub fn points_since(&self, since: DateTime<Utc>) -> Vec<PricePoint> {
+        self.points
+            .iter()
+            .filter(|p| p.timestamp >= since)
+            .cloned()
+            .collect()
+    }
+
+    pub fn points(&self) -> impl Iterator<Item = &PricePoint> {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&PricePoint> {
+        self.points.back()
+    }
+
+    /// Time-weighted average price up to `end`
+    pub fn twap(&self, end: DateTime<Utc>) -> OracleResult<Decimal> {
+        let points: Vec<PricePoint> = self.points.iter().cloned().collect();
+        time_weighted_average(&points, end)
+    }
+
+    /// Volume-weighted average price over the window
+    pub fn vwap(&self) -> OracleResult<Decimal> {
+        let points: Vec<PricePoint> = self.points.iter().cloned().collect();
+        volume_weighted_average(&points)
+    }
+}
+
+/// Observation windows keyed by feed ID
+#[derive(Debug, Clone, Default)]
+pub struct ObservationStore {
+    config: WindowConfig,
+    windows: HashMap<String, ObservationWindow>,
+}
+
+impl ObservationStore {
+    pub fn new(config: WindowConfig) -> Self {
+        Self {
+            config,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Record an observation for a feed
+    pub fn record(&mut self, feed_id: &str, point: PricePoint) {
+        let config = &self.config;
+        self.windows
+            .entry(feed_id.to_string())
+            .or_insert_with(|| ObservationWindow::new(config.clone()))
+            .push(point);
+    }
+
+    pub fn window(&self, feed_id: &str) -> Option<&ObservationWindow> {
+        self.windows.get(feed_id)
+    }
+}
+
+impl From<&PriceData> for PricePoint {
+    fn from(price_data: &PriceData) -> Self {
+        Self {
+            timestamp: price_data.timestamp,
+            open: price_data.price,
+            high: price_data.price,
+            low: price_data.price,
+            close: price_data.price,
+            volume: price_data.volume.unwrap_or(Decimal::ZERO),
+            source_count: 1,
+        }
+    }
+}
+
+/// Time-weighted average of closing prices.
+///
+/// Each close is held from its timestamp until the next observation, and the
+/// last one until `end`. When all observations share one instant the
+/// plain mean is returned.
+pub fn time_weighted_average(points: &[PricePoint], end: DateTime<Utc>) -> OracleResult<Decimal> {
+    if points.is_empty() {
+        return Err(OracleError::insufficient_data("twap", 1, 0));
+    }
+
+    let mut sorted: Vec<&PricePoint> = points.iter().collect();
+    sorted.sort_by_key(|p| p.timestamp);
+
+    let mut weighted_sum = Decimal::ZERO;
+    let mut total_ms = Decimal::ZERO;
+    for (index, point) in sorted.iter().enumerate() {
+        let until = sorted.get(index + 1).map_or(end, |next| next.timestamp);
+        let held_ms = (until - point.timestamp).num_milliseconds().max(0);
+        weighted_sum += point.close * Decimal::from(held_ms);
+        total_ms += Decimal::from(held_ms);
+    }
+
+    if total_ms.is_zero() {
+        let sum: Decimal = sorted.iter().map(|p| p.close).sum();
+        return Ok(sum / Decimal::from(sorted.len()));
+    }
+
+    Ok(weighted_sum / total_ms)
+}
+
+/// Volume-weighted average of typical prices, (high + low + close) / 3
+pub fn volume_weighted_average(points: &[PricePoint]) -> OracleResult<Decimal> {
+    let total_volume: Decimal = points.iter().map(|p| p.volume).sum();
+    if total_volume <= Decimal::ZERO {
+        return Err(OracleError::aggregation_error(
+            "VWAP requires observations with volume",
+        ));
+    }
+
+    let weighted_sum: Decimal = points
+        .iter()
+        .map(|p| (p.high + p.low + p.close) / Decimal::new(3, 0) * p.volume)
+        .sum();
+
+    Ok(weighted_sum / total_volume)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(seconds: i64, close: i64, volume: i64) -> PricePoint {
+        let price = Decimal::new(close, 0);
+        PricePoint {
+            timestamp: DateTime::from_timestamp(1_700_000_000 + seconds, 0).unwrap(),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::new(volume, 0),
+            source_count: 1,
+        }
+    }
+
+    #[test]
+    fn test_time_weighted_average() {
+        let points = vec![point(0, 100, 1), point(10, 110, 1), point(40, 120, 1)];
+        let end = points[0].timestamp + Duration::seconds(60);
+
+        // (100 * 10 + 110 * 30 + 120 * 20) / 60
+        let twap = time_weighted_average(&points, end).unwrap();
+        assert_eq!(twap.round_dp(6), Decimal::new(111_666_667, 6));
+
+        // Simultaneous observations fall back to the mean
+        let twap = time_weighted_average(&points[..1], points[0].timestamp).unwrap();
+        assert_eq!(twap, Decimal::new(100, 0));
+    }
+
+    #[test]
+    fn test_volume_weighted_average() {
+        let mut bar = point(0, 100, 2);
+        bar.high = Decimal::new(106, 0);
+        bar.low = Decimal::new(97, 0);
+        let points = vec![bar, point(10, 110, 3)];
+
+        // (101 * 2 + 110 * 3) / 5
+        assert_eq!(volume_weighted_average(&points).unwrap(), Decimal::new(1064, 1));
+        assert!(volume_weighted_average(&[point(0, 100, 0)]).is_err());
+    }
+
+    #[test]
+    fn test_window_evicts_by_capacity_and_age() {
+        let mut window = ObservationWindow::new(WindowConfig {
+            capacity: 3,
+            max_age_seconds: 60,
+        });
+        for (seconds, price) in [(0, 100), (20, 101), (10, 102), (30, 103)] {
+            window.push(point(seconds, price, 1));
+        }
+
+        // Out-of-order observations are kept sorted; the oldest is dropped at capacity
+        let closes: Vec<Decimal> = window.points().map(|p| p.close).collect();
+        assert_eq!(closes, vec![Decimal::new(102, 0), Decimal::new(101, 0), Decimal::new(103, 0)]);
+
+        window.push(point(85, 104, 1));
+        assert_eq!(window.len(), 2);
+        assert_eq!(window.latest().unwrap().close, Decimal::new(104, 0));
+    }
+}
@@ -111,6 +111,8 @@ impl Default for OracleTestConfig {
                     failure_threshold: 5,
                     recovery_timeout_seconds: 300,
                     half_open_max_calls: 3,
+                    max_cross_provider_deviation_percent: 5.0,
+                    max_tick_change_percent: 10.0,
                 },
             },
             aggregation_config: AggregationConfig {
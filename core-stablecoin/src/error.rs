@@ -125,6 +125,10 @@ pub enum StablecoinError {
     #[error("Price feed manipulation detected: {0}")]
     PriceFeedManipulation(String),
 
+    // Reserve errors
+    #[error("Reserve proof invalid: {0}")]
+    ReserveProofInvalid(String),
+
     // Governance errors
     #[error("Proposal not found: {0}")]
     ProposalNotFound(String),
@@ -204,6 +208,7 @@ impl StablecoinError {
             StablecoinError::RiskLimitExceeded(_) | StablecoinError::LiquidationThreshold(_) => "risk_management",
             StablecoinError::TransactionFailed(_) | StablecoinError::SmartContractError(_) => "blockchain",
             StablecoinError::OracleUnavailable(_) | StablecoinError::StalePriceData { .. } => "oracle",
+            StablecoinError::ReserveProofInvalid(_) => "reserves",
             StablecoinError::ProposalNotFound(_) | StablecoinError::VotingPeriodEnded(_) => "governance",
             _ => "system",
         }
@@ -243,6 +248,13 @@ impl From<serde_json::Error> for StablecoinError {
     }
 }
 
+#[cfg(feature = "database")]
+impl From<sqlx::Error> for StablecoinError {
+    fn from(err: sqlx::Error) -> Self {
+        StablecoinError::DatabaseError(err.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
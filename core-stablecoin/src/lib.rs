@@ -29,6 +29,9 @@
 //! - `governance`: Decentralized governance mechanisms
 //! - `compliance`: Regulatory compliance and reporting
 //! - `risk_management`: Risk assessment and mitigation
+//! - `oracle_adapter`: core-oracle providers and services as `PriceOracle` sources
+//! - `reserves`: Proof of reserves with Merkle sum tree liabilities and signed asset attestations
+//! - `stress_testing`: Deterministic depeg, bank-run and oracle outage scenario simulation
 //! - `service`: High-level service interfaces
 //!
 //! ## Usage Example
@@ -64,7 +67,10 @@ pub mod compliance;
 pub mod monitoring;
 pub mod risk_management;
 pub mod oracle;
+pub mod oracle_adapter;
 pub mod liquidity;
+pub mod reserves;
+pub mod stress_testing;
 pub mod service;
 
 // Re-export main types and traits
@@ -78,9 +84,17 @@ pub use mechanisms::{
     StabilityMechanismTrait
 };
 pub use issuance::{IssuanceService, IssuanceManager};
-pub use redemption::{RedemptionService, RedemptionManager};
+pub use redemption::{
+    RedemptionService, RedemptionManager, RedemptionQueueConfig, SettlementSchedule, QueuePosition,
+    EpochSettlement,
+};
 pub use stability::{StabilityService, EnterprisePriceStabilizer, EnterpriseArbitrageBot};
-pub use governance::{GovernanceService, ProposalManager};
+pub use governance::{
+    GovernanceConfig, GovernanceService, GovernanceStore, InMemoryGovernanceStore,
+    InMemoryTokenLedger, ParameterChange, ProposalManager, TokenLedger,
+};
+#[cfg(feature = "database")]
+pub use governance::PostgresGovernanceStore;
 pub use compliance::{
     StablecoinComplianceService, EnterpriseStablecoinCompliance,
     StablecoinComplianceConfig, StablecoinTransactionData, StablecoinTransactionType,
@@ -95,7 +109,15 @@ pub use monitoring::{
 };
 pub use risk_management::{RiskManager, CollateralManager};
 pub use oracle::{PriceOracle, OracleAggregator, PriceData, OracleConfig};
+pub use oracle_adapter::{AdapterConfig, OracleServiceAdapter, ProviderClientAdapter};
 pub use liquidity::{LiquidityManager, EnterpriseLiquidityManager, LiquidityPosition, PoolInfo};
+pub use reserves::{
+    AssetAttestation, CustodianAssetEntry, HolderBalance, InclusionProof, LiabilityTree,
+    ProofOfReservesGenerator, ProofOfReservesReport,
+};
+pub use stress_testing::{
+    MarketShock, SimulationConfig, StressScenario, StressTestReport, StressTestSimulator,
+};
 pub use service::{StablecoinService, StablecoinServiceImpl};
 
 /// Current version of the core-stablecoin module
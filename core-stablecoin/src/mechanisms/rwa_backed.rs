@@ -76,6 +76,20 @@ impl RWABacked {
         Ok(())
     }
 
+    /// Current RWA portfolio
+    pub fn portfolio(&self) -> &RWAPortfolio {
+        &self.portfolio
+    }
+
+    /// Custodian-reported entries for every portfolio asset, for proof of reserves
+    pub fn custodian_entries(&self, custodian: &str) -> Vec<crate::reserves::CustodianAssetEntry> {
+        self.portfolio
+            .assets
+            .values()
+            .map(|asset| crate::reserves::CustodianAssetEntry::from_rwa_asset(custodian, asset))
+            .collect()
+    }
+
     /// Validate RWA asset for inclusion
     async fn validate_rwa_asset(&self, asset: &RWAAsset) -> StablecoinResult<()> {
         // Check asset type is supported
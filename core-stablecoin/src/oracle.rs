@@ -180,6 +180,11 @@ impl OracleAggregator {
             return Ok(false);
         }
 
+        // Check source confidence
+        if price.confidence < self.config.min_confidence {
+            return Ok(false);
+        }
+
         // Check against cached price for deviation
         if let Some(cached) = self.price_cache.get(&price.asset_symbol) {
             let deviation = ((price.price - cached.price.price) / cached.price.price).abs();
@@ -356,6 +361,8 @@ pub struct OracleConfig {
     pub cache_ttl_seconds: u64,
     pub min_oracle_sources: u32,
     pub max_price_deviation: Decimal,
+    /// Minimum source confidence (0-100), matching core-oracle's 0.5 floor
+    pub min_confidence: Decimal,
     pub emergency_cache_minutes: u32,
     pub circuit_breaker_threshold: u32,
 }
@@ -368,6 +375,7 @@ impl Default for OracleConfig {
             cache_ttl_seconds: 60,      // 1 minute
             min_oracle_sources: 2,
             max_price_deviation: Decimal::new(5, 2), // 5%
+            min_confidence: Decimal::new(50, 0),
             emergency_cache_minutes: 30,
             circuit_breaker_threshold: 5,
         }
@@ -637,6 +645,13 @@ mod tests {
             confidence: Decimal::new(95, 0),
         };
         assert!(!aggregator.validate_price(&stale_price).await.unwrap());
+
+        // Low confidence price
+        let uncertain_price = PriceData {
+            confidence: Decimal::new(20, 0),
+            ..valid_price
+        };
+        assert!(!aggregator.validate_price(&uncertain_price).await.unwrap());
     }
 
     #[tokio::test]
@@ -0,0 +1,387 @@
+// =====================================================================================
+// File: core-stablecoin/src/oracle_adapter.rs
+// Description: Adapters plugging core-oracle providers and services into OracleAggregator
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use core_oracle::{
+    service::OracleService,
+    types::{HealthStatus, TimeInterval},
+    HistoricalQuery, OracleError, OracleServiceImpl, ProviderClient,
+};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use tracing::warn;
+
+use crate::oracle::{OracleConfig, OracleHealth, OracleMetadata, PriceData, PriceOracle};
+use crate::{StablecoinError, StablecoinResult};
+
+/// Shared settings for core-oracle adapters.
+///
+/// Staleness and confidence thresholds are applied by the adapter before a
+/// price reaches `OracleAggregator`, so a stale or low-confidence provider
+/// fails over to the next oracle exactly like an unavailable one.
+#[derive(Debug, Clone)]
+pub struct AdapterConfig {
+    /// Asset symbol -> core-oracle feed ID; unmapped assets use `{symbol}{feed_suffix}`
+    pub feed_ids: HashMap<String, String>,
+    pub feed_suffix: String,
+    pub max_price_age_seconds: u64,
+    /// Minimum confidence on the 0-100 scale used by `PriceData`
+    pub min_confidence: Decimal,
+    pub update_frequency_seconds: u64,
+    /// Bucket size of historical price queries
+    pub history_interval: TimeInterval,
+}
+
+impl Default for AdapterConfig {
+    fn default() -> Self {
+        let oracle_config = OracleConfig::default();
+        Self {
+            feed_ids: HashMap::new(),
+            feed_suffix: "/USD".to_string(),
+            max_price_age_seconds: oracle_config.max_price_age_seconds,
+            min_confidence: oracle_config.min_confidence,
+            update_frequency_seconds: 60,
+            history_interval: TimeInterval::OneHour,
+        }
+    }
+}
+
+impl AdapterConfig {
+    /// Thresholds taken from the aggregator configuration the adapter will be plugged into
+    pub fn from_oracle_config(config: &OracleConfig) -> Self {
+        Self {
+            max_price_age_seconds: config.max_price_age_seconds,
+            min_confidence: config.min_confidence,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_feed(mut self, asset_symbol: &str, feed_id: &str) -> Self {
+        self.feed_ids
+            .insert(asset_symbol.to_string(), feed_id.to_string());
+        self
+    }
+
+    /// core-oracle feed ID for an asset symbol
+    pub fn feed_id(&self, asset_symbol: &str) -> String {
+        self.feed_ids
+            .get(asset_symbol)
+            .cloned()
+            .unwrap_or_else(|| format!("{}{}", asset_symbol, self.feed_suffix))
+    }
+
+    /// Convert and check a core-oracle price
+    fn accept(
+        &self,
+        asset_symbol: &str,
+        source: String,
+        price: &core_oracle::PriceData,
+    ) -> StablecoinResult<PriceData> {
+        let converted = convert_price(asset_symbol, source, price);
+
+        let age = Utc::now() - converted.timestamp;
+        if age.num_seconds() > self.max_price_age_seconds as i64 {
+            return Err(StablecoinError::StalePriceData {
+                last_update: converted.timestamp.to_rfc3339(),
+                max_age: format!("{}s", self.max_price_age_seconds),
+            });
+        }
+
+        if converted.confidence < self.min_confidence {
+            return Err(StablecoinError::OracleUnavailable(format!(
+                "Confidence {} below {} for {} from {}",
+                converted.confidence, self.min_confidence, asset_symbol, converted.source
+            )));
+        }
+
+        Ok(converted)
+    }
+
+    fn supported_assets(&self) -> Vec<String> {
+        let mut assets: Vec<String> = self.feed_ids.keys().cloned().collect();
+        assets.sort();
+        assets
+    }
+}
+
+/// Convert a core-oracle price (confidence 0-1) to a stablecoin price (confidence 0-100)
+pub fn convert_price(
+    asset_symbol: &str,
+    source: String,
+    price: &core_oracle::PriceData,
+) -> PriceData {
+    let confidence = Decimal::from_f64(price.confidence * 100.0)
+        .unwrap_or(Decimal::ZERO)
+        .max(Decimal::ZERO)
+        .min(Decimal::new(100, 0))
+        .round_dp(2);
+
+    PriceData {
+        asset_symbol: asset_symbol.to_string(),
+        price: price.price,
+        timestamp: price.timestamp,
+        source,
+        confidence,
+    }
+}
+
+/// Map core-oracle errors onto the stablecoin error taxonomy
+pub fn map_oracle_error(error: OracleError) -> StablecoinError {
+    match error {
+        OracleError::StaleData {
+            age_seconds,
+            threshold_seconds,
+            ..
+        } => StablecoinError::StalePriceData {
+            last_update: format!("{}s ago", age_seconds),
+            max_age: format!("{}s", threshold_seconds),
+        },
+        OracleError::Timeout { operation } => StablecoinError::TimeoutError(operation),
+        OracleError::PriceDeviation { .. } | OracleError::CircuitBreakerTriggered { .. } => {
+            StablecoinError::PriceFeedManipulation(error.to_string())
+        }
+        OracleError::RateLimitExceeded { .. } => {
+            StablecoinError::RateLimitExceeded(error.to_string())
+        }
+        other => StablecoinError::OracleUnavailable(other.to_string()),
+    }
+}
+
+fn oracle_health(
+    status: HealthStatus,
+    last_update: Option<DateTime<Utc>>,
+    response_time_ms: u64,
+) -> OracleHealth {
+    OracleHealth {
+        is_healthy: matches!(status, HealthStatus::Healthy | HealthStatus::Degraded),
+        last_update,
+        error_message: match status {
+            HealthStatus::Healthy => None,
+            other => Some(format!("{:?}", other)),
+        },
+        response_time_ms: Some(response_time_ms),
+    }
+}
+
+/// A single core-oracle provider (Chainlink, Pyth, Band, ...) as a `PriceOracle`
+pub struct ProviderClientAdapter<P: ProviderClient> {
+    client: P,
+    config: AdapterConfig,
+}
+
+impl<P: ProviderClient> ProviderClientAdapter<P> {
+    pub fn new(client: P, config: AdapterConfig) -> Self {
+        Self { client, config }
+    }
+
+    fn source(&self) -> String {
+        format!("{:?}", self.client.provider()).to_lowercase()
+    }
+}
+
+#[async_trait]
+impl<P: ProviderClient> PriceOracle for ProviderClientAdapter<P> {
+    async fn get_price(&self, asset_symbol: &str) -> StablecoinResult<PriceData> {
+        let feed_id = self.config.feed_id(asset_symbol);
+        let price = self
+            .client
+            .get_price(&feed_id)
+            .await
+            .map_err(map_oracle_error)?;
+        self.config.accept(asset_symbol, self.source(), &price)
+    }
+
+    async fn get_historical_prices(
+        &self,
+        asset_symbol: &str,
+        _from: DateTime<Utc>,
+        _to: DateTime<Utc>,
+    ) -> StablecoinResult<Vec<PriceData>> {
+        // Provider clients only expose the latest round
+        Err(StablecoinError::OracleUnavailable(format!(
+            "{} does not serve historical prices for {}",
+            self.source(),
+            asset_symbol
+        )))
+    }
+
+    async fn health_check(&self) -> StablecoinResult<OracleHealth> {
+        let health = self.client.get_health().await.map_err(map_oracle_error)?;
+        Ok(oracle_health(
+            health.status,
+            Some(health.last_successful_update),
+            health.response_time_ms,
+        ))
+    }
+
+    fn supported_assets(&self) -> Vec<String> {
+        self.config.supported_assets()
+    }
+
+    fn metadata(&self) -> OracleMetadata {
+        OracleMetadata {
+            name: self.source(),
+            version: crate::VERSION.to_string(),
+            supported_assets: self.supported_assets(),
+            update_frequency_seconds: self.config.update_frequency_seconds,
+            reliability_score: Decimal::new(90, 0),
+        }
+    }
+}
+
+/// The full core-oracle service, with its aggregation and circuit breakers, as a `PriceOracle`.
+///
+/// Frozen feeds are reported as unavailable rather than served, so
+/// `OracleAggregator` fails over instead of consuming a held price.
+pub struct OracleServiceAdapter {
+    service: Arc<OracleServiceImpl>,
+    config: AdapterConfig,
+}
+
+impl OracleServiceAdapter {
+    pub fn new(service: Arc<OracleServiceImpl>, config: AdapterConfig) -> Self {
+        Self { service, config }
+    }
+}
+
+#[async_trait]
+impl PriceOracle for OracleServiceAdapter {
+    async fn get_price(&self, asset_symbol: &str) -> StablecoinResult<PriceData> {
+        let feed_id = self.config.feed_id(asset_symbol);
+        let guarded = self
+            .service
+            .get_guarded_price(&feed_id)
+            .await
+            .map_err(map_oracle_error)?;
+
+        if guarded.is_frozen() {
+            warn!("core-oracle feed {} is frozen, failing over", feed_id);
+            return Err(StablecoinError::OracleUnavailable(format!(
+                "Feed {} is frozen by its circuit breaker",
+                feed_id
+            )));
+        }
+
+        self.config
+            .accept(asset_symbol, "core-oracle".to_string(), &guarded.price)
+    }
+
+    async fn get_historical_prices(
+        &self,
+        asset_symbol: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> StablecoinResult<Vec<PriceData>> {
+        let query = HistoricalQuery {
+            feed_id: self.config.feed_id(asset_symbol),
+            start_time: from,
+            end_time: to,
+            interval: self.config.history_interval,
+            providers: None,
+        };
+        let series = self
+            .service
+            .query_historical_prices(&query)
+            .await
+            .map_err(map_oracle_error)?;
+
+        Ok(series
+            .points
+            .into_iter()
+            .map(|point| PriceData {
+                asset_symbol: asset_symbol.to_string(),
+                price: point.close,
+                timestamp: point.timestamp,
+                source: "core-oracle".to_string(),
+                confidence: Decimal::new(100, 0),
+            })
+            .collect())
+    }
+
+    async fn health_check(&self) -> StablecoinResult<OracleHealth> {
+        let started = Instant::now();
+        let health = self
+            .service
+            .get_health_status()
+            .await
+            .map_err(map_oracle_error)?;
+        Ok(oracle_health(
+            health.overall_status,
+            Some(health.last_updated),
+            started.elapsed().as_millis() as u64,
+        ))
+    }
+
+    fn supported_assets(&self) -> Vec<String> {
+        self.config.supported_assets()
+    }
+
+    fn metadata(&self) -> OracleMetadata {
+        OracleMetadata {
+            name: "core-oracle".to_string(),
+            version: crate::VERSION.to_string(),
+            supported_assets: self.supported_assets(),
+            update_frequency_seconds: self.config.update_frequency_seconds,
+            reliability_score: Decimal::new(95, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_oracle::{MockProviderClient, OracleProvider};
+
+    #[test]
+    fn test_feed_mapping_and_conversion() {
+        let config = AdapterConfig::default().with_feed("RWAUSD", "RWA-USD-NAV");
+        assert_eq!(config.feed_id("BTC"), "BTC/USD");
+        assert_eq!(config.feed_id("RWAUSD"), "RWA-USD-NAV");
+
+        let mut price = core_oracle::PriceData {
+            price: Decimal::new(100, 2),
+            timestamp: Utc::now(),
+            source: OracleProvider::Chainlink,
+            confidence: 0.95,
+            volume: None,
+            market_cap: None,
+            deviation: None,
+            round_id: None,
+        };
+        let accepted = config
+            .accept("USDC", "chainlink".to_string(), &price)
+            .unwrap();
+        assert_eq!(accepted.confidence, Decimal::new(95, 0));
+
+        price.confidence = 0.2;
+        assert!(config.accept("USDC", "chainlink".to_string(), &price).is_err());
+
+        price.confidence = 0.95;
+        price.timestamp = Utc::now() - chrono::Duration::hours(1);
+        assert!(matches!(
+            config.accept("USDC", "chainlink".to_string(), &price),
+            Err(StablecoinError::StalePriceData { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_provider_client_adapter() {
+        let adapter = ProviderClientAdapter::new(
+            MockProviderClient::new(OracleProvider::PythNetwork),
+            AdapterConfig::default(),
+        );
+
+        let price = adapter.get_price("ETH").await.unwrap();
+        assert_eq!(price.asset_symbol, "ETH");
+        assert_eq!(price.price, Decimal::new(199975, 2));
+        assert_eq!(price.source, "pythnetwork");
+        assert!(adapter.health_check().await.unwrap().is_healthy);
+    }
+}
@@ -6,11 +6,13 @@
 
 use async_trait::async_trait;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::RoundingStrategy;
 use uuid::Uuid;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 use crate::{StablecoinResult, StablecoinError};
 use crate::types::{RedemptionRequest, CollateralPosition, CollateralType, CollateralStatus};
@@ -39,6 +41,119 @@ pub enum RedemptionStatus {
     Cancelled,
 }
 
+/// Settlement delay per collateral class, in days (T+N)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettlementSchedule {
+    pub fiat_days: u32,
+    pub crypto_days: u32,
+    pub rwa_days: u32,
+}
+
+impl Default for SettlementSchedule {
+    fn default() -> Self {
+        Self {
+            fiat_days: 1,   // T+1 bank wire
+            crypto_days: 0, // T+0 on-chain transfer
+            rwa_days: 3,    // T+3 custodian transfer
+        }
+    }
+}
+
+impl SettlementSchedule {
+    /// Settlement delay for a collateral type; a basket settles with its slowest component
+    pub fn days_for(&self, collateral_type: &CollateralType) -> u32 {
+        match collateral_type {
+            CollateralType::Fiat { .. } => self.fiat_days,
+            CollateralType::Crypto { .. } => self.crypto_days,
+            CollateralType::RWA { .. } => self.rwa_days,
+            CollateralType::Basket { assets } => assets
+                .iter()
+                .map(|asset| self.days_for(asset))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+/// Queue settings enabling epoch-gated redemptions
+#[derive(Debug, Clone)]
+pub struct RedemptionQueueConfig {
+    /// Minimum time between epoch settlements
+    pub epoch_duration_seconds: i64,
+    /// Maximum total amount redeemed per epoch
+    pub epoch_cap: Decimal,
+    /// Maximum amount a single user can redeem per epoch
+    pub per_user_epoch_cap: Option<Decimal>,
+    pub settlement: SettlementSchedule,
+}
+
+impl Default for RedemptionQueueConfig {
+    fn default() -> Self {
+        Self {
+            epoch_duration_seconds: 86_400, // daily
+            epoch_cap: Decimal::new(5_000_000, 0),
+            per_user_epoch_cap: None,
+            settlement: SettlementSchedule::default(),
+        }
+    }
+}
+
+/// A redemption waiting in the queue
+#[derive(Debug, Clone)]
+pub struct QueuedRedemption {
+    pub transaction_id: Uuid,
+    pub request: RedemptionRequest,
+    pub remaining: Decimal,
+    pub filled: Decimal,
+}
+
+/// A user's view of their place in the queue
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuePosition {
+    pub stablecoin_id: Uuid,
+    /// 1-based position in the queue
+    pub position: usize,
+    /// Unfilled amount queued ahead of this request
+    pub amount_ahead: Decimal,
+    pub remaining: Decimal,
+    pub filled: Decimal,
+    /// Epochs until fully filled if every epoch runs at its cap
+    pub estimated_epochs: u64,
+}
+
+/// Collateral released to one request in an epoch
+#[derive(Debug, Clone)]
+pub struct RedemptionFill {
+    pub transaction_id: Uuid,
+    pub user_id: String,
+    pub amount: Decimal,
+    /// Released collateral; `locked_until` is the settlement date of each leg
+    pub collateral: Vec<CollateralPosition>,
+    pub fully_filled: bool,
+}
+
+/// Result of settling one epoch
+#[derive(Debug, Clone)]
+pub struct EpochSettlement {
+    pub stablecoin_id: Uuid,
+    pub epoch: u64,
+    pub settled_at: DateTime<Utc>,
+    /// Amount eligible this epoch after per-user caps
+    pub requested: Decimal,
+    /// Amount that could be released, bounded by the epoch cap and the collateral pool
+    pub capacity: Decimal,
+    /// Fraction of each eligible amount filled
+    pub fill_ratio: Decimal,
+    pub fills: Vec<RedemptionFill>,
+}
+
+#[derive(Debug, Default)]
+struct RedemptionQueue {
+    entries: VecDeque<QueuedRedemption>,
+    epoch: u64,
+    last_settled_at: Option<DateTime<Utc>>,
+}
+
 /// Redemption service trait
 #[async_trait]
 pub trait RedemptionService: Send + Sync {
@@ -73,6 +188,8 @@ pub struct RedemptionManager {
     user_transactions: Arc<Mutex<HashMap<String, Vec<Uuid>>>>,
     stablecoin_totals: Arc<Mutex<HashMap<Uuid, Decimal>>>,
     available_collateral: Arc<Mutex<HashMap<Uuid, Vec<CollateralPosition>>>>,
+    queue_config: Option<RedemptionQueueConfig>,
+    queues: Arc<Mutex<HashMap<Uuid, RedemptionQueue>>>,
 }
 
 impl RedemptionManager {
@@ -85,6 +202,8 @@ impl RedemptionManager {
             user_transactions: Arc::new(Mutex::new(HashMap::new())),
             stablecoin_totals: Arc::new(Mutex::new(HashMap::new())),
             available_collateral: Arc::new(Mutex::new(HashMap::new())),
+            queue_config: None,
+            queues: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -101,6 +220,8 @@ impl RedemptionManager {
             user_transactions: Arc::new(Mutex::new(HashMap::new())),
             stablecoin_totals: Arc::new(Mutex::new(HashMap::new())),
             available_collateral: Arc::new(Mutex::new(HashMap::new())),
+            queue_config: None,
+            queues: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -113,6 +234,204 @@ impl RedemptionManager {
         Ok(())
     }
 
+    /// Route redemptions through an epoch-gated queue instead of settling them immediately
+    pub fn with_queue(mut self, config: RedemptionQueueConfig) -> Self {
+        self.queue_config = Some(config);
+        self
+    }
+
+    /// Settle one epoch of queued redemptions.
+    ///
+    /// Every queued request receives the same fraction of its eligible amount
+    /// and the same mix of pool collateral, so early redeemers cannot drain the
+    /// liquid part of the pool and leave later ones with illiquid assets.
+    pub async fn process_epoch(&self, stablecoin_id: Uuid, now: DateTime<Utc>) -> StablecoinResult<EpochSettlement> {
+        let config = self.queue_config.as_ref()
+            .ok_or(StablecoinError::InvalidConfiguration("Redemption queue is not enabled".to_string()))?;
+
+        let mut queues = self.queues.lock().await;
+        let queue = queues.entry(stablecoin_id).or_default();
+
+        if let Some(last_settled_at) = queue.last_settled_at {
+            let next_epoch = last_settled_at + Duration::seconds(config.epoch_duration_seconds);
+            if now < next_epoch {
+                return Err(StablecoinError::RateLimitExceeded(format!(
+                    "Epoch {} cannot settle before {}",
+                    queue.epoch + 1,
+                    next_epoch.to_rfc3339()
+                )));
+            }
+        }
+
+        // Eligible amount per request after per-user caps, allocated in queue order
+        let mut user_budgets: HashMap<String, Decimal> = HashMap::new();
+        let eligible: Vec<Decimal> = queue.entries.iter()
+            .map(|entry| match config.per_user_epoch_cap {
+                Some(cap) => {
+                    let budget = user_budgets.entry(entry.request.user_id.clone()).or_insert(cap);
+                    let amount = entry.remaining.min(*budget);
+                    *budget -= amount;
+                    amount
+                }
+                None => entry.remaining,
+            })
+            .collect();
+        let requested: Decimal = eligible.iter().sum();
+
+        let mut available = self.available_collateral.lock().await;
+        let pool = available.entry(stablecoin_id).or_default();
+        let pool_snapshot: Vec<Decimal> = pool.iter()
+            .map(|position| if position.status == CollateralStatus::Active { position.value_usd } else { Decimal::ZERO })
+            .collect();
+        let pool_total: Decimal = pool_snapshot.iter().sum();
+
+        let capacity = config.epoch_cap.min(pool_total);
+        let fill_ratio = if requested <= capacity { Decimal::ONE } else { capacity / requested };
+
+        let mut fills = Vec::new();
+        for (entry, eligible) in queue.entries.iter_mut().zip(eligible) {
+            let target = (eligible * fill_ratio).round_dp_with_strategy(8, RoundingStrategy::ToZero);
+            if target.is_zero() {
+                continue;
+            }
+
+            let collateral = release_pro_rata(pool, &pool_snapshot, pool_total, target, now, &config.settlement);
+            let amount: Decimal = collateral.iter().map(|c| c.value_usd).sum();
+            entry.remaining -= amount;
+            entry.filled += amount;
+
+            fills.push(RedemptionFill {
+                transaction_id: entry.transaction_id,
+                user_id: entry.request.user_id.clone(),
+                amount,
+                collateral,
+                fully_filled: entry.remaining.is_zero(),
+            });
+        }
+
+        queue.entries.retain(|entry| !entry.remaining.is_zero());
+        queue.epoch += 1;
+        queue.last_settled_at = Some(now);
+        let epoch = queue.epoch;
+        drop(available);
+        drop(queues);
+
+        {
+            let mut transactions = self.transactions.lock().await;
+            for fill in &fills {
+                if let Some(transaction) = transactions.get_mut(&fill.transaction_id) {
+                    transaction.collateral_released.extend(fill.collateral.iter().cloned());
+                    if fill.fully_filled {
+                        transaction.status = RedemptionStatus::Completed;
+                        transaction.completed_at = Some(now);
+                    } else {
+                        transaction.status = RedemptionStatus::Processing;
+                    }
+                }
+            }
+        }
+
+        {
+            let mut totals = self.stablecoin_totals.lock().await;
+            let filled: Decimal = fills.iter().map(|fill| fill.amount).sum();
+            *totals.entry(stablecoin_id).or_insert(Decimal::ZERO) += filled;
+        }
+
+        Ok(EpochSettlement {
+            stablecoin_id,
+            epoch,
+            settled_at: now,
+            requested,
+            capacity,
+            fill_ratio,
+            fills,
+        })
+    }
+
+    /// Where a queued redemption stands, or `None` once it has left the queue
+    pub async fn queue_position(&self, transaction_id: Uuid) -> StablecoinResult<Option<QueuePosition>> {
+        let epoch_cap = self.queue_config.as_ref().map(|config| config.epoch_cap);
+        let queues = self.queues.lock().await;
+
+        for (stablecoin_id, queue) in queues.iter() {
+            let total_queued: Decimal = queue.entries.iter().map(|entry| entry.remaining).sum();
+            let mut amount_ahead = Decimal::ZERO;
+
+            for (index, entry) in queue.entries.iter().enumerate() {
+                if entry.transaction_id == transaction_id {
+                    // Pro-rata fills clear the whole queue together
+                    let estimated_epochs = match epoch_cap {
+                        Some(cap) if cap > Decimal::ZERO => (total_queued / cap).ceil().to_u64().unwrap_or(u64::MAX),
+                        _ => 0,
+                    };
+
+                    return Ok(Some(QueuePosition {
+                        stablecoin_id: *stablecoin_id,
+                        position: index + 1,
+                        amount_ahead,
+                        remaining: entry.remaining,
+                        filled: entry.filled,
+                        estimated_epochs,
+                    }));
+                }
+                amount_ahead += entry.remaining;
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Redemptions waiting in a stablecoin's queue, in queue order
+    pub async fn queued_redemptions(&self, stablecoin_id: Uuid) -> StablecoinResult<Vec<QueuedRedemption>> {
+        let queues = self.queues.lock().await;
+        Ok(queues.get(&stablecoin_id)
+            .map(|queue| queue.entries.iter().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    /// Record a validated request as pending and append it to the queue
+    async fn enqueue_redemption(&self, request: RedemptionRequest, fees: Decimal) -> StablecoinResult<RedemptionTransaction> {
+        let tx_id = Uuid::new_v4();
+        let transaction = RedemptionTransaction {
+            id: tx_id,
+            request_id: request.id,
+            tx_hash: format!("redemption_tx_{}", tx_id.simple()),
+            amount: request.amount,
+            fees,
+            collateral_released: Vec::new(),
+            status: RedemptionStatus::Pending,
+            created_at: Utc::now(),
+            completed_at: None,
+        };
+
+        {
+            let mut transactions = self.transactions.lock().await;
+            transactions.insert(tx_id, transaction.clone());
+        }
+
+        {
+            let mut user_txs = self.user_transactions.lock().await;
+            user_txs.entry(request.user_id.clone())
+                .or_default()
+                .push(tx_id);
+        }
+
+        {
+            let mut queues = self.queues.lock().await;
+            queues.entry(request.stablecoin_id)
+                .or_default()
+                .entries
+                .push_back(QueuedRedemption {
+                    transaction_id: tx_id,
+                    remaining: request.amount,
+                    filled: Decimal::ZERO,
+                    request,
+                });
+        }
+
+        Ok(transaction)
+    }
+
     /// Select collateral for redemption based on preference
     async fn select_collateral(
         &self,
@@ -186,6 +505,60 @@ impl RedemptionManager {
     }
 }
 
+/// Take `amount` from the pool in proportion to each position's share of the epoch snapshot
+fn release_pro_rata(
+    pool: &mut [CollateralPosition],
+    snapshot: &[Decimal],
+    pool_total: Decimal,
+    amount: Decimal,
+    now: DateTime<Utc>,
+    schedule: &SettlementSchedule,
+) -> Vec<CollateralPosition> {
+    let last_funded = snapshot.iter().rposition(|share| !share.is_zero());
+    let mut outstanding = amount;
+    let mut released = Vec::new();
+
+    for (index, (position, share)) in pool.iter_mut().zip(snapshot).enumerate() {
+        if share.is_zero() || outstanding.is_zero() {
+            continue;
+        }
+
+        // The last funded position absorbs rounding so the fill matches the target
+        let slice = if Some(index) == last_funded {
+            outstanding
+        } else {
+            (amount * *share / pool_total).round_dp_with_strategy(8, RoundingStrategy::ToZero)
+        }
+        .min(position.value_usd);
+        if slice.is_zero() {
+            continue;
+        }
+
+        let units = if position.value_usd.is_zero() {
+            Decimal::ZERO
+        } else {
+            (position.amount * slice / position.value_usd).round_dp(8)
+        };
+
+        let mut leg = position.clone();
+        leg.amount = units;
+        leg.value_usd = slice;
+        leg.status = CollateralStatus::Locked;
+        leg.locked_until = Some(now + Duration::days(schedule.days_for(&position.collateral_type) as i64));
+
+        position.amount -= units;
+        position.value_usd -= slice;
+        if position.value_usd <= Decimal::ZERO {
+            position.status = CollateralStatus::Used;
+        }
+
+        outstanding -= slice;
+        released.push(leg);
+    }
+
+    released
+}
+
 impl Default for RedemptionManager {
     fn default() -> Self {
         Self::new()
@@ -201,6 +574,11 @@ impl RedemptionService for RedemptionManager {
         // Calculate fees
         let fees = self.calculate_fees(request.amount).await?;
 
+        // Queued redemptions settle in epochs
+        if self.queue_config.is_some() {
+            return self.enqueue_redemption(request, fees).await;
+        }
+
         // Select collateral for redemption
         let collateral_released = self.select_collateral(
             request.stablecoin_id,
@@ -316,19 +694,28 @@ impl RedemptionService for RedemptionManager {
     }
 
     async fn cancel_redemption(&self, tx_id: Uuid) -> StablecoinResult<bool> {
-        let mut transactions = self.transactions.lock().await;
+        {
+            let mut transactions = self.transactions.lock().await;
 
-        if let Some(transaction) = transactions.get_mut(&tx_id) {
-            match transaction.status {
-                RedemptionStatus::Pending | RedemptionStatus::Processing => {
-                    transaction.status = RedemptionStatus::Cancelled;
-                    Ok(true)
+            if let Some(transaction) = transactions.get_mut(&tx_id) {
+                match transaction.status {
+                    RedemptionStatus::Pending | RedemptionStatus::Processing => {
+                        transaction.status = RedemptionStatus::Cancelled;
+                    }
+                    _ => return Err(StablecoinError::InvalidRequest("Cannot cancel completed or failed transaction".to_string()))
                 }
-                _ => Err(StablecoinError::InvalidRequest("Cannot cancel completed or failed transaction".to_string()))
+            } else {
+                return Err(StablecoinError::TransactionNotFound);
             }
-        } else {
-            Err(StablecoinError::TransactionNotFound)
         }
+
+        // Drop any unfilled remainder from the queue
+        let mut queues = self.queues.lock().await;
+        for queue in queues.values_mut() {
+            queue.entries.retain(|entry| entry.transaction_id != tx_id);
+        }
+
+        Ok(true)
     }
 
     async fn get_total_redeemed(&self, stablecoin_id: Uuid) -> StablecoinResult<Decimal> {
@@ -651,4 +1038,132 @@ mod tests {
         let updated_tx = manager.get_transaction(tx_id).await.unwrap().unwrap();
         assert_eq!(updated_tx.status, RedemptionStatus::Cancelled);
     }
+
+    fn queued_request(stablecoin_id: Uuid, user_id: &str, amount: i64) -> RedemptionRequest {
+        RedemptionRequest {
+            id: Uuid::new_v4(),
+            user_id: user_id.to_string(),
+            stablecoin_id,
+            amount: Decimal::new(amount, 0),
+            preferred_collateral: None,
+            network_id: 1,
+            sender_address: "0x456".to_string(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn pool_position(collateral_type: CollateralType, amount: i64, value_usd: i64) -> CollateralPosition {
+        CollateralPosition {
+            id: Uuid::new_v4(),
+            collateral_type,
+            amount: Decimal::new(amount, 0),
+            value_usd: Decimal::new(value_usd, 0),
+            locked_until: None,
+            status: CollateralStatus::Active,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_epoch_fills_pro_rata_with_same_collateral_mix() {
+        let manager = RedemptionManager::new().with_queue(RedemptionQueueConfig {
+            epoch_cap: Decimal::new(10_000, 0),
+            ..RedemptionQueueConfig::default()
+        });
+        let stablecoin_id = Uuid::new_v4();
+        manager.add_collateral(stablecoin_id, pool_position(CollateralType::Fiat { currency: "USD".to_string() }, 600, 600)).await.unwrap();
+        manager.add_collateral(stablecoin_id, pool_position(CollateralType::RWA { asset_id: Uuid::new_v4(), asset_type: "treasury".to_string() }, 4, 400)).await.unwrap();
+
+        let alice = manager.process_redemption(queued_request(stablecoin_id, "alice", 1000)).await.unwrap();
+        let bob = manager.process_redemption(queued_request(stablecoin_id, "bob", 1000)).await.unwrap();
+        assert_eq!(alice.status, RedemptionStatus::Pending);
+        assert!(alice.collateral_released.is_empty());
+
+        let now = Utc::now();
+        let settlement = manager.process_epoch(stablecoin_id, now).await.unwrap();
+        assert_eq!(settlement.requested, Decimal::new(2000, 0));
+        assert_eq!(settlement.capacity, Decimal::new(1000, 0));
+        assert_eq!(settlement.fill_ratio, Decimal::new(5, 1));
+
+        // Both redeemers get $300 of T+1 fiat and $200 (2 units) of T+3 RWA
+        for fill in &settlement.fills {
+            assert_eq!(fill.amount, Decimal::new(500, 0));
+            assert!(!fill.fully_filled);
+            assert_eq!(fill.collateral.len(), 2);
+            assert_eq!(fill.collateral[0].value_usd, Decimal::new(300, 0));
+            assert_eq!(fill.collateral[0].locked_until, Some(now + Duration::days(1)));
+            assert_eq!(fill.collateral[1].value_usd, Decimal::new(200, 0));
+            assert_eq!(fill.collateral[1].amount, Decimal::new(2, 0));
+            assert_eq!(fill.collateral[1].locked_until, Some(now + Duration::days(3)));
+        }
+
+        let bob_tx = manager.get_transaction(bob.id).await.unwrap().unwrap();
+        assert_eq!(bob_tx.status, RedemptionStatus::Processing);
+        assert_eq!(manager.get_total_redeemed(stablecoin_id).await.unwrap(), Decimal::new(1000, 0));
+        assert_eq!(manager.queue_position(alice.id).await.unwrap().unwrap().remaining, Decimal::new(500, 0));
+    }
+
+    #[tokio::test]
+    async fn test_queue_position_and_epoch_rate_limit() {
+        let manager = RedemptionManager::new().with_queue(RedemptionQueueConfig {
+            epoch_cap: Decimal::new(100, 0),
+            ..RedemptionQueueConfig::default()
+        });
+        let stablecoin_id = Uuid::new_v4();
+        manager.add_collateral(stablecoin_id, pool_position(CollateralType::Fiat { currency: "USD".to_string() }, 10_000, 10_000)).await.unwrap();
+
+        let first = manager.process_redemption(queued_request(stablecoin_id, "alice", 150)).await.unwrap();
+        let second = manager.process_redemption(queued_request(stablecoin_id, "bob", 50)).await.unwrap();
+
+        let position = manager.queue_position(second.id).await.unwrap().unwrap();
+        assert_eq!(position.position, 2);
+        assert_eq!(position.amount_ahead, Decimal::new(150, 0));
+        assert_eq!(position.estimated_epochs, 2);
+
+        let now = Utc::now();
+        let settlement = manager.process_epoch(stablecoin_id, now).await.unwrap();
+        assert_eq!(settlement.fills[0].amount, Decimal::new(75, 0));
+        assert_eq!(settlement.fills[1].amount, Decimal::new(25, 0));
+
+        assert!(matches!(
+            manager.process_epoch(stablecoin_id, now + Duration::hours(1)).await,
+            Err(StablecoinError::RateLimitExceeded(_))
+        ));
+
+        let settlement = manager.process_epoch(stablecoin_id, now + Duration::days(1)).await.unwrap();
+        assert_eq!(settlement.epoch, 2);
+        assert!(settlement.fills.iter().all(|fill| fill.fully_filled));
+        assert!(manager.queue_position(first.id).await.unwrap().is_none());
+        assert_eq!(manager.get_transaction(first.id).await.unwrap().unwrap().status, RedemptionStatus::Completed);
+        assert_eq!(manager.get_total_redeemed(stablecoin_id).await.unwrap(), Decimal::new(200, 0));
+    }
+
+    #[tokio::test]
+    async fn test_per_user_cap_cancellation_and_settlement_schedule() {
+        let manager = RedemptionManager::new().with_queue(RedemptionQueueConfig {
+            epoch_cap: Decimal::new(1000, 0),
+            per_user_epoch_cap: Some(Decimal::new(40, 0)),
+            ..RedemptionQueueConfig::default()
+        });
+        let stablecoin_id = Uuid::new_v4();
+        manager.add_collateral(stablecoin_id, pool_position(CollateralType::Fiat { currency: "USD".to_string() }, 1000, 1000)).await.unwrap();
+
+        manager.process_redemption(queued_request(stablecoin_id, "alice", 30)).await.unwrap();
+        let alice_second = manager.process_redemption(queued_request(stablecoin_id, "alice", 30)).await.unwrap();
+        let bob = manager.process_redemption(queued_request(stablecoin_id, "bob", 50)).await.unwrap();
+        assert!(manager.cancel_redemption(bob.id).await.unwrap());
+        assert!(manager.queue_position(bob.id).await.unwrap().is_none());
+
+        let settlement = manager.process_epoch(stablecoin_id, Utc::now()).await.unwrap();
+        let filled: Vec<Decimal> = settlement.fills.iter().map(|fill| fill.amount).collect();
+        assert_eq!(filled, vec![Decimal::new(30, 0), Decimal::new(10, 0)]);
+        assert_eq!(manager.queue_position(alice_second.id).await.unwrap().unwrap().remaining, Decimal::new(20, 0));
+
+        let basket = CollateralType::Basket {
+            assets: vec![
+                CollateralType::Fiat { currency: "USD".to_string() },
+                CollateralType::RWA { asset_id: Uuid::new_v4(), asset_type: "real_estate".to_string() },
+            ],
+        };
+        assert_eq!(SettlementSchedule::default().days_for(&basket), 3);
+    }
 }
@@ -0,0 +1,573 @@
+// =====================================================================================
+// File: core-stablecoin/src/reserves.rs
+// Description: Proof of reserves for RWA-backed stablecoins
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Proof of reserves.
+//!
+//! Liabilities are committed to with a Merkle sum tree over holder balances:
+//! each node carries a hash and the sum of the balances beneath it, so the
+//! root commits to total supply and every holder can check their balance is
+//! counted. Assets are custodian-reported RWA values signed by the attestor
+//! key. The report compares the two totals and can be reproduced by an
+//! auditor from the same snapshot inputs.
+
+use chrono::{DateTime, Utc};
+use core_oracle::{verify_digest, ReportSigner, SignatureScheme};
+use core_security::HashUtils;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::mechanisms::rwa_backed::RWAAsset;
+use crate::{StablecoinError, StablecoinResult};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+const EMPTY_PREFIX: u8 = 0x02;
+
+fn hash_leaf(salt: &[u8], balance: Decimal) -> [u8; 32] {
+    let mut data = vec![LEAF_PREFIX];
+    data.extend_from_slice(salt);
+    data.extend_from_slice(balance.normalize().to_string().as_bytes());
+    to_digest(HashUtils::sha256(&data))
+}
+
+fn hash_node(left: &SumNode, right: &SumNode) -> [u8; 32] {
+    let mut data = vec![NODE_PREFIX];
+    data.extend_from_slice(&left.hash);
+    data.extend_from_slice(left.sum.normalize().to_string().as_bytes());
+    data.push(b'|');
+    data.extend_from_slice(&right.hash);
+    data.extend_from_slice(right.sum.normalize().to_string().as_bytes());
+    to_digest(HashUtils::sha256(&data))
+}
+
+fn empty_node() -> SumNode {
+    SumNode {
+        hash: to_digest(HashUtils::sha256(&[EMPTY_PREFIX])),
+        sum: Decimal::ZERO,
+    }
+}
+
+fn to_digest(bytes: Vec<u8>) -> [u8; 32] {
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&bytes[..32]);
+    digest
+}
+
+fn decode_digest(value: &str) -> StablecoinResult<[u8; 32]> {
+    let bytes = hex::decode(value)
+        .map_err(|e| StablecoinError::ReserveProofInvalid(format!("Malformed hash: {}", e)))?;
+    if bytes.len() != 32 {
+        return Err(StablecoinError::ReserveProofInvalid(format!(
+            "Hash must be 32 bytes, got {}",
+            bytes.len()
+        )));
+    }
+    Ok(to_digest(bytes))
+}
+
+/// Per-holder salt, derived from the snapshot nonce so leaves do not reveal holder IDs
+pub fn holder_salt(snapshot_nonce: &[u8], holder_id: &str) -> Vec<u8> {
+    HashUtils::hmac_sha256(snapshot_nonce, holder_id.as_bytes())
+}
+
+/// A holder's stablecoin balance at the snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HolderBalance {
+    pub holder_id: String,
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SumNode {
+    hash: [u8; 32],
+    sum: Decimal,
+}
+
+/// Merkle sum tree over holder balances
+#[derive(Debug, Clone)]
+pub struct LiabilityTree {
+    holders: Vec<HolderBalance>,
+    salts: Vec<Vec<u8>>,
+    /// Levels from leaves (padded to a power of two) up to the root
+    levels: Vec<Vec<SumNode>>,
+}
+
+impl LiabilityTree {
+    /// Build the tree. Holders are sorted by ID so the root is reproducible.
+    pub fn build(snapshot_nonce: &[u8], balances: &[HolderBalance]) -> StablecoinResult<Self> {
+        let mut holders = balances.to_vec();
+        holders.sort_by(|a, b| a.holder_id.cmp(&b.holder_id));
+
+        for pair in holders.windows(2) {
+            if pair[0].holder_id == pair[1].holder_id {
+                return Err(StablecoinError::InvalidRequest(format!(
+                    "Duplicate holder {} in snapshot",
+                    pair[0].holder_id
+                )));
+            }
+        }
+        if let Some(negative) = holders.iter().find(|h| h.balance < Decimal::ZERO) {
+            return Err(StablecoinError::InvalidAmount(format!(
+                "Negative balance for holder {}",
+                negative.holder_id
+            )));
+        }
+
+        let salts: Vec<Vec<u8>> = holders
+            .iter()
+            .map(|h| holder_salt(snapshot_nonce, &h.holder_id))
+            .collect();
+        let mut leaves: Vec<SumNode> = holders
+            .iter()
+            .zip(&salts)
+            .map(|(holder, salt)| SumNode {
+                hash: hash_leaf(salt, holder.balance),
+                sum: holder.balance,
+            })
+            .collect();
+        leaves.resize(leaves.len().max(1).next_power_of_two(), empty_node());
+
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| SumNode {
+                    hash: hash_node(&pair[0], &pair[1]),
+                    sum: pair[0].sum + pair[1].sum,
+                })
+                .collect();
+            levels.push(next);
+        }
+
+        Ok(Self {
+            holders,
+            salts,
+            levels,
+        })
+    }
+
+    pub fn root_hash(&self) -> String {
+        hex::encode(self.root().hash)
+    }
+
+    /// Total liabilities committed to by the root
+    pub fn total_liabilities(&self) -> Decimal {
+        self.root().sum
+    }
+
+    pub fn holder_count(&self) -> usize {
+        self.holders.len()
+    }
+
+    /// Inclusion proof for a holder
+    pub fn proof(&self, holder_id: &str) -> StablecoinResult<InclusionProof> {
+        let index = self
+            .holders
+            .binary_search_by(|h| h.holder_id.as_str().cmp(holder_id))
+            .map_err(|_| {
+                StablecoinError::InvalidRequest(format!("Holder {} not in snapshot", holder_id))
+            })?;
+
+        let mut siblings = Vec::with_capacity(self.levels.len() - 1);
+        let mut position = index;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = level[position ^ 1];
+            siblings.push(ProofStep {
+                hash: hex::encode(sibling.hash),
+                sum: sibling.sum,
+                sibling_is_left: position % 2 == 1,
+            });
+            position /= 2;
+        }
+
+        Ok(InclusionProof {
+            holder_id: holder_id.to_string(),
+            balance: self.holders[index].balance,
+            salt: hex::encode(&self.salts[index]),
+            siblings,
+            root_hash: self.root_hash(),
+            total_liabilities: self.total_liabilities(),
+        })
+    }
+
+    fn root(&self) -> SumNode {
+        self.levels[self.levels.len() - 1][0]
+    }
+}
+
+/// One step from a leaf towards the root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofStep {
+    pub hash: String,
+    pub sum: Decimal,
+    pub sibling_is_left: bool,
+}
+
+/// Proof that a holder's balance is counted in the published liabilities root
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub holder_id: String,
+    pub balance: Decimal,
+    /// Hex salt; holders can check it against `holder_salt` once the nonce is published
+    pub salt: String,
+    pub siblings: Vec<ProofStep>,
+    pub root_hash: String,
+    pub total_liabilities: Decimal,
+}
+
+impl InclusionProof {
+    /// Recompute the root from the holder's leaf.
+    ///
+    /// Sibling sums must be non-negative, otherwise a negative node could
+    /// hide liabilities while still producing a consistent root.
+    pub fn verify(&self) -> StablecoinResult<()> {
+        let salt = hex::decode(&self.salt)
+            .map_err(|e| StablecoinError::ReserveProofInvalid(format!("Malformed salt: {}", e)))?;
+        let mut node = SumNode {
+            hash: hash_leaf(&salt, self.balance),
+            sum: self.balance,
+        };
+
+        for step in &self.siblings {
+            if step.sum < Decimal::ZERO {
+                return Err(StablecoinError::ReserveProofInvalid(
+                    "Negative sibling sum in proof".to_string(),
+                ));
+            }
+            let sibling = SumNode {
+                hash: decode_digest(&step.hash)?,
+                sum: step.sum,
+            };
+            let (left, right) = if step.sibling_is_left {
+                (sibling, node)
+            } else {
+                (node, sibling)
+            };
+            node = SumNode {
+                hash: hash_node(&left, &right),
+                sum: left.sum + right.sum,
+            };
+        }
+
+        if hex::encode(node.hash) != self.root_hash || node.sum != self.total_liabilities {
+            return Err(StablecoinError::ReserveProofInvalid(format!(
+                "Proof for {} does not reach root {}",
+                self.holder_id, self.root_hash
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Custodian-reported value of a reserve asset
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CustodianAssetEntry {
+    pub asset_id: Uuid,
+    pub custodian: String,
+    pub asset_type: String,
+    pub value: Decimal,
+    pub valued_at: DateTime<Utc>,
+    pub valuation_method: String,
+}
+
+impl CustodianAssetEntry {
+    pub fn from_rwa_asset(custodian: &str, asset: &RWAAsset) -> Self {
+        Self {
+            asset_id: asset.id,
+            custodian: custodian.to_string(),
+            asset_type: asset.asset_type.clone(),
+            value: asset.current_value,
+            valued_at: asset.last_valuation,
+            valuation_method: asset.valuation_method.clone(),
+        }
+    }
+}
+
+/// Asset side of the report, signed by the attestor
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssetAttestation {
+    pub stablecoin_symbol: String,
+    pub entries: Vec<CustodianAssetEntry>,
+    pub total_assets: Decimal,
+    pub as_of: DateTime<Utc>,
+    pub scheme: SignatureScheme,
+    pub signer: String,
+    pub signature: String,
+}
+
+impl AssetAttestation {
+    /// Canonical payload covered by the signature
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut lines = vec![
+            "stablerwa-asset-attestation-v1".to_string(),
+            self.stablecoin_symbol.clone(),
+            self.total_assets.normalize().to_string(),
+            self.as_of.timestamp_millis().to_string(),
+        ];
+        for entry in &self.entries {
+            lines.push(format!(
+                "{}|{}|{}|{}|{}|{}",
+                entry.asset_id,
+                entry.custodian,
+                entry.asset_type,
+                entry.value.normalize(),
+                entry.valued_at.timestamp_millis(),
+                entry.valuation_method
+            ));
+        }
+        lines.join("\n").into_bytes()
+    }
+
+    pub fn digest(&self) -> [u8; 32] {
+        to_digest(HashUtils::sha256(&self.signing_payload()))
+    }
+
+    /// Check the signature and that the total matches the entries
+    pub fn verify(&self) -> StablecoinResult<()> {
+        let total: Decimal = self.entries.iter().map(|e| e.value).sum();
+        if total != self.total_assets {
+            return Err(StablecoinError::ReserveProofInvalid(format!(
+                "Asset entries sum to {}, attestation claims {}",
+                total, self.total_assets
+            )));
+        }
+        verify_digest(self.scheme, &self.signer, &self.digest(), &self.signature)
+            .map_err(|e| StablecoinError::ReserveProofInvalid(e.to_string()))
+    }
+}
+
+/// Proof-of-reserves report comparing attested assets with committed liabilities
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProofOfReservesReport {
+    pub report_id: Uuid,
+    pub stablecoin_symbol: String,
+    pub as_of: DateTime<Utc>,
+    pub liabilities_root: String,
+    pub total_liabilities: Decimal,
+    pub holder_count: usize,
+    pub assets: AssetAttestation,
+    /// Assets / liabilities; zero when there are no liabilities
+    pub reserve_ratio: Decimal,
+    pub surplus: Decimal,
+}
+
+impl ProofOfReservesReport {
+    pub fn is_fully_reserved(&self) -> bool {
+        self.surplus >= Decimal::ZERO
+    }
+}
+
+/// Generates proof-of-reserves reports for a stablecoin
+pub struct ProofOfReservesGenerator {
+    stablecoin_symbol: String,
+    signer: Box<dyn ReportSigner>,
+}
+
+impl ProofOfReservesGenerator {
+    pub fn new(stablecoin_symbol: &str, signer: Box<dyn ReportSigner>) -> Self {
+        Self {
+            stablecoin_symbol: stablecoin_symbol.to_string(),
+            signer,
+        }
+    }
+
+    /// Build the liability tree and the signed report for a snapshot
+    pub fn generate(
+        &self,
+        snapshot_nonce: &[u8],
+        balances: &[HolderBalance],
+        assets: &[CustodianAssetEntry],
+        as_of: DateTime<Utc>,
+    ) -> StablecoinResult<(ProofOfReservesReport, LiabilityTree)> {
+        if let Some(entry) = assets.iter().find(|e| e.value < Decimal::ZERO) {
+            return Err(StablecoinError::InvalidAmount(format!(
+                "Negative value reported for asset {}",
+                entry.asset_id
+            )));
+        }
+
+        let tree = LiabilityTree::build(snapshot_nonce, balances)?;
+
+        let mut entries = assets.to_vec();
+        entries.sort_by_key(|e| e.asset_id);
+        let mut attestation = AssetAttestation {
+            stablecoin_symbol: self.stablecoin_symbol.clone(),
+            total_assets: entries.iter().map(|e| e.value).sum(),
+            entries,
+            as_of,
+            scheme: self.signer.scheme(),
+            signer: self.signer.signer_id(),
+            signature: String::new(),
+        };
+        let signature = self
+            .signer
+            .sign_digest(&attestation.digest())
+            .map_err(|e| StablecoinError::InternalError(e.to_string()))?;
+        attestation.signature = hex::encode(signature);
+
+        let report = Self::compare(&self.stablecoin_symbol, &tree, attestation, as_of);
+        Ok((report, tree))
+    }
+
+    fn compare(
+        symbol: &str,
+        tree: &LiabilityTree,
+        assets: AssetAttestation,
+        as_of: DateTime<Utc>,
+    ) -> ProofOfReservesReport {
+        let total_liabilities = tree.total_liabilities();
+        let reserve_ratio = if total_liabilities.is_zero() {
+            Decimal::ZERO
+        } else {
+            (assets.total_assets / total_liabilities).round_dp(6)
+        };
+
+        ProofOfReservesReport {
+            report_id: Uuid::new_v4(),
+            stablecoin_symbol: symbol.to_string(),
+            as_of,
+            liabilities_root: tree.root_hash(),
+            total_liabilities,
+            holder_count: tree.holder_count(),
+            surplus: assets.total_assets - total_liabilities,
+            reserve_ratio,
+            assets,
+        }
+    }
+}
+
+/// Auditor-side reproduction of a report from the snapshot inputs
+pub fn audit_report(
+    report: &ProofOfReservesReport,
+    snapshot_nonce: &[u8],
+    balances: &[HolderBalance],
+    trusted_signer: &str,
+) -> StablecoinResult<()> {
+    if report.assets.signer.to_lowercase() != trusted_signer.to_lowercase() {
+        return Err(StablecoinError::ReserveProofInvalid(format!(
+            "Assets attested by {}, expected {}",
+            report.assets.signer, trusted_signer
+        )));
+    }
+    report.assets.verify()?;
+
+    let tree = LiabilityTree::build(snapshot_nonce, balances)?;
+    let expected = ProofOfReservesGenerator::compare(
+        &report.stablecoin_symbol,
+        &tree,
+        report.assets.clone(),
+        report.as_of,
+    );
+    if expected.liabilities_root != report.liabilities_root
+        || expected.total_liabilities != report.total_liabilities
+        || expected.holder_count != report.holder_count
+        || expected.reserve_ratio != report.reserve_ratio
+        || expected.surplus != report.surplus
+    {
+        return Err(StablecoinError::ReserveProofInvalid(format!(
+            "Report {} does not match the snapshot",
+            report.report_id
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_oracle::Ed25519Signer;
+
+    const NONCE: &[u8] = b"snapshot-2024-06-30";
+
+    fn balances() -> Vec<HolderBalance> {
+        [("carol", 250), ("alice", 1000), ("bob", 500), ("dave", 0), ("erin", 75)]
+            .iter()
+            .map(|&(holder, balance)| HolderBalance {
+                holder_id: holder.to_string(),
+                balance: Decimal::new(balance, 0),
+            })
+            .collect()
+    }
+
+    fn assets(value: i64) -> Vec<CustodianAssetEntry> {
+        vec![CustodianAssetEntry {
+            asset_id: Uuid::new_v4(),
+            custodian: "Custodian A".to_string(),
+            asset_type: "bonds".to_string(),
+            value: Decimal::new(value, 0),
+            valued_at: Utc::now(),
+            valuation_method: "mark_to_market".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        let tree = LiabilityTree::build(NONCE, &balances()).unwrap();
+        assert_eq!(tree.total_liabilities(), Decimal::new(1825, 0));
+
+        for holder in balances() {
+            let proof = tree.proof(&holder.holder_id).unwrap();
+            assert_eq!(proof.balance, holder.balance);
+            assert_eq!(proof.salt, hex::encode(holder_salt(NONCE, &holder.holder_id)));
+            proof.verify().unwrap();
+        }
+
+        // Understating a balance or hiding liabilities in a sibling breaks the proof
+        let mut proof = tree.proof("alice").unwrap();
+        proof.balance = Decimal::new(1, 0);
+        assert!(proof.verify().is_err());
+
+        let mut proof = tree.proof("bob").unwrap();
+        proof.siblings[0].sum = Decimal::new(-100, 0);
+        assert!(proof.verify().is_err());
+
+        assert!(tree.proof("mallory").is_err());
+    }
+
+    #[test]
+    fn test_tree_is_reproducible() {
+        let mut reversed = balances();
+        reversed.reverse();
+        let a = LiabilityTree::build(NONCE, &balances()).unwrap();
+        let b = LiabilityTree::build(NONCE, &reversed).unwrap();
+        assert_eq!(a.root_hash(), b.root_hash());
+
+        let other = LiabilityTree::build(b"another-nonce", &balances()).unwrap();
+        assert_ne!(a.root_hash(), other.root_hash());
+    }
+
+    #[test]
+    fn test_report_and_audit() {
+        let (signer, _) = Ed25519Signer::generate().unwrap();
+        let signer_id = signer.signer_id();
+        let generator = ProofOfReservesGenerator::new("RWAUSD", Box::new(signer));
+
+        let (report, _) = generator
+            .generate(NONCE, &balances(), &assets(2000), Utc::now())
+            .unwrap();
+        assert!(report.is_fully_reserved());
+        assert_eq!(report.surplus, Decimal::new(175, 0));
+        assert_eq!(report.holder_count, 5);
+        audit_report(&report, NONCE, &balances(), &signer_id).unwrap();
+
+        // Inflated asset values invalidate the attestation
+        let mut tampered = report.clone();
+        tampered.assets.entries[0].value = Decimal::new(5000, 0);
+        tampered.assets.total_assets = Decimal::new(5000, 0);
+        assert!(audit_report(&tampered, NONCE, &balances(), &signer_id).is_err());
+
+        // Omitting a holder changes the liabilities root
+        let mut omitted = balances();
+        omitted.retain(|h| h.holder_id != "alice");
+        assert!(audit_report(&report, NONCE, &omitted, &signer_id).is_err());
+
+        let (short, _) = generator
+            .generate(NONCE, &balances(), &assets(1500), Utc::now())
+            .unwrap();
+        assert!(!short.is_fully_reserved());
+    }
+}
@@ -153,6 +153,31 @@ impl EnterprisePriceStabilizer {
         Ok(())
     }
 
+    /// Feed an observed market price and the current collateral backing into monitoring
+    pub async fn record_market_data(
+        &self,
+        stablecoin_id: Uuid,
+        price: Decimal,
+        positions: Vec<CollateralPosition>,
+        issued_tokens: Decimal,
+    ) -> StablecoinResult<()> {
+        {
+            let mut feeds = self.price_feeds.lock().await;
+            let feed = feeds.get_mut(&stablecoin_id)
+                .ok_or(StablecoinError::InvalidRequest("Price feed not found".to_string()))?;
+            feed.update_price(price);
+        }
+
+        {
+            let mut monitors = self.collateral_monitors.lock().await;
+            let monitor = monitors.get_mut(&stablecoin_id)
+                .ok_or(StablecoinError::InvalidRequest("Collateral monitor not found".to_string()))?;
+            monitor.update_collateral(positions, issued_tokens);
+        }
+
+        Ok(())
+    }
+
     /// Check if action is allowed (cooldown period)
     async fn is_action_allowed(&self, stablecoin_id: Uuid) -> StablecoinResult<bool> {
         let last_actions = self.last_actions.lock().await;
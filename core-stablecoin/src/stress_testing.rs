@@ -0,0 +1,873 @@
+// =====================================================================================
+// File: core-stablecoin/src/stress_testing.rs
+// Description: Stress testing and depeg simulation for stablecoin mechanisms
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+//! Scenario-driven stress testing.
+//!
+//! A `StressScenario` is a schedule of market shocks (collateral crashes,
+//! redemption runs, oracle outages, secondary market selling). The
+//! `StressTestSimulator` opens the configured vaults through `IssuanceManager`,
+//! serves redemptions through `RedemptionManager`, feeds every step into
+//! `EnterprisePriceStabilizer` and asks the configured stability mechanism for
+//! its response. Each run produces a `StressTestReport` with peg deviation,
+//! collateral ratio and the liquidation timeline per step.
+//!
+//! Runs are deterministic: timestamps are derived from the configured start
+//! time and step length, and no wall-clock or random input reaches the report.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::issuance::{IssuanceManager, IssuanceService};
+use crate::mechanisms::{StabilityAction as MechanismAction, StabilityMechanismFactory};
+use crate::redemption::{RedemptionManager, RedemptionService};
+use crate::stability::{
+    EnterprisePriceStabilizer, StabilityAction, StabilityParameters as MonitoringParameters,
+    StabilityService,
+};
+use crate::types::{
+    CollateralPosition, CollateralStatus, CollateralType, IssuanceRequest, RedemptionRequest,
+    StabilityMechanism, StabilityParameters, Stablecoin, StablecoinStatus,
+};
+use crate::{StablecoinError, StablecoinResult};
+
+/// A single market shock
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarketShock {
+    /// Move a collateral asset's price by a percentage (negative for a crash)
+    CollateralPriceChange { symbol: String, change_percent: Decimal },
+    /// Holders ask to redeem a percentage of the outstanding supply
+    RedemptionRun { supply_percent: Decimal },
+    /// The oracle stops reporting for a number of steps
+    OracleOutage { steps: u32 },
+    /// Stablecoins dumped directly on secondary markets
+    MarketSell { amount: Decimal },
+}
+
+/// A shock applied at the start of a given step
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledShock {
+    pub step: u32,
+    pub shock: MarketShock,
+}
+
+/// A named schedule of market shocks
+#[derive(Debug, Clone)]
+pub struct StressScenario {
+    pub name: String,
+    pub steps: u32,
+    pub step_seconds: i64,
+    pub shocks: Vec<ScheduledShock>,
+}
+
+impl StressScenario {
+    pub fn new(name: &str, steps: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            steps,
+            step_seconds: 3600,
+            shocks: Vec::new(),
+        }
+    }
+
+    pub fn with_step_seconds(mut self, step_seconds: i64) -> Self {
+        self.step_seconds = step_seconds;
+        self
+    }
+
+    pub fn with_shock(mut self, step: u32, shock: MarketShock) -> Self {
+        self.shocks.push(ScheduledShock { step, shock });
+        self
+    }
+
+    /// Collateral loses 10% per step for six consecutive steps
+    pub fn collateral_crash(symbol: &str) -> Self {
+        (1..=6).fold(Self::new("collateral_crash", 12), |scenario, step| {
+            scenario.with_shock(step, MarketShock::CollateralPriceChange {
+                symbol: symbol.to_string(),
+                change_percent: Decimal::new(-10, 0),
+            })
+        })
+    }
+
+    /// Holders redeem 15% of the remaining supply per step for five steps
+    pub fn bank_run() -> Self {
+        (1..=5).fold(Self::new("bank_run", 12), |scenario, step| {
+            scenario.with_shock(step, MarketShock::RedemptionRun {
+                supply_percent: Decimal::new(15, 0),
+            })
+        })
+    }
+
+    /// The oracle goes dark for five steps while collateral falls 10% per step
+    pub fn oracle_outage(symbol: &str) -> Self {
+        (2..=6).fold(
+            Self::new("oracle_outage", 12).with_shock(2, MarketShock::OracleOutage { steps: 5 }),
+            |scenario, step| {
+                scenario.with_shock(step, MarketShock::CollateralPriceChange {
+                    symbol: symbol.to_string(),
+                    change_percent: Decimal::new(-10, 0),
+                })
+            },
+        )
+    }
+
+    fn shocks_at(&self, step: u32) -> impl Iterator<Item = &MarketShock> {
+        self.shocks
+            .iter()
+            .filter(move |scheduled| scheduled.step == step)
+            .map(|scheduled| &scheduled.shock)
+    }
+}
+
+/// A collateralized debt position opened at the start of every run
+#[derive(Debug, Clone)]
+pub struct VaultSpec {
+    pub owner: String,
+    pub collateral_symbol: String,
+    pub collateral_amount: Decimal,
+    pub debt: Decimal,
+}
+
+/// Simulation environment shared by all scenarios
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub mechanism: StabilityMechanism,
+    pub parameters: StabilityParameters,
+    /// Stabilizer thresholds, in percent to match `PriceFeed` and `CollateralMonitor`
+    pub monitoring: MonitoringParameters,
+    /// Starting USD price per collateral symbol
+    pub collateral_prices: BTreeMap<String, Decimal>,
+    pub vaults: Vec<VaultSpec>,
+    /// Stable USD reserves available to redemptions and buybacks
+    pub reserve_usd: Decimal,
+    /// Vault collateral ratio (percent) below which the vault is liquidated
+    pub liquidation_ratio: Decimal,
+    /// Liquidation penalty as a fraction of the repaid debt
+    pub liquidation_penalty: Decimal,
+    /// USD of net stablecoin selling that moves the price by 100%
+    pub stablecoin_market_depth: Decimal,
+    /// USD of collateral selling that moves the collateral price by 100%
+    pub collateral_market_depth: Decimal,
+    /// Fraction of the remaining peg deviation closed by arbitrage each step
+    pub peg_recovery_rate: Decimal,
+    /// Absolute peg deviation (percent) counted as a depeg
+    pub depeg_threshold_percent: Decimal,
+    pub start_time: DateTime<Utc>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        let parameters = StabilityParameters::default();
+        Self {
+            mechanism: StabilityMechanism::Crypto,
+            liquidation_ratio: parameters.liquidation_threshold * Decimal::new(100, 0),
+            parameters,
+            monitoring: MonitoringParameters {
+                max_price_deviation: Decimal::new(2, 0),
+                target_collateral_ratio: Decimal::new(150, 0),
+                min_collateral_ratio: Decimal::new(120, 0),
+                volatility_threshold: Decimal::new(5, 0),
+                ..MonitoringParameters::default()
+            },
+            collateral_prices: BTreeMap::new(),
+            vaults: Vec::new(),
+            reserve_usd: Decimal::ZERO,
+            liquidation_penalty: Decimal::new(13, 2), // 13%
+            stablecoin_market_depth: Decimal::new(10_000_000, 0),
+            collateral_market_depth: Decimal::new(50_000_000, 0),
+            peg_recovery_rate: Decimal::new(25, 2), // 25%
+            depeg_threshold_percent: Decimal::new(2, 0),
+            start_time: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn with_mechanism(mut self, mechanism: StabilityMechanism) -> Self {
+        self.mechanism = mechanism;
+        self
+    }
+
+    pub fn with_collateral_price(mut self, symbol: &str, price: Decimal) -> Self {
+        self.collateral_prices.insert(symbol.to_string(), price);
+        self
+    }
+
+    pub fn with_vault(mut self, owner: &str, collateral_symbol: &str, collateral_amount: Decimal, debt: Decimal) -> Self {
+        self.vaults.push(VaultSpec {
+            owner: owner.to_string(),
+            collateral_symbol: collateral_symbol.to_string(),
+            collateral_amount,
+            debt,
+        });
+        self
+    }
+
+    pub fn with_reserves(mut self, reserve_usd: Decimal) -> Self {
+        self.reserve_usd = reserve_usd;
+        self
+    }
+
+    pub fn with_market_depth(mut self, stablecoin_depth: Decimal, collateral_depth: Decimal) -> Self {
+        self.stablecoin_market_depth = stablecoin_depth;
+        self.collateral_market_depth = collateral_depth;
+        self
+    }
+
+    fn validate(&self) -> StablecoinResult<()> {
+        if self.vaults.is_empty() {
+            return Err(StablecoinError::InvalidConfiguration(
+                "Simulation needs at least one vault".to_string(),
+            ));
+        }
+
+        for vault in &self.vaults {
+            if !self.collateral_prices.contains_key(&vault.collateral_symbol) {
+                return Err(StablecoinError::InvalidConfiguration(format!(
+                    "No starting price for collateral {}",
+                    vault.collateral_symbol
+                )));
+            }
+            if vault.debt <= Decimal::ZERO || vault.collateral_amount <= Decimal::ZERO {
+                return Err(StablecoinError::InvalidConfiguration(format!(
+                    "Vault {} needs positive collateral and debt",
+                    vault.owner
+                )));
+            }
+        }
+
+        if self.stablecoin_market_depth <= Decimal::ZERO || self.collateral_market_depth <= Decimal::ZERO {
+            return Err(StablecoinError::InvalidConfiguration(
+                "Market depth must be positive".to_string(),
+            ));
+        }
+
+        if self.peg_recovery_rate < Decimal::ZERO || self.peg_recovery_rate > Decimal::ONE {
+            return Err(StablecoinError::InvalidConfiguration(
+                "Peg recovery rate must be between 0 and 1".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// A vault liquidation
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiquidationEvent {
+    pub step: u32,
+    pub timestamp: DateTime<Utc>,
+    pub owner: String,
+    pub collateral_symbol: String,
+    pub collateral_price: Decimal,
+    /// Vault collateral ratio (percent) when liquidated
+    pub collateral_ratio: Decimal,
+    pub debt_repaid: Decimal,
+    pub collateral_seized: Decimal,
+    /// Debt the seized collateral could not cover
+    pub bad_debt: Decimal,
+}
+
+/// System state at the end of a step
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepSnapshot {
+    pub step: u32,
+    pub timestamp: DateTime<Utc>,
+    pub market_price: Decimal,
+    /// Signed deviation from the target price, in percent
+    pub peg_deviation_percent: Decimal,
+    pub collateral_prices: BTreeMap<String, Decimal>,
+    /// Collateral ratio (percent) at actual market prices
+    pub collateral_ratio: Decimal,
+    /// Collateral ratio (percent) at the last oracle prices
+    pub reported_collateral_ratio: Decimal,
+    pub total_supply: Decimal,
+    pub reserves: Decimal,
+    pub redeemed: Decimal,
+    pub unserved_redemptions: Decimal,
+    pub liquidations: usize,
+    pub oracle_available: bool,
+    pub stabilizer_stable: bool,
+    pub recommended_actions: Vec<StabilityAction>,
+    pub mechanism_action: MechanismAction,
+}
+
+/// Outcome of one scenario
+#[derive(Debug, Clone, PartialEq)]
+pub struct StressTestReport {
+    pub scenario: String,
+    pub mechanism: StabilityMechanism,
+    pub steps: Vec<StepSnapshot>,
+    pub liquidations: Vec<LiquidationEvent>,
+    pub max_peg_deviation_percent: Decimal,
+    pub min_collateral_ratio: Decimal,
+    pub total_redeemed: Decimal,
+    pub total_unserved_redemptions: Decimal,
+    pub reserve_buybacks: Decimal,
+    pub bad_debt: Decimal,
+    pub depegged_at_step: Option<u32>,
+    pub repegged_at_step: Option<u32>,
+}
+
+impl StressTestReport {
+    /// True if the peg never broke or was restored before the run ended
+    pub fn held_peg(&self) -> bool {
+        self.depegged_at_step.is_none() || self.repegged_at_step.is_some()
+    }
+
+    /// True if the system stayed fully collateralized without bad debt
+    pub fn is_solvent(&self) -> bool {
+        self.bad_debt.is_zero() && self.min_collateral_ratio >= Decimal::new(100, 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct VaultState {
+    owner: String,
+    collateral_symbol: String,
+    collateral_amount: Decimal,
+    debt: Decimal,
+    liquidated: bool,
+}
+
+/// Drives stress scenarios through the issuance, redemption and stability components
+pub struct StressTestSimulator {
+    config: SimulationConfig,
+}
+
+impl StressTestSimulator {
+    pub fn new(config: SimulationConfig) -> StablecoinResult<Self> {
+        config.validate()?;
+        Ok(Self { config })
+    }
+
+    pub fn config(&self) -> &SimulationConfig {
+        &self.config
+    }
+
+    /// Run every scenario against a fresh copy of the configured system
+    pub async fn run_all(&self, scenarios: &[StressScenario]) -> StablecoinResult<Vec<StressTestReport>> {
+        let mut reports = Vec::with_capacity(scenarios.len());
+        for scenario in scenarios {
+            reports.push(self.run(scenario).await?);
+        }
+        Ok(reports)
+    }
+
+    /// Run a single scenario.
+    ///
+    /// Each step applies its shocks, serves redemptions, liquidates unhealthy
+    /// vaults, moves the market price, then lets the stabilizer and the
+    /// mechanism react. Redemptions and liquidations need a fresh oracle
+    /// valuation and are suspended during an outage.
+    pub async fn run(&self, scenario: &StressScenario) -> StablecoinResult<StressTestReport> {
+        let config = &self.config;
+        if scenario.step_seconds <= 0 {
+            return Err(StablecoinError::InvalidConfiguration(
+                "Scenario step length must be positive".to_string(),
+            ));
+        }
+
+        let stablecoin_id = Uuid::new_v4();
+        let target_price = Decimal::ONE;
+        let hundred = Decimal::new(100, 0);
+
+        // Open vaults through the issuance path
+        let issuance = IssuanceManager::with_config(Decimal::new(10, 4), Decimal::ONE, Decimal::MAX);
+        let mut vaults = Vec::with_capacity(config.vaults.len());
+        for (index, spec) in config.vaults.iter().enumerate() {
+            let price = config.collateral_prices[&spec.collateral_symbol];
+            let request = IssuanceRequest {
+                id: Uuid::new_v4(),
+                user_id: spec.owner.clone(),
+                stablecoin_id,
+                amount: spec.debt,
+                collateral: vec![crypto_position(&spec.collateral_symbol, spec.collateral_amount, price)],
+                network_id: 1,
+                recipient_address: format!("0x{:040x}", index + 1),
+                created_at: config.start_time,
+            };
+            issuance.process_issuance(request).await?;
+
+            vaults.push(VaultState {
+                owner: spec.owner.clone(),
+                collateral_symbol: spec.collateral_symbol.clone(),
+                collateral_amount: spec.collateral_amount,
+                debt: spec.debt,
+                liquidated: false,
+            });
+        }
+        let mut supply = issuance.get_total_issued(stablecoin_id).await?;
+
+        let redemption = RedemptionManager::with_config(Decimal::new(10, 4), Decimal::ONE, Decimal::MAX);
+        let mut reserves = config.reserve_usd;
+        if reserves > Decimal::ZERO {
+            redemption.add_collateral(stablecoin_id, reserve_position(reserves)).await?;
+        }
+
+        let stabilizer = EnterprisePriceStabilizer::new();
+        stabilizer.initialize_monitoring(stablecoin_id, config.monitoring.clone()).await?;
+
+        let mechanism = StabilityMechanismFactory::create(config.mechanism, config.parameters.clone())?;
+        let mut stablecoin = Stablecoin {
+            id: stablecoin_id,
+            symbol: format!("STRESS-{}", scenario.name),
+            name: format!("Stress test: {}", scenario.name),
+            decimals: 18,
+            stability_mechanism: config.mechanism,
+            target_price,
+            current_price: target_price,
+            total_supply: supply,
+            total_collateral_value: Decimal::ZERO,
+            collateral_ratio: Decimal::ZERO,
+            supported_collateral: vec![],
+            contract_addresses: HashMap::new(),
+            stability_parameters: config.parameters.clone(),
+            status: StablecoinStatus::Active,
+            created_at: config.start_time,
+            updated_at: config.start_time,
+        };
+
+        let mut collateral_prices = config.collateral_prices.clone();
+        let mut oracle_prices = collateral_prices.clone();
+        let mut market_price = target_price;
+        let mut outage_until: Option<u32> = None;
+        let mut pending_support = Decimal::ZERO;
+
+        let mut snapshots = Vec::with_capacity(scenario.steps as usize);
+        let mut liquidations = Vec::new();
+        let mut totals = RunTotals::default();
+
+        for step in 0..scenario.steps {
+            let timestamp = config.start_time + Duration::seconds(scenario.step_seconds * step as i64);
+            let mut redemption_demand = Decimal::ZERO;
+            let mut sell_pressure = -pending_support;
+            pending_support = Decimal::ZERO;
+
+            for shock in scenario.shocks_at(step) {
+                match shock {
+                    MarketShock::CollateralPriceChange { symbol, change_percent } => {
+                        let price = collateral_prices.get_mut(symbol).ok_or_else(|| {
+                            StablecoinError::InvalidConfiguration(format!("Unknown collateral {}", symbol))
+                        })?;
+                        *price = (*price * (Decimal::ONE + change_percent / hundred)).max(Decimal::ZERO);
+                    }
+                    MarketShock::RedemptionRun { supply_percent } => {
+                        redemption_demand += supply * supply_percent / hundred;
+                    }
+                    MarketShock::OracleOutage { steps } => {
+                        outage_until = Some(outage_until.unwrap_or(0).max(step + steps));
+                    }
+                    MarketShock::MarketSell { amount } => {
+                        sell_pressure += *amount;
+                    }
+                }
+            }
+
+            let oracle_available = !matches!(outage_until, Some(until) if step < until);
+            if oracle_available {
+                oracle_prices = collateral_prices.clone();
+            }
+
+            // Redemptions are served from reserves; the rest is sold on the market
+            let mut redeemed = Decimal::ZERO;
+            if oracle_available {
+                let served = redemption_demand.min(reserves);
+                if served >= Decimal::ONE {
+                    redeem(&redemption, stablecoin_id, "redemption-run", served, timestamp).await?;
+                    redeemed = served;
+                }
+            }
+            reserves -= redeemed;
+            supply -= redeemed;
+            totals.redeemed += redeemed;
+            let unserved = redemption_demand - redeemed;
+            totals.unserved += unserved;
+            sell_pressure += unserved;
+
+            // Liquidate unhealthy vaults; keepers sell the seized collateral
+            let mut step_liquidations = 0;
+            let mut collateral_sold: BTreeMap<String, Decimal> = BTreeMap::new();
+            if oracle_available {
+                for vault in vaults.iter_mut().filter(|vault| !vault.liquidated) {
+                    let price = collateral_prices[&vault.collateral_symbol];
+                    let value = vault.collateral_amount * price;
+                    let ratio = ratio_percent(value, vault.debt);
+                    if ratio >= config.liquidation_ratio {
+                        continue;
+                    }
+
+                    let owed = vault.debt * (Decimal::ONE + config.liquidation_penalty);
+                    let seized_value = value.min(owed);
+                    let seized_amount = if price.is_zero() {
+                        vault.collateral_amount
+                    } else {
+                        seized_value / price
+                    };
+                    let shortfall = (vault.debt - value).max(Decimal::ZERO);
+
+                    liquidations.push(LiquidationEvent {
+                        step,
+                        timestamp,
+                        owner: vault.owner.clone(),
+                        collateral_symbol: vault.collateral_symbol.clone(),
+                        collateral_price: price,
+                        collateral_ratio: ratio.round_dp(4),
+                        debt_repaid: vault.debt - shortfall,
+                        collateral_seized: seized_amount.round_dp(8),
+                        bad_debt: shortfall,
+                    });
+
+                    supply -= vault.debt - shortfall;
+                    totals.bad_debt += shortfall;
+                    vault.collateral_amount -= seized_amount;
+                    vault.debt = Decimal::ZERO;
+                    vault.liquidated = true;
+                    step_liquidations += 1;
+                    *collateral_sold.entry(vault.collateral_symbol.clone()).or_insert(Decimal::ZERO) += seized_value;
+                }
+            }
+            for (symbol, sold) in collateral_sold {
+                if let Some(price) = collateral_prices.get_mut(&symbol) {
+                    let impact = (sold / config.collateral_market_depth).min(Decimal::ONE);
+                    *price = (*price * (Decimal::ONE - impact)).round_dp(8);
+                }
+            }
+
+            let collateral_value = vault_value(&vaults, &collateral_prices) + reserves;
+            let collateral_ratio = ratio_percent(collateral_value, supply);
+            let reported_ratio = ratio_percent(vault_value(&vaults, &oracle_prices) + reserves, supply);
+
+            // Arbitrage pulls the price back, selling pushes it down, and an
+            // undercollateralized coin cannot trade above its backing
+            market_price = market_price + (target_price - market_price) * config.peg_recovery_rate
+                - target_price * sell_pressure / config.stablecoin_market_depth;
+            if collateral_ratio < hundred {
+                market_price = market_price.min(target_price * collateral_ratio / hundred);
+            }
+            market_price = market_price.max(Decimal::ZERO).round_dp(6);
+
+            // The stabilizer only sees fresh data while the oracle is up
+            if oracle_available {
+                let positions = backing_positions(&vaults, &collateral_prices, reserves);
+                stabilizer.record_market_data(stablecoin_id, market_price, positions, supply).await?;
+            }
+            let status = stabilizer.monitor_stability(stablecoin_id).await?;
+            let recommended_actions = stabilizer.get_recommended_actions(stablecoin_id).await?;
+
+            stablecoin.current_price = market_price;
+            stablecoin.total_supply = supply;
+            stablecoin.total_collateral_value = collateral_value;
+            stablecoin.collateral_ratio = collateral_ratio / hundred;
+            stablecoin.updated_at = timestamp;
+            let mechanism_action = mechanism.perform_stability_action(&mut stablecoin, market_price).await?;
+
+            // Supply contraction is a reserve-funded buyback that supports the next step
+            if let MechanismAction::DecreaseSupply { amount } = &mechanism_action {
+                let buyback = (*amount).min(reserves).round_dp(6);
+                if oracle_available && buyback >= Decimal::ONE {
+                    redeem(&redemption, stablecoin_id, "stability-desk", buyback, timestamp).await?;
+                    reserves -= buyback;
+                    supply -= buyback;
+                    totals.buybacks += buyback;
+                    pending_support = buyback;
+                }
+            }
+
+            snapshots.push(StepSnapshot {
+                step,
+                timestamp,
+                market_price,
+                peg_deviation_percent: ((market_price - target_price) / target_price * hundred).round_dp(4),
+                collateral_prices: collateral_prices.clone(),
+                collateral_ratio: collateral_ratio.round_dp(4),
+                reported_collateral_ratio: reported_ratio.round_dp(4),
+                total_supply: supply,
+                reserves,
+                redeemed,
+                unserved_redemptions: unserved,
+                liquidations: step_liquidations,
+                oracle_available,
+                stabilizer_stable: status.is_stable,
+                recommended_actions,
+                mechanism_action,
+            });
+        }
+
+        Ok(summarize(scenario, config, snapshots, liquidations, totals))
+    }
+}
+
+#[derive(Debug, Default)]
+struct RunTotals {
+    redeemed: Decimal,
+    unserved: Decimal,
+    buybacks: Decimal,
+    bad_debt: Decimal,
+}
+
+fn summarize(
+    scenario: &StressScenario,
+    config: &SimulationConfig,
+    steps: Vec<StepSnapshot>,
+    liquidations: Vec<LiquidationEvent>,
+    totals: RunTotals,
+) -> StressTestReport {
+    let max_peg_deviation_percent = steps
+        .iter()
+        .map(|snapshot| snapshot.peg_deviation_percent.abs())
+        .max()
+        .unwrap_or(Decimal::ZERO);
+    let min_collateral_ratio = steps
+        .iter()
+        .map(|snapshot| snapshot.collateral_ratio)
+        .min()
+        .unwrap_or(Decimal::ZERO);
+
+    let depegged_at_step = steps
+        .iter()
+        .find(|snapshot| snapshot.peg_deviation_percent.abs() > config.depeg_threshold_percent)
+        .map(|snapshot| snapshot.step);
+    let repegged_at_step = depegged_at_step.and_then(|depeg| {
+        steps
+            .iter()
+            .find(|snapshot| {
+                snapshot.step > depeg && snapshot.peg_deviation_percent.abs() <= config.depeg_threshold_percent
+            })
+            .map(|snapshot| snapshot.step)
+    });
+
+    StressTestReport {
+        scenario: scenario.name.clone(),
+        mechanism: config.mechanism,
+        steps,
+        liquidations,
+        max_peg_deviation_percent,
+        min_collateral_ratio,
+        total_redeemed: totals.redeemed,
+        total_unserved_redemptions: totals.unserved,
+        reserve_buybacks: totals.buybacks,
+        bad_debt: totals.bad_debt,
+        depegged_at_step,
+        repegged_at_step,
+    }
+}
+
+async fn redeem(
+    redemption: &RedemptionManager,
+    stablecoin_id: Uuid,
+    user_id: &str,
+    amount: Decimal,
+    at: DateTime<Utc>,
+) -> StablecoinResult<()> {
+    let request = RedemptionRequest {
+        id: Uuid::new_v4(),
+        user_id: user_id.to_string(),
+        stablecoin_id,
+        amount,
+        preferred_collateral: None,
+        network_id: 1,
+        sender_address: format!("0x{:040x}", 0),
+        created_at: at,
+    };
+    redemption.process_redemption(request).await?;
+    Ok(())
+}
+
+/// Collateral ratio in percent, matching `CollateralMonitor` for an empty supply
+fn ratio_percent(collateral_value: Decimal, debt: Decimal) -> Decimal {
+    if debt <= Decimal::ZERO {
+        return Decimal::new(1000, 0);
+    }
+    collateral_value / debt * Decimal::new(100, 0)
+}
+
+fn vault_value(vaults: &[VaultState], prices: &BTreeMap<String, Decimal>) -> Decimal {
+    vaults
+        .iter()
+        .filter(|vault| !vault.liquidated)
+        .map(|vault| vault.collateral_amount * prices.get(&vault.collateral_symbol).copied().unwrap_or(Decimal::ZERO))
+        .sum()
+}
+
+fn crypto_position(symbol: &str, amount: Decimal, price: Decimal) -> CollateralPosition {
+    CollateralPosition {
+        id: Uuid::new_v4(),
+        collateral_type: CollateralType::Crypto {
+            token_address: String::new(),
+            symbol: symbol.to_string(),
+        },
+        amount,
+        value_usd: amount * price,
+        locked_until: None,
+        status: CollateralStatus::Active,
+    }
+}
+
+fn reserve_position(value_usd: Decimal) -> CollateralPosition {
+    CollateralPosition {
+        id: Uuid::new_v4(),
+        collateral_type: CollateralType::Fiat { currency: "USD".to_string() },
+        amount: value_usd,
+        value_usd,
+        locked_until: None,
+        status: CollateralStatus::Active,
+    }
+}
+
+fn backing_positions(
+    vaults: &[VaultState],
+    prices: &BTreeMap<String, Decimal>,
+    reserves: Decimal,
+) -> Vec<CollateralPosition> {
+    let mut positions: Vec<CollateralPosition> = vaults
+        .iter()
+        .filter(|vault| !vault.liquidated)
+        .map(|vault| {
+            let price = prices.get(&vault.collateral_symbol).copied().unwrap_or(Decimal::ZERO);
+            crypto_position(&vault.collateral_symbol, vault.collateral_amount, price)
+        })
+        .collect();
+    if reserves > Decimal::ZERO {
+        positions.push(reserve_position(reserves));
+    }
+    positions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eth_config() -> SimulationConfig {
+        SimulationConfig::default()
+            .with_collateral_price("ETH", Decimal::new(2000, 0))
+            .with_vault("alice", "ETH", Decimal::new(10, 0), Decimal::new(10_000, 0))
+            .with_vault("bob", "ETH", Decimal::new(10, 0), Decimal::new(13_000, 0))
+    }
+
+    #[tokio::test]
+    async fn test_collateral_crash_liquidation_timeline_is_deterministic() {
+        let simulator = StressTestSimulator::new(eth_config().with_reserves(Decimal::new(5_000, 0))).unwrap();
+        let scenario = StressScenario::collateral_crash("ETH");
+
+        let first = simulator.run(&scenario).await.unwrap();
+        let second = simulator.run(&scenario).await.unwrap();
+        assert_eq!(first, second);
+
+        assert_eq!(first.steps.len(), 12);
+        let timeline: Vec<(u32, &str)> = first
+            .liquidations
+            .iter()
+            .map(|event| (event.step, event.owner.as_str()))
+            .collect();
+        assert_eq!(timeline, vec![(4, "bob"), (6, "alice")]);
+        assert!(first.bad_debt.is_zero());
+        assert_eq!(first.steps[1].collateral_prices["ETH"], Decimal::new(1800, 0));
+    }
+
+    #[tokio::test]
+    async fn test_bank_run_exhausts_reserves_and_depegs() {
+        let config = SimulationConfig::default()
+            .with_collateral_price("ETH", Decimal::new(2000, 0))
+            .with_vault("whale", "ETH", Decimal::new(1000, 0), Decimal::new(1_000_000, 0))
+            .with_reserves(Decimal::new(300_000, 0))
+            .with_market_depth(Decimal::new(1_000_000, 0), Decimal::new(50_000_000, 0));
+        let report = StressTestSimulator::new(config)
+            .unwrap()
+            .run(&StressScenario::bank_run())
+            .await
+            .unwrap();
+
+        // 150k and 127.5k are served, then only 22.5k of the 108,375 demanded
+        assert_eq!(report.steps[1].redeemed, Decimal::new(150_000, 0));
+        assert_eq!(report.steps[3].reserves, Decimal::ZERO);
+        assert_eq!(report.steps[3].unserved_redemptions, Decimal::new(85_875, 0));
+        assert_eq!(report.steps[3].market_price, Decimal::new(914125, 6));
+        assert_eq!(report.depegged_at_step, Some(3));
+        assert_eq!(report.total_redeemed, Decimal::new(300_000, 0));
+        assert!(report.max_peg_deviation_percent > Decimal::new(2, 0));
+    }
+
+    #[tokio::test]
+    async fn test_algorithmic_contraction_buys_back_from_reserves() {
+        let config = SimulationConfig::default()
+            .with_mechanism(StabilityMechanism::Algorithmic)
+            .with_collateral_price("ETH", Decimal::new(2000, 0))
+            .with_vault("whale", "ETH", Decimal::new(1000, 0), Decimal::new(1_000_000, 0))
+            .with_reserves(Decimal::new(300_000, 0))
+            .with_market_depth(Decimal::new(1_000_000, 0), Decimal::new(50_000_000, 0));
+        let scenario = StressScenario::new("market_sell", 8)
+            .with_shock(1, MarketShock::MarketSell { amount: Decimal::new(100_000, 0) });
+        let report = StressTestSimulator::new(config).unwrap().run(&scenario).await.unwrap();
+
+        // A 10% discount contracts 3bp of supply per point of deviation
+        assert_eq!(report.mechanism, StabilityMechanism::Algorithmic);
+        assert_eq!(report.steps[1].market_price, Decimal::new(9, 1));
+        assert_eq!(
+            report.steps[1].mechanism_action,
+            MechanismAction::DecreaseSupply { amount: Decimal::new(30, 0) }
+        );
+        assert_eq!(report.steps[1].reserves, Decimal::new(299_970, 0));
+
+        // The buyback supports the next step on top of arbitrage
+        assert_eq!(report.steps[2].market_price, Decimal::new(92503, 5));
+        assert!(report.reserve_buybacks > Decimal::new(30, 0));
+        assert!(report.is_solvent());
+    }
+
+    #[tokio::test]
+    async fn test_fiat_backed_peg_recovers_through_arbitrage() {
+        let config = SimulationConfig::default()
+            .with_mechanism(StabilityMechanism::Fiat)
+            .with_collateral_price("USDC", Decimal::ONE)
+            .with_vault("issuer", "USDC", Decimal::new(1_500_000, 0), Decimal::new(1_000_000, 0))
+            .with_reserves(Decimal::new(200_000, 0))
+            .with_market_depth(Decimal::new(1_000_000, 0), Decimal::new(50_000_000, 0));
+        let scenario = StressScenario::new("market_sell", 8)
+            .with_shock(1, MarketShock::MarketSell { amount: Decimal::new(30_000, 0) });
+        let report = StressTestSimulator::new(config).unwrap().run(&scenario).await.unwrap();
+
+        // Beyond 2% the issuer pays the larger arbitrage incentive, inside it the smaller one
+        assert_eq!(
+            report.steps[1].mechanism_action,
+            MechanismAction::TriggerArbitrage { incentive_rate: Decimal::new(50, 4) }
+        );
+        assert_eq!(report.steps[3].market_price, Decimal::new(983125, 6));
+        assert_eq!(
+            report.steps[3].mechanism_action,
+            MechanismAction::TriggerArbitrage { incentive_rate: Decimal::new(25, 4) }
+        );
+
+        // Fiat backing never contracts supply, so reserves are untouched
+        assert!(report.reserve_buybacks.is_zero());
+        assert_eq!(report.steps[7].reserves, Decimal::new(200_000, 0));
+        assert_eq!(report.depegged_at_step, Some(1));
+        assert_eq!(report.repegged_at_step, Some(3));
+        assert!(report.held_peg());
+    }
+
+    #[tokio::test]
+    async fn test_oracle_outage_defers_liquidation_into_bad_debt() {
+        let config = eth_config().with_vault("carol", "ETH", Decimal::new(100, 0), Decimal::new(50_000, 0));
+        let report = StressTestSimulator::new(config)
+            .unwrap()
+            .run(&StressScenario::oracle_outage("ETH"))
+            .await
+            .unwrap();
+
+        let dark = &report.steps[4];
+        assert!(!dark.oracle_available);
+        assert!(dark.reported_collateral_ratio > dark.collateral_ratio);
+        assert!(report.liquidations.iter().all(|event| event.step >= 7));
+
+        let bob = report.liquidations.iter().find(|event| event.owner == "bob").unwrap();
+        assert_eq!(bob.step, 7);
+        assert_eq!(bob.bad_debt, Decimal::new(11902, 1));
+        assert!(!report.is_solvent());
+    }
+}
@@ -0,0 +1,137 @@
+// =====================================================================================
+// File: core-stablecoin/tests/oracle_integration.rs
+// Description: Integration tests for core-oracle sources behind OracleAggregator
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use core_oracle::{
+    types::ProviderHealth, MockProviderClient, OracleProvider, OracleResult, OracleServiceImpl,
+    ProviderClient,
+};
+use core_stablecoin::{
+    AdapterConfig, OracleAggregator, OracleConfig, OracleServiceAdapter, PriceOracle,
+    ProviderClientAdapter,
+};
+
+/// Provider client that only ever returns an hour-old price
+struct StaleProviderClient;
+
+#[async_trait]
+impl ProviderClient for StaleProviderClient {
+    async fn get_price(&self, feed_id: &str) -> OracleResult<core_oracle::PriceData> {
+        let mut price = MockProviderClient::new(OracleProvider::Tellor)
+            .get_price(feed_id)
+            .await?;
+        price.timestamp = Utc::now() - chrono::Duration::hours(1);
+        Ok(price)
+    }
+
+    async fn get_health(&self) -> OracleResult<ProviderHealth> {
+        MockProviderClient::new(OracleProvider::Tellor).get_health().await
+    }
+
+    fn provider(&self) -> OracleProvider {
+        OracleProvider::Tellor
+    }
+}
+
+async fn oracle_service() -> Arc<OracleServiceImpl> {
+    let mut clients: HashMap<OracleProvider, Box<dyn ProviderClient>> = HashMap::new();
+    for provider in [OracleProvider::Chainlink, OracleProvider::PythNetwork] {
+        clients.insert(provider, Box::new(MockProviderClient::new(provider)));
+    }
+    let service = OracleServiceImpl::new(core_oracle::OracleConfig::default())
+        .with_provider_clients(clients);
+    service.initialize().await.unwrap();
+    Arc::new(service)
+}
+
+fn provider_adapter(provider: OracleProvider) -> Box<dyn PriceOracle> {
+    Box::new(ProviderClientAdapter::new(
+        MockProviderClient::new(provider),
+        AdapterConfig::from_oracle_config(&OracleConfig::default()),
+    ))
+}
+
+#[tokio::test]
+async fn test_oracle_service_as_primary() {
+    let primary = Box::new(OracleServiceAdapter::new(
+        oracle_service().await,
+        AdapterConfig::default(),
+    ));
+    let mut aggregator = OracleAggregator::new(
+        primary,
+        vec![provider_adapter(OracleProvider::BandProtocol)],
+        OracleConfig::default(),
+    );
+
+    let price = aggregator.get_validated_price("ETH").await.unwrap();
+    assert_eq!(price.asset_symbol, "ETH");
+    assert_eq!(price.source, "core-oracle");
+    assert!(price.price >= Decimal::new(199975, 2) && price.price <= Decimal::new(200000, 2));
+    assert_eq!(price.confidence, Decimal::new(95, 0));
+}
+
+#[tokio::test]
+async fn test_frozen_feed_fails_over_to_backup() {
+    let service = oracle_service().await;
+    service
+        .circuit_breakers()
+        .write()
+        .await
+        .trip("ETH/USD", "maintenance", Utc::now());
+
+    let primary = Box::new(OracleServiceAdapter::new(service, AdapterConfig::default()));
+    let mut aggregator = OracleAggregator::new(
+        primary,
+        vec![provider_adapter(OracleProvider::BandProtocol)],
+        OracleConfig::default(),
+    );
+
+    let price = aggregator.get_validated_price("ETH").await.unwrap();
+    assert_eq!(price.source, "bandprotocol");
+    assert_eq!(price.price, Decimal::new(200050, 2));
+}
+
+#[tokio::test]
+async fn test_stale_provider_fails_over_to_backup() {
+    let primary = Box::new(ProviderClientAdapter::new(
+        StaleProviderClient,
+        AdapterConfig::default(),
+    ));
+    let mut aggregator = OracleAggregator::new(
+        primary,
+        vec![provider_adapter(OracleProvider::Chainlink)],
+        OracleConfig::default(),
+    );
+
+    let price = aggregator.get_validated_price("BTC").await.unwrap();
+    assert_eq!(price.source, "chainlink");
+    assert_eq!(price.price, Decimal::new(5000000, 2));
+}
+
+#[tokio::test]
+async fn test_median_across_core_oracle_providers() {
+    let aggregator = OracleAggregator::new(
+        provider_adapter(OracleProvider::Chainlink),
+        vec![
+            provider_adapter(OracleProvider::PythNetwork),
+            provider_adapter(OracleProvider::BandProtocol),
+        ],
+        OracleConfig::default(),
+    );
+
+    let price = aggregator.get_aggregated_price("ETH").await.unwrap();
+    assert_eq!(price.source, "aggregated");
+    assert_eq!(price.price, Decimal::new(200000, 2));
+
+    let health = aggregator.get_health_status().await;
+    assert_eq!(health.healthy_oracles, 3);
+    assert!(health.overall_healthy);
+}
@@ -0,0 +1,849 @@
+// =====================================================================================
+// File: core-trading/src/auction.rs
+// Description: Call auction mode for thinly traded RWA pairs
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::{OrderSide, OrderStatus, OrderType, TimeInForce, TradingPair},
+    order_book::{Order, OrderBookManager},
+    matching::{MatchResult, MatchingEngine, Trade},
+    TradingConfig,
+};
+
+/// Auction configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionConfig {
+    /// Default call window length in seconds
+    pub call_window_seconds: u64,
+    /// Fee percentage charged on auction executions
+    pub auction_fee_percentage: Decimal,
+    /// Reference price used when no trade has printed yet
+    pub default_reference_price: Option<Decimal>,
+}
+
+impl Default for AuctionConfig {
+    fn default() -> Self {
+        Self {
+            call_window_seconds: 300, // 5 minutes
+            auction_fee_percentage: Decimal::new(10, 4), // 0.10%
+            default_reference_price: None,
+        }
+    }
+}
+
+/// How a trading pair is matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradingMode {
+    /// Orders match on arrival
+    Continuous,
+    /// Orders are collected and uncrossed at the end of each call window
+    PeriodicAuction { call_window_seconds: u64 },
+}
+
+/// Auction type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuctionType {
+    /// Opening auction, serves ATO orders
+    Opening,
+    /// Closing auction, serves ATC orders
+    Closing,
+    /// Recurring call of a pair in periodic auction mode
+    Periodic,
+}
+
+impl AuctionType {
+    /// Check if an order's time in force may join this auction
+    pub fn accepts(&self, time_in_force: TimeInForce) -> bool {
+        match time_in_force {
+            TimeInForce::ATO => *self == AuctionType::Opening,
+            TimeInForce::ATC => *self == AuctionType::Closing,
+            TimeInForce::FOK => false,
+            _ => true,
+        }
+    }
+}
+
+/// Equilibrium computed for a call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuctionClearing {
+    pub price: Decimal,
+    pub executable_volume: Decimal,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    /// Signed surplus at the clearing price: positive for buy pressure
+    pub imbalance: Decimal,
+}
+
+/// Outcome of an uncross
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuctionResult {
+    pub auction_id: Uuid,
+    pub trading_pair: TradingPair,
+    pub auction_type: AuctionType,
+    pub clearing: Option<AuctionClearing>,
+    pub match_result: MatchResult,
+    pub uncrossed_at: DateTime<Utc>,
+}
+
+/// A single call auction for one trading pair
+#[derive(Debug, Clone)]
+pub struct CallAuction {
+    pub id: Uuid,
+    pub trading_pair: TradingPair,
+    pub auction_type: AuctionType,
+    pub opened_at: DateTime<Utc>,
+    pub closes_at: DateTime<Utc>,
+    orders: Vec<Order>,
+}
+
+impl CallAuction {
+    /// Open a new call window
+    pub fn new(
+        trading_pair: TradingPair,
+        auction_type: AuctionType,
+        opened_at: DateTime<Utc>,
+        call_window_seconds: u64,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            trading_pair,
+            auction_type,
+            opened_at,
+            closes_at: opened_at + Duration::seconds(call_window_seconds as i64),
+            orders: Vec::new(),
+        }
+    }
+
+    /// Check if the call window has ended
+    pub fn is_due(&self, now: DateTime<Utc>) -> bool {
+        now >= self.closes_at
+    }
+
+    /// Orders collected during the call
+    pub fn orders(&self) -> &[Order] {
+        &self.orders
+    }
+
+    /// Collect an order into the call.
+    ///
+    /// Orders that may rest are checked against the book's order validation
+    /// up front, so any remainder can be put back in the book after the uncross.
+    pub fn collect(
+        &mut self,
+        mut order: Order,
+        order_book: &OrderBookManager,
+    ) -> TradingResult<()> {
+        if order.trading_pair != self.trading_pair {
+            return Err(TradingError::unsupported_trading_pair(order.trading_pair.symbol()));
+        }
+
+        if matches!(order.order_type, OrderType::Stop | OrderType::StopLimit | OrderType::FOK) {
+            return Err(TradingError::invalid_order(
+                "Stop and FOK orders are not accepted during a call auction",
+            ));
+        }
+
+        if !self.auction_type.accepts(order.time_in_force) {
+            return Err(TradingError::invalid_order(format!(
+                "{:?} orders cannot join a {:?} auction",
+                order.time_in_force, self.auction_type
+            )));
+        }
+
+        if order.order_type != OrderType::Market && order.price <= Decimal::ZERO {
+            return Err(TradingError::validation_error("price", "Order price must be positive"));
+        }
+
+        if may_rest(&order) {
+            order_book.validate_order(&order)?;
+        }
+
+        order.status = OrderStatus::Pending;
+        order.updated_at = Utc::now();
+        self.orders.push(order);
+        Ok(())
+    }
+
+    /// Cancel a collected order
+    pub fn cancel(&mut self, order_id: Uuid) -> Option<Order> {
+        let pos = self.orders.iter().position(|o| o.id == order_id)?;
+        let mut order = self.orders.remove(pos);
+        order.status = OrderStatus::Cancelled;
+        Some(order)
+    }
+
+    /// Indicative clearing price if the call were uncrossed now
+    pub fn indicative_clearing(
+        &self,
+        order_book: &OrderBookManager,
+        reference_price: Option<Decimal>,
+    ) -> Option<AuctionClearing> {
+        let resting_bids = order_book.resting_orders(OrderSide::Buy);
+        let resting_asks = order_book.resting_orders(OrderSide::Sell);
+        let participants: Vec<&Order> = self
+            .orders
+            .iter()
+            .chain(resting_bids)
+            .chain(resting_asks)
+            .collect();
+
+        compute_clearing(&participants, reference_price)
+    }
+
+    /// Uncross the call and the resting book in one batch at a single price.
+    ///
+    /// Crossing resting orders are pulled from the book, allocated in
+    /// price-time priority together with the collected orders, and any
+    /// remainder that may rest is put back in its original priority order.
+    /// If any step fails the book is restored and nothing reaches the feed.
+    pub fn uncross(
+        &self,
+        order_book: &mut OrderBookManager,
+        config: &AuctionConfig,
+    ) -> TradingResult<AuctionResult> {
+        // Hold feed broadcasts so a failed uncross never leaks to subscribers
+        order_book.begin_feed_batch();
+        let checkpoint = order_book.clone();
+        let result = self.uncross_book(order_book, config);
+        if result.is_err() {
+            *order_book = checkpoint;
+        }
+        order_book.commit_feed_batch();
+        result
+    }
+
+    /// Allocate the call and the crossing resting orders at the clearing price
+    fn uncross_book(
+        &self,
+        order_book: &mut OrderBookManager,
+        config: &AuctionConfig,
+    ) -> TradingResult<AuctionResult> {
+        let reference_price = order_book.last_trade_price().or(config.default_reference_price);
+        let clearing = self.indicative_clearing(order_book, reference_price);
+        let mut match_result = MatchResult::new();
+
+        let crossing_price = clearing
+            .as_ref()
+            .filter(|clearing| clearing.executable_volume > Decimal::ZERO)
+            .map(|clearing| clearing.price);
+
+        let price = match crossing_price {
+            Some(price) => price,
+            None => {
+                // Nothing crosses: collected orders go to the book or are cancelled
+                for order in self.orders.iter().cloned() {
+                    Self::release(order, order_book, &mut match_result)?;
+                }
+                return Ok(AuctionResult {
+                    auction_id: self.id,
+                    trading_pair: self.trading_pair.clone(),
+                    auction_type: self.auction_type,
+                    clearing,
+                    match_result,
+                    uncrossed_at: Utc::now(),
+                });
+            }
+        };
+
+        // Pull crossing resting orders out of the book, keeping their priority
+        let mut buys = Vec::new();
+        let mut sells = Vec::new();
+        for side in [OrderSide::Buy, OrderSide::Sell] {
+            let ids: Vec<Uuid> = order_book
+                .resting_orders(side)
+                .into_iter()
+                .filter(|order| is_eligible(order, price))
+                .map(|order| order.id)
+                .collect();
+            for id in ids {
+                if let Some(order) = order_book.remove_order(id)? {
+                    match side {
+                        OrderSide::Buy => buys.push(order),
+                        OrderSide::Sell => sells.push(order),
+                    }
+                }
+            }
+        }
+
+        let mut leftovers = Vec::new();
+        for order in self.orders.iter().cloned() {
+            if !is_eligible(&order, price) {
+                leftovers.push(order);
+                continue;
+            }
+            match order.side {
+                OrderSide::Buy => buys.push(order),
+                OrderSide::Sell => sells.push(order),
+            }
+        }
+
+        // Market orders first, then best price; stable sort keeps time priority
+        buys.sort_by_key(|order| Reverse(priority_price(order)));
+        sells.sort_by_key(|order| Reverse(priority_price(order)));
+
+        let (mut b, mut s) = (0, 0);
+        while b < buys.len() && s < sells.len() {
+            let quantity = buys[b].remaining_quantity.min(sells[s].remaining_quantity);
+            let value = price * quantity;
+            let fee = value * config.auction_fee_percentage;
+            let trade = Trade::new(
+                self.trading_pair.clone(),
+                &buys[b],
+                &sells[s],
+                price,
+                quantity,
+                fee,
+                fee,
+            );
+            match_result.add_trade(trade);
+
+            buys[b].update_filled(quantity);
+            sells[s].update_filled(quantity);
+            if buys[b].is_filled() {
+                b += 1;
+            }
+            if sells[s].is_filled() {
+                s += 1;
+            }
+        }
+
+        order_book.record_trade_price(price);
+
+        for order in buys.into_iter().chain(sells).chain(leftovers) {
+            Self::release(order, order_book, &mut match_result)?;
+        }
+
+        Ok(AuctionResult {
+            auction_id: self.id,
+            trading_pair: self.trading_pair.clone(),
+            auction_type: self.auction_type,
+            clearing,
+            match_result,
+            uncrossed_at: Utc::now(),
+        })
+    }
+
+    /// Put an order back in the book, or cancel it if it may not rest
+    fn release(
+        mut order: Order,
+        order_book: &mut OrderBookManager,
+        result: &mut MatchResult,
+    ) -> TradingResult<()> {
+        if order.is_filled() {
+            result.add_updated_order(order);
+            return Ok(());
+        }
+
+        if may_rest(&order) {
+            order_book.add_order(order.clone())?;
+            if let Some(resting) = order_book.get_order(order.id) {
+                result.add_updated_order(resting.clone());
+            }
+        } else {
+            order.status = OrderStatus::Cancelled;
+            order.updated_at = Utc::now();
+            result.add_updated_order(order);
+        }
+
+        Ok(())
+    }
+}
+
+/// Whether an unfilled remainder may rest in the book after the uncross
+fn may_rest(order: &Order) -> bool {
+    order.order_type != OrderType::Market
+        && order.order_type != OrderType::IOC
+        && !matches!(order.time_in_force, TimeInForce::IOC | TimeInForce::ATO | TimeInForce::ATC)
+}
+
+/// Whether an order participates at the given clearing price
+fn is_eligible(order: &Order, price: Decimal) -> bool {
+    if order.order_type == OrderType::Market {
+        return true;
+    }
+    match order.side {
+        OrderSide::Buy => order.price >= price,
+        OrderSide::Sell => order.price <= price,
+    }
+}
+
+/// Sort key, highest first: market orders, then the most aggressive limit
+fn priority_price(order: &Order) -> (bool, Decimal) {
+    match (order.order_type, order.side) {
+        (OrderType::Market, _) => (true, Decimal::ZERO),
+        (_, OrderSide::Buy) => (false, order.price),
+        (_, OrderSide::Sell) => (false, -order.price),
+    }
+}
+
+/// Find the price that maximizes executed volume.
+///
+/// Ties are broken by the smallest imbalance, then by proximity to the
+/// reference price, then by the lower price.
+pub fn compute_clearing(
+    orders: &[&Order],
+    reference_price: Option<Decimal>,
+) -> Option<AuctionClearing> {
+    let mut candidates: Vec<Decimal> = orders
+        .iter()
+        .filter(|o| o.order_type != OrderType::Market)
+        .map(|o| o.price)
+        .collect();
+    if candidates.is_empty() {
+        candidates.extend(reference_price);
+    }
+    candidates.sort();
+    candidates.dedup();
+
+    let mut best: Option<AuctionClearing> = None;
+    for price in candidates {
+        let buy_volume: Decimal = orders
+            .iter()
+            .filter(|o| o.side == OrderSide::Buy && is_eligible(o, price))
+            .map(|o| o.remaining_quantity)
+            .sum();
+        let sell_volume: Decimal = orders
+            .iter()
+            .filter(|o| o.side == OrderSide::Sell && is_eligible(o, price))
+            .map(|o| o.remaining_quantity)
+            .sum();
+
+        let candidate = AuctionClearing {
+            price,
+            executable_volume: buy_volume.min(sell_volume),
+            buy_volume,
+            sell_volume,
+            imbalance: buy_volume - sell_volume,
+        };
+
+        let better = match &best {
+            None => true,
+            Some(current) => is_better(&candidate, current, reference_price),
+        };
+        if better {
+            best = Some(candidate);
+        }
+    }
+
+    best
+}
+
+fn is_better(
+    candidate: &AuctionClearing,
+    current: &AuctionClearing,
+    reference_price: Option<Decimal>,
+) -> bool {
+    if candidate.executable_volume != current.executable_volume {
+        return candidate.executable_volume > current.executable_volume;
+    }
+    if candidate.imbalance.abs() != current.imbalance.abs() {
+        return candidate.imbalance.abs() < current.imbalance.abs();
+    }
+    if let Some(reference) = reference_price {
+        let candidate_distance = (candidate.price - reference).abs();
+        let current_distance = (current.price - reference).abs();
+        if candidate_distance != current_distance {
+            return candidate_distance < current_distance;
+        }
+    }
+    // Candidates are visited in ascending order, so keep the lower price
+    false
+}
+
+/// Routes orders per trading pair between continuous matching and call auctions
+pub struct AuctionManager {
+    config: AuctionConfig,
+    modes: HashMap<TradingPair, TradingMode>,
+    auctions: HashMap<TradingPair, CallAuction>,
+}
+
+impl AuctionManager {
+    /// Create a new auction manager
+    pub fn new(config: AuctionConfig) -> Self {
+        Self {
+            config,
+            modes: HashMap::new(),
+            auctions: HashMap::new(),
+        }
+    }
+
+    /// Create a manager with every supported pair in its configured trading mode
+    pub fn from_config(config: &TradingConfig, now: DateTime<Utc>) -> Self {
+        let mut manager = Self::new(config.auction_config.clone());
+        for pair in &config.global_settings.supported_pairs {
+            let trading_pair = TradingPair::new(pair.base_asset.clone(), pair.quote_asset.clone());
+            manager.set_mode(trading_pair, pair.trading_mode, now);
+        }
+        manager
+    }
+
+    /// Set the trading mode of a pair, opening a call window if it enters auction mode
+    pub fn set_mode(&mut self, trading_pair: TradingPair, mode: TradingMode, now: DateTime<Utc>) {
+        if let TradingMode::PeriodicAuction { call_window_seconds } = mode {
+            self.auctions.entry(trading_pair.clone()).or_insert_with(|| {
+                CallAuction::new(trading_pair.clone(), AuctionType::Periodic, now, call_window_seconds)
+            });
+        }
+        self.modes.insert(trading_pair, mode);
+    }
+
+    /// Get the trading mode of a pair
+    pub fn mode(&self, trading_pair: &TradingPair) -> TradingMode {
+        self.modes.get(trading_pair).copied().unwrap_or(TradingMode::Continuous)
+    }
+
+    /// Open a scheduled opening or closing auction for a pair
+    pub fn open_auction(
+        &mut self,
+        trading_pair: TradingPair,
+        auction_type: AuctionType,
+        now: DateTime<Utc>,
+    ) -> TradingResult<Uuid> {
+        if self.auctions.contains_key(&trading_pair) {
+            return Err(TradingError::BusinessRuleViolation {
+                rule: "single_call".to_string(),
+                message: format!("A call auction is already open for {}", trading_pair.symbol()),
+            });
+        }
+
+        let auction = CallAuction::new(
+            trading_pair.clone(),
+            auction_type,
+            now,
+            self.config.call_window_seconds,
+        );
+        let id = auction.id;
+        self.auctions.insert(trading_pair, auction);
+        Ok(id)
+    }
+
+    /// Get the open call for a pair
+    pub fn auction(&self, trading_pair: &TradingPair) -> Option<&CallAuction> {
+        self.auctions.get(trading_pair)
+    }
+
+    /// Submit an order, collecting it into an open call or matching it continuously
+    pub fn submit_order(
+        &mut self,
+        order: Order,
+        order_book: &mut OrderBookManager,
+        engine: &MatchingEngine,
+    ) -> TradingResult<MatchResult> {
+        if let Some(auction) = self.auctions.get_mut(&order.trading_pair) {
+            let mut result = MatchResult::new();
+            auction.collect(order.clone(), order_book)?;
+            let mut collected = order;
+            collected.status = OrderStatus::Pending;
+            result.add_updated_order(collected);
+            return Ok(result);
+        }
+
+        engine.submit_order(order, order_book)
+    }
+
+    /// Uncross the pair's call if its window has ended.
+    ///
+    /// Stops triggered by the uncross price are released through the engine
+    /// and their fills are added to the result. Pairs in periodic auction
+    /// mode then immediately open their next call.
+    pub fn uncross_if_due(
+        &mut self,
+        trading_pair: &TradingPair,
+        order_book: &mut OrderBookManager,
+        engine: &MatchingEngine,
+        now: DateTime<Utc>,
+    ) -> TradingResult<Option<AuctionResult>> {
+        let due = self
+            .auctions
+            .get(trading_pair)
+            .is_some_and(|auction| auction.is_due(now));
+        if !due {
+            return Ok(None);
+        }
+
+        let mut result = match self.auctions.get(trading_pair) {
+            Some(auction) => auction.uncross(order_book, &self.config)?,
+            None => return Ok(None),
+        };
+        // The call is only closed once its uncross has been applied to the book
+        self.auctions.remove(trading_pair);
+        engine.process_triggered_stops(order_book, &mut result.match_result)?;
+
+        if let TradingMode::PeriodicAuction { call_window_seconds } = self.mode(trading_pair) {
+            self.auctions.insert(
+                trading_pair.clone(),
+                CallAuction::new(trading_pair.clone(), AuctionType::Periodic, now, call_window_seconds),
+            );
+        }
+
+        Ok(Some(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::OrderBookConfig;
+    use crate::matching::MatchingConfig;
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    fn limit(user: &str, side: OrderSide, price: i64, quantity: i64) -> Order {
+        Order::new(
+            user.to_string(),
+            pair(),
+            side,
+            OrderType::Limit,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        )
+    }
+
+    fn clearing_for(orders: &[Order], reference: Option<Decimal>) -> AuctionClearing {
+        let refs: Vec<&Order> = orders.iter().collect();
+        compute_clearing(&refs, reference).unwrap()
+    }
+
+    #[test]
+    fn test_clearing_price_maximizes_volume() {
+        let orders = vec![
+            limit("b1", OrderSide::Buy, 102, 10),
+            limit("b2", OrderSide::Buy, 101, 5),
+            limit("b3", OrderSide::Buy, 100, 5),
+            limit("s1", OrderSide::Sell, 99, 5),
+            limit("s2", OrderSide::Sell, 100, 5),
+            limit("s3", OrderSide::Sell, 101, 10),
+        ];
+
+        let clearing = clearing_for(&orders, None);
+        assert_eq!(clearing.price, Decimal::new(101, 0));
+        assert_eq!(clearing.executable_volume, Decimal::new(15, 0));
+        assert_eq!(clearing.imbalance, Decimal::new(-5, 0));
+    }
+
+    #[test]
+    fn test_clearing_imbalance_tie_breaker() {
+        let orders = vec![
+            limit("b1", OrderSide::Buy, 101, 10),
+            limit("b2", OrderSide::Buy, 100, 2),
+            limit("s1", OrderSide::Sell, 100, 10),
+        ];
+
+        let clearing = clearing_for(&orders, None);
+        assert_eq!(clearing.price, Decimal::new(101, 0));
+        assert_eq!(clearing.imbalance, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_clearing_reference_price_tie_breaker() {
+        let orders = vec![
+            limit("b1", OrderSide::Buy, 101, 10),
+            limit("s1", OrderSide::Sell, 100, 10),
+        ];
+
+        assert_eq!(clearing_for(&orders, Some(Decimal::new(1012, 1))).price, Decimal::new(101, 0));
+        assert_eq!(clearing_for(&orders, Some(Decimal::new(99, 0))).price, Decimal::new(100, 0));
+        assert_eq!(clearing_for(&orders, None).price, Decimal::new(100, 0));
+    }
+
+    #[test]
+    fn test_uncross_book_and_collected_orders() {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        book.add_order(limit("b3", OrderSide::Buy, 100, 5)).unwrap();
+        book.add_order(limit("s3", OrderSide::Sell, 101, 10)).unwrap();
+
+        let mut auction = CallAuction::new(pair(), AuctionType::Periodic, Utc::now(), 60);
+        auction.collect(limit("b1", OrderSide::Buy, 102, 10), &book).unwrap();
+        auction.collect(limit("b2", OrderSide::Buy, 101, 5), &book).unwrap();
+        auction.collect(limit("s1", OrderSide::Sell, 99, 5), &book).unwrap();
+        auction.collect(limit("s2", OrderSide::Sell, 100, 5), &book).unwrap();
+
+        let result = auction.uncross(&mut book, &AuctionConfig::default()).unwrap();
+
+        assert_eq!(result.clearing.unwrap().price, Decimal::new(101, 0));
+        let trades = &result.match_result.trades;
+        assert!(trades.iter().all(|t| t.price == Decimal::new(101, 0)));
+        let volume: Decimal = trades.iter().map(|t| t.quantity).sum();
+        assert_eq!(volume, Decimal::new(15, 0));
+
+        // Unfilled ask remainder rests, the non-crossing bid was never touched
+        assert_eq!(book.best_bid(), Some(Decimal::new(100, 0)));
+        assert_eq!(book.best_ask(), Some(Decimal::new(101, 0)));
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(5, 0));
+        assert_eq!(book.last_trade_price(), Some(Decimal::new(101, 0)));
+    }
+
+    #[test]
+    fn test_collect_applies_book_order_validation() {
+        let config = OrderBookConfig {
+            max_order_size: Decimal::new(5, 0),
+            ..OrderBookConfig::default()
+        };
+        let book = OrderBookManager::new(config, pair());
+        let mut auction = CallAuction::new(pair(), AuctionType::Periodic, Utc::now(), 60);
+
+        assert!(auction.collect(limit("b1", OrderSide::Buy, 100, 10), &book).is_err());
+        assert!(auction.collect(limit("b1", OrderSide::Buy, 100, 5), &book).is_ok());
+        assert_eq!(auction.orders().len(), 1);
+    }
+
+    #[test]
+    fn test_failed_uncross_restores_book_and_keeps_call() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut manager = AuctionManager::new(AuctionConfig::default());
+        let start = Utc::now();
+        manager.open_auction(pair(), AuctionType::Opening, start).unwrap();
+
+        // Collected under a looser book, uncrossed against one that rejects the remainder
+        let mut loose = OrderBookManager::new(OrderBookConfig::default(), pair());
+        manager.submit_order(limit("b1", OrderSide::Buy, 102, 10), &mut loose, &engine).unwrap();
+
+        let config = OrderBookConfig {
+            max_order_size: Decimal::new(5, 0),
+            ..OrderBookConfig::default()
+        };
+        let mut book = OrderBookManager::new(config, pair());
+        book.add_order(limit("s1", OrderSide::Sell, 100, 5)).unwrap();
+
+        let later = start + Duration::seconds(301);
+        assert!(manager.uncross_if_due(&pair(), &mut book, &engine, later).is_err());
+
+        assert_eq!(book.best_ask(), Some(Decimal::new(100, 0)));
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(5, 0));
+        assert!(book.best_bid().is_none());
+        assert!(book.last_trade_price().is_none());
+        assert_eq!(manager.auction(&pair()).unwrap().orders().len(), 1);
+    }
+
+    #[test]
+    fn test_ato_orders_only_join_opening_auction() {
+        let book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let ato = limit("b1", OrderSide::Buy, 100, 1).with_time_in_force(TimeInForce::ATO);
+
+        let mut closing = CallAuction::new(pair(), AuctionType::Closing, Utc::now(), 60);
+        assert!(closing.collect(ato.clone(), &book).is_err());
+
+        let mut opening = CallAuction::new(pair(), AuctionType::Opening, Utc::now(), 60);
+        assert!(opening.collect(ato, &book).is_ok());
+    }
+
+    #[test]
+    fn test_unfilled_ato_is_cancelled_after_uncross() {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut auction = CallAuction::new(pair(), AuctionType::Opening, Utc::now(), 60);
+
+        let ato = limit("b1", OrderSide::Buy, 100, 3).with_time_in_force(TimeInForce::ATO);
+        let ato_id = ato.id;
+        auction.collect(ato, &book).unwrap();
+        auction.collect(limit("s1", OrderSide::Sell, 100, 1), &book).unwrap();
+
+        let result = auction.uncross(&mut book, &AuctionConfig::default()).unwrap();
+
+        assert_eq!(result.match_result.trades.len(), 1);
+        let ato_state = result
+            .match_result
+            .updated_orders
+            .iter()
+            .find(|o| o.id == ato_id)
+            .unwrap();
+        assert_eq!(ato_state.status, OrderStatus::Cancelled);
+        assert_eq!(ato_state.filled_quantity, Decimal::new(1, 0));
+        assert!(book.best_bid().is_none());
+    }
+
+    #[test]
+    fn test_periodic_mode_collects_then_uncrosses() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut manager = AuctionManager::new(AuctionConfig::default());
+        let start = Utc::now();
+
+        manager.set_mode(pair(), TradingMode::PeriodicAuction { call_window_seconds: 60 }, start);
+
+        let buy = manager.submit_order(limit("b1", OrderSide::Buy, 100, 1), &mut book, &engine).unwrap();
+        let sell = manager.submit_order(limit("s1", OrderSide::Sell, 100, 1), &mut book, &engine).unwrap();
+        assert!(!buy.has_matches() && !sell.has_matches());
+        assert_eq!(manager.auction(&pair()).unwrap().orders().len(), 2);
+
+        assert!(manager.uncross_if_due(&pair(), &mut book, &engine, start).unwrap().is_none());
+
+        let later = start + Duration::seconds(61);
+        let result = manager.uncross_if_due(&pair(), &mut book, &engine, later).unwrap().unwrap();
+        assert_eq!(result.match_result.trades.len(), 1);
+
+        // The next call window opens straight away
+        let next = manager.auction(&pair()).unwrap();
+        assert!(next.orders().is_empty());
+        assert_eq!(next.opened_at, later);
+    }
+
+    #[test]
+    fn test_uncross_price_triggers_parked_stops() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut manager = AuctionManager::new(AuctionConfig::default());
+        let start = Utc::now();
+
+        book.add_order(limit("s2", OrderSide::Sell, 105, 1)).unwrap();
+        let stop = Order::new(
+            "stopper".to_string(),
+            pair(),
+            OrderSide::Buy,
+            OrderType::Stop,
+            Decimal::ZERO,
+            Decimal::new(1, 0),
+        )
+        .with_stop_price(Decimal::new(100, 0));
+        book.add_stop_order(stop).unwrap();
+
+        manager.open_auction(pair(), AuctionType::Opening, start).unwrap();
+        manager.submit_order(limit("b1", OrderSide::Buy, 100, 1), &mut book, &engine).unwrap();
+        manager.submit_order(limit("s1", OrderSide::Sell, 100, 1), &mut book, &engine).unwrap();
+
+        let later = start + Duration::seconds(301);
+        let result = manager.uncross_if_due(&pair(), &mut book, &engine, later).unwrap().unwrap();
+
+        // The print at 100 wakes the stop, which lifts the resting ask at 105
+        let trades = &result.match_result.trades;
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, Decimal::new(100, 0));
+        assert_eq!(trades[1].price, Decimal::new(105, 0));
+        assert_eq!(trades[1].buyer_user_id, "stopper");
+        assert!(book.stop_orders().is_empty());
+        assert_eq!(book.last_trade_price(), Some(Decimal::new(105, 0)));
+    }
+
+    #[test]
+    fn test_pair_config_selects_trading_mode() {
+        let mut config = TradingConfig::default();
+        for pair_config in config.global_settings.supported_pairs.iter_mut() {
+            if pair_config.symbol() == "RWA/USD" {
+                pair_config.trading_mode = TradingMode::PeriodicAuction { call_window_seconds: 60 };
+            }
+        }
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut manager = AuctionManager::from_config(&config, Utc::now());
+
+        let btc = TradingPair::new("BTC".to_string(), "USD".to_string());
+        assert_eq!(manager.mode(&btc), TradingMode::Continuous);
+        assert!(manager.auction(&btc).is_none());
+        assert_eq!(manager.mode(&pair()), TradingMode::PeriodicAuction { call_window_seconds: 60 });
+
+        // Orders for the auction pair wait for the call instead of resting on the book
+        manager.submit_order(limit("b1", OrderSide::Buy, 100, 1), &mut book, &engine).unwrap();
+        assert_eq!(manager.auction(&pair()).unwrap().orders().len(), 1);
+        assert!(book.best_bid().is_none());
+    }
+}
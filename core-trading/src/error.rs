@@ -113,6 +113,18 @@ pub enum TradingError {
     #[error("Position limit exceeded: {asset} - current {current}, limit {limit}")]
     PositionLimitExceeded { asset: String, current: String, limit: String },
 
+    /// Order notional exceeds the pre-trade limit
+    #[error("Order notional exceeded: {notional} > {limit}")]
+    NotionalLimitExceeded { notional: String, limit: String },
+
+    /// Kill switch engaged for an account or trading pair
+    #[error("Kill switch active: {scope} {target}")]
+    KillSwitchActive { scope: String, target: String },
+
+    /// Market data feed sequence gap; the consumer must resync from a snapshot
+    #[error("Feed sequence gap: {trading_pair} - expected {expected}, received {received}")]
+    FeedSequenceGap { trading_pair: String, expected: u64, received: u64 },
+
     /// Margin requirement not met
     #[error("Margin requirement not met: required {required}, available {available}")]
     MarginRequirementNotMet { required: String, available: String },
@@ -258,6 +270,31 @@ impl TradingError {
         Self::SelfTradePrevented { user_id: user_id.into() }
     }
 
+    /// Create a position limit exceeded error
+    pub fn position_limit_exceeded<S: Into<String>>(asset: S, current: S, limit: S) -> Self {
+        Self::PositionLimitExceeded {
+            asset: asset.into(),
+            current: current.into(),
+            limit: limit.into(),
+        }
+    }
+
+    /// Create a notional limit exceeded error
+    pub fn notional_limit_exceeded<S: Into<String>>(notional: S, limit: S) -> Self {
+        Self::NotionalLimitExceeded {
+            notional: notional.into(),
+            limit: limit.into(),
+        }
+    }
+
+    /// Create a kill switch active error
+    pub fn kill_switch_active<S: Into<String>>(scope: S, target: S) -> Self {
+        Self::KillSwitchActive {
+            scope: scope.into(),
+            target: target.into(),
+        }
+    }
+
     /// Create a validation error
     pub fn validation_error<S: Into<String>>(field: S, message: S) -> Self {
         Self::ValidationError {
@@ -306,6 +343,9 @@ impl TradingError {
             TradingError::SelfTradePrevented { .. } => "self_trade",
             TradingError::SlippageTooHigh { .. } => "slippage",
             TradingError::PositionLimitExceeded { .. } => "position_limit",
+            TradingError::NotionalLimitExceeded { .. } => "notional_limit",
+            TradingError::KillSwitchActive { .. } => "kill_switch",
+            TradingError::FeedSequenceGap { .. } => "feed_gap",
             TradingError::MarginRequirementNotMet { .. } => "margin_requirement",
             TradingError::TradeAlreadySettled { .. } => "trade_settled",
             TradingError::SettlementTimeout { .. } => "settlement_timeout",
@@ -329,6 +369,7 @@ impl TradingError {
             TradingError::InternalError { .. } => ErrorSeverity::Critical,
             TradingError::CircuitBreakerTriggered { .. } => ErrorSeverity::Critical,
             TradingError::TradingHalted { .. } => ErrorSeverity::High,
+            TradingError::KillSwitchActive { .. } => ErrorSeverity::High,
             TradingError::SettlementError { .. } => ErrorSeverity::High,
             TradingError::DatabaseError { .. } => ErrorSeverity::High,
             TradingError::AuthenticationError { .. } => ErrorSeverity::High,
@@ -377,6 +418,9 @@ impl TradingError {
                 | TradingError::OrderExpired { .. }
                 | TradingError::SelfTradePrevented { .. }
                 | TradingError::SlippageTooHigh { .. }
+                | TradingError::PositionLimitExceeded { .. }
+                | TradingError::NotionalLimitExceeded { .. }
+                | TradingError::KillSwitchActive { .. }
                 | TradingError::ValidationError { .. }
         )
     }
@@ -411,6 +455,12 @@ impl From<sqlx::Error> for TradingError {
     }
 }
 
+impl From<core_events::EventError> for TradingError {
+    fn from(err: core_events::EventError) -> Self {
+        Self::DatabaseError { message: err.to_string() }
+    }
+}
+
 impl From<validator::ValidationErrors> for TradingError {
     fn from(err: validator::ValidationErrors) -> Self {
         Self::ValidationError {
@@ -0,0 +1,455 @@
+// =====================================================================================
+// File: core-trading/src/journal.rs
+// Description: Event-sourced order book journal with checkpoints and replay
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use core_events::{AggregateSnapshot, Event, EventEnvelope, EventError, EventResult, EventStore};
+
+use crate::{
+    error::TradingResult,
+    types::TradingPair,
+    order_book::{Order, OrderBookConfig, OrderBookManager, OrderBookState},
+};
+
+/// Aggregate type used for order book streams
+pub const ORDER_BOOK_AGGREGATE: &str = "OrderBook";
+
+/// A single order book mutation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum OrderBookEvent {
+    /// Order rested in the book, carrying its state as stored
+    OrderAdded { order: Order },
+    /// Resting or stop order left the book
+    OrderRemoved { order_id: Uuid },
+    /// Resting order was filled, carrying its state after the fill
+    OrderFilled { order: Order, quantity: Decimal, refreshed: bool },
+    /// Resting order shrunk without a fill, carrying its state after the decrement
+    OrderDecremented { order: Order, displayed_reduction: Decimal },
+    /// Stop order parked in the trigger book
+    StopOrderAdded { order: Order },
+    /// Stop orders released from the trigger book
+    StopOrdersTriggered { order_ids: Vec<Uuid> },
+    /// Last trade price updated
+    TradePriceRecorded { price: Decimal },
+}
+
+impl OrderBookEvent {
+    /// Event type name
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrderBookEvent::OrderAdded { .. } => "OrderAdded",
+            OrderBookEvent::OrderRemoved { .. } => "OrderRemoved",
+            OrderBookEvent::OrderFilled { .. } => "OrderFilled",
+            OrderBookEvent::OrderDecremented { .. } => "OrderDecremented",
+            OrderBookEvent::StopOrderAdded { .. } => "StopOrderAdded",
+            OrderBookEvent::StopOrdersTriggered { .. } => "StopOrdersTriggered",
+            OrderBookEvent::TradePriceRecorded { .. } => "TradePriceRecorded",
+        }
+    }
+}
+
+/// Sequenced journal entry for one order book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub event_id: String,
+    pub stream_id: String,
+    pub sequence: u64,
+    pub event: OrderBookEvent,
+    pub recorded_at: DateTime<Utc>,
+    pub metadata: HashMap<String, serde_json::Value>,
+}
+
+impl JournalEntry {
+    /// Create a new journal entry
+    pub fn new(trading_pair: &TradingPair, sequence: u64, event: OrderBookEvent) -> Self {
+        Self {
+            event_id: Uuid::new_v4().to_string(),
+            stream_id: stream_id(trading_pair),
+            sequence,
+            event,
+            recorded_at: Utc::now(),
+            metadata: HashMap::new(),
+        }
+    }
+}
+
+impl Event for JournalEntry {
+    fn event_type(&self) -> &str { self.event.name() }
+    fn event_id(&self) -> &str { &self.event_id }
+    fn aggregate_id(&self) -> &str { &self.stream_id }
+    fn timestamp(&self) -> DateTime<Utc> { self.recorded_at }
+    fn version(&self) -> u64 { self.sequence }
+    fn metadata(&self) -> &HashMap<String, serde_json::Value> { &self.metadata }
+
+    fn to_json(&self) -> EventResult<String> {
+        serde_json::to_string(self)
+            .map_err(|e| EventError::SerializationError(e.to_string()))
+    }
+}
+
+/// Event store stream holding a trading pair's journal
+pub fn stream_id(trading_pair: &TradingPair) -> String {
+    format!("order-book-{}", trading_pair.symbol())
+}
+
+/// Point-in-time checkpoint of a full order book
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookCheckpoint {
+    pub state: OrderBookState,
+    pub created_at: DateTime<Utc>,
+}
+
+impl OrderBookCheckpoint {
+    /// Capture the current state of a book
+    pub fn capture(order_book: &OrderBookManager) -> Self {
+        Self {
+            state: order_book.state(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Sequence covered by this checkpoint
+    pub fn sequence(&self) -> u64 {
+        self.state.journal_sequence
+    }
+}
+
+/// Journal configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalConfig {
+    /// Take a checkpoint every N journal entries
+    pub checkpoint_interval: u64,
+    /// Maximum entries read from the store per batch during recovery
+    pub replay_batch_size: usize,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            checkpoint_interval: 1000,
+            replay_batch_size: 10_000,
+        }
+    }
+}
+
+/// Persists order book journals to an event store and recovers books from them
+pub struct OrderBookJournal<S: EventStore> {
+    store: Arc<S>,
+    config: JournalConfig,
+}
+
+impl<S: EventStore> OrderBookJournal<S> {
+    /// Create a new journal over an event store
+    pub fn new(store: Arc<S>, config: JournalConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Persist all pending journal entries of a book, checkpointing when due.
+    ///
+    /// Entries stay pending until the append succeeds, so a failed persist
+    /// can simply be retried. Returns the stream version after the append.
+    pub async fn persist(&self, order_book: &mut OrderBookManager) -> TradingResult<u64> {
+        let entries = order_book.pending_journal();
+        let (first_sequence, last_sequence) = match (entries.first(), entries.last()) {
+            (Some(first), Some(last)) => (first.sequence, last.sequence),
+            _ => return Ok(order_book.journal_sequence()),
+        };
+
+        let envelopes = entries
+            .iter()
+            .map(|entry| {
+                EventEnvelope::new(entry, ORDER_BOOK_AGGREGATE.to_string(), None, None, None)
+            })
+            .collect::<EventResult<Vec<_>>>()?;
+
+        let stream = entries[0].stream_id.clone();
+        let version = self
+            .store
+            .append_events(&stream, Some(first_sequence - 1), envelopes)
+            .await?;
+        order_book.acknowledge_journal(last_sequence);
+
+        let interval = self.config.checkpoint_interval.max(1);
+        if (first_sequence - 1) / interval != last_sequence / interval {
+            self.checkpoint(order_book).await?;
+        }
+
+        Ok(version)
+    }
+
+    /// Save a checkpoint of the book's current state
+    pub async fn checkpoint(&self, order_book: &OrderBookManager) -> TradingResult<()> {
+        let checkpoint = OrderBookCheckpoint::capture(order_book);
+        let stream = stream_id(&checkpoint.state.trading_pair);
+        let version = checkpoint.sequence();
+
+        let snapshot = AggregateSnapshot {
+            stream_id: stream.clone(),
+            aggregate_type: ORDER_BOOK_AGGREGATE.to_string(),
+            version,
+            data: serde_json::to_value(&checkpoint)?,
+            metadata: HashMap::new(),
+            created_at: checkpoint.created_at,
+        };
+
+        self.store.save_snapshot(&stream, version, snapshot).await?;
+        Ok(())
+    }
+
+    /// Rebuild a book from its latest checkpoint and the journal entries after it
+    pub async fn recover(
+        &self,
+        config: OrderBookConfig,
+        trading_pair: TradingPair,
+    ) -> TradingResult<OrderBookManager> {
+        let stream = stream_id(&trading_pair);
+
+        let mut order_book = match self.store.load_snapshot(&stream).await? {
+            Some(snapshot) => {
+                let checkpoint: OrderBookCheckpoint = serde_json::from_value(snapshot.data)?;
+                OrderBookManager::from_state(config, checkpoint.state)
+            }
+            None => OrderBookManager::new(config, trading_pair),
+        };
+
+        loop {
+            let envelopes = self
+                .store
+                .read_stream(
+                    &stream,
+                    Some(order_book.journal_sequence() + 1),
+                    Some(self.config.replay_batch_size),
+                )
+                .await?;
+            if envelopes.is_empty() {
+                break;
+            }
+
+            let entries = envelopes
+                .iter()
+                .map(|envelope| envelope.deserialize_payload::<JournalEntry>())
+                .collect::<EventResult<Vec<_>>>()?;
+            order_book.replay(&entries)?;
+
+            if entries.len() < self.config.replay_batch_size {
+                break;
+            }
+        }
+
+        Ok(order_book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::{MatchingConfig, MatchingEngine};
+    use crate::order_book::TimeInForce;
+    use crate::types::{OrderSide, OrderType, SelfTradePreventionMode};
+    use core_events::InMemoryEventStore;
+    use proptest::prelude::*;
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    fn journaled() -> OrderBookConfig {
+        OrderBookConfig {
+            enable_journal: true,
+            ..OrderBookConfig::default()
+        }
+    }
+
+    fn state_bytes(order_book: &OrderBookManager) -> Vec<u8> {
+        serde_json::to_vec(&order_book.state()).unwrap()
+    }
+
+    /// Round-trip entries through the event store envelope format
+    fn through_envelopes(entries: &[JournalEntry]) -> Vec<JournalEntry> {
+        entries
+            .iter()
+            .map(|entry| {
+                EventEnvelope::new(entry, ORDER_BOOK_AGGREGATE.to_string(), None, None, None)
+                    .unwrap()
+                    .deserialize_payload::<JournalEntry>()
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Submit { buy: bool, price: i64, quantity: i64, kind: u8 },
+        Cancel { index: usize },
+        Expire,
+    }
+
+    fn op_strategy() -> impl Strategy<Value = Op> {
+        prop_oneof![
+            4 => (any::<bool>(), 95i64..105, 1i64..20, 0u8..5).prop_map(|(buy, price, quantity, kind)| {
+                Op::Submit { buy, price, quantity, kind }
+            }),
+            1 => any::<usize>().prop_map(|index| Op::Cancel { index }),
+            1 => Just(Op::Expire),
+        ]
+    }
+
+    fn build_order(buy: bool, price: i64, quantity: i64, kind: u8, user: usize) -> Order {
+        let side = if buy { OrderSide::Buy } else { OrderSide::Sell };
+        let order_type = match kind {
+            1 => OrderType::Iceberg,
+            2 => OrderType::IOC,
+            3 => OrderType::StopLimit,
+            _ => OrderType::Limit,
+        };
+        let order = Order::new(
+            format!("user{}", user % 4),
+            pair(),
+            side,
+            order_type,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        );
+        match kind {
+            1 => order.with_iceberg_quantity(Decimal::new(quantity.min(3), 0)),
+            3 => order.with_stop_price(Decimal::new(price, 0)),
+            4 => order
+                .with_time_in_force(TimeInForce::GTD)
+                .with_expiry(Utc::now() + chrono::Duration::milliseconds(1)),
+            _ => order,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_replay_matches_live_book(
+            ops in prop::collection::vec(op_strategy(), 1..60),
+            checkpoint_at in 0usize..60,
+        ) {
+            // Decrement-and-cancel exercises the decrement event on self-trades
+            let engine = MatchingEngine::new(MatchingConfig {
+                default_stp_mode: SelfTradePreventionMode::DecrementAndCancel,
+                ..MatchingConfig::default()
+            });
+            let mut live = OrderBookManager::new(journaled(), pair());
+            let mut submitted: Vec<Uuid> = Vec::new();
+            let mut checkpoint = None;
+            let mut journal = Vec::new();
+
+            for (i, op) in ops.iter().enumerate() {
+                if i == checkpoint_at {
+                    journal.extend(live.drain_journal());
+                    checkpoint = Some(OrderBookCheckpoint::capture(&live));
+                }
+                match op {
+                    Op::Submit { buy, price, quantity, kind } => {
+                        let order = build_order(*buy, *price, *quantity, *kind, i);
+                        submitted.push(order.id);
+                        let _ = engine.submit_order(order, &mut live);
+                    }
+                    Op::Cancel { index } => {
+                        if !submitted.is_empty() {
+                            let id = submitted[index % submitted.len()];
+                            live.remove_order(id).unwrap();
+                        }
+                    }
+                    Op::Expire => {
+                        engine.expire_orders(&mut live, Utc::now()).unwrap();
+                    }
+                }
+            }
+            journal.extend(live.drain_journal());
+            let journal = through_envelopes(&journal);
+
+            // Full replay from an empty book
+            let mut replayed = OrderBookManager::new(OrderBookConfig::default(), pair());
+            replayed.replay(&journal).unwrap();
+            prop_assert_eq!(state_bytes(&replayed), state_bytes(&live));
+
+            // Replay from a mid-stream checkpoint
+            if let Some(checkpoint) = checkpoint {
+                let mut restored = OrderBookManager::from_state(OrderBookConfig::default(), checkpoint.state);
+                restored.replay(&journal).unwrap();
+                prop_assert_eq!(state_bytes(&restored), state_bytes(&live));
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_rejects_sequence_gap() {
+        let mut live = OrderBookManager::new(journaled(), pair());
+        for price in [99, 100, 101] {
+            live.add_order(build_order(true, price, 1, 0, 0)).unwrap();
+        }
+        let mut journal = live.drain_journal();
+        journal.remove(1);
+
+        let mut replayed = OrderBookManager::new(OrderBookConfig::default(), pair());
+        assert!(replayed.replay(&journal).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recover_from_event_store() {
+        let store = Arc::new(InMemoryEventStore::new());
+        let journal = OrderBookJournal::new(
+            store.clone(),
+            JournalConfig {
+                checkpoint_interval: 3,
+                ..JournalConfig::default()
+            },
+        );
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut live = OrderBookManager::new(journaled(), pair());
+
+        let orders = [(true, 99), (true, 100), (false, 102), (false, 101), (true, 101), (true, 98)];
+        for (i, (buy, price)) in orders.into_iter().enumerate() {
+            engine.submit_order(build_order(buy, price, 2, 0, i), &mut live).unwrap();
+            journal.persist(&mut live).await.unwrap();
+        }
+
+        // A checkpoint was taken part way through the stream
+        let snapshot = store.load_snapshot(&stream_id(&pair())).await.unwrap().unwrap();
+        assert!(snapshot.version >= 3);
+        assert!(snapshot.version < live.journal_sequence());
+
+        let recovered = journal.recover(OrderBookConfig::default(), pair()).await.unwrap();
+        assert_eq!(state_bytes(&recovered), state_bytes(&live));
+        assert_eq!(recovered.best_bid(), Some(Decimal::new(100, 0)));
+        assert_eq!(recovered.best_ask(), Some(Decimal::new(102, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_failed_persist_keeps_entries_pending() {
+        // Another writer already owns the start of the stream, so appends conflict
+        let contended = Arc::new(InMemoryEventStore::new());
+        let mut other = OrderBookManager::new(journaled(), pair());
+        other.add_order(build_order(false, 105, 1, 0, 0)).unwrap();
+        OrderBookJournal::new(contended.clone(), JournalConfig::default())
+            .persist(&mut other)
+            .await
+            .unwrap();
+
+        let mut live = OrderBookManager::new(journaled(), pair());
+        for price in [99, 100] {
+            live.add_order(build_order(true, price, 1, 0, 0)).unwrap();
+        }
+        let conflicting = OrderBookJournal::new(contended, JournalConfig::default());
+        assert!(conflicting.persist(&mut live).await.is_err());
+        assert_eq!(live.pending_journal().len(), 2);
+
+        let journal = OrderBookJournal::new(Arc::new(InMemoryEventStore::new()), JournalConfig::default());
+        journal.persist(&mut live).await.unwrap();
+        assert!(live.pending_journal().is_empty());
+
+        let recovered = journal.recover(OrderBookConfig::default(), pair()).await.unwrap();
+        assert_eq!(state_bytes(&recovered), state_bytes(&live));
+    }
+}
@@ -11,12 +11,18 @@
 //! market making, and trade settlement.
 
 pub mod order_book;
+pub mod journal;
 pub mod liquidity;
 pub mod matching;
+pub mod auction;
 pub mod settlement;
+pub mod netting;
 pub mod market_data;
+pub mod candles;
+pub mod market_feed;
 pub mod price_discovery;
 pub mod market_making;
+pub mod routing;
 pub mod risk_controls;
 pub mod error;
 pub mod types;
@@ -30,12 +36,21 @@ pub use types::{
 };
 pub use service::TradingService;
 pub use order_book::{OrderBookManager, OrderBookSnapshot};
+pub use journal::{OrderBookJournal, OrderBookCheckpoint, JournalEntry};
 pub use liquidity::{LiquidityManager, LiquidityProvider};
 pub use matching::{MatchingEngine, MatchResult};
+pub use auction::{AuctionManager, CallAuction, TradingMode};
 pub use settlement::{SettlementService, SettlementResult};
+pub use netting::{NettingEngine, NettingMode, NettingResult};
 pub use market_data::{MarketDataService, MarketDataFeed};
+pub use candles::{CandleService, CandleStore, InMemoryCandleStore};
+#[cfg(feature = "database")]
+pub use candles::PostgresCandleStore;
+pub use market_feed::{FeedConsumer, FeedMessage, FeedPublisher};
 pub use price_discovery::{PriceDiscoveryEngine, PriceQuote};
 pub use market_making::{MarketMaker, MarketMakingStrategy};
+pub use routing::{ExecutionPlan, RoutingConfig, SmartOrderRouter};
+pub use risk_controls::{PreTradeRiskGateway, RiskCheck, RiskControlsConfig};
 
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
@@ -52,6 +67,8 @@ pub struct TradingConfig {
     pub liquidity_config: liquidity::LiquidityConfig,
     /// Matching engine configuration
     pub matching_config: matching::MatchingConfig,
+    /// Call auction configuration
+    pub auction_config: auction::AuctionConfig,
     /// Settlement configuration
     pub settlement_config: settlement::SettlementConfig,
     /// Market data configuration
@@ -60,6 +77,8 @@ pub struct TradingConfig {
     pub price_discovery_config: price_discovery::PriceDiscoveryConfig,
     /// Market making configuration
     pub market_making_config: market_making::MarketMakingConfig,
+    /// Smart order routing configuration
+    pub routing_config: routing::RoutingConfig,
     /// Risk controls configuration
     pub risk_controls_config: risk_controls::RiskControlsConfig,
     /// Global trading settings
@@ -72,10 +91,12 @@ impl Default for TradingConfig {
             order_book_config: order_book::OrderBookConfig::default(),
             liquidity_config: liquidity::LiquidityConfig::default(),
             matching_config: matching::MatchingConfig::default(),
+            auction_config: auction::AuctionConfig::default(),
             settlement_config: settlement::SettlementConfig::default(),
             market_data_config: market_data::MarketDataConfig::default(),
             price_discovery_config: price_discovery::PriceDiscoveryConfig::default(),
             market_making_config: market_making::MarketMakingConfig::default(),
+            routing_config: routing::RoutingConfig::default(),
             risk_controls_config: risk_controls::RiskControlsConfig::default(),
             global_settings: GlobalTradingSettings::default(),
         }
@@ -134,6 +155,7 @@ pub struct TradingPairConfig {
     pub min_quantity_increment: Decimal,
     pub max_price_deviation: Decimal,
     pub liquidity_requirements: LiquidityRequirements,
+    pub trading_mode: auction::TradingMode,
 }
 
 impl TradingPairConfig {
@@ -146,6 +168,7 @@ impl TradingPairConfig {
             min_quantity_increment: Decimal::new(1, 8), // 0.00000001
             max_price_deviation: Decimal::new(10, 0), // 10%
             liquidity_requirements: LiquidityRequirements::default(),
+            trading_mode: auction::TradingMode::Continuous,
         }
     }
 
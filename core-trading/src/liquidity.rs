@@ -345,6 +345,47 @@ impl AMMPool {
         }
     }
 
+    /// Calculate the input amount required to receive an exact output amount.
+    ///
+    /// Returns `None` when the output would drain the pool.
+    pub fn calculate_input(&self, output_amount: Decimal, output_is_base: bool) -> Option<Decimal> {
+        let (input_reserve, output_reserve) = if output_is_base {
+            (self.quote_reserve, self.base_reserve)
+        } else {
+            (self.base_reserve, self.quote_reserve)
+        };
+
+        if output_amount >= output_reserve || self.fee_percentage >= Decimal::ONE {
+            return None;
+        }
+
+        let input_with_fee = input_reserve * output_amount / (output_reserve - output_amount);
+        Some(input_with_fee / (Decimal::ONE - self.fee_percentage))
+    }
+
+    /// Swap an input amount through the pool, returning the output amount
+    pub fn swap(&mut self, input_amount: Decimal, input_is_base: bool) -> TradingResult<Decimal> {
+        if input_amount <= Decimal::ZERO {
+            return Err(TradingError::LiquidityError {
+                pool_id: self.id.to_string(),
+                message: format!("Invalid swap input {}", input_amount),
+            });
+        }
+
+        let output_amount = self.calculate_output(input_amount, input_is_base);
+        if input_is_base {
+            self.base_reserve += input_amount;
+            self.quote_reserve -= output_amount;
+        } else {
+            self.quote_reserve += input_amount;
+            self.base_reserve -= output_amount;
+        }
+        self.k_constant = self.base_reserve * self.quote_reserve;
+        self.last_updated = Utc::now();
+
+        Ok(output_amount)
+    }
+
     /// Calculate price impact for a trade
     pub fn calculate_price_impact(&self, input_amount: Decimal, input_is_base: bool) -> Decimal {
         let current_price = self.current_price();
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc, Duration};
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use std::collections::HashMap;
 
 use crate::{
@@ -58,7 +59,7 @@ impl Default for MarketDataConfig {
 }
 
 /// Candlestick interval enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum CandlestickInterval {
     OneMinute,
     FiveMinutes,
@@ -96,6 +97,37 @@ impl CandlestickInterval {
             CandlestickInterval::OneMonth => 2592000, // 30 days
         }
     }
+
+    /// Intervals maintained by the candle store, each rolled up from the previous one
+    pub fn rollup_hierarchy() -> [CandlestickInterval; 4] {
+        [
+            CandlestickInterval::OneMinute,
+            CandlestickInterval::FiveMinutes,
+            CandlestickInterval::OneHour,
+            CandlestickInterval::OneDay,
+        ]
+    }
+
+    /// Interval this one is rolled up from in the hierarchy
+    pub fn rollup_source(&self) -> Option<CandlestickInterval> {
+        match self {
+            CandlestickInterval::FiveMinutes => Some(CandlestickInterval::OneMinute),
+            CandlestickInterval::OneHour => Some(CandlestickInterval::FiveMinutes),
+            CandlestickInterval::OneDay => Some(CandlestickInterval::OneHour),
+            _ => None,
+        }
+    }
+
+    /// Check if candles of this interval can be built by merging `source` candles
+    pub fn can_rollup_from(&self, source: CandlestickInterval) -> bool {
+        let (target, source) = (self.duration_seconds(), source.duration_seconds());
+        target > source && target % source == 0
+    }
+
+    /// Start of the interval containing the given timestamp
+    pub fn open_time(&self, timestamp: DateTime<Utc>) -> DateTime<Utc> {
+        Candlestick::align_timestamp(timestamp, *self)
+    }
 }
 
 /// Candlestick data structure
@@ -154,6 +186,62 @@ impl Candlestick {
         self.taker_buy_quote_volume += trade.price * trade.quantity;
     }
 
+    /// Create an empty candle carrying the previous close, used to fill intervals without trades
+    pub fn empty(
+        trading_pair: TradingPair,
+        interval: CandlestickInterval,
+        open_time: DateTime<Utc>,
+        previous_close: Decimal,
+    ) -> Self {
+        let interval_duration = Duration::seconds(interval.duration_seconds() as i64);
+
+        Self {
+            trading_pair,
+            interval,
+            open_time,
+            close_time: open_time + interval_duration - Duration::milliseconds(1),
+            open_price: previous_close,
+            high_price: previous_close,
+            low_price: previous_close,
+            close_price: previous_close,
+            volume: Decimal::ZERO,
+            quote_volume: Decimal::ZERO,
+            trade_count: 0,
+            taker_buy_volume: Decimal::ZERO,
+            taker_buy_quote_volume: Decimal::ZERO,
+        }
+    }
+
+    /// Check if the candle was gap-filled rather than built from trades
+    pub fn is_empty(&self) -> bool {
+        self.trade_count == 0
+    }
+
+    /// Re-bucket this candle into a coarser interval
+    pub fn rolled_up(&self, interval: CandlestickInterval) -> Self {
+        let open_time = Self::align_timestamp(self.open_time, interval);
+        let interval_duration = Duration::seconds(interval.duration_seconds() as i64);
+
+        Self {
+            interval,
+            open_time,
+            close_time: open_time + interval_duration - Duration::milliseconds(1),
+            ..self.clone()
+        }
+    }
+
+    /// Merge a later candle of the same bucket into this one
+    pub fn merge(&mut self, later: &Candlestick) {
+        self.high_price = self.high_price.max(later.high_price);
+        self.low_price = self.low_price.min(later.low_price);
+        self.close_price = later.close_price;
+        self.volume += later.volume;
+        self.quote_volume += later.quote_volume;
+        self.trade_count += later.trade_count;
+        self.taker_buy_volume += later.taker_buy_volume;
+        self.taker_buy_quote_volume += later.taker_buy_quote_volume;
+    }
+
     /// Align timestamp to interval boundary
     fn align_timestamp(timestamp: DateTime<Utc>, interval: CandlestickInterval) -> DateTime<Utc> {
         let duration_seconds = interval.duration_seconds() as i64;
@@ -163,6 +251,27 @@ impl Candlestick {
     }
 }
 
+/// Realized volatility of close-to-close log returns, expressed per square root of a second.
+///
+/// Returns `None` when fewer than three candles are available.
+pub fn realized_volatility(candles: &[Candlestick]) -> Option<f64> {
+    let closes: Vec<f64> = candles
+        .iter()
+        .filter_map(|candle| candle.close_price.to_f64())
+        .filter(|price| *price > 0.0)
+        .collect();
+    if closes.len() < 3 {
+        return None;
+    }
+
+    let returns: Vec<f64> = closes.windows(2).map(|pair| (pair[1] / pair[0]).ln()).collect();
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    let interval_seconds = candles[0].interval.duration_seconds() as f64;
+
+    Some((variance / interval_seconds).sqrt())
+}
+
 /// Market ticker information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ticker {
@@ -362,6 +471,33 @@ mod tests {
         assert_eq!(candlestick.trade_count, 1);
     }
 
+    #[test]
+    fn test_realized_volatility() {
+        let trading_pair = TradingPair::new("BTC".to_string(), "USD".to_string());
+        let candle = |minute: i64, close: i64| {
+            let open_time = DateTime::from_timestamp(minute * 60, 0).unwrap();
+            let mut candle = Candlestick::empty(
+                trading_pair.clone(),
+                CandlestickInterval::OneMinute,
+                open_time,
+                Decimal::new(close, 0),
+            );
+            candle.trade_count = 1;
+            candle
+        };
+
+        let flat: Vec<Candlestick> = (0..5).map(|minute| candle(minute, 100)).collect();
+        assert_eq!(realized_volatility(&flat), Some(0.0));
+
+        let choppy: Vec<Candlestick> = [100, 102, 99, 103, 98]
+            .iter()
+            .enumerate()
+            .map(|(minute, close)| candle(minute as i64, *close))
+            .collect();
+        assert!(realized_volatility(&choppy).unwrap() > 0.0);
+        assert_eq!(realized_volatility(&choppy[..2]), None);
+    }
+
     #[test]
     fn test_trade_data_conversion() {
         let trading_pair = TradingPair::new("BTC".to_string(), "USD".to_string());
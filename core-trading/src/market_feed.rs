@@ -0,0 +1,810 @@
+// =====================================================================================
+// File: core-trading/src/market_feed.rs
+// Description: Sequenced L2/L3 market data feed with snapshots and incremental deltas
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tokio::sync::broadcast::{self, error::{RecvError, TryRecvError}};
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::{OrderSide, TradingPair},
+    order_book::{Order, OrderBookConfig, OrderBookManager},
+    journal::{JournalEntry, OrderBookEvent},
+    matching::Trade,
+    market_data::{DepthLevel, Ticker, TradeData},
+};
+
+/// Resting order as published on the L3 feed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct L3Order {
+    pub order_id: Uuid,
+    pub side: OrderSide,
+    pub price: Decimal,
+    /// Visible quantity
+    pub quantity: Decimal,
+}
+
+impl From<&Order> for L3Order {
+    fn from(order: &Order) -> Self {
+        Self {
+            order_id: order.id,
+            side: order.side,
+            price: order.price,
+            quantity: order.displayed_quantity,
+        }
+    }
+}
+
+/// Aggregated price level update; a zero quantity removes the level
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelUpdate {
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Order-by-order update
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum OrderUpdate {
+    /// Order joined the back of its price level
+    Add { order: L3Order },
+    /// Visible quantity changed in place
+    Modify { order_id: Uuid, quantity: Decimal },
+    /// Order left the book
+    Remove { order_id: Uuid },
+}
+
+/// Full book image
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub bids: Vec<DepthLevel>,
+    pub asks: Vec<DepthLevel>,
+    /// Resting orders in priority order, empty for L2 subscribers
+    pub orders: Vec<L3Order>,
+}
+
+/// Incremental book change produced by one order book event
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub levels: Vec<LevelUpdate>,
+    /// Order-by-order detail, empty for L2 subscribers
+    pub orders: Vec<OrderUpdate>,
+}
+
+/// Feed payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum FeedPayload {
+    Snapshot(BookSnapshot),
+    BookDelta(BookDelta),
+    Trade(TradeData),
+    Ticker(Ticker),
+}
+
+/// Sequenced feed message.
+///
+/// Deltas, trades and tickers carry consecutive sequence numbers. A snapshot
+/// carries the sequence of the last message it already reflects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedMessage {
+    pub trading_pair: TradingPair,
+    pub sequence: u64,
+    pub timestamp: DateTime<Utc>,
+    pub payload: FeedPayload,
+}
+
+impl FeedMessage {
+    /// Check if this message is a book snapshot
+    pub fn is_snapshot(&self) -> bool {
+        matches!(self.payload, FeedPayload::Snapshot(_))
+    }
+
+    /// Copy of this message without order-by-order detail
+    pub fn to_l2(&self) -> FeedMessage {
+        let mut message = self.clone();
+        match &mut message.payload {
+            FeedPayload::Snapshot(snapshot) => snapshot.orders.clear(),
+            FeedPayload::BookDelta(delta) => delta.orders.clear(),
+            _ => {}
+        }
+        message
+    }
+}
+
+/// Publishes a sequenced feed for one order book.
+///
+/// The publisher keeps a mirror of the book that it advances from journal
+/// entries, so deltas are derived from the same events that are persisted.
+pub struct FeedPublisher {
+    trading_pair: TradingPair,
+    mirror: OrderBookManager,
+    sequence: u64,
+    sender: broadcast::Sender<FeedMessage>,
+    /// Published trades inside the rolling ticker window, oldest first
+    recent_trades: VecDeque<TradeData>,
+}
+
+impl FeedPublisher {
+    /// Create a publisher starting from the current state of a book
+    pub fn new(order_book: &OrderBookManager, capacity: usize) -> Self {
+        let state = order_book.state();
+        let (sender, _) = broadcast::channel(capacity);
+
+        Self {
+            trading_pair: state.trading_pair.clone(),
+            mirror: OrderBookManager::from_state(Self::mirror_config(), state),
+            sequence: 0,
+            sender,
+            recent_trades: VecDeque::new(),
+        }
+    }
+
+    /// Get the trading pair
+    pub fn trading_pair(&self) -> &TradingPair {
+        &self.trading_pair
+    }
+
+    /// Sequence of the last published message
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    /// Number of live subscribers
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+
+    /// Snapshot of the mirrored book at the current sequence
+    pub fn snapshot(&self) -> FeedMessage {
+        let depth = |side: OrderSide| -> Vec<DepthLevel> {
+            let mut levels: Vec<DepthLevel> = Vec::new();
+            for order in self.mirror.resting_orders(side) {
+                if levels.last().map(|level| level.price) != Some(order.price) {
+                    levels.push(DepthLevel {
+                        price: order.price,
+                        quantity: self.mirror.level_quantity(side, order.price),
+                    });
+                }
+            }
+            levels
+        };
+        let orders = [OrderSide::Buy, OrderSide::Sell]
+            .into_iter()
+            .flat_map(|side| self.mirror.resting_orders(side))
+            .map(L3Order::from)
+            .collect();
+
+        FeedMessage {
+            trading_pair: self.trading_pair.clone(),
+            sequence: self.sequence,
+            timestamp: Utc::now(),
+            payload: FeedPayload::Snapshot(BookSnapshot {
+                bids: depth(OrderSide::Buy),
+                asks: depth(OrderSide::Sell),
+                orders,
+            }),
+        }
+    }
+
+    /// Subscribe with an in-process consumer primed from the current snapshot
+    pub fn subscribe(&self) -> FeedConsumer {
+        let receiver = self.sender.subscribe();
+        let mut consumer = FeedConsumer {
+            trading_pair: self.trading_pair.clone(),
+            receiver,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            orders: HashMap::new(),
+            last_sequence: 0,
+            gap: None,
+            pending: None,
+        };
+        consumer.load_snapshot(&self.snapshot());
+        consumer
+    }
+
+    /// Publish book deltas for journal entries received from the live book.
+    ///
+    /// Entries come from `OrderBookManager::subscribe_journal`; draining the
+    /// journal here would take entries away from the persister.
+    ///
+    /// Entries already reflected by the mirror are skipped; stop-order and
+    /// trade-price events do not change the visible book and publish nothing.
+    pub fn publish_journal(&mut self, entries: &[JournalEntry]) -> TradingResult<Vec<FeedMessage>> {
+        let mut published = Vec::new();
+        for entry in entries {
+            if entry.sequence <= self.mirror.journal_sequence() {
+                continue;
+            }
+
+            let removed = match &entry.event {
+                OrderBookEvent::OrderRemoved { order_id } => self.mirror.get_order(*order_id).cloned(),
+                _ => None,
+            };
+            self.mirror.replay(std::slice::from_ref(entry))?;
+
+            if let Some(delta) = self.delta_for(&entry.event, removed) {
+                published.push(self.publish(FeedPayload::BookDelta(delta)));
+            }
+        }
+        Ok(published)
+    }
+
+    /// Rebuild the mirror from the live book after journal entries were missed.
+    ///
+    /// One sequence number is skipped so every consumer detects a gap on the
+    /// next message and resyncs from a fresh snapshot.
+    pub fn rebase(&mut self, order_book: &OrderBookManager) {
+        self.mirror = OrderBookManager::from_state(Self::mirror_config(), order_book.state());
+        self.sequence += 1;
+    }
+
+    /// Publish executed trades
+    pub fn publish_trades(&mut self, trades: &[Trade]) -> Vec<FeedMessage> {
+        trades
+            .iter()
+            .map(|trade| {
+                let data = TradeData::from(trade);
+                self.recent_trades.push_back(data.clone());
+                self.publish(FeedPayload::Trade(data))
+            })
+            .collect()
+    }
+
+    /// Publish the trades of a match followed by the resulting ticker.
+    ///
+    /// `bid` and `ask` are the live book's best levels after the match; the
+    /// mirror may still be catching up on the match's journal entries.
+    pub fn publish_execution(
+        &mut self,
+        trades: &[Trade],
+        bid: Option<DepthLevel>,
+        ask: Option<DepthLevel>,
+    ) -> Vec<FeedMessage> {
+        if trades.is_empty() {
+            return Vec::new();
+        }
+        let mut published = self.publish_trades(trades);
+        if let Some(ticker) = self.ticker(bid, ask, Utc::now()) {
+            published.push(self.publish_ticker(ticker));
+        }
+        published
+    }
+
+    /// Rolling 24 hour ticker over published trades; `None` before the first trade
+    pub fn ticker(&mut self, bid: Option<DepthLevel>, ask: Option<DepthLevel>, now: DateTime<Utc>) -> Option<Ticker> {
+        let window_start = now - Duration::hours(24);
+        while self.recent_trades.front().is_some_and(|trade| trade.timestamp < window_start) {
+            self.recent_trades.pop_front();
+        }
+
+        let open = self.recent_trades.front()?.price;
+        let last = self.recent_trades.back()?.price;
+        let price_change = last - open;
+        Some(Ticker {
+            trading_pair: self.trading_pair.clone(),
+            last_price: last,
+            price_change,
+            price_change_percent: if open.is_zero() {
+                Decimal::ZERO
+            } else {
+                price_change / open * Decimal::ONE_HUNDRED
+            },
+            high_price_24h: self.recent_trades.iter().map(|trade| trade.price).max()?,
+            low_price_24h: self.recent_trades.iter().map(|trade| trade.price).min()?,
+            volume_24h: self.recent_trades.iter().map(|trade| trade.quantity).sum(),
+            quote_volume_24h: self.recent_trades.iter().map(|trade| trade.price * trade.quantity).sum(),
+            open_price_24h: open,
+            trade_count_24h: self.recent_trades.len() as u64,
+            bid_price: bid.as_ref().map(|level| level.price),
+            ask_price: ask.as_ref().map(|level| level.price),
+            bid_quantity: bid.map(|level| level.quantity),
+            ask_quantity: ask.map(|level| level.quantity),
+            timestamp: now,
+        })
+    }
+
+    /// Publish a ticker update
+    pub fn publish_ticker(&mut self, ticker: Ticker) -> FeedMessage {
+        self.publish(FeedPayload::Ticker(ticker))
+    }
+
+    /// Derive the visible change caused by an event already applied to the mirror
+    fn delta_for(&self, event: &OrderBookEvent, removed: Option<Order>) -> Option<BookDelta> {
+        let (side, price, orders) = match event {
+            OrderBookEvent::OrderAdded { order } => {
+                (order.side, order.price, vec![OrderUpdate::Add { order: L3Order::from(order) }])
+            }
+            OrderBookEvent::OrderRemoved { .. } => {
+                let order = removed?;
+                (order.side, order.price, vec![OrderUpdate::Remove { order_id: order.id }])
+            }
+            OrderBookEvent::OrderFilled { order, refreshed, .. } => {
+                let updates = match self.mirror.get_order(order.id) {
+                    None => vec![OrderUpdate::Remove { order_id: order.id }],
+                    // A refreshed iceberg slice loses its queue position
+                    Some(resting) if *refreshed => vec![
+                        OrderUpdate::Remove { order_id: order.id },
+                        OrderUpdate::Add { order: L3Order::from(resting) },
+                    ],
+                    Some(resting) => vec![OrderUpdate::Modify {
+                        order_id: order.id,
+                        quantity: resting.displayed_quantity,
+                    }],
+                };
+                (order.side, order.price, updates)
+            }
+            OrderBookEvent::OrderDecremented { order, .. } => (
+                order.side,
+                order.price,
+                vec![OrderUpdate::Modify {
+                    order_id: order.id,
+                    quantity: order.displayed_quantity,
+                }],
+            ),
+            OrderBookEvent::StopOrderAdded { .. }
+            | OrderBookEvent::StopOrdersTriggered { .. }
+            | OrderBookEvent::TradePriceRecorded { .. } => return None,
+        };
+
+        Some(BookDelta {
+            levels: vec![LevelUpdate {
+                side,
+                price,
+                quantity: self.mirror.level_quantity(side, price),
+            }],
+            orders,
+        })
+    }
+
+    /// The mirror only replays entries, it never journals its own
+    fn mirror_config() -> OrderBookConfig {
+        OrderBookConfig {
+            enable_journal: false,
+            ..OrderBookConfig::default()
+        }
+    }
+
+    /// Assign the next sequence number and broadcast
+    fn publish(&mut self, payload: FeedPayload) -> FeedMessage {
+        self.sequence += 1;
+        let message = FeedMessage {
+            trading_pair: self.trading_pair.clone(),
+            sequence: self.sequence,
+            timestamp: Utc::now(),
+            payload,
+        };
+        // Sending only fails when nobody is subscribed
+        let _ = self.sender.send(message.clone());
+        message
+    }
+}
+
+/// In-process feed consumer maintaining a local copy of the book.
+///
+/// On a sequence gap the consumer stops applying messages and every call to
+/// `next` fails with `TradingError::FeedSequenceGap` until `resync` is called
+/// with a fresh snapshot.
+pub struct FeedConsumer {
+    trading_pair: TradingPair,
+    receiver: broadcast::Receiver<FeedMessage>,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    orders: HashMap<Uuid, L3Order>,
+    last_sequence: u64,
+    gap: Option<(u64, u64)>,
+    /// First message newer than the last snapshot, read while discarding older ones
+    pending: Option<FeedMessage>,
+}
+
+impl FeedConsumer {
+    /// Receive and apply the next message
+    pub async fn next(&mut self) -> TradingResult<FeedMessage> {
+        if let Some((expected, received)) = self.gap {
+            return Err(self.gap_error(expected, received));
+        }
+
+        loop {
+            let received = match self.pending.take() {
+                Some(message) => Ok(message),
+                None => self.receiver.recv().await,
+            };
+            let message = match received {
+                Ok(message) => message,
+                Err(RecvError::Lagged(skipped)) => {
+                    let expected = self.last_sequence + 1;
+                    return Err(self.mark_gap(expected, expected + skipped));
+                }
+                Err(RecvError::Closed) => {
+                    return Err(TradingError::ExternalServiceError {
+                        service: "market_feed".to_string(),
+                        message: format!("Feed for {} closed", self.trading_pair.symbol()),
+                    });
+                }
+            };
+
+            // Already reflected by the snapshot we started from
+            if message.sequence <= self.last_sequence {
+                continue;
+            }
+            if message.sequence != self.last_sequence + 1 {
+                return Err(self.mark_gap(self.last_sequence + 1, message.sequence));
+            }
+
+            self.apply(&message);
+            self.last_sequence = message.sequence;
+            return Ok(message);
+        }
+    }
+
+    /// Rebuild the local book from a snapshot and resume after its sequence
+    pub fn resync(&mut self, snapshot: &FeedMessage) -> TradingResult<()> {
+        if !snapshot.is_snapshot() {
+            return Err(TradingError::validation_error(
+                "payload".to_string(),
+                format!("Resync requires a snapshot, got sequence {} delta", snapshot.sequence),
+            ));
+        }
+        self.load_snapshot(snapshot);
+
+        // Discard messages the snapshot already reflects so they cannot crowd
+        // newer ones out of the channel and surface as a false lag
+        self.pending = None;
+        loop {
+            match self.receiver.try_recv() {
+                Ok(message) if message.sequence <= self.last_sequence => {}
+                Ok(message) => {
+                    self.pending = Some(message);
+                    break;
+                }
+                Err(TryRecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Sequence of the last applied message
+    pub fn last_sequence(&self) -> u64 {
+        self.last_sequence
+    }
+
+    /// Check if the local book is consistent with the feed
+    pub fn is_in_sync(&self) -> bool {
+        self.gap.is_none()
+    }
+
+    /// Best bid price
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.keys().next_back().copied()
+    }
+
+    /// Best ask price
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Bid levels, best first
+    pub fn bids(&self) -> Vec<DepthLevel> {
+        self.bids
+            .iter()
+            .rev()
+            .map(|(price, quantity)| DepthLevel { price: *price, quantity: *quantity })
+            .collect()
+    }
+
+    /// Ask levels, best first
+    pub fn asks(&self) -> Vec<DepthLevel> {
+        self.asks
+            .iter()
+            .map(|(price, quantity)| DepthLevel { price: *price, quantity: *quantity })
+            .collect()
+    }
+
+    /// Resting orders by id
+    pub fn orders(&self) -> &HashMap<Uuid, L3Order> {
+        &self.orders
+    }
+
+    fn load_snapshot(&mut self, snapshot: &FeedMessage) {
+        if let FeedPayload::Snapshot(book) = &snapshot.payload {
+            self.bids = book.bids.iter().map(|level| (level.price, level.quantity)).collect();
+            self.asks = book.asks.iter().map(|level| (level.price, level.quantity)).collect();
+            self.orders = book.orders.iter().map(|order| (order.order_id, order.clone())).collect();
+            self.last_sequence = snapshot.sequence;
+            self.gap = None;
+        }
+    }
+
+    fn apply(&mut self, message: &FeedMessage) {
+        let FeedPayload::BookDelta(delta) = &message.payload else {
+            return;
+        };
+
+        for update in &delta.levels {
+            let levels = match update.side {
+                OrderSide::Buy => &mut self.bids,
+                OrderSide::Sell => &mut self.asks,
+            };
+            if update.quantity <= Decimal::ZERO {
+                levels.remove(&update.price);
+            } else {
+                levels.insert(update.price, update.quantity);
+            }
+        }
+
+        for update in &delta.orders {
+            match update {
+                OrderUpdate::Add { order } => {
+                    self.orders.insert(order.order_id, order.clone());
+                }
+                OrderUpdate::Modify { order_id, quantity } => {
+                    if let Some(order) = self.orders.get_mut(order_id) {
+                        order.quantity = *quantity;
+                    }
+                }
+                OrderUpdate::Remove { order_id } => {
+                    self.orders.remove(order_id);
+                }
+            }
+        }
+    }
+
+    fn mark_gap(&mut self, expected: u64, received: u64) -> TradingError {
+        self.gap = Some((expected, received));
+        self.gap_error(expected, received)
+    }
+
+    fn gap_error(&self, expected: u64, received: u64) -> TradingError {
+        TradingError::FeedSequenceGap {
+            trading_pair: self.trading_pair.symbol(),
+            expected,
+            received,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matching::{MatchingConfig, MatchingEngine};
+    use crate::types::{OrderType, TimeInForce};
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    fn limit(user: &str, side: OrderSide, price: i64, quantity: i64) -> Order {
+        Order::new(
+            user.to_string(),
+            pair(),
+            side,
+            OrderType::Limit,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        )
+    }
+
+    fn journaled() -> OrderBookConfig {
+        OrderBookConfig {
+            enable_journal: true,
+            ..OrderBookConfig::default()
+        }
+    }
+
+    fn levels(levels: &[DepthLevel]) -> Vec<(Decimal, Decimal)> {
+        levels.iter().map(|level| (level.price, level.quantity)).collect()
+    }
+
+    fn submit(
+        engine: &MatchingEngine,
+        book: &mut OrderBookManager,
+        publisher: &mut FeedPublisher,
+        order: Order,
+    ) {
+        let result = engine.submit_order(order, book).unwrap();
+        publisher.publish_journal(&book.drain_journal()).unwrap();
+        publisher.publish_trades(&result.trades);
+    }
+
+    #[tokio::test]
+    async fn test_consumer_tracks_book_through_deltas() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(journaled(), pair());
+        book.add_order(limit("maker", OrderSide::Sell, 101, 5)).unwrap();
+        book.drain_journal();
+
+        let mut publisher = FeedPublisher::new(&book, 64);
+        let mut consumer = publisher.subscribe();
+        assert_eq!(consumer.best_ask(), Some(Decimal::new(101, 0)));
+
+        submit(&engine, &mut book, &mut publisher, limit("a", OrderSide::Buy, 99, 2));
+        submit(&engine, &mut book, &mut publisher, limit("b", OrderSide::Sell, 102, 4));
+        submit(&engine, &mut book, &mut publisher, limit("c", OrderSide::Buy, 101, 3));
+        let iceberg = limit("d", OrderSide::Sell, 100, 6).with_iceberg_quantity(Decimal::new(2, 0));
+        submit(&engine, &mut book, &mut publisher, iceberg);
+        submit(&engine, &mut book, &mut publisher, limit("e", OrderSide::Buy, 100, 3));
+
+        let mut trades = 0;
+        while consumer.last_sequence() < publisher.sequence() {
+            let message = consumer.next().await.unwrap();
+            if matches!(message.payload, FeedPayload::Trade(_)) {
+                trades += 1;
+            }
+        }
+        assert_eq!(trades, 3);
+
+        let FeedPayload::Snapshot(expected) = publisher.snapshot().payload else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(levels(&consumer.bids()), levels(&expected.bids));
+        assert_eq!(levels(&consumer.asks()), levels(&expected.asks));
+        assert_eq!(consumer.orders().len(), expected.orders.len());
+        assert_eq!(consumer.best_ask(), book.best_ask());
+        assert_eq!(consumer.best_bid(), book.best_bid());
+        assert_eq!(
+            consumer.asks()[0].quantity,
+            book.level_quantity(OrderSide::Sell, Decimal::new(100, 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_gap_requires_resync() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(journaled(), pair());
+        let mut publisher = FeedPublisher::new(&book, 2);
+        let mut consumer = publisher.subscribe();
+
+        for price in 90..95 {
+            submit(&engine, &mut book, &mut publisher, limit("a", OrderSide::Buy, price, 1));
+        }
+
+        let err = consumer.next().await.unwrap_err();
+        assert!(matches!(err, TradingError::FeedSequenceGap { expected: 1, .. }));
+        assert!(!consumer.is_in_sync());
+        assert!(consumer.next().await.is_err());
+
+        consumer.resync(&publisher.snapshot()).unwrap();
+        assert!(consumer.is_in_sync());
+        assert_eq!(consumer.last_sequence(), 5);
+        assert_eq!(consumer.bids().len(), 5);
+
+        submit(&engine, &mut book, &mut publisher, limit("a", OrderSide::Buy, 95, 1));
+        let message = consumer.next().await.unwrap();
+        assert_eq!(message.sequence, 6);
+        assert_eq!(consumer.best_bid(), Some(Decimal::new(95, 0)));
+    }
+
+    #[tokio::test]
+    async fn test_journal_subscription_leaves_entries_for_persister() {
+        let mut book = OrderBookManager::new(journaled(), pair());
+        let mut journal = book.subscribe_journal(2);
+        let mut publisher = FeedPublisher::new(&book, 8);
+        let mut consumer = publisher.subscribe();
+
+        book.add_order(limit("a", OrderSide::Buy, 99, 2)).unwrap();
+        let entry = journal.recv().await.unwrap();
+        assert_eq!(publisher.publish_journal(&[entry]).unwrap().len(), 1);
+        assert_eq!(book.drain_journal().len(), 1);
+
+        // The subscription lags behind; rebasing forces consumers to resync
+        for price in [98, 97, 96] {
+            book.add_order(limit("a", OrderSide::Buy, price, 2)).unwrap();
+        }
+        assert!(journal.recv().await.is_err());
+        publisher.rebase(&book);
+        while let Ok(entry) = journal.try_recv() {
+            // Already reflected by the rebased mirror
+            assert!(publisher.publish_journal(&[entry]).unwrap().is_empty());
+        }
+        book.add_order(limit("a", OrderSide::Buy, 95, 2)).unwrap();
+        let entry = journal.recv().await.unwrap();
+        publisher.publish_journal(&[entry]).unwrap();
+
+        assert_eq!(consumer.next().await.unwrap().sequence, 1);
+        assert!(matches!(consumer.next().await, Err(TradingError::FeedSequenceGap { expected: 2, received: 3, .. })));
+        consumer.resync(&publisher.snapshot()).unwrap();
+        assert_eq!(consumer.bids().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_killed_fok_never_reaches_feed() {
+        let engine = MatchingEngine::new(MatchingConfig {
+            max_matches_per_order: 1,
+            ..MatchingConfig::default()
+        });
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut journal = book.subscribe_journal(64);
+        let mut publisher = FeedPublisher::new(&book, 64);
+
+        book.add_order(limit("s1", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("s2", OrderSide::Sell, 101, 2)).unwrap();
+
+        // Depth covers the order but the match cap forces a rollback
+        let fok = limit("buyer", OrderSide::Buy, 101, 3).with_time_in_force(TimeInForce::FOK);
+        assert!(!engine.submit_order(fok, &mut book).unwrap().has_matches());
+        book.add_order(limit("b", OrderSide::Buy, 90, 1)).unwrap();
+
+        while let Ok(entry) = journal.try_recv() {
+            publisher.publish_journal(&[entry]).unwrap();
+        }
+
+        let FeedPayload::Snapshot(mirror) = publisher.snapshot().payload else {
+            panic!("expected snapshot");
+        };
+        let FeedPayload::Snapshot(live) = FeedPublisher::new(&book, 1).snapshot().payload else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(levels(&mirror.bids), levels(&live.bids));
+        assert_eq!(levels(&mirror.asks), levels(&live.asks));
+        assert_eq!(mirror.orders.len(), live.orders.len());
+        assert_eq!(levels(&mirror.bids), vec![(Decimal::new(90, 0), Decimal::new(1, 0))]);
+    }
+
+    #[tokio::test]
+    async fn test_execution_publishes_trades_then_ticker() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        let mut publisher = FeedPublisher::new(&book, 16);
+        let mut consumer = publisher.subscribe();
+
+        book.add_order(limit("s1", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("s2", OrderSide::Sell, 102, 2)).unwrap();
+        let result = engine.submit_order(limit("b", OrderSide::Buy, 102, 2), &mut book).unwrap();
+
+        let ask = book.best_ask().map(|price| DepthLevel {
+            price,
+            quantity: book.level_quantity(OrderSide::Sell, price),
+        });
+        let published = publisher.publish_execution(&result.trades, None, ask);
+        assert_eq!(published.len(), 3);
+        assert!(publisher.publish_execution(&[], None, None).is_empty());
+
+        let mut payloads = Vec::new();
+        while consumer.last_sequence() < publisher.sequence() {
+            payloads.push(consumer.next().await.unwrap().payload);
+        }
+        assert!(matches!(payloads[0], FeedPayload::Trade(_)));
+        let FeedPayload::Ticker(ticker) = &payloads[2] else {
+            panic!("expected ticker");
+        };
+        assert_eq!(ticker.open_price_24h, Decimal::new(100, 0));
+        assert_eq!(ticker.last_price, Decimal::new(102, 0));
+        assert_eq!(ticker.volume_24h, Decimal::new(2, 0));
+        assert_eq!(ticker.trade_count_24h, 2);
+        assert_eq!(ticker.ask_price, Some(Decimal::new(102, 0)));
+        assert_eq!(ticker.ask_quantity, Some(Decimal::new(1, 0)));
+
+        // Trades age out of the rolling window
+        assert!(publisher.ticker(None, None, Utc::now() + Duration::hours(25)).is_none());
+    }
+
+    #[test]
+    fn test_l2_view_strips_orders() {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        book.add_order(limit("a", OrderSide::Buy, 99, 2)).unwrap();
+        let publisher = FeedPublisher::new(&book, 8);
+
+        let snapshot = publisher.snapshot();
+        let FeedPayload::Snapshot(full) = &snapshot.payload else {
+            panic!("expected snapshot");
+        };
+        assert_eq!(full.orders.len(), 1);
+
+        let FeedPayload::Snapshot(l2) = snapshot.to_l2().payload else {
+            panic!("expected snapshot");
+        };
+        assert!(l2.orders.is_empty());
+        assert_eq!(l2.bids.len(), 1);
+
+        let json = serde_json::to_value(&snapshot).unwrap();
+        assert_eq!(json["payload"]["type"], "snapshot");
+    }
+}
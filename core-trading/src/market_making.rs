@@ -0,0 +1,523 @@
+// =====================================================================================
+// File: core-trading/src/market_making.rs
+// Description: Market making strategies and simulation for liquidity providers
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::OrderSide,
+    matching::Trade,
+    liquidity::{LiquidityConfig, LiquidityProvider, LiquidityQuote},
+    market_data::{realized_volatility, Candlestick, CandlestickInterval},
+};
+
+/// Market making configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketMakingConfig {
+    /// Risk aversion (gamma); higher values skew quotes harder against inventory
+    pub risk_aversion: f64,
+    /// Order book liquidity (k); higher values mean fills decay faster away from mid
+    pub order_book_liquidity: f64,
+    /// Quoting horizon (T - t) in seconds
+    pub horizon_seconds: f64,
+    /// Volatility floor, relative per square root of a second
+    pub min_volatility: f64,
+    /// Notional quoted on each side (in quote asset)
+    pub quote_notional: Decimal,
+    /// Quote validity in seconds
+    pub quote_validity_seconds: u64,
+    /// Candle interval used to estimate volatility
+    pub volatility_interval: CandlestickInterval,
+    /// Number of candles used to estimate volatility
+    pub volatility_window: usize,
+}
+
+impl Default for MarketMakingConfig {
+    fn default() -> Self {
+        Self {
+            risk_aversion: 0.5,
+            order_book_liquidity: 5000.0,
+            horizon_seconds: 86400.0, // 1 day
+            min_volatility: 0.00005,
+            quote_notional: Decimal::new(1000, 0), // $1,000
+            quote_validity_seconds: 5,
+            volatility_interval: CandlestickInterval::OneMinute,
+            volatility_window: 60,
+        }
+    }
+}
+
+/// Market state a strategy quotes against
+#[derive(Debug, Clone)]
+pub struct QuoteContext {
+    pub mid_price: Decimal,
+    /// Relative volatility per square root of a second
+    pub volatility: f64,
+}
+
+/// Market making strategy
+pub trait MarketMakingStrategy: Send + Sync {
+    /// Strategy name
+    fn name(&self) -> &'static str;
+
+    /// Generate a two-sided quote, or `None` when the provider should not quote
+    fn generate_quote(
+        &self,
+        provider: &LiquidityProvider,
+        context: &QuoteContext,
+    ) -> TradingResult<Option<LiquidityQuote>>;
+}
+
+/// Avellaneda–Stoikov inventory-aware quoting.
+///
+/// Quotes are centred on the reservation price `r = s - q·γ·σ²·τ` with a total
+/// spread of `γ·σ²·τ + (2/γ)·ln(1 + γ/k)`, computed relative to the mid price.
+/// `q` is the inventory deviation from a 50/50 value split, in quote lots.
+pub struct AvellanedaStoikovStrategy {
+    config: MarketMakingConfig,
+    liquidity_config: LiquidityConfig,
+}
+
+impl AvellanedaStoikovStrategy {
+    /// Create a new strategy
+    pub fn new(config: MarketMakingConfig, liquidity_config: LiquidityConfig) -> Self {
+        Self { config, liquidity_config }
+    }
+
+    /// Inventory deviation from target, in lots of `quote_notional`
+    pub fn inventory_lots(&self, provider: &LiquidityProvider, mid_price: Decimal) -> f64 {
+        let total_value = provider.base_inventory * mid_price + provider.quote_inventory;
+        let deviation = provider.inventory_imbalance(mid_price) * total_value;
+        (deviation / self.config.quote_notional).to_f64().unwrap_or(0.0)
+    }
+
+    /// Reservation price for the given inventory and volatility
+    pub fn reservation_price(&self, mid_price: Decimal, inventory_lots: f64, volatility: f64) -> Decimal {
+        let skew = inventory_lots * self.risk_term(volatility);
+        mid_price * Self::to_decimal(1.0 - skew)
+    }
+
+    /// Optimal total spread before configuration limits
+    pub fn optimal_spread(&self, mid_price: Decimal, volatility: f64) -> Decimal {
+        let gamma = self.config.risk_aversion;
+        let liquidity_term = (2.0 / gamma) * (1.0 + gamma / self.config.order_book_liquidity).ln();
+        mid_price * Self::to_decimal(self.risk_term(volatility) + liquidity_term)
+    }
+
+    /// γ·σ²·τ in relative terms
+    fn risk_term(&self, volatility: f64) -> f64 {
+        let sigma = volatility.max(self.config.min_volatility);
+        self.config.risk_aversion * sigma * sigma * self.config.horizon_seconds
+    }
+
+    /// Quote notional clamped to the configured and provider size limits
+    fn quote_notional(&self, provider: &LiquidityProvider) -> Decimal {
+        let min = self.liquidity_config.min_order_size.max(provider.min_order_size);
+        let max = self.liquidity_config.max_order_size.min(provider.max_order_size);
+        self.config.quote_notional.max(min).min(max)
+    }
+
+    fn to_decimal(value: f64) -> Decimal {
+        Decimal::from_f64(value).unwrap_or(Decimal::ZERO)
+    }
+}
+
+impl MarketMakingStrategy for AvellanedaStoikovStrategy {
+    fn name(&self) -> &'static str {
+        "avellaneda_stoikov"
+    }
+
+    fn generate_quote(
+        &self,
+        provider: &LiquidityProvider,
+        context: &QuoteContext,
+    ) -> TradingResult<Option<LiquidityQuote>> {
+        let mid_price = context.mid_price;
+        if !provider.is_active {
+            return Ok(None);
+        }
+        if mid_price <= Decimal::ZERO {
+            return Err(TradingError::MarketMakingError {
+                strategy: self.name().to_string(),
+                message: format!("Invalid mid price {}", mid_price),
+            });
+        }
+
+        let (reservation, spread) = if self.liquidity_config.enable_dynamic_pricing {
+            let lots = self.inventory_lots(provider, mid_price);
+            (
+                self.reservation_price(mid_price, lots, context.volatility),
+                self.optimal_spread(mid_price, context.volatility),
+            )
+        } else {
+            (mid_price, provider.target_spread * mid_price)
+        };
+        let spread = spread.min(self.liquidity_config.max_spread_percentage * mid_price);
+
+        let notional = self.quote_notional(provider);
+        let mut bid_size = notional / mid_price;
+        let mut ask_size = bid_size;
+
+        // Never quote more than the inventory can deliver
+        let half_spread = spread / Decimal::TWO;
+        let bid_price = reservation - half_spread;
+        if bid_price > Decimal::ZERO {
+            bid_size = bid_size.min(provider.quote_inventory / bid_price);
+        }
+        ask_size = ask_size.min(provider.base_inventory);
+
+        // Stop adding to an inventory imbalance that has reached its limit
+        if self.liquidity_config.inventory_management_enabled {
+            let imbalance = provider.inventory_imbalance(mid_price);
+            if imbalance >= self.liquidity_config.max_inventory_imbalance {
+                bid_size = Decimal::ZERO;
+            } else if -imbalance >= self.liquidity_config.max_inventory_imbalance {
+                ask_size = Decimal::ZERO;
+            }
+        }
+
+        if bid_price <= Decimal::ZERO || (bid_size <= Decimal::ZERO && ask_size <= Decimal::ZERO) {
+            return Ok(None);
+        }
+
+        let mut quote = LiquidityQuote::new(
+            provider.id,
+            provider.trading_pair.clone(),
+            reservation,
+            spread,
+            bid_size.max(Decimal::ZERO),
+            ask_size.max(Decimal::ZERO),
+            self.config.quote_validity_seconds,
+        );
+        quote.mid_price = mid_price;
+        Ok(Some(quote))
+    }
+}
+
+/// Market maker driving a liquidity provider with a strategy
+pub struct MarketMaker {
+    provider: LiquidityProvider,
+    strategy: Box<dyn MarketMakingStrategy>,
+    config: MarketMakingConfig,
+    liquidity_config: LiquidityConfig,
+}
+
+impl MarketMaker {
+    /// Create a new market maker
+    pub fn new(
+        provider: LiquidityProvider,
+        strategy: Box<dyn MarketMakingStrategy>,
+        config: MarketMakingConfig,
+        liquidity_config: LiquidityConfig,
+    ) -> Self {
+        Self {
+            provider,
+            strategy,
+            config,
+            liquidity_config,
+        }
+    }
+
+    /// Create a market maker using the Avellaneda–Stoikov strategy
+    pub fn avellaneda_stoikov(
+        provider: LiquidityProvider,
+        config: MarketMakingConfig,
+        liquidity_config: LiquidityConfig,
+    ) -> Self {
+        let strategy = AvellanedaStoikovStrategy::new(config.clone(), liquidity_config.clone());
+        Self::new(provider, Box::new(strategy), config, liquidity_config)
+    }
+
+    /// Get the liquidity provider
+    pub fn provider(&self) -> &LiquidityProvider {
+        &self.provider
+    }
+
+    /// Get the strategy name
+    pub fn strategy_name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    /// Quote against the mid price, estimating volatility from recent candles
+    pub fn quote(&self, mid_price: Decimal, candles: &[Candlestick]) -> TradingResult<Option<LiquidityQuote>> {
+        let window = &candles[candles.len().saturating_sub(self.config.volatility_window)..];
+        let volatility = realized_volatility(window)
+            .unwrap_or(self.config.min_volatility)
+            .max(self.config.min_volatility);
+
+        self.strategy
+            .generate_quote(&self.provider, &QuoteContext { mid_price, volatility })
+    }
+
+    /// Apply a fill of one of our quotes
+    pub fn on_fill(&mut self, side: OrderSide, price: Decimal, quantity: Decimal) {
+        let notional = price * quantity;
+        let fee_earned = notional * self.liquidity_config.lp_fee_percentage;
+        match side {
+            OrderSide::Buy => self.provider.update_inventory(quantity, -notional, fee_earned),
+            OrderSide::Sell => self.provider.update_inventory(-quantity, notional, fee_earned),
+        }
+        self.provider.total_volume += notional;
+    }
+}
+
+/// One step of a simulation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationStep {
+    pub timestamp: DateTime<Utc>,
+    pub mid_price: Decimal,
+    pub bid_price: Option<Decimal>,
+    pub ask_price: Option<Decimal>,
+    pub base_inventory: Decimal,
+    pub quote_inventory: Decimal,
+    /// Mark-to-market P&L relative to holding the starting inventory
+    pub pnl: Decimal,
+}
+
+/// Result of replaying a trade tape
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub strategy: String,
+    pub steps: Vec<SimulationStep>,
+    pub fills: u64,
+    pub volume: Decimal,
+    pub fees_earned: Decimal,
+    pub final_pnl: Decimal,
+    pub max_inventory: Decimal,
+    pub min_inventory: Decimal,
+    pub max_drawdown: Decimal,
+}
+
+/// Replays a trade tape against a market maker's quotes.
+///
+/// Before each tape trade the maker quotes against the previous trade price.
+/// A tape trade at or through our bid (ask) fills our bid (ask) up to the
+/// traded quantity at our quoted price.
+pub struct MarketMakingSimulator {
+    volatility_interval: CandlestickInterval,
+}
+
+impl MarketMakingSimulator {
+    /// Create a simulator estimating volatility at the given candle interval
+    pub fn new(volatility_interval: CandlestickInterval) -> Self {
+        Self { volatility_interval }
+    }
+
+    /// Replay the tape and report P&L and inventory paths
+    pub fn run(&self, maker: &mut MarketMaker, tape: &[Trade]) -> TradingResult<SimulationReport> {
+        let mut tape: Vec<&Trade> = tape.iter().collect();
+        tape.sort_by_key(|trade| trade.executed_at);
+
+        let first = tape.first().ok_or_else(|| TradingError::MarketMakingError {
+            strategy: maker.strategy_name().to_string(),
+            message: "Empty trade tape".to_string(),
+        })?;
+
+        let start_base = maker.provider().base_inventory;
+        let start_quote = maker.provider().quote_inventory;
+        let start_fees = maker.provider().total_fees_earned;
+        let pnl_at = |maker: &MarketMaker, mid: Decimal| {
+            let provider = maker.provider();
+            (provider.base_inventory - start_base) * mid + (provider.quote_inventory - start_quote)
+        };
+
+        let mut candles: Vec<Candlestick> = Vec::new();
+        let mut mid_price = first.price;
+        let mut fills = 0u64;
+        let mut volume = Decimal::ZERO;
+        let mut steps = Vec::with_capacity(tape.len());
+        let mut peak_pnl = Decimal::ZERO;
+        let mut max_drawdown = Decimal::ZERO;
+        let mut max_inventory = start_base;
+        let mut min_inventory = start_base;
+
+        for (index, trade) in tape.iter().enumerate() {
+            let quote = if index == 0 { None } else { maker.quote(mid_price, &candles)? };
+
+            if let Some(quote) = &quote {
+                let fill = if quote.bid_size > Decimal::ZERO && trade.price <= quote.bid_price {
+                    Some((OrderSide::Buy, quote.bid_price, quote.bid_size.min(trade.quantity)))
+                } else if quote.ask_size > Decimal::ZERO && trade.price >= quote.ask_price {
+                    Some((OrderSide::Sell, quote.ask_price, quote.ask_size.min(trade.quantity)))
+                } else {
+                    None
+                };
+
+                if let Some((side, price, quantity)) = fill {
+                    maker.on_fill(side, price, quantity);
+                    fills += 1;
+                    volume += price * quantity;
+                }
+            }
+
+            mid_price = trade.price;
+            let open_time = self.volatility_interval.open_time(trade.executed_at);
+            match candles.last_mut() {
+                Some(candle) if candle.open_time == open_time => candle.update_with_trade(trade),
+                _ => candles.push(Candlestick::new(trade, self.volatility_interval)),
+            }
+
+            let pnl = pnl_at(maker, mid_price);
+            peak_pnl = peak_pnl.max(pnl);
+            max_drawdown = max_drawdown.max(peak_pnl - pnl);
+            let provider = maker.provider();
+            max_inventory = max_inventory.max(provider.base_inventory);
+            min_inventory = min_inventory.min(provider.base_inventory);
+
+            steps.push(SimulationStep {
+                timestamp: trade.executed_at,
+                mid_price,
+                bid_price: quote.as_ref().filter(|q| q.bid_size > Decimal::ZERO).map(|q| q.bid_price),
+                ask_price: quote.as_ref().filter(|q| q.ask_size > Decimal::ZERO).map(|q| q.ask_price),
+                base_inventory: provider.base_inventory,
+                quote_inventory: provider.quote_inventory,
+                pnl,
+            });
+        }
+
+        Ok(SimulationReport {
+            strategy: maker.strategy_name().to_string(),
+            final_pnl: pnl_at(maker, mid_price),
+            fees_earned: maker.provider().total_fees_earned - start_fees,
+            steps,
+            fills,
+            volume,
+            max_inventory,
+            min_inventory,
+            max_drawdown,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TradingPair;
+    use chrono::Duration;
+    use uuid::Uuid;
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    /// Provider holding $100,000 at a price of 100, split by `base_share`
+    fn provider(base_share: i64) -> LiquidityProvider {
+        let total = Decimal::new(100000, 0);
+        let base_value = total * Decimal::new(base_share, 2);
+        let mut provider = LiquidityProvider::new(
+            "mm".to_string(),
+            pair(),
+            base_value / Decimal::new(100, 0),
+            total - base_value,
+            Decimal::new(100, 4),
+        );
+        provider.max_order_size = Decimal::new(100000, 0);
+        provider
+    }
+
+    fn strategy() -> AvellanedaStoikovStrategy {
+        AvellanedaStoikovStrategy::new(MarketMakingConfig::default(), LiquidityConfig::default())
+    }
+
+    fn context(volatility: f64) -> QuoteContext {
+        QuoteContext {
+            mid_price: Decimal::new(100, 0),
+            volatility,
+        }
+    }
+
+    #[test]
+    fn test_flat_inventory_quotes_around_mid() {
+        let quote = strategy().generate_quote(&provider(50), &context(0.0001)).unwrap().unwrap();
+
+        assert_eq!(quote.mid_price, Decimal::new(100, 0));
+        assert!(quote.bid_price < quote.mid_price && quote.ask_price > quote.mid_price);
+        let skew = (quote.ask_price - quote.mid_price) - (quote.mid_price - quote.bid_price);
+        assert!(skew.abs() < Decimal::new(1, 6));
+        assert_eq!(quote.bid_size, Decimal::new(10, 0));
+    }
+
+    #[test]
+    fn test_inventory_skews_reservation_price() {
+        let long = strategy().generate_quote(&provider(65), &context(0.0001)).unwrap().unwrap();
+        let short = strategy().generate_quote(&provider(35), &context(0.0001)).unwrap().unwrap();
+
+        // Long inventory lowers quotes to attract buyers, short inventory raises them
+        assert!(long.ask_price < short.ask_price);
+        assert!(long.bid_price < short.bid_price);
+        assert!(long.ask_price - long.mid_price < long.mid_price - long.bid_price);
+        assert!(short.ask_price - short.mid_price > short.mid_price - short.bid_price);
+    }
+
+    #[test]
+    fn test_volatility_widens_spread_up_to_limit() {
+        let calm = strategy().generate_quote(&provider(50), &context(0.00005)).unwrap().unwrap();
+        let volatile = strategy().generate_quote(&provider(50), &context(0.0003)).unwrap().unwrap();
+        assert!(volatile.spread > calm.spread);
+
+        let extreme = strategy().generate_quote(&provider(50), &context(0.01)).unwrap().unwrap();
+        assert_eq!(extreme.spread, LiquidityConfig::default().max_spread_percentage * Decimal::new(100, 0));
+    }
+
+    #[test]
+    fn test_inventory_limit_pulls_one_side() {
+        let quote = strategy().generate_quote(&provider(75), &context(0.0001)).unwrap().unwrap();
+        assert_eq!(quote.bid_size, Decimal::ZERO);
+        assert!(quote.ask_size > Decimal::ZERO);
+
+        let quote = strategy().generate_quote(&provider(25), &context(0.0001)).unwrap().unwrap();
+        assert_eq!(quote.ask_size, Decimal::ZERO);
+        assert!(quote.bid_size > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_simulation_reports_pnl_and_inventory_paths() {
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let tape: Vec<Trade> = (0..240)
+            .map(|i| {
+                // Price oscillates around 100 so quotes on both sides are hit
+                let offset = [0, 40, -10, 60, -50, 20, -40, 30][i % 8];
+                Trade {
+                    id: Uuid::new_v4(),
+                    trading_pair: pair(),
+                    buyer_order_id: Uuid::new_v4(),
+                    seller_order_id: Uuid::new_v4(),
+                    buyer_user_id: "taker".to_string(),
+                    seller_user_id: "taker".to_string(),
+                    price: Decimal::new(10000 + offset, 2),
+                    quantity: Decimal::new(5, 0),
+                    buyer_fee: Decimal::ZERO,
+                    seller_fee: Decimal::ZERO,
+                    executed_at: start + Duration::seconds(15 * i as i64),
+                }
+            })
+            .collect();
+
+        let mut maker = MarketMaker::avellaneda_stoikov(
+            provider(50),
+            MarketMakingConfig::default(),
+            LiquidityConfig::default(),
+        );
+        let report = MarketMakingSimulator::new(CandlestickInterval::OneMinute)
+            .run(&mut maker, &tape)
+            .unwrap();
+
+        assert_eq!(report.strategy, "avellaneda_stoikov");
+        assert_eq!(report.steps.len(), tape.len());
+        assert!(report.fills > 0);
+        assert!(report.volume > Decimal::ZERO);
+        assert!(report.fees_earned > Decimal::ZERO);
+        assert!(report.max_inventory >= report.min_inventory);
+        assert_eq!(report.final_pnl, report.steps.last().unwrap().pnl);
+        assert!(report.steps[0].bid_price.is_none());
+
+        let empty = MarketMakingSimulator::new(CandlestickInterval::OneMinute).run(&mut maker, &[]);
+        assert!(empty.is_err());
+    }
+}
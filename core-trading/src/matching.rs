@@ -10,10 +10,11 @@ use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use rust_decimal::Decimal;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use crate::{
     error::{TradingError, TradingResult},
-    types::{OrderSide, OrderType, TradingPair},
+    types::{OrderSide, OrderStatus, OrderType, SelfTradePreventionMode, TimeInForce, TradingPair},
     order_book::{Order, OrderBookManager},
 };
 
@@ -30,6 +31,10 @@ pub struct MatchingConfig {
     pub max_matches_per_order: u32,
     /// Enable self-trade prevention
     pub self_trade_prevention: bool,
+    /// Self-trade prevention mode used when neither the order nor its account sets one
+    pub default_stp_mode: SelfTradePreventionMode,
+    /// Per-account self-trade prevention modes
+    pub account_stp_modes: HashMap<String, SelfTradePreventionMode>,
     /// Matching fee percentage
     pub matching_fee_percentage: Decimal,
     /// Enable partial fills
@@ -44,12 +49,24 @@ impl Default for MatchingConfig {
             min_match_size: Decimal::new(1, 8), // 0.00000001
             max_matches_per_order: 100,
             self_trade_prevention: true,
+            default_stp_mode: SelfTradePreventionMode::CancelNewest,
+            account_stp_modes: HashMap::new(),
             matching_fee_percentage: Decimal::new(10, 4), // 0.10%
             allow_partial_fills: true,
         }
     }
 }
 
+impl MatchingConfig {
+    /// Resolve the self-trade prevention mode for an order: order, then account, then default
+    pub fn stp_mode_for(&self, order: &Order) -> SelfTradePreventionMode {
+        order
+            .stp_mode
+            .or_else(|| self.account_stp_modes.get(&order.user_id).copied())
+            .unwrap_or(self.default_stp_mode)
+    }
+}
+
 /// Trade execution result
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
@@ -98,11 +115,23 @@ impl Trade {
     }
 }
 
+/// Quantity cancelled by self-trade prevention
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfTradeCancellation {
+    pub order_id: Uuid,
+    pub user_id: String,
+    pub mode: SelfTradePreventionMode,
+    pub cancelled_quantity: Decimal,
+    /// Whether the cancelled order was the incoming order rather than the resting one
+    pub incoming: bool,
+}
+
 /// Match result containing all trades from a matching operation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchResult {
     pub trades: Vec<Trade>,
     pub updated_orders: Vec<Order>,
+    pub self_trade_cancellations: Vec<SelfTradeCancellation>,
     pub total_volume: Decimal,
     pub total_fees: Decimal,
     pub matched_at: DateTime<Utc>,
@@ -114,6 +143,7 @@ impl MatchResult {
         Self {
             trades: Vec::new(),
             updated_orders: Vec::new(),
+            self_trade_cancellations: Vec::new(),
             total_volume: Decimal::ZERO,
             total_fees: Decimal::ZERO,
             matched_at: Utc::now(),
@@ -136,6 +166,20 @@ impl MatchResult {
     pub fn has_matches(&self) -> bool {
         !self.trades.is_empty()
     }
+
+    /// Fold another match result into this one
+    pub fn merge(&mut self, other: MatchResult) {
+        for trade in other.trades {
+            self.add_trade(trade);
+        }
+        self.updated_orders.extend(other.updated_orders);
+        self.self_trade_cancellations.extend(other.self_trade_cancellations);
+    }
+
+    /// Record a self-trade prevention cancellation
+    pub fn add_self_trade_cancellation(&mut self, cancellation: SelfTradeCancellation) {
+        self.self_trade_cancellations.push(cancellation);
+    }
 }
 
 /// Matching engine implementation
@@ -149,7 +193,44 @@ impl MatchingEngine {
         Self { config }
     }
 
-    /// Match an incoming order against the order book
+    /// Submit an order through its full lifecycle.
+    ///
+    /// Untriggered stops are parked in the book's trigger book, other orders are
+    /// matched, and any remainder that is allowed to rest is added to the book.
+    /// Stops triggered by the resulting trades are processed in arrival order.
+    pub fn submit_order(
+        &self,
+        mut order: Order,
+        order_book: &mut OrderBookManager,
+    ) -> TradingResult<MatchResult> {
+        self.validate_submission(&order, Utc::now())?;
+
+        let mut result = MatchResult::new();
+
+        if matches!(order.order_type, OrderType::Stop | OrderType::StopLimit) {
+            let triggered = order_book
+                .last_trade_price()
+                .is_some_and(|last_price| order.is_stop_triggered(last_price));
+
+            if !triggered {
+                order_book.add_stop_order(order.clone())?;
+                order.status = OrderStatus::Pending;
+                result.add_updated_order(order);
+                return Ok(result);
+            }
+        }
+
+        self.execute_and_rest(order, order_book, &mut result)?;
+        self.process_triggered_stops(order_book, &mut result)?;
+
+        Ok(result)
+    }
+
+    /// Match an incoming order against the order book.
+    ///
+    /// Honors FOK (all-or-nothing with rollback) and cancels the unfilled
+    /// remainder of IOC, market and triggered stop orders. The order is not
+    /// added to the book; use `submit_order` for the full lifecycle.
     pub fn match_order(
         &self,
         incoming_order: &mut Order,
@@ -157,181 +238,337 @@ impl MatchingEngine {
     ) -> TradingResult<MatchResult> {
         let mut result = MatchResult::new();
 
-        match incoming_order.side {
-            OrderSide::Buy => {
-                self.match_buy_order(incoming_order, order_book, &mut result)?;
+        if Self::is_fill_or_kill(incoming_order) {
+            if !self.fills_completely(incoming_order, order_book) {
+                incoming_order.status = OrderStatus::Cancelled;
+                incoming_order.updated_at = Utc::now();
+                result.add_updated_order(incoming_order.clone());
+                return Ok(result);
             }
-            OrderSide::Sell => {
-                self.match_sell_order(incoming_order, order_book, &mut result)?;
+
+            self.match_against_book(incoming_order, order_book, &mut result)?;
+            debug_assert!(incoming_order.is_filled(), "FOK dry run diverged from matching");
+        } else {
+            self.match_against_book(incoming_order, order_book, &mut result)?;
+
+            if Self::is_immediate(incoming_order) && !incoming_order.is_filled() {
+                incoming_order.status = OrderStatus::Cancelled;
+                incoming_order.updated_at = Utc::now();
             }
         }
 
+        result.add_updated_order(incoming_order.clone());
         Ok(result)
     }
 
-    /// Match a buy order against sell orders in the book
-    fn match_buy_order(
+    /// Match an order and rest whatever remainder its time in force allows
+    fn execute_and_rest(
         &self,
-        buy_order: &mut Order,
+        mut order: Order,
         order_book: &mut OrderBookManager,
         result: &mut MatchResult,
     ) -> TradingResult<()> {
-        while buy_order.remaining_quantity > Decimal::ZERO {
-            // Get best ask price
-            let best_ask = match order_book.best_ask() {
-                Some(price) => price,
-                None => break, // No more sell orders
-            };
+        let order_result = self.match_order(&mut order, order_book)?;
+        result.merge(order_result);
 
-            // Check if buy order price is high enough
-            if buy_order.price < best_ask {
-                break; // No match possible
+        if !order.is_filled() && !order.status.is_final() {
+            order_book.add_order(order.clone())?;
+            if let Some(resting) = order_book.get_order(order.id) {
+                result.add_updated_order(resting.clone());
             }
+        }
 
-            // Find matching sell orders at the best ask price
-            let matching_orders = self.find_matching_orders(order_book, OrderSide::Sell, best_ask)?;
-            
-            if matching_orders.is_empty() {
+        Ok(())
+    }
+
+    /// Release stop orders triggered by the last trade price, cascading as new trades print
+    pub fn process_triggered_stops(
+        &self,
+        order_book: &mut OrderBookManager,
+        result: &mut MatchResult,
+    ) -> TradingResult<()> {
+        while let Some(last_price) = order_book.last_trade_price() {
+            let triggered = order_book.take_triggered_stops(last_price);
+            if triggered.is_empty() {
                 break;
             }
 
-            // Execute matches
-            for sell_order_id in matching_orders {
-                if buy_order.remaining_quantity <= Decimal::ZERO {
-                    break;
-                }
-
-                let sell_order = match order_book.get_order(sell_order_id) {
-                    Some(order) => order.clone(),
-                    None => continue,
-                };
-
-                // Check self-trade prevention
-                if self.config.self_trade_prevention && buy_order.user_id == sell_order.user_id {
+            for order in triggered {
+                if order.is_expired_at(Utc::now()) {
+                    let mut expired = order;
+                    expired.status = OrderStatus::Expired;
+                    result.add_updated_order(expired);
                     continue;
                 }
+                self.execute_and_rest(order, order_book, result)?;
+            }
+        }
 
-                // Calculate match quantity
-                let match_quantity = buy_order.remaining_quantity.min(sell_order.remaining_quantity);
-                
-                if match_quantity < self.config.min_match_size {
-                    continue;
-                }
+        Ok(())
+    }
+
+    /// Sweep expired GTD orders out of the book and trigger book
+    pub fn expire_orders(
+        &self,
+        order_book: &mut OrderBookManager,
+        now: DateTime<Utc>,
+    ) -> TradingResult<Vec<Order>> {
+        order_book.expire_orders(now)
+    }
 
-                // Execute the trade
-                let trade = self.execute_trade(buy_order, &sell_order, best_ask, match_quantity)?;
-                result.add_trade(trade);
+    /// Cross an order against resting liquidity in price-time priority
+    fn match_against_book(
+        &self,
+        incoming: &mut Order,
+        order_book: &mut OrderBookManager,
+        result: &mut MatchResult,
+    ) -> TradingResult<()> {
+        let contra_side = incoming.side.opposite();
+        let now = Utc::now();
+        let mut skipped: HashSet<Uuid> = HashSet::new();
+        let mut match_count = 0u32;
 
-                // Update orders
-                buy_order.update_filled(match_quantity);
-                let mut updated_sell_order = sell_order;
-                updated_sell_order.update_filled(match_quantity);
+        while incoming.remaining_quantity > Decimal::ZERO
+            && match_count < self.config.max_matches_per_order
+        {
+            let resting = match order_book.next_resting_order(contra_side, &skipped) {
+                Some(order) => order.clone(),
+                None => break, // No more contra orders
+            };
 
-                result.add_updated_order(buy_order.clone());
-                result.add_updated_order(updated_sell_order.clone());
+            if !Self::crosses(incoming, resting.price) {
+                break; // No match possible
+            }
 
-                // Remove filled sell order from book
-                if updated_sell_order.is_filled() {
-                    order_book.remove_order(sell_order_id)?;
+            // Expired resting orders are swept as they are encountered
+            if resting.is_expired_at(now) {
+                if let Some(mut expired) = order_book.remove_order(resting.id)? {
+                    expired.status = OrderStatus::Expired;
+                    result.add_updated_order(expired);
                 }
+                continue;
+            }
 
-                // Check if we've reached max matches
-                if result.trades.len() >= self.config.max_matches_per_order as usize {
+            // Check self-trade prevention
+            if self.config.self_trade_prevention && incoming.user_id == resting.user_id {
+                if self.prevent_self_trade(incoming, &resting, order_book, result)? {
                     break;
                 }
+                continue;
+            }
+
+            // Calculate match quantity against the visible slice
+            let match_quantity = incoming.remaining_quantity.min(resting.displayed_quantity);
+
+            if match_quantity < self.config.min_match_size {
+                skipped.insert(resting.id);
+                continue;
             }
+
+            // Execute the trade at the resting order's price
+            let trade = match incoming.side {
+                OrderSide::Buy => self.execute_trade(incoming, &resting, resting.price, match_quantity)?,
+                OrderSide::Sell => self.execute_trade(&resting, incoming, resting.price, match_quantity)?,
+            };
+            result.add_trade(trade);
+            order_book.record_trade_price(resting.price);
+
+            // Update orders
+            incoming.update_filled(match_quantity);
+            let updated_resting = order_book.fill_order(resting.id, match_quantity)?;
+            result.add_updated_order(updated_resting);
+
+            match_count += 1;
         }
 
         Ok(())
     }
 
-    /// Match a sell order against buy orders in the book
-    fn match_sell_order(
+    /// Apply the self-trade prevention mode to a pair of orders from the same user.
+    ///
+    /// Returns true when the incoming order is done and matching must stop.
+    fn prevent_self_trade(
         &self,
-        sell_order: &mut Order,
+        incoming: &mut Order,
+        resting: &Order,
         order_book: &mut OrderBookManager,
         result: &mut MatchResult,
-    ) -> TradingResult<()> {
-        while sell_order.remaining_quantity > Decimal::ZERO {
-            // Get best bid price
-            let best_bid = match order_book.best_bid() {
-                Some(price) => price,
-                None => break, // No more buy orders
-            };
+    ) -> TradingResult<bool> {
+        let mode = self.config.stp_mode_for(incoming);
 
-            // Check if sell order price is low enough
-            if sell_order.price > best_bid {
-                break; // No match possible
+        let cancel_incoming = |incoming: &mut Order, result: &mut MatchResult| {
+            result.add_self_trade_cancellation(SelfTradeCancellation {
+                order_id: incoming.id,
+                user_id: incoming.user_id.clone(),
+                mode,
+                cancelled_quantity: incoming.remaining_quantity,
+                incoming: true,
+            });
+            incoming.status = OrderStatus::Cancelled;
+            incoming.updated_at = Utc::now();
+        };
+
+        let cancel_resting = |order_book: &mut OrderBookManager, result: &mut MatchResult| {
+            if let Some(mut cancelled) = order_book.remove_order(resting.id)? {
+                result.add_self_trade_cancellation(SelfTradeCancellation {
+                    order_id: cancelled.id,
+                    user_id: cancelled.user_id.clone(),
+                    mode,
+                    cancelled_quantity: cancelled.remaining_quantity,
+                    incoming: false,
+                });
+                cancelled.status = OrderStatus::Cancelled;
+                cancelled.updated_at = Utc::now();
+                result.add_updated_order(cancelled);
             }
+            TradingResult::Ok(())
+        };
 
-            // Find matching buy orders at the best bid price
-            let matching_orders = self.find_matching_orders(order_book, OrderSide::Buy, best_bid)?;
-            
-            if matching_orders.is_empty() {
-                break;
+        match mode {
+            SelfTradePreventionMode::CancelNewest => {
+                cancel_incoming(incoming, result);
+                Ok(true)
             }
+            SelfTradePreventionMode::CancelOldest => {
+                cancel_resting(order_book, result)?;
+                Ok(false)
+            }
+            SelfTradePreventionMode::CancelBoth => {
+                cancel_resting(order_book, result)?;
+                cancel_incoming(incoming, result);
+                Ok(true)
+            }
+            SelfTradePreventionMode::DecrementAndCancel => {
+                let quantity = incoming.remaining_quantity.min(resting.remaining_quantity);
 
-            // Execute matches
-            for buy_order_id in matching_orders {
-                if sell_order.remaining_quantity <= Decimal::ZERO {
-                    break;
+                let updated_resting = order_book.decrement_order(resting.id, quantity)?;
+                result.add_self_trade_cancellation(SelfTradeCancellation {
+                    order_id: resting.id,
+                    user_id: resting.user_id.clone(),
+                    mode,
+                    cancelled_quantity: quantity,
+                    incoming: false,
+                });
+                result.add_updated_order(updated_resting);
+
+                incoming.decrement(quantity);
+                result.add_self_trade_cancellation(SelfTradeCancellation {
+                    order_id: incoming.id,
+                    user_id: incoming.user_id.clone(),
+                    mode,
+                    cancelled_quantity: quantity,
+                    incoming: true,
+                });
+
+                if incoming.remaining_quantity <= Decimal::ZERO {
+                    incoming.status = OrderStatus::Cancelled;
+                    return Ok(true);
                 }
+                Ok(false)
+            }
+        }
+    }
 
-                let buy_order = match order_book.get_order(buy_order_id) {
-                    Some(order) => order.clone(),
-                    None => continue,
-                };
+    /// Whether an order would fill completely right now.
+    ///
+    /// Dry-runs `match_against_book` without touching the book: crossing levels
+    /// are visited lazily and each is replayed as a queue, so iceberg slices
+    /// rejoin the back and expiry, self-trade prevention, the minimum match size
+    /// and the match limit apply exactly as they do when matching.
+    fn fills_completely(&self, incoming: &Order, order_book: &OrderBookManager) -> bool {
+        let now = Utc::now();
+        let mut remaining = incoming.remaining_quantity;
+        let mut match_count = 0u32;
 
-                // Check self-trade prevention
-                if self.config.self_trade_prevention && sell_order.user_id == buy_order.user_id {
+        for level in order_book.levels(incoming.side.opposite()) {
+            if remaining <= Decimal::ZERO || !Self::crosses(incoming, level.price) {
+                break;
+            }
+
+            let mut queue: VecDeque<Order> = level
+                .orders
+                .iter()
+                .filter_map(|id| order_book.get_order(*id).cloned())
+                .collect();
+            while let Some(mut resting) = queue.pop_front() {
+                if remaining <= Decimal::ZERO || match_count >= self.config.max_matches_per_order {
+                    break;
+                }
+                if resting.is_expired_at(now) {
                     continue;
                 }
+                if self.config.self_trade_prevention && incoming.user_id == resting.user_id {
+                    // Only cancelling the resting order lets the incoming order keep filling
+                    if self.config.stp_mode_for(incoming) == SelfTradePreventionMode::CancelOldest {
+                        continue;
+                    }
+                    return false;
+                }
 
-                // Calculate match quantity
-                let match_quantity = sell_order.remaining_quantity.min(buy_order.remaining_quantity);
-                
+                let match_quantity = remaining.min(resting.displayed_quantity);
                 if match_quantity < self.config.min_match_size {
                     continue;
                 }
+                remaining -= match_quantity;
+                match_count += 1;
 
-                // Execute the trade
-                let trade = self.execute_trade(&buy_order, sell_order, best_bid, match_quantity)?;
-                result.add_trade(trade);
+                resting.update_filled(match_quantity);
+                if resting.needs_refresh() {
+                    resting.refresh_display();
+                    queue.push_back(resting);
+                }
+            }
+        }
 
-                // Update orders
-                sell_order.update_filled(match_quantity);
-                let mut updated_buy_order = buy_order;
-                updated_buy_order.update_filled(match_quantity);
+        remaining <= Decimal::ZERO
+    }
 
-                result.add_updated_order(sell_order.clone());
-                result.add_updated_order(updated_buy_order.clone());
+    /// Check whether an order's limit crosses a resting price
+    fn crosses(incoming: &Order, resting_price: Decimal) -> bool {
+        match incoming.order_type {
+            OrderType::Market | OrderType::Stop => true,
+            _ => match incoming.side {
+                OrderSide::Buy => incoming.price >= resting_price,
+                OrderSide::Sell => incoming.price <= resting_price,
+            },
+        }
+    }
 
-                // Remove filled buy order from book
-                if updated_buy_order.is_filled() {
-                    order_book.remove_order(buy_order_id)?;
-                }
+    /// FOK applies when either the order type or time in force requests it
+    fn is_fill_or_kill(order: &Order) -> bool {
+        order.order_type == OrderType::FOK || order.time_in_force == TimeInForce::FOK
+    }
 
-                // Check if we've reached max matches
-                if result.trades.len() >= self.config.max_matches_per_order as usize {
-                    break;
-                }
-            }
+    /// Orders whose unfilled remainder never rests in the book
+    fn is_immediate(order: &Order) -> bool {
+        matches!(order.order_type, OrderType::Market | OrderType::Stop | OrderType::IOC)
+            || order.time_in_force == TimeInForce::IOC
+    }
+
+    /// Validate order type and time in force constraints at submission
+    fn validate_submission(&self, order: &Order, now: DateTime<Utc>) -> TradingResult<()> {
+        if matches!(order.order_type, OrderType::Stop | OrderType::StopLimit) && order.stop_price.is_none() {
+            return Err(TradingError::invalid_order("Stop orders require a stop price"));
         }
 
-        Ok(())
-    }
+        if order.order_type == OrderType::Iceberg && order.iceberg_quantity.is_none() {
+            return Err(TradingError::invalid_order("Iceberg orders require a peak quantity"));
+        }
 
-    /// Find matching orders at a specific price level
-    fn find_matching_orders(
-        &self,
-        order_book: &OrderBookManager,
-        side: OrderSide,
-        price: Decimal,
-    ) -> TradingResult<Vec<Uuid>> {
-        // This is a simplified implementation
-        // In a real system, this would access the order book's internal structure
-        // to get orders at the specific price level
-        Ok(Vec::new())
+        match order.time_in_force {
+            TimeInForce::GTD => match order.expires_at {
+                None => Err(TradingError::invalid_order("GTD orders require an expiry time")),
+                Some(expires_at) if now >= expires_at => Err(TradingError::OrderExpired {
+                    order_id: order.id.to_string(),
+                    expired_at: expires_at.to_rfc3339(),
+                }),
+                Some(_) => Ok(()),
+            },
+            TimeInForce::ATO | TimeInForce::ATC => Err(TradingError::invalid_order(
+                "ATO/ATC orders are only accepted during an opening or closing auction",
+            )),
+            _ => Ok(()),
+        }
     }
 
     /// Execute a trade between two orders
@@ -391,7 +628,7 @@ pub struct MatchingStats {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::OrderStatus;
+    use crate::order_book::OrderBookConfig;
 
     #[test]
     fn test_trade_creation() {
@@ -483,4 +720,347 @@ mod tests {
         assert!(config.allow_partial_fills);
         assert_eq!(config.matching_fee_percentage, Decimal::new(10, 4));
     }
+
+    fn test_book() -> OrderBookManager {
+        OrderBookManager::new(
+            OrderBookConfig::default(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+        )
+    }
+
+    fn limit(user: &str, side: OrderSide, price: i64, quantity: i64) -> Order {
+        Order::new(
+            user.to_string(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+            side,
+            OrderType::Limit,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        )
+    }
+
+    #[test]
+    fn test_limit_order_rests_remainder() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        book.add_order(limit("seller", OrderSide::Sell, 100, 1)).unwrap();
+
+        let buy = limit("buyer", OrderSide::Buy, 100, 3);
+        let buy_id = buy.id;
+        let result = engine.submit_order(buy, &mut book).unwrap();
+
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(book.best_ask(), None);
+        assert_eq!(book.best_bid(), Some(Decimal::new(100, 0)));
+        let resting = book.get_order(buy_id).unwrap();
+        assert_eq!(resting.remaining_quantity, Decimal::new(2, 0));
+        assert_eq!(resting.status, OrderStatus::PartiallyFilled);
+    }
+
+    #[test]
+    fn test_stop_order_triggers_on_last_trade_price() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        book.add_order(limit("s1", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("s2", OrderSide::Sell, 105, 1)).unwrap();
+
+        let stop = Order::new(
+            "stopper".to_string(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+            OrderSide::Buy,
+            OrderType::Stop,
+            Decimal::ZERO,
+            Decimal::new(1, 0),
+        )
+        .with_stop_price(Decimal::new(100, 0));
+
+        // No trade has printed yet, so the stop is parked
+        let parked = engine.submit_order(stop, &mut book).unwrap();
+        assert!(!parked.has_matches());
+        assert_eq!(book.stop_orders().len(), 1);
+
+        // A trade at 100 wakes the stop, which lifts the next ask at 105
+        let result = engine
+            .submit_order(limit("buyer", OrderSide::Buy, 100, 1), &mut book)
+            .unwrap();
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[1].price, Decimal::new(105, 0));
+        assert_eq!(result.trades[1].buyer_user_id, "stopper");
+        assert!(book.stop_orders().is_empty());
+        assert_eq!(book.last_trade_price(), Some(Decimal::new(105, 0)));
+    }
+
+    #[test]
+    fn test_iceberg_refreshes_hidden_quantity() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+
+        let iceberg = Order::new(
+            "whale".to_string(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+            OrderSide::Sell,
+            OrderType::Iceberg,
+            Decimal::new(100, 0),
+            Decimal::new(10, 0),
+        )
+        .with_iceberg_quantity(Decimal::new(2, 0));
+        let iceberg_id = iceberg.id;
+        engine.submit_order(iceberg, &mut book).unwrap();
+
+        // Only the peak is visible in the book
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(2, 0));
+
+        // A later order at the same price queues behind the first slice
+        book.add_order(limit("other", OrderSide::Sell, 100, 1)).unwrap();
+
+        let result = engine
+            .submit_order(limit("buyer", OrderSide::Buy, 100, 5), &mut book)
+            .unwrap();
+
+        // 2 from the first slice, 1 from "other", then 2 from the refreshed slice
+        let sellers: Vec<&str> = result.trades.iter().map(|t| t.seller_user_id.as_str()).collect();
+        assert_eq!(sellers, vec!["whale", "other", "whale"]);
+
+        let resting = book.get_order(iceberg_id).unwrap();
+        assert_eq!(resting.remaining_quantity, Decimal::new(6, 0));
+        assert_eq!(resting.displayed_quantity, Decimal::new(2, 0));
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(2, 0));
+    }
+
+    #[test]
+    fn test_fok_kills_when_depth_insufficient() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        book.add_order(limit("seller", OrderSide::Sell, 100, 1)).unwrap();
+
+        let fok = limit("buyer", OrderSide::Buy, 100, 2).with_time_in_force(TimeInForce::FOK);
+        let fok_id = fok.id;
+        let result = engine.submit_order(fok, &mut book).unwrap();
+
+        assert!(!result.has_matches());
+        let killed = result.updated_orders.iter().find(|o| o.id == fok_id).unwrap();
+        assert_eq!(killed.status, OrderStatus::Cancelled);
+        assert_eq!(killed.filled_quantity, Decimal::ZERO);
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(1, 0));
+        assert!(book.get_order(fok_id).is_none());
+    }
+
+    #[test]
+    fn test_fok_rolls_back_partial_execution() {
+        let config = MatchingConfig {
+            max_matches_per_order: 1,
+            ..MatchingConfig::default()
+        };
+        let engine = MatchingEngine::new(config);
+        let mut book = test_book();
+        book.add_order(limit("s1", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("s2", OrderSide::Sell, 101, 1)).unwrap();
+
+        // Depth is sufficient but the match cap would stop execution halfway
+        let mut fok = Order::new(
+            "buyer".to_string(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+            OrderSide::Buy,
+            OrderType::FOK,
+            Decimal::new(101, 0),
+            Decimal::new(2, 0),
+        );
+        let result = engine.match_order(&mut fok, &mut book).unwrap();
+
+        assert!(!result.has_matches());
+        assert_eq!(fok.status, OrderStatus::Cancelled);
+        assert_eq!(fok.filled_quantity, Decimal::ZERO);
+        assert_eq!(book.best_ask(), Some(Decimal::new(100, 0)));
+        assert_eq!(book.last_trade_price(), None);
+    }
+
+    #[test]
+    fn test_fok_fills_completely() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        book.add_order(limit("s1", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("s2", OrderSide::Sell, 101, 1)).unwrap();
+
+        let mut fok = limit("buyer", OrderSide::Buy, 101, 2).with_time_in_force(TimeInForce::FOK);
+        let result = engine.match_order(&mut fok, &mut book).unwrap();
+
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(fok.status, OrderStatus::Filled);
+        assert_eq!(book.best_ask(), None);
+    }
+
+    #[test]
+    fn test_fok_check_follows_icebergs_and_self_trades() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        let iceberg = Order::new(
+            "whale".to_string(),
+            TradingPair::new("BTC".to_string(), "USD".to_string()),
+            OrderSide::Sell,
+            OrderType::Iceberg,
+            Decimal::new(100, 0),
+            Decimal::new(10, 0),
+        )
+        .with_iceberg_quantity(Decimal::new(2, 0));
+        engine.submit_order(iceberg, &mut book).unwrap();
+        book.add_order(limit("other", OrderSide::Sell, 100, 1)).unwrap();
+        book.add_order(limit("alice", OrderSide::Sell, 101, 2)).unwrap();
+        book.add_order(limit("bob", OrderSide::Sell, 101, 5)).unwrap();
+
+        // Hidden iceberg quantity counts towards the fill
+        let mut fok = limit("buyer", OrderSide::Buy, 100, 11).with_time_in_force(TimeInForce::FOK);
+        let result = engine.match_order(&mut fok, &mut book).unwrap();
+        assert_eq!(fok.status, OrderStatus::Filled);
+        assert_eq!(result.trades.len(), 6);
+
+        // Alice's own ask comes first and cancel-newest would stop her order
+        let mut fok = limit("alice", OrderSide::Buy, 101, 3).with_time_in_force(TimeInForce::FOK);
+        let result = engine.match_order(&mut fok, &mut book).unwrap();
+        assert_eq!(fok.status, OrderStatus::Cancelled);
+        assert!(result.self_trade_cancellations.is_empty());
+        assert_eq!(book.level_quantity(OrderSide::Sell, Decimal::new(101, 0)), Decimal::new(7, 0));
+    }
+
+    #[test]
+    fn test_ioc_cancels_remainder() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        book.add_order(limit("seller", OrderSide::Sell, 100, 1)).unwrap();
+
+        let ioc = limit("buyer", OrderSide::Buy, 100, 3).with_time_in_force(TimeInForce::IOC);
+        let ioc_id = ioc.id;
+        let result = engine.submit_order(ioc, &mut book).unwrap();
+
+        assert_eq!(result.trades.len(), 1);
+        let final_state = result.updated_orders.iter().rev().find(|o| o.id == ioc_id).unwrap();
+        assert_eq!(final_state.status, OrderStatus::Cancelled);
+        assert_eq!(final_state.filled_quantity, Decimal::new(1, 0));
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn test_gtd_expiry_sweep() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+        let now = Utc::now();
+
+        let gtd = limit("seller", OrderSide::Sell, 100, 1)
+            .with_time_in_force(TimeInForce::GTD)
+            .with_expiry(now + chrono::Duration::minutes(5));
+        let gtd_id = gtd.id;
+        engine.submit_order(gtd, &mut book).unwrap();
+        book.add_order(limit("gtc", OrderSide::Sell, 101, 1)).unwrap();
+
+        assert!(engine.expire_orders(&mut book, now).unwrap().is_empty());
+
+        let expired = engine
+            .expire_orders(&mut book, now + chrono::Duration::minutes(10))
+            .unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, gtd_id);
+        assert_eq!(expired[0].status, OrderStatus::Expired);
+        assert_eq!(book.best_ask(), Some(Decimal::new(101, 0)));
+    }
+
+    #[test]
+    fn test_gtd_rejected_when_already_expired() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = test_book();
+
+        let gtd = limit("buyer", OrderSide::Buy, 100, 1)
+            .with_time_in_force(TimeInForce::GTD)
+            .with_expiry(Utc::now() - chrono::Duration::minutes(1));
+
+        let result = engine.submit_order(gtd, &mut book);
+        assert!(matches!(result, Err(TradingError::OrderExpired { .. })));
+    }
+
+    fn self_trade_book() -> OrderBookManager {
+        let mut book = test_book();
+        book.add_order(limit("alice", OrderSide::Sell, 100, 2)).unwrap();
+        book.add_order(limit("bob", OrderSide::Sell, 101, 5)).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_stp_cancel_newest() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = self_trade_book();
+
+        let buy = limit("alice", OrderSide::Buy, 101, 3);
+        let result = engine.submit_order(buy, &mut book).unwrap();
+
+        assert!(result.trades.is_empty());
+        assert_eq!(result.self_trade_cancellations.len(), 1);
+        assert!(result.self_trade_cancellations[0].incoming);
+        assert_eq!(result.self_trade_cancellations[0].cancelled_quantity, Decimal::new(3, 0));
+        assert_eq!(book.best_ask(), Some(Decimal::new(100, 0)));
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn test_stp_cancel_oldest_keeps_matching() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = self_trade_book();
+
+        let buy = limit("alice", OrderSide::Buy, 101, 3)
+            .with_stp_mode(SelfTradePreventionMode::CancelOldest);
+        let result = engine.submit_order(buy, &mut book).unwrap();
+
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].seller_user_id, "bob");
+        assert_eq!(result.trades[0].quantity, Decimal::new(3, 0));
+        assert_eq!(result.self_trade_cancellations.len(), 1);
+        assert!(!result.self_trade_cancellations[0].incoming);
+        assert_eq!(book.best_ask(), Some(Decimal::new(101, 0)));
+    }
+
+    #[test]
+    fn test_stp_cancel_both() {
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = self_trade_book();
+
+        let buy = limit("alice", OrderSide::Buy, 101, 3)
+            .with_stp_mode(SelfTradePreventionMode::CancelBoth);
+        let result = engine.submit_order(buy, &mut book).unwrap();
+
+        assert!(result.trades.is_empty());
+        assert_eq!(result.self_trade_cancellations.len(), 2);
+        assert_eq!(book.best_ask(), Some(Decimal::new(101, 0)));
+        assert_eq!(book.best_bid(), None);
+    }
+
+    #[test]
+    fn test_stp_decrement_and_cancel_per_account() {
+        let mut config = MatchingConfig::default();
+        config
+            .account_stp_modes
+            .insert("alice".to_string(), SelfTradePreventionMode::DecrementAndCancel);
+        let engine = MatchingEngine::new(config);
+        let mut book = self_trade_book();
+
+        let buy = limit("alice", OrderSide::Buy, 101, 3);
+        let buy_id = buy.id;
+        let result = engine.submit_order(buy, &mut book).unwrap();
+
+        // Resting 2 and incoming 3 overlap by 2: resting is cancelled, incoming keeps 1
+        assert_eq!(result.self_trade_cancellations.len(), 2);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].quantity, Decimal::ONE);
+        let incoming = result.updated_orders.iter().rev().find(|o| o.id == buy_id).unwrap();
+        assert_eq!(incoming.quantity, Decimal::ONE);
+        assert_eq!(incoming.status, OrderStatus::Filled);
+        assert_eq!(book.best_ask(), Some(Decimal::new(101, 0)));
+
+        // Larger resting order is decremented and keeps its place in the book
+        let mut book = test_book();
+        let resting = limit("alice", OrderSide::Sell, 100, 5);
+        let resting_id = resting.id;
+        book.add_order(resting).unwrap();
+        let result = engine.submit_order(limit("alice", OrderSide::Buy, 100, 3), &mut book).unwrap();
+
+        assert!(result.trades.is_empty());
+        assert_eq!(book.get_order(resting_id).unwrap().remaining_quantity, Decimal::new(2, 0));
+        assert_eq!(book.snapshot().asks[0].quantity, Decimal::new(2, 0));
+    }
 }
@@ -0,0 +1,660 @@
+// =====================================================================================
+// File: core-trading/src/netting.rs
+// Description: Bilateral and multilateral netting of settlement cycles
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::TradingPair,
+    matching::Trade,
+    settlement::{
+        DVPInstruction, DVPStatus, DeliveryLeg, DeliveryStatus, NettingPosition, PaymentLeg,
+        PaymentStatus, SettlementConfig, SettlementInstruction, SettlementStatus,
+    },
+};
+
+/// Netting mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NettingMode {
+    /// Every trade settles on its own
+    Gross,
+    /// Trades are netted per pair of counterparties
+    Bilateral,
+    /// Trades are netted per participant against the central clearing account
+    Multilateral,
+}
+
+/// Net obligation between a participant and a counterparty in one asset.
+///
+/// Positive amounts are received by the participant, negative amounts are owed by it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetObligation {
+    pub participant: String,
+    pub counterparty: String,
+    pub asset: String,
+    pub amount: Decimal,
+    pub trade_ids: Vec<Uuid>,
+}
+
+impl NetObligation {
+    fn new(participant: String, counterparty: String, asset: String) -> Self {
+        Self {
+            participant,
+            counterparty,
+            asset,
+            amount: Decimal::ZERO,
+            trade_ids: Vec::new(),
+        }
+    }
+
+    /// Check if nothing moves under this obligation
+    pub fn is_flat(&self) -> bool {
+        self.amount.is_zero()
+    }
+}
+
+/// Obligations keyed by (participant, counterparty, asset)
+type ObligationBook = BTreeMap<(String, String, String), NetObligation>;
+
+/// Obligations received and paid between the same two parties
+type ObligationLegs<'a> = (Vec<&'a NetObligation>, Vec<&'a NetObligation>);
+
+/// Add an amount to an obligation, creating it on first use
+fn accrue(
+    obligations: &mut ObligationBook,
+    participant: &str,
+    counterparty: &str,
+    asset: &str,
+    amount: Decimal,
+    trade_id: Uuid,
+) {
+    let obligation = obligations
+        .entry((participant.to_string(), counterparty.to_string(), asset.to_string()))
+        .or_insert_with(|| NetObligation::new(participant.to_string(), counterparty.to_string(), asset.to_string()));
+    obligation.amount += amount;
+    if obligation.trade_ids.last() != Some(&trade_id) {
+        obligation.trade_ids.push(trade_id);
+    }
+}
+
+/// Gross-versus-net comparison for a settlement cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NettingReport {
+    pub mode: NettingMode,
+    pub trade_count: usize,
+    pub gross_transfer_count: usize,
+    pub net_transfer_count: usize,
+    /// Asset -> total quantity moved without netting
+    pub gross_volume: HashMap<String, Decimal>,
+    /// Asset -> total quantity moved after netting
+    pub net_volume: HashMap<String, Decimal>,
+}
+
+impl NettingReport {
+    /// Percentage of transfers eliminated by netting
+    pub fn transfer_reduction_percentage(&self) -> Decimal {
+        if self.gross_transfer_count == 0 {
+            return Decimal::ZERO;
+        }
+        let eliminated = Decimal::from(self.gross_transfer_count - self.net_transfer_count);
+        eliminated / Decimal::from(self.gross_transfer_count) * Decimal::ONE_HUNDRED
+    }
+
+    /// Percentage of an asset's volume eliminated by netting
+    pub fn volume_reduction_percentage(&self, asset: &str) -> Option<Decimal> {
+        let gross = *self.gross_volume.get(asset)?;
+        if gross.is_zero() {
+            return None;
+        }
+        let net = self.net_volume.get(asset).copied().unwrap_or(Decimal::ZERO);
+        Some((gross - net) / gross * Decimal::ONE_HUNDRED)
+    }
+}
+
+/// Result of netting a settlement cycle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NettingResult {
+    pub cycle_id: Uuid,
+    pub mode: NettingMode,
+    pub obligations: Vec<NetObligation>,
+    pub positions: Vec<NettingPosition>,
+    pub instructions: Vec<SettlementInstruction>,
+    pub dvp_instructions: Vec<DVPInstruction>,
+    pub report: NettingReport,
+}
+
+/// Netting engine
+pub struct NettingEngine {
+    config: SettlementConfig,
+    clearing_account: String,
+}
+
+impl NettingEngine {
+    /// Create a new netting engine; multilateral obligations face `clearing_account`
+    pub fn new(config: SettlementConfig, clearing_account: String) -> Self {
+        Self { config, clearing_account }
+    }
+
+    /// Choose the effective mode for a cycle.
+    ///
+    /// Netting only runs when enabled and the cycle's gross value reaches
+    /// `netting_threshold`; otherwise the cycle settles gross.
+    pub fn effective_mode(&self, trades: &[Trade], requested: NettingMode) -> NettingMode {
+        let gross_value: Decimal = trades.iter().map(|t| t.total_value()).sum();
+        if !self.config.enable_netting || gross_value < self.config.netting_threshold {
+            NettingMode::Gross
+        } else {
+            requested
+        }
+    }
+
+    /// Net a settlement cycle's trades into obligations and settlement instructions
+    pub fn net_cycle(
+        &self,
+        trades: &[Trade],
+        mode: NettingMode,
+        settlement_date: DateTime<Utc>,
+    ) -> TradingResult<NettingResult> {
+        for trade in trades {
+            if trade.buyer_user_id == self.clearing_account || trade.seller_user_id == self.clearing_account {
+                return Err(TradingError::settlement_error(
+                    trade.id.to_string(),
+                    format!("Trade counterparty collides with clearing account {}", self.clearing_account),
+                ));
+            }
+        }
+
+        let cycle_id = Uuid::new_v4();
+        let mode = self.effective_mode(trades, mode);
+        let positions = Self::positions(trades);
+
+        let (obligations, instructions, dvp_instructions) = match mode {
+            NettingMode::Gross => {
+                let mut instructions: Vec<SettlementInstruction> = trades
+                    .iter()
+                    .map(|trade| SettlementInstruction::from_trade(trade, settlement_date))
+                    .collect();
+                let dvps = instructions.iter().map(|i| self.dvp_for(i)).collect();
+                // Fees are collected by the clearing account free of payment
+                for trade in trades {
+                    let mut fees = ObligationBook::new();
+                    self.accrue_fees(&mut fees, trade);
+                    let fees: Vec<NetObligation> = fees.into_values().collect();
+                    instructions.extend(self.instructions_for(&fees, &[], trade.id, settlement_date));
+                }
+                (Vec::new(), instructions, dvps)
+            }
+            NettingMode::Bilateral | NettingMode::Multilateral => {
+                let obligations = match mode {
+                    NettingMode::Bilateral => self.bilateral_obligations(trades),
+                    _ => self.multilateral_obligations(trades),
+                };
+                let base_assets: Vec<&str> = trades.iter().map(|t| t.trading_pair.base_asset.as_str()).collect();
+
+                let instructions = self.instructions_for(&obligations, &base_assets, cycle_id, settlement_date);
+                // Only exchanges of both legs are delivery-versus-payment
+                let dvps = instructions
+                    .iter()
+                    .filter(|i| !i.delivery_quantity.is_zero() && !i.payment_amount.is_zero())
+                    .map(|i| self.dvp_for(i))
+                    .collect();
+                (obligations, instructions, dvps)
+            }
+        };
+
+        let report = Self::report(trades, mode, &obligations);
+        let dvp_instructions = if self.config.enable_dvp { dvp_instructions } else { Vec::new() };
+
+        Ok(NettingResult {
+            cycle_id,
+            mode,
+            obligations,
+            positions,
+            instructions,
+            dvp_instructions,
+            report,
+        })
+    }
+
+    /// Per-participant, per-asset gross and net positions, fees included
+    fn positions(trades: &[Trade]) -> Vec<NettingPosition> {
+        fn position<'a>(
+            positions: &'a mut BTreeMap<(String, String), NettingPosition>,
+            user: &str,
+            asset: &str,
+        ) -> &'a mut NettingPosition {
+            positions
+                .entry((user.to_string(), asset.to_string()))
+                .or_insert_with(|| NettingPosition::new(user.to_string(), asset.to_string()))
+        }
+
+        let mut positions = BTreeMap::new();
+        for trade in trades {
+            let base = &trade.trading_pair.base_asset;
+            let quote = &trade.trading_pair.quote_asset;
+            let value = trade.total_value();
+
+            position(&mut positions, &trade.buyer_user_id, base).add_buy(trade.quantity);
+            position(&mut positions, &trade.buyer_user_id, quote).add_sell(value + trade.buyer_fee);
+            position(&mut positions, &trade.seller_user_id, base).add_sell(trade.quantity);
+            position(&mut positions, &trade.seller_user_id, quote).add_buy(value - trade.seller_fee);
+        }
+
+        positions.into_values().collect()
+    }
+
+    /// Both counterparties' fees, owed to the clearing account in the quote asset
+    fn accrue_fees(&self, obligations: &mut ObligationBook, trade: &Trade) {
+        for (user, fee) in [(&trade.buyer_user_id, trade.buyer_fee), (&trade.seller_user_id, trade.seller_fee)] {
+            if !fee.is_zero() {
+                accrue(obligations, user, &self.clearing_account, &trade.trading_pair.quote_asset, -fee, trade.id);
+            }
+        }
+    }
+
+    /// Net each pair of counterparties against each other, per asset
+    fn bilateral_obligations(&self, trades: &[Trade]) -> Vec<NetObligation> {
+        let mut obligations = ObligationBook::new();
+
+        for trade in trades {
+            // Key each pair from the lexicographically smaller participant's side
+            let (participant, counterparty, sign) = if trade.buyer_user_id <= trade.seller_user_id {
+                (&trade.buyer_user_id, &trade.seller_user_id, Decimal::ONE)
+            } else {
+                (&trade.seller_user_id, &trade.buyer_user_id, Decimal::NEGATIVE_ONE)
+            };
+
+            let pair = &trade.trading_pair;
+            accrue(&mut obligations, participant, counterparty, &pair.base_asset, sign * trade.quantity, trade.id);
+            accrue(&mut obligations, participant, counterparty, &pair.quote_asset, -sign * trade.total_value(), trade.id);
+            self.accrue_fees(&mut obligations, trade);
+        }
+
+        obligations.into_values().filter(|o| !o.is_flat()).collect()
+    }
+
+    /// Net each participant's assets across all trading pairs against the clearing account
+    fn multilateral_obligations(&self, trades: &[Trade]) -> Vec<NetObligation> {
+        let mut obligations = ObligationBook::new();
+
+        for trade in trades {
+            let pair = &trade.trading_pair;
+            for (participant, sign, fee) in [
+                (&trade.buyer_user_id, Decimal::ONE, trade.buyer_fee),
+                (&trade.seller_user_id, Decimal::NEGATIVE_ONE, trade.seller_fee),
+            ] {
+                let clearing = &self.clearing_account;
+                accrue(&mut obligations, participant, clearing, &pair.base_asset, sign * trade.quantity, trade.id);
+                accrue(
+                    &mut obligations,
+                    participant,
+                    clearing,
+                    &pair.quote_asset,
+                    -sign * trade.total_value() - fee,
+                    trade.id,
+                );
+            }
+        }
+
+        obligations.into_values().filter(|o| !o.is_flat()).collect()
+    }
+
+    /// Turn obligations into settlement instructions.
+    ///
+    /// Between the same two parties, an asset received is paired with an asset
+    /// paid into one exchange instruction, delivering a traded base asset where
+    /// possible. Unpaired obligations settle free of payment as delivery-only
+    /// instructions.
+    fn instructions_for(
+        &self,
+        obligations: &[NetObligation],
+        base_assets: &[&str],
+        trade_id: Uuid,
+        settlement_date: DateTime<Utc>,
+    ) -> Vec<SettlementInstruction> {
+        let instruction = |buyer: &str, seller: &str, delivered: &NetObligation, paid: Option<&NetObligation>| {
+            let now = Utc::now();
+            let (asset_to_receive, payment_amount) = match paid {
+                Some(paid) => (paid.asset.clone(), paid.amount.abs()),
+                None => (delivered.asset.clone(), Decimal::ZERO),
+            };
+            SettlementInstruction {
+                id: Uuid::new_v4(),
+                trade_id,
+                trading_pair: TradingPair::new(delivered.asset.clone(), asset_to_receive.clone()),
+                buyer_id: buyer.to_string(),
+                seller_id: seller.to_string(),
+                asset_to_deliver: delivered.asset.clone(),
+                asset_to_receive,
+                delivery_quantity: delivered.amount.abs(),
+                payment_amount,
+                settlement_date,
+                status: SettlementStatus::Pending,
+                created_at: now,
+                updated_at: now,
+                attempts: 0,
+                error_message: None,
+            }
+        };
+
+        // (participant, counterparty) -> (obligations received, obligations paid)
+        let mut legs: BTreeMap<(&str, &str), ObligationLegs> = BTreeMap::new();
+        for obligation in obligations {
+            let (receives, pays) = legs
+                .entry((obligation.participant.as_str(), obligation.counterparty.as_str()))
+                .or_default();
+            if obligation.amount > Decimal::ZERO {
+                receives.push(obligation);
+            } else {
+                pays.push(obligation);
+            }
+        }
+
+        let mut instructions = Vec::new();
+        for ((participant, counterparty), (receives, pays)) in legs {
+            let mut receives = receives.into_iter();
+            let mut pays = pays.into_iter();
+            loop {
+                instructions.push(match (receives.next(), pays.next()) {
+                    (Some(received), Some(paid)) => {
+                        if base_assets.contains(&received.asset.as_str()) || !base_assets.contains(&paid.asset.as_str()) {
+                            instruction(participant, counterparty, received, Some(paid))
+                        } else {
+                            instruction(counterparty, participant, paid, Some(received))
+                        }
+                    }
+                    (Some(received), None) => instruction(participant, counterparty, received, None),
+                    (None, Some(paid)) => instruction(counterparty, participant, paid, None),
+                    (None, None) => break,
+                });
+            }
+        }
+        instructions
+    }
+
+    /// Build the DvP legs for an instruction
+    fn dvp_for(&self, instruction: &SettlementInstruction) -> DVPInstruction {
+        DVPInstruction {
+            id: Uuid::new_v4(),
+            settlement_instruction_id: instruction.id,
+            delivery_leg: DeliveryLeg {
+                asset: instruction.asset_to_deliver.clone(),
+                quantity: instruction.delivery_quantity,
+                from_account: instruction.seller_id.clone(),
+                to_account: instruction.buyer_id.clone(),
+                delivery_status: DeliveryStatus::Pending,
+                tx_hash: None,
+            },
+            payment_leg: PaymentLeg {
+                currency: instruction.asset_to_receive.clone(),
+                amount: instruction.payment_amount,
+                from_account: instruction.buyer_id.clone(),
+                to_account: instruction.seller_id.clone(),
+                payment_status: PaymentStatus::Pending,
+                tx_hash: None,
+            },
+            status: DVPStatus::Pending,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Compare gross and net transfers, counting fee payments as transfers
+    fn report(trades: &[Trade], mode: NettingMode, obligations: &[NetObligation]) -> NettingReport {
+        let mut gross_volume: HashMap<String, Decimal> = HashMap::new();
+        let mut gross_transfer_count = 0;
+        for trade in trades {
+            let fees = trade.buyer_fee + trade.seller_fee;
+            *gross_volume.entry(trade.trading_pair.base_asset.clone()).or_default() += trade.quantity;
+            *gross_volume.entry(trade.trading_pair.quote_asset.clone()).or_default() += trade.total_value() + fees;
+            gross_transfer_count += 2
+                + usize::from(!trade.buyer_fee.is_zero())
+                + usize::from(!trade.seller_fee.is_zero());
+        }
+
+        let (net_volume, net_transfer_count) = match mode {
+            NettingMode::Gross => (gross_volume.clone(), gross_transfer_count),
+            _ => {
+                let mut net_volume: HashMap<String, Decimal> = HashMap::new();
+                for obligation in obligations {
+                    *net_volume.entry(obligation.asset.clone()).or_default() += obligation.amount.abs();
+                }
+                (net_volume, obligations.len())
+            }
+        };
+
+        NettingReport {
+            mode,
+            trade_count: trades.len(),
+            gross_transfer_count,
+            net_transfer_count,
+            gross_volume,
+            net_volume,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CCP: &str = "clearing-house";
+
+    fn trade(buyer: &str, seller: &str, quantity: i64, price: i64) -> Trade {
+        trade_in("RWA", buyer, seller, quantity, price)
+    }
+
+    fn trade_in(base: &str, buyer: &str, seller: &str, quantity: i64, price: i64) -> Trade {
+        Trade {
+            id: Uuid::new_v4(),
+            trading_pair: TradingPair::new(base.to_string(), "USD".to_string()),
+            buyer_order_id: Uuid::new_v4(),
+            seller_order_id: Uuid::new_v4(),
+            buyer_user_id: buyer.to_string(),
+            seller_user_id: seller.to_string(),
+            price: Decimal::new(price, 0),
+            quantity: Decimal::new(quantity, 0),
+            buyer_fee: Decimal::ZERO,
+            seller_fee: Decimal::ZERO,
+            executed_at: Utc::now(),
+        }
+    }
+
+    fn engine() -> NettingEngine {
+        NettingEngine::new(SettlementConfig::default(), CCP.to_string())
+    }
+
+    #[test]
+    fn test_bilateral_netting() {
+        let trades = vec![trade("bob", "alice", 10, 100), trade("alice", "bob", 6, 100)];
+
+        let result = engine().net_cycle(&trades, NettingMode::Bilateral, Utc::now()).unwrap();
+
+        assert_eq!(result.mode, NettingMode::Bilateral);
+        assert_eq!(result.obligations.len(), 2);
+        assert_eq!(result.instructions.len(), 1);
+        let instruction = &result.instructions[0];
+        assert_eq!(instruction.buyer_id, "bob");
+        assert_eq!(instruction.seller_id, "alice");
+        assert_eq!(instruction.delivery_quantity, Decimal::new(4, 0));
+        assert_eq!(instruction.payment_amount, Decimal::new(400, 0));
+
+        let dvp = &result.dvp_instructions[0];
+        assert_eq!(dvp.delivery_leg.from_account, "alice");
+        assert_eq!(dvp.payment_leg.from_account, "bob");
+
+        assert_eq!(result.report.gross_transfer_count, 4);
+        assert_eq!(result.report.net_transfer_count, 2);
+        assert_eq!(result.report.transfer_reduction_percentage(), Decimal::new(50, 0));
+        assert_eq!(result.report.volume_reduction_percentage("RWA"), Some(Decimal::new(75, 0)));
+    }
+
+    #[test]
+    fn test_multilateral_netting_cancels_circular_trades() {
+        let trades = vec![
+            trade("bob", "alice", 10, 100),
+            trade("carol", "bob", 10, 100),
+            trade("alice", "carol", 10, 100),
+        ];
+
+        let bilateral = engine().net_cycle(&trades, NettingMode::Bilateral, Utc::now()).unwrap();
+        assert_eq!(bilateral.report.net_transfer_count, 6);
+
+        let multilateral = engine().net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+        assert!(multilateral.obligations.is_empty());
+        assert!(multilateral.instructions.is_empty());
+        assert_eq!(multilateral.report.net_transfer_count, 0);
+        assert_eq!(multilateral.report.transfer_reduction_percentage(), Decimal::ONE_HUNDRED);
+    }
+
+    #[test]
+    fn test_multilateral_obligations_face_clearing_account() {
+        let trades = vec![
+            trade("bob", "alice", 10, 100),
+            trade("carol", "bob", 4, 110),
+        ];
+
+        let result = engine().net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+
+        for asset in ["RWA", "USD"] {
+            let total: Decimal = result.obligations.iter().filter(|o| o.asset == asset).map(|o| o.amount).sum();
+            assert_eq!(total, Decimal::ZERO);
+        }
+        assert!(result.obligations.iter().all(|o| o.counterparty == CCP));
+
+        let bob = |asset: &str| {
+            result
+                .obligations
+                .iter()
+                .find(|o| o.participant == "bob" && o.asset == asset)
+                .unwrap()
+                .amount
+        };
+        assert_eq!(bob("RWA"), Decimal::new(6, 0));
+        assert_eq!(bob("USD"), Decimal::new(-560, 0));
+    }
+
+    #[test]
+    fn test_multilateral_netting_spans_trading_pairs() {
+        // Alice's USD paid for RWA is covered by USD received for GOLD
+        let trades = vec![
+            trade_in("RWA", "alice", "bob", 10, 100),
+            trade_in("GOLD", "carol", "alice", 5, 200),
+        ];
+
+        let result = engine().net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+        let alice: Vec<&NetObligation> = result.obligations.iter().filter(|o| o.participant == "alice").collect();
+
+        assert_eq!(alice.len(), 2);
+        assert!(alice.iter().all(|o| o.asset != "USD"));
+        assert!(alice.iter().any(|o| o.asset == "RWA" && o.amount == Decimal::new(10, 0)));
+        assert!(alice.iter().any(|o| o.asset == "GOLD" && o.amount == Decimal::new(-5, 0)));
+
+        // Alice swaps GOLD for RWA with the clearing account in one exchange
+        let instruction = result
+            .instructions
+            .iter()
+            .find(|i| i.buyer_id == "alice" || i.seller_id == "alice")
+            .unwrap();
+        assert_eq!(instruction.asset_to_deliver, "RWA");
+        assert_eq!(instruction.asset_to_receive, "GOLD");
+        assert_eq!(instruction.buyer_id, "alice");
+        assert_eq!(result.report.net_volume.get("USD"), Some(&Decimal::new(2000, 0)));
+    }
+
+    #[test]
+    fn test_fees_settle_to_clearing_account() {
+        let mut first = trade("bob", "alice", 10, 100);
+        first.buyer_fee = Decimal::new(2, 0);
+        first.seller_fee = Decimal::new(1, 0);
+        let trades = vec![first, trade("alice", "bob", 6, 100)];
+
+        let multilateral = engine().net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+        let usd = |user: &str| {
+            multilateral
+                .obligations
+                .iter()
+                .find(|o| o.participant == user && o.asset == "USD")
+                .unwrap()
+                .amount
+        };
+        assert_eq!(usd("bob"), Decimal::new(-402, 0));
+        assert_eq!(usd("alice"), Decimal::new(399, 0));
+
+        let bilateral = engine().net_cycle(&trades, NettingMode::Bilateral, Utc::now()).unwrap();
+        let fees: Vec<&NetObligation> = bilateral.obligations.iter().filter(|o| o.counterparty == CCP).collect();
+        assert_eq!(fees.len(), 2);
+        assert!(fees.iter().any(|o| o.participant == "bob" && o.amount == Decimal::new(-2, 0)));
+        assert_eq!(bilateral.report.gross_transfer_count, 6);
+        assert_eq!(bilateral.report.net_transfer_count, 4);
+
+        let bob_usd = multilateral
+            .positions
+            .iter()
+            .find(|p| p.user_id == "bob" && p.asset == "USD")
+            .unwrap();
+        assert_eq!(bob_usd.settlement_amount, Decimal::new(402, 0));
+    }
+
+    #[test]
+    fn test_mixed_direction_obligation_splits_legs() {
+        // Bob buys 20 at 10 and sells 10 at 30: he receives both base and quote
+        let trades = vec![trade("bob", "alice", 20, 10), trade("carol", "bob", 10, 30)];
+
+        let result = engine().net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+        let bob: Vec<&SettlementInstruction> = result
+            .instructions
+            .iter()
+            .filter(|i| i.buyer_id == "bob" || i.seller_id == "bob")
+            .collect();
+
+        assert_eq!(bob.len(), 2);
+        assert!(bob.iter().all(|i| i.buyer_id == "bob" && i.payment_amount.is_zero()));
+        assert!(bob.iter().any(|i| i.asset_to_deliver == "RWA" && i.delivery_quantity == Decimal::new(10, 0)));
+        assert!(bob.iter().any(|i| i.asset_to_deliver == "USD" && i.delivery_quantity == Decimal::new(100, 0)));
+        assert!(result.dvp_instructions.iter().all(|d| d.delivery_leg.to_account != "bob"));
+    }
+
+    #[test]
+    fn test_gross_settlement_when_netting_disabled_or_below_threshold() {
+        let trades = vec![trade("bob", "alice", 10, 100), trade("alice", "bob", 6, 100)];
+
+        let disabled = NettingEngine::new(
+            SettlementConfig {
+                enable_netting: false,
+                ..SettlementConfig::default()
+            },
+            CCP.to_string(),
+        );
+        let result = disabled.net_cycle(&trades, NettingMode::Multilateral, Utc::now()).unwrap();
+        assert_eq!(result.mode, NettingMode::Gross);
+        assert_eq!(result.instructions.len(), 2);
+        assert_eq!(result.dvp_instructions.len(), 2);
+        assert_eq!(result.report.transfer_reduction_percentage(), Decimal::ZERO);
+
+        let small = vec![trade("bob", "alice", 1, 1)];
+        let result = engine().net_cycle(&small, NettingMode::Bilateral, Utc::now()).unwrap();
+        assert_eq!(result.mode, NettingMode::Gross);
+    }
+
+    #[test]
+    fn test_positions_track_gross_and_net() {
+        let trades = vec![trade("bob", "alice", 10, 100), trade("alice", "bob", 6, 100)];
+        let result = engine().net_cycle(&trades, NettingMode::Bilateral, Utc::now()).unwrap();
+
+        let bob_rwa = result
+            .positions
+            .iter()
+            .find(|p| p.user_id == "bob" && p.asset == "RWA")
+            .unwrap();
+        assert_eq!(bob_rwa.gross_buy, Decimal::new(10, 0));
+        assert_eq!(bob_rwa.gross_sell, Decimal::new(6, 0));
+        assert_eq!(bob_rwa.net_position, Decimal::new(4, 0));
+    }
+}
@@ -9,13 +9,17 @@ use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use rust_decimal::Decimal;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tokio::sync::broadcast;
 
 use crate::{
     error::{TradingError, TradingResult},
-    types::{OrderSide, OrderType, OrderStatus, TradingPair},
+    types::{OrderSide, OrderType, OrderStatus, SelfTradePreventionMode, TradingPair},
+    journal::{JournalEntry, OrderBookEvent},
 };
 
+pub use crate::types::TimeInForce;
+
 /// Order book configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderBookConfig {
@@ -35,6 +39,11 @@ pub struct OrderBookConfig {
     pub max_orders_per_level: usize,
     /// Order book depth for snapshots
     pub snapshot_depth: usize,
+    /// Journal every mutation for crash recovery.
+    ///
+    /// Entries accumulate until `OrderBookJournal::persist` stores them, so
+    /// only enable this for books that have a persister attached.
+    pub enable_journal: bool,
 }
 
 impl Default for OrderBookConfig {
@@ -48,6 +57,7 @@ impl Default for OrderBookConfig {
             enable_aggregation: true,
             max_orders_per_level: 100,
             snapshot_depth: 20,
+            enable_journal: false,
         }
     }
 }
@@ -69,6 +79,14 @@ pub struct Order {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    /// Trigger price for stop and stop-limit orders
+    pub stop_price: Option<Decimal>,
+    /// Peak size shown in the book for iceberg orders
+    pub iceberg_quantity: Option<Decimal>,
+    /// Quantity currently visible in the book
+    pub displayed_quantity: Decimal,
+    /// Self-trade prevention mode overriding the account and engine defaults
+    pub stp_mode: Option<SelfTradePreventionMode>,
 }
 
 impl Order {
@@ -97,9 +115,44 @@ impl Order {
             created_at: now,
             updated_at: now,
             expires_at: None,
+            stop_price: None,
+            iceberg_quantity: None,
+            displayed_quantity: quantity,
+            stp_mode: None,
         }
     }
 
+    /// Set the time in force
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+
+    /// Set the expiry time (used by GTD orders)
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
+
+    /// Set the stop trigger price
+    pub fn with_stop_price(mut self, stop_price: Decimal) -> Self {
+        self.stop_price = Some(stop_price);
+        self
+    }
+
+    /// Set the iceberg peak size; only this much is displayed at a time
+    pub fn with_iceberg_quantity(mut self, peak: Decimal) -> Self {
+        self.iceberg_quantity = Some(peak);
+        self.displayed_quantity = peak.min(self.remaining_quantity);
+        self
+    }
+
+    /// Set the self-trade prevention mode for this order
+    pub fn with_stp_mode(mut self, mode: SelfTradePreventionMode) -> Self {
+        self.stp_mode = Some(mode);
+        self
+    }
+
     /// Check if order is fully filled
     pub fn is_filled(&self) -> bool {
         self.filled_quantity >= self.quantity
@@ -110,10 +163,46 @@ impl Order {
         self.filled_quantity > Decimal::ZERO && self.filled_quantity < self.quantity
     }
 
+    /// Check if order has expired at the given time
+    pub fn is_expired_at(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.expires_at, Some(expires_at) if now >= expires_at)
+    }
+
+    /// Check if the stop trigger is satisfied by the given last trade price
+    pub fn is_stop_triggered(&self, last_price: Decimal) -> bool {
+        match (self.stop_price, self.side) {
+            (Some(stop), OrderSide::Buy) => last_price >= stop,
+            (Some(stop), OrderSide::Sell) => last_price <= stop,
+            (None, _) => true,
+        }
+    }
+
+    /// Check if an iceberg order has exhausted its visible slice but still has hidden quantity
+    pub fn needs_refresh(&self) -> bool {
+        self.iceberg_quantity.is_some()
+            && self.displayed_quantity <= Decimal::ZERO
+            && self.remaining_quantity > Decimal::ZERO
+    }
+
+    /// Reveal the next iceberg slice
+    pub fn refresh_display(&mut self) {
+        let peak = self.iceberg_quantity.unwrap_or(self.remaining_quantity);
+        self.displayed_quantity = peak.min(self.remaining_quantity);
+    }
+
+    /// Shrink the order without a fill, as done by decrement-and-cancel self-trade prevention
+    pub fn decrement(&mut self, quantity: Decimal) {
+        self.quantity -= quantity;
+        self.remaining_quantity = self.quantity - self.filled_quantity;
+        self.displayed_quantity = self.displayed_quantity.min(self.remaining_quantity);
+        self.updated_at = Utc::now();
+    }
+
     /// Update filled quantity
     pub fn update_filled(&mut self, filled_qty: Decimal) {
         self.filled_quantity += filled_qty;
         self.remaining_quantity = self.quantity - self.filled_quantity;
+        self.displayed_quantity = (self.displayed_quantity - filled_qty).max(Decimal::ZERO);
         self.updated_at = Utc::now();
         
         if self.is_filled() {
@@ -124,19 +213,6 @@ impl Order {
     }
 }
 
-/// Time in force enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
-pub enum TimeInForce {
-    /// Good Till Cancelled
-    GTC,
-    /// Immediate Or Cancel
-    IOC,
-    /// Fill Or Kill
-    FOK,
-    /// Good Till Time
-    GTT,
-}
-
 /// Price level in order book
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PriceLevel {
@@ -189,14 +265,35 @@ pub struct OrderBookSnapshot {
     pub timestamp: DateTime<Utc>,
 }
 
+/// Full book state, used for checkpoints and replay verification
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBookState {
+    pub trading_pair: TradingPair,
+    pub bids: Vec<PriceLevel>, // Ascending by price
+    pub asks: Vec<PriceLevel>, // Ascending by price
+    pub orders: Vec<Order>,    // Sorted by order ID
+    pub stop_orders: Vec<Order>,
+    pub last_trade_price: Option<Decimal>,
+    pub last_update_id: u64,
+    pub journal_sequence: u64,
+}
+
 /// Order book manager
+#[derive(Debug, Clone)]
 pub struct OrderBookManager {
     config: OrderBookConfig,
     trading_pair: TradingPair,
     bids: BTreeMap<Decimal, PriceLevel>, // Price -> PriceLevel (descending)
     asks: BTreeMap<Decimal, PriceLevel>, // Price -> PriceLevel (ascending)
     orders: HashMap<Uuid, Order>,
+    stop_orders: Vec<Order>, // Untriggered stop orders in arrival order
+    last_trade_price: Option<Decimal>,
     last_update_id: u64,
+    journal_sequence: u64,
+    pending_journal: Vec<JournalEntry>,
+    journal_feed: Option<broadcast::Sender<JournalEntry>>,
+    feed_batch_depth: u32,
+    unpublished_journal: Vec<JournalEntry>,
 }
 
 impl OrderBookManager {
@@ -208,7 +305,57 @@ impl OrderBookManager {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             orders: HashMap::new(),
+            stop_orders: Vec::new(),
+            last_trade_price: None,
             last_update_id: 0,
+            journal_sequence: 0,
+            pending_journal: Vec::new(),
+            journal_feed: None,
+            feed_batch_depth: 0,
+            unpublished_journal: Vec::new(),
+        }
+    }
+
+    /// Rebuild an order book from a checkpointed state
+    pub fn from_state(config: OrderBookConfig, state: OrderBookState) -> Self {
+        let level_map = |levels: Vec<PriceLevel>| {
+            levels
+                .into_iter()
+                .map(|level| (level.price, level))
+                .collect::<BTreeMap<Decimal, PriceLevel>>()
+        };
+
+        Self {
+            config,
+            trading_pair: state.trading_pair,
+            bids: level_map(state.bids),
+            asks: level_map(state.asks),
+            orders: state.orders.into_iter().map(|order| (order.id, order)).collect(),
+            stop_orders: state.stop_orders,
+            last_trade_price: state.last_trade_price,
+            last_update_id: state.last_update_id,
+            journal_sequence: state.journal_sequence,
+            pending_journal: Vec::new(),
+            journal_feed: None,
+            feed_batch_depth: 0,
+            unpublished_journal: Vec::new(),
+        }
+    }
+
+    /// Export the full book state
+    pub fn state(&self) -> OrderBookState {
+        let mut orders: Vec<Order> = self.orders.values().cloned().collect();
+        orders.sort_by_key(|order| order.id);
+
+        OrderBookState {
+            trading_pair: self.trading_pair.clone(),
+            bids: self.bids.values().cloned().collect(),
+            asks: self.asks.values().cloned().collect(),
+            orders,
+            stop_orders: self.stop_orders.clone(),
+            last_trade_price: self.last_trade_price,
+            last_update_id: self.last_update_id,
+            journal_sequence: self.journal_sequence,
         }
     }
 
@@ -218,70 +365,408 @@ impl OrderBookManager {
         self.validate_order(&order)?;
 
         // Update order status
-        order.status = OrderStatus::Open;
+        order.status = if order.is_partially_filled() {
+            OrderStatus::PartiallyFilled
+        } else {
+            OrderStatus::Open
+        };
         order.updated_at = Utc::now();
 
-        let order_id = order.id;
-        let price = order.price;
-        let quantity = order.remaining_quantity;
-        let side = order.side;
+        if order.iceberg_quantity.is_none() {
+            order.displayed_quantity = order.remaining_quantity;
+        } else if order.displayed_quantity <= Decimal::ZERO {
+            order.refresh_display();
+        }
+
+        self.record(OrderBookEvent::OrderAdded { order });
+        Ok(())
+    }
+
+    /// Remove order from order book
+    pub fn remove_order(&mut self, order_id: Uuid) -> TradingResult<Option<Order>> {
+        let removed = self
+            .stop_orders
+            .iter()
+            .find(|o| o.id == order_id)
+            .or_else(|| self.orders.get(&order_id))
+            .cloned();
+
+        if removed.is_some() {
+            self.record(OrderBookEvent::OrderRemoved { order_id });
+        }
+        Ok(removed)
+    }
+
+    /// Get order by ID
+    pub fn get_order(&self, order_id: Uuid) -> Option<&Order> {
+        self.orders.get(&order_id)
+    }
+
+    /// Apply a fill to a resting order.
+    ///
+    /// Filled orders leave the book. An iceberg whose visible slice is used up
+    /// reveals its next slice at the back of the queue, losing time priority.
+    pub fn fill_order(&mut self, order_id: Uuid, quantity: Decimal) -> TradingResult<Order> {
+        let mut order = self
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or_else(|| TradingError::order_not_found(order_id.to_string()))?;
+
+        if quantity > order.displayed_quantity {
+            return Err(TradingError::matching_error(format!(
+                "Fill quantity {} exceeds displayed quantity {} of order {}",
+                quantity, order.displayed_quantity, order_id
+            )));
+        }
+
+        order.update_filled(quantity);
+        let refreshed = order.needs_refresh();
+        if refreshed {
+            order.refresh_display();
+        }
+
+        self.record(OrderBookEvent::OrderFilled {
+            order: order.clone(),
+            quantity,
+            refreshed,
+        });
+        Ok(order)
+    }
 
-        // Add to orders map
-        self.orders.insert(order_id, order);
+    /// Get the highest-priority resting order on a side, skipping the given IDs.
+    ///
+    /// Priority is best price first, then arrival order within the level.
+    /// Levels are walked lazily, so only the orders ahead of the result are visited.
+    pub fn next_resting_order(&self, side: OrderSide, skip: &HashSet<Uuid>) -> Option<&Order> {
+        self.levels(side)
+            .flat_map(|level| level.orders.iter())
+            .filter(|id| !skip.contains(id))
+            .find_map(|id| self.orders.get(id))
+    }
 
-        // Add to appropriate side of order book
+    /// Get all resting orders on a side in priority order
+    pub fn resting_orders(&self, side: OrderSide) -> Vec<&Order> {
+        self.levels(side)
+            .flat_map(|level| level.orders.iter())
+            .filter_map(|id| self.orders.get(id))
+            .collect()
+    }
+
+    /// Price levels on a side, best price first
+    pub fn levels(&self, side: OrderSide) -> Box<dyn Iterator<Item = &PriceLevel> + '_> {
         match side {
-            OrderSide::Buy => {
-                let price_level = self.bids.entry(price).or_insert_with(|| PriceLevel::new(price));
-                price_level.add_order(order_id, quantity);
+            OrderSide::Buy => Box::new(self.bids.values().rev()),
+            OrderSide::Sell => Box::new(self.asks.values()),
+        }
+    }
+
+    /// Remaining quantity of a user's open orders on a side, including untriggered stops
+    pub fn open_quantity(&self, user_id: &str, side: OrderSide) -> Decimal {
+        self.orders
+            .values()
+            .chain(self.stop_orders.iter())
+            .filter(|order| order.user_id == user_id && order.side == side)
+            .map(|order| order.remaining_quantity)
+            .sum()
+    }
+
+    /// Park an untriggered stop order until the last trade price reaches it
+    pub fn add_stop_order(&mut self, mut order: Order) -> TradingResult<()> {
+        if order.stop_price.is_none_or(|stop| stop <= Decimal::ZERO) {
+            return Err(TradingError::validation_error(
+                "stop_price",
+                "Stop orders require a positive stop price",
+            ));
+        }
+
+        order.status = OrderStatus::Pending;
+        order.updated_at = Utc::now();
+        self.record(OrderBookEvent::StopOrderAdded { order });
+        Ok(())
+    }
+
+    /// Get untriggered stop orders
+    pub fn stop_orders(&self) -> &[Order] {
+        &self.stop_orders
+    }
+
+    /// Remove and return stop orders triggered by the given last trade price
+    pub fn take_triggered_stops(&mut self, last_price: Decimal) -> Vec<Order> {
+        let triggered: Vec<Order> = self
+            .stop_orders
+            .iter()
+            .filter(|order| order.is_stop_triggered(last_price))
+            .cloned()
+            .collect();
+
+        if !triggered.is_empty() {
+            let order_ids = triggered.iter().map(|order| order.id).collect();
+            self.record(OrderBookEvent::StopOrdersTriggered { order_ids });
+        }
+        triggered
+    }
+
+    /// Shrink a resting order without a fill, cancelling it once nothing remains
+    pub fn decrement_order(&mut self, order_id: Uuid, quantity: Decimal) -> TradingResult<Order> {
+        let mut order = self
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or_else(|| TradingError::order_not_found(order_id.to_string()))?;
+
+        if quantity > order.remaining_quantity {
+            return Err(TradingError::matching_error(format!(
+                "Decrement quantity {} exceeds remaining quantity {} of order {}",
+                quantity, order.remaining_quantity, order_id
+            )));
+        }
+
+        let displayed_before = order.displayed_quantity;
+        order.decrement(quantity);
+
+        if order.remaining_quantity <= Decimal::ZERO {
+            self.remove_order(order_id)?;
+            order.status = OrderStatus::Cancelled;
+        } else {
+            self.record(OrderBookEvent::OrderDecremented {
+                order: order.clone(),
+                displayed_reduction: displayed_before - order.displayed_quantity,
+            });
+        }
+        Ok(order)
+    }
+
+    /// Record the price of the last executed trade
+    pub fn record_trade_price(&mut self, price: Decimal) {
+        self.record(OrderBookEvent::TradePriceRecorded { price });
+    }
+
+    /// Sequence number of the last journaled mutation
+    pub fn journal_sequence(&self) -> u64 {
+        self.journal_sequence
+    }
+
+    /// Take journal entries that have not been persisted yet
+    pub fn drain_journal(&mut self) -> Vec<JournalEntry> {
+        std::mem::take(&mut self.pending_journal)
+    }
+
+    /// Journal entries that have not been persisted yet
+    pub fn pending_journal(&self) -> &[JournalEntry] {
+        &self.pending_journal
+    }
+
+    /// Discard pending entries up to and including a persisted sequence
+    pub fn acknowledge_journal(&mut self, sequence: u64) {
+        self.pending_journal.retain(|entry| entry.sequence > sequence);
+    }
+
+    /// Receive every journal entry recorded from now on.
+    ///
+    /// Live readers such as market data feeds subscribe here instead of
+    /// draining the journal, which is reserved for the persister.
+    pub fn subscribe_journal(&mut self, capacity: usize) -> broadcast::Receiver<JournalEntry> {
+        match &self.journal_feed {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(capacity);
+                self.journal_feed = Some(sender);
+                receiver
             }
-            OrderSide::Sell => {
-                let price_level = self.asks.entry(price).or_insert_with(|| PriceLevel::new(price));
-                price_level.add_order(order_id, quantity);
+        }
+    }
+
+    /// Hold journal broadcasts until the matching `commit_feed_batch`.
+    ///
+    /// Callers that may roll the book back to a clone open a batch first, so
+    /// subscribers never see mutations that are later undone. Batches nest.
+    pub fn begin_feed_batch(&mut self) {
+        self.feed_batch_depth += 1;
+    }
+
+    /// Close a batch, broadcasting held entries once the outermost batch commits
+    pub fn commit_feed_batch(&mut self) {
+        self.feed_batch_depth = self.feed_batch_depth.saturating_sub(1);
+        if self.feed_batch_depth == 0 {
+            self.flush_journal_feed();
+        }
+    }
+
+    fn flush_journal_feed(&mut self) {
+        let entries = std::mem::take(&mut self.unpublished_journal);
+        if let Some(sender) = &self.journal_feed {
+            for entry in entries {
+                // No live subscribers is not an error
+                let _ = sender.send(entry);
             }
         }
+    }
 
-        self.last_update_id += 1;
+    /// Replay journal entries on top of the current state.
+    ///
+    /// Entries must continue the book's sequence without gaps; entries at or
+    /// below the current sequence are already reflected and are skipped.
+    pub fn replay(&mut self, entries: &[JournalEntry]) -> TradingResult<()> {
+        for entry in entries {
+            if entry.sequence <= self.journal_sequence {
+                continue;
+            }
+            if entry.sequence != self.journal_sequence + 1 {
+                return Err(TradingError::order_book_error(
+                    self.trading_pair.symbol(),
+                    format!(
+                        "Journal gap: expected sequence {}, got {}",
+                        self.journal_sequence + 1,
+                        entry.sequence
+                    ),
+                ));
+            }
+            self.apply(&entry.event);
+            self.journal_sequence = entry.sequence;
+        }
         Ok(())
     }
 
-    /// Remove order from order book
-    pub fn remove_order(&mut self, order_id: Uuid) -> TradingResult<Option<Order>> {
-        if let Some(order) = self.orders.remove(&order_id) {
-            let price = order.price;
-            let quantity = order.remaining_quantity;
-            let side = order.side;
-
-            // Remove from appropriate side
-            match side {
-                OrderSide::Buy => {
-                    if let Some(price_level) = self.bids.get_mut(&price) {
-                        price_level.remove_order(order_id, quantity);
-                        if price_level.is_empty() {
-                            self.bids.remove(&price);
+    /// Apply a mutation and journal it
+    fn record(&mut self, event: OrderBookEvent) {
+        self.apply(&event);
+        self.journal_sequence += 1;
+        if !self.config.enable_journal && self.journal_feed.is_none() {
+            return;
+        }
+
+        let entry = JournalEntry::new(&self.trading_pair, self.journal_sequence, event);
+        if self.journal_feed.is_some() {
+            self.unpublished_journal.push(entry.clone());
+            if self.feed_batch_depth == 0 {
+                self.flush_journal_feed();
+            }
+        }
+        if self.config.enable_journal {
+            self.pending_journal.push(entry);
+        }
+    }
+
+    /// Apply a mutation to the in-memory state.
+    ///
+    /// Must stay deterministic: everything time- or ID-dependent is carried
+    /// in the event so replay reproduces the live book exactly.
+    fn apply(&mut self, event: &OrderBookEvent) {
+        match event {
+            OrderBookEvent::OrderAdded { order } => {
+                let levels = match order.side {
+                    OrderSide::Buy => &mut self.bids,
+                    OrderSide::Sell => &mut self.asks,
+                };
+                levels
+                    .entry(order.price)
+                    .or_insert_with(|| PriceLevel::new(order.price))
+                    .add_order(order.id, order.displayed_quantity);
+                self.orders.insert(order.id, order.clone());
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::OrderRemoved { order_id } => {
+                if let Some(pos) = self.stop_orders.iter().position(|o| o.id == *order_id) {
+                    self.stop_orders.remove(pos);
+                } else if let Some(order) = self.orders.remove(order_id) {
+                    let levels = match order.side {
+                        OrderSide::Buy => &mut self.bids,
+                        OrderSide::Sell => &mut self.asks,
+                    };
+                    if let Some(level) = levels.get_mut(&order.price) {
+                        level.remove_order(*order_id, order.displayed_quantity);
+                        if level.is_empty() {
+                            levels.remove(&order.price);
                         }
                     }
                 }
-                OrderSide::Sell => {
-                    if let Some(price_level) = self.asks.get_mut(&price) {
-                        price_level.remove_order(order_id, quantity);
-                        if price_level.is_empty() {
-                            self.asks.remove(&price);
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::OrderFilled { order, quantity, refreshed } => {
+                let levels = match order.side {
+                    OrderSide::Buy => &mut self.bids,
+                    OrderSide::Sell => &mut self.asks,
+                };
+                if order.is_filled() {
+                    self.orders.remove(&order.id);
+                    if let Some(level) = levels.get_mut(&order.price) {
+                        level.remove_order(order.id, *quantity);
+                        if level.is_empty() {
+                            levels.remove(&order.price);
+                        }
+                    }
+                } else {
+                    if let Some(level) = levels.get_mut(&order.price) {
+                        level.quantity -= *quantity;
+                        if *refreshed {
+                            level.remove_order(order.id, Decimal::ZERO);
+                            level.add_order(order.id, order.displayed_quantity);
                         }
                     }
+                    self.orders.insert(order.id, order.clone());
                 }
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::OrderDecremented { order, displayed_reduction } => {
+                let levels = match order.side {
+                    OrderSide::Buy => &mut self.bids,
+                    OrderSide::Sell => &mut self.asks,
+                };
+                if let Some(level) = levels.get_mut(&order.price) {
+                    level.quantity -= *displayed_reduction;
+                }
+                self.orders.insert(order.id, order.clone());
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::StopOrderAdded { order } => {
+                self.stop_orders.push(order.clone());
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::StopOrdersTriggered { order_ids } => {
+                self.stop_orders.retain(|order| !order_ids.contains(&order.id));
+                self.last_update_id += 1;
+            }
+            OrderBookEvent::TradePriceRecorded { price } => {
+                self.last_trade_price = Some(*price);
             }
+        }
+    }
 
-            self.last_update_id += 1;
-            Ok(Some(order))
-        } else {
-            Ok(None)
+    /// Get the price of the last executed trade
+    pub fn last_trade_price(&self) -> Option<Decimal> {
+        self.last_trade_price
+    }
+
+    /// Remove all resting and stop orders whose expiry has passed
+    pub fn expire_orders(&mut self, now: DateTime<Utc>) -> TradingResult<Vec<Order>> {
+        let expired_ids: Vec<Uuid> = self
+            .orders
+            .values()
+            .chain(self.stop_orders.iter())
+            .filter(|order| order.is_expired_at(now))
+            .map(|order| order.id)
+            .collect();
+
+        let mut expired = Vec::with_capacity(expired_ids.len());
+        for order_id in expired_ids {
+            if let Some(mut order) = self.remove_order(order_id)? {
+                order.status = OrderStatus::Expired;
+                order.updated_at = now;
+                expired.push(order);
+            }
         }
+
+        Ok(expired)
     }
 
-    /// Get order by ID
-    pub fn get_order(&self, order_id: Uuid) -> Option<&Order> {
-        self.orders.get(&order_id)
+    /// Get the aggregated visible quantity at a price level
+    pub fn level_quantity(&self, side: OrderSide, price: Decimal) -> Decimal {
+        let levels = match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        };
+        levels.get(&price).map_or(Decimal::ZERO, |level| level.quantity)
     }
 
     /// Get best bid price
@@ -334,8 +819,8 @@ impl OrderBookManager {
         }
     }
 
-    /// Validate order
-    fn validate_order(&self, order: &Order) -> TradingResult<()> {
+    /// Validate an order against the book configuration
+    pub fn validate_order(&self, order: &Order) -> TradingResult<()> {
         if order.quantity < self.config.min_order_size {
             return Err(TradingError::validation_error(
                 "quantity",
@@ -357,6 +842,22 @@ impl OrderBookManager {
             ));
         }
 
+        if let Some(peak) = order.iceberg_quantity {
+            if peak <= Decimal::ZERO || peak > order.quantity {
+                return Err(TradingError::validation_error(
+                    "iceberg_quantity".to_string(),
+                    format!("Iceberg peak {} must be positive and not exceed quantity {}", peak, order.quantity),
+                ));
+            }
+        }
+
+        if order.time_in_force == TimeInForce::GTD && order.expires_at.is_none() {
+            return Err(TradingError::validation_error(
+                "expires_at",
+                "GTD orders require an expiry time",
+            ));
+        }
+
         Ok(())
     }
 }
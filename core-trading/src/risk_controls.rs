@@ -0,0 +1,554 @@
+// =====================================================================================
+// File: core-trading/src/risk_controls.rs
+// Description: Pre-trade risk controls gateway for RWA trading system
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::{OrderSide, OrderType},
+    order_book::{Order, OrderBookManager},
+    matching::{MatchResult, MatchingEngine, Trade},
+};
+
+/// Risk controls configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RiskControlsConfig {
+    /// Enable pre-trade risk checks
+    pub enabled: bool,
+    /// Maximum notional value of a single order (in quote asset)
+    pub max_order_notional: Decimal,
+    /// Default maximum absolute position per trading pair (in base asset)
+    pub max_position: Decimal,
+    /// Per-pair position limits overriding `max_position`, keyed by symbol
+    pub position_limits: HashMap<String, Decimal>,
+    /// Maximum deviation of a limit price from the mid price in basis points
+    pub price_band_bps: u32,
+    /// Maximum quantity of a single order (fat-finger limit)
+    pub max_order_quantity: Decimal,
+    /// Maximum orders per user within the throttle window
+    pub max_orders_per_window: u32,
+    /// Order throttle window in seconds
+    pub throttle_window_seconds: u64,
+}
+
+impl Default for RiskControlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_order_notional: Decimal::new(100000000, 2), // $1,000,000.00
+            max_position: Decimal::new(10000, 0),
+            position_limits: HashMap::new(),
+            price_band_bps: 1000, // 10%
+            max_order_quantity: Decimal::new(1000, 0),
+            max_orders_per_window: 10,
+            throttle_window_seconds: 1,
+        }
+    }
+}
+
+impl RiskControlsConfig {
+    /// Position limit for a trading pair symbol
+    pub fn position_limit(&self, symbol: &str) -> Decimal {
+        self.position_limits.get(symbol).copied().unwrap_or(self.max_position)
+    }
+}
+
+/// Market and account state a risk check is evaluated against
+#[derive(Debug, Clone)]
+pub struct RiskContext {
+    /// Current mid price of the order's book
+    pub mid_price: Option<Decimal>,
+    /// User's current net position in the order's trading pair
+    pub position: Decimal,
+    /// Remaining quantity of the user's open buy orders in the book
+    pub open_buy_quantity: Decimal,
+    /// Remaining quantity of the user's open sell orders in the book
+    pub open_sell_quantity: Decimal,
+    /// Evaluation time
+    pub now: DateTime<Utc>,
+}
+
+impl RiskContext {
+    /// Price used to value an order; market orders are valued at the mid price
+    pub fn reference_price(&self, order: &Order) -> Option<Decimal> {
+        match order.order_type {
+            OrderType::Market | OrderType::Stop => self.mid_price,
+            _ => Some(order.price),
+        }
+    }
+}
+
+/// Pluggable stateless pre-trade risk check
+pub trait RiskCheck: Send + Sync {
+    /// Name of the check for logging
+    fn name(&self) -> &'static str;
+
+    /// Accept or reject an order
+    fn check(&self, order: &Order, context: &RiskContext, config: &RiskControlsConfig) -> TradingResult<()>;
+}
+
+/// Rejects orders whose notional value exceeds the configured limit
+pub struct NotionalLimitCheck;
+
+impl RiskCheck for NotionalLimitCheck {
+    fn name(&self) -> &'static str {
+        "max_order_notional"
+    }
+
+    fn check(&self, order: &Order, context: &RiskContext, config: &RiskControlsConfig) -> TradingResult<()> {
+        // Without a mid price a market order cannot be valued, so it is rejected
+        let Some(price) = context.reference_price(order) else {
+            return Err(TradingError::insufficient_liquidity(
+                order.trading_pair.symbol(),
+                "No mid price to value the order against its notional limit".to_string(),
+            ));
+        };
+        let notional = price * order.remaining_quantity;
+        if notional > config.max_order_notional {
+            return Err(TradingError::notional_limit_exceeded(
+                notional.to_string(),
+                config.max_order_notional.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects orders that would grow a position past its per-pair limit.
+///
+/// Open orders on the same side count as if they had filled, so the limit
+/// cannot be bypassed by resting several orders before any of them trade.
+pub struct PositionLimitCheck;
+
+impl RiskCheck for PositionLimitCheck {
+    fn name(&self) -> &'static str {
+        "max_position"
+    }
+
+    fn check(&self, order: &Order, context: &RiskContext, config: &RiskControlsConfig) -> TradingResult<()> {
+        let symbol = order.trading_pair.symbol();
+        let limit = config.position_limit(&symbol);
+        let current = match order.side {
+            OrderSide::Buy => context.position + context.open_buy_quantity,
+            OrderSide::Sell => context.position - context.open_sell_quantity,
+        };
+        let projected = match order.side {
+            OrderSide::Buy => current + order.remaining_quantity,
+            OrderSide::Sell => current - order.remaining_quantity,
+        };
+
+        // Orders that reduce exposure are always allowed
+        if projected.abs() > limit && projected.abs() > current.abs() {
+            return Err(TradingError::position_limit_exceeded(
+                symbol,
+                projected.to_string(),
+                limit.to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rejects limit prices too far away from the mid price
+pub struct PriceBandCheck;
+
+impl RiskCheck for PriceBandCheck {
+    fn name(&self) -> &'static str {
+        "price_band"
+    }
+
+    fn check(&self, order: &Order, context: &RiskContext, config: &RiskControlsConfig) -> TradingResult<()> {
+        if matches!(order.order_type, OrderType::Market | OrderType::Stop) {
+            return Ok(());
+        }
+        let Some(mid) = context.mid_price else {
+            return Ok(());
+        };
+
+        let band = mid * Decimal::from(config.price_band_bps) / Decimal::new(10000, 0);
+        let (min_price, max_price) = (mid - band, mid + band);
+        if order.price < min_price || order.price > max_price {
+            return Err(TradingError::PriceOutOfRange {
+                price: order.price.to_string(),
+                min_price: min_price.to_string(),
+                max_price: max_price.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Rejects orders with an implausibly large quantity
+pub struct FatFingerCheck;
+
+impl RiskCheck for FatFingerCheck {
+    fn name(&self) -> &'static str {
+        "fat_finger"
+    }
+
+    fn check(&self, order: &Order, _context: &RiskContext, config: &RiskControlsConfig) -> TradingResult<()> {
+        if order.quantity > config.max_order_quantity {
+            return Err(TradingError::OrderSizeTooLarge {
+                size: order.quantity.to_string(),
+                max_size: config.max_order_quantity.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Pre-trade risk gateway in front of the matching engine.
+///
+/// Kill switches and order-rate throttles are evaluated first, followed by the
+/// registered `RiskCheck`s in order. Positions are updated from executed trades.
+pub struct PreTradeRiskGateway {
+    config: RiskControlsConfig,
+    checks: Vec<Box<dyn RiskCheck>>,
+    positions: HashMap<(String, String), Decimal>,
+    order_history: HashMap<String, VecDeque<DateTime<Utc>>>,
+    killed_accounts: HashSet<String>,
+    killed_pairs: HashSet<String>,
+}
+
+impl PreTradeRiskGateway {
+    /// Create a gateway with the built-in checks registered
+    pub fn new(config: RiskControlsConfig) -> Self {
+        Self {
+            config,
+            checks: vec![
+                Box::new(FatFingerCheck),
+                Box::new(PriceBandCheck),
+                Box::new(NotionalLimitCheck),
+                Box::new(PositionLimitCheck),
+            ],
+            positions: HashMap::new(),
+            order_history: HashMap::new(),
+            killed_accounts: HashSet::new(),
+            killed_pairs: HashSet::new(),
+        }
+    }
+
+    /// Register an additional risk check
+    pub fn with_check(mut self, check: Box<dyn RiskCheck>) -> Self {
+        self.checks.push(check);
+        self
+    }
+
+    /// Get the configuration
+    pub fn config(&self) -> &RiskControlsConfig {
+        &self.config
+    }
+
+    /// Names of the registered checks in evaluation order
+    pub fn check_names(&self) -> Vec<&'static str> {
+        self.checks.iter().map(|check| check.name()).collect()
+    }
+
+    /// Block all new orders from an account
+    pub fn kill_account(&mut self, user_id: &str) {
+        self.killed_accounts.insert(user_id.to_string());
+    }
+
+    /// Lift an account kill switch
+    pub fn restore_account(&mut self, user_id: &str) {
+        self.killed_accounts.remove(user_id);
+    }
+
+    /// Block all new orders for a trading pair symbol
+    pub fn kill_pair(&mut self, symbol: &str) {
+        self.killed_pairs.insert(symbol.to_string());
+    }
+
+    /// Lift a trading pair kill switch
+    pub fn restore_pair(&mut self, symbol: &str) {
+        self.killed_pairs.remove(symbol);
+    }
+
+    /// Current net position of a user in a trading pair symbol
+    pub fn position(&self, user_id: &str, symbol: &str) -> Decimal {
+        self.positions
+            .get(&(user_id.to_string(), symbol.to_string()))
+            .copied()
+            .unwrap_or(Decimal::ZERO)
+    }
+
+    /// Run all pre-trade checks for an order at the current time
+    pub fn check_order(&mut self, order: &Order, order_book: &OrderBookManager) -> TradingResult<()> {
+        self.check_order_at(order, order_book, Utc::now())
+    }
+
+    /// Run all pre-trade checks for an order at a given time.
+    ///
+    /// An order that passes counts towards the user's order-rate throttle.
+    pub fn check_order_at(
+        &mut self,
+        order: &Order,
+        order_book: &OrderBookManager,
+        now: DateTime<Utc>,
+    ) -> TradingResult<()> {
+        self.evaluate(order, order_book, now)?;
+        self.record_order(&order.user_id, now);
+        Ok(())
+    }
+
+    /// Check an order and, if accepted, submit it to the matching engine.
+    ///
+    /// Only orders the matching engine accepts count towards the throttle.
+    pub fn submit_order(
+        &mut self,
+        engine: &MatchingEngine,
+        order: Order,
+        order_book: &mut OrderBookManager,
+    ) -> TradingResult<MatchResult> {
+        let now = Utc::now();
+        let user_id = order.user_id.clone();
+        self.evaluate(&order, order_book, now)?;
+        let result = engine.submit_order(order, order_book)?;
+        self.record_order(&user_id, now);
+        for trade in &result.trades {
+            self.record_trade(trade);
+        }
+        Ok(result)
+    }
+
+    /// Update positions from an executed trade
+    pub fn record_trade(&mut self, trade: &Trade) {
+        let symbol = trade.trading_pair.symbol();
+        *self
+            .positions
+            .entry((trade.buyer_user_id.clone(), symbol.clone()))
+            .or_insert(Decimal::ZERO) += trade.quantity;
+        *self
+            .positions
+            .entry((trade.seller_user_id.clone(), symbol))
+            .or_insert(Decimal::ZERO) -= trade.quantity;
+    }
+
+    /// Kill switches, throttle and registered checks, without recording the order
+    fn evaluate(&mut self, order: &Order, order_book: &OrderBookManager, now: DateTime<Utc>) -> TradingResult<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let symbol = order.trading_pair.symbol();
+        if self.killed_accounts.contains(&order.user_id) {
+            return Err(TradingError::kill_switch_active("account".to_string(), order.user_id.clone()));
+        }
+        if self.killed_pairs.contains(&symbol) {
+            return Err(TradingError::kill_switch_active("trading_pair".to_string(), symbol));
+        }
+
+        self.throttle(&order.user_id, now)?;
+
+        let context = RiskContext {
+            mid_price: order_book.mid_price(),
+            position: self.position(&order.user_id, &symbol),
+            open_buy_quantity: order_book.open_quantity(&order.user_id, OrderSide::Buy),
+            open_sell_quantity: order_book.open_quantity(&order.user_id, OrderSide::Sell),
+            now,
+        };
+        for check in &self.checks {
+            check.check(order, &context, &self.config)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sliding-window order-rate throttle per user
+    fn throttle(&mut self, user_id: &str, now: DateTime<Utc>) -> TradingResult<()> {
+        let window_start = now - Duration::seconds(self.config.throttle_window_seconds as i64);
+        let history = self.order_history.entry(user_id.to_string()).or_default();
+        while history.front().is_some_and(|time| *time <= window_start) {
+            history.pop_front();
+        }
+
+        if history.len() >= self.config.max_orders_per_window as usize {
+            return Err(TradingError::rate_limit_exceeded(
+                "orders_per_window".to_string(),
+                format!(
+                    "User {} exceeded {} orders per {}s",
+                    user_id, self.config.max_orders_per_window, self.config.throttle_window_seconds
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Count an accepted order towards the user's throttle window
+    fn record_order(&mut self, user_id: &str, now: DateTime<Utc>) {
+        if self.config.enabled {
+            self.order_history.entry(user_id.to_string()).or_default().push_back(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{matching::MatchingConfig, order_book::OrderBookConfig, types::TradingPair};
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    fn limit(user: &str, side: OrderSide, price: i64, quantity: i64) -> Order {
+        Order::new(
+            user.to_string(),
+            pair(),
+            side,
+            OrderType::Limit,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        )
+    }
+
+    fn quoted_book() -> OrderBookManager {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        book.add_order(limit("mm", OrderSide::Buy, 99, 500)).unwrap();
+        book.add_order(limit("mm", OrderSide::Sell, 101, 500)).unwrap();
+        book
+    }
+
+    #[test]
+    fn test_accepts_order_within_limits() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig::default());
+        let book = quoted_book();
+        assert!(gateway.check_order(&limit("alice", OrderSide::Buy, 100, 10), &book).is_ok());
+    }
+
+    #[test]
+    fn test_fat_finger_and_notional_limits() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig {
+            max_order_notional: Decimal::new(50000, 0),
+            ..RiskControlsConfig::default()
+        });
+        let book = quoted_book();
+
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 100, 5000), &book).unwrap_err();
+        assert!(matches!(err, TradingError::OrderSizeTooLarge { .. }));
+
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 100, 600), &book).unwrap_err();
+        assert!(matches!(err, TradingError::NotionalLimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_price_band_around_mid() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig::default());
+        let book = quoted_book();
+
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 120, 1), &book).unwrap_err();
+        assert!(matches!(err, TradingError::PriceOutOfRange { .. }));
+        assert!(gateway.check_order(&limit("alice", OrderSide::Sell, 91, 1), &book).is_ok());
+    }
+
+    #[test]
+    fn test_position_limit_allows_reducing_orders() {
+        let mut config = RiskControlsConfig::default();
+        config.position_limits.insert(pair().symbol(), Decimal::new(100, 0));
+        let mut gateway = PreTradeRiskGateway::new(config);
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = quoted_book();
+
+        gateway.submit_order(&engine, limit("alice", OrderSide::Buy, 101, 80), &mut book).unwrap();
+        assert_eq!(gateway.position("alice", &pair().symbol()), Decimal::new(80, 0));
+        assert_eq!(gateway.position("mm", &pair().symbol()), Decimal::new(-80, 0));
+
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 100, 30), &book).unwrap_err();
+        assert!(matches!(err, TradingError::PositionLimitExceeded { .. }));
+        assert!(gateway.check_order(&limit("alice", OrderSide::Sell, 100, 30), &book).is_ok());
+    }
+
+    #[test]
+    fn test_position_limit_counts_open_orders() {
+        let mut config = RiskControlsConfig::default();
+        config.position_limits.insert(pair().symbol(), Decimal::new(100, 0));
+        let mut gateway = PreTradeRiskGateway::new(config);
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let mut book = quoted_book();
+
+        gateway.submit_order(&engine, limit("alice", OrderSide::Buy, 98, 80), &mut book).unwrap();
+        assert_eq!(gateway.position("alice", &pair().symbol()), Decimal::ZERO);
+
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 97, 30), &book).unwrap_err();
+        assert!(matches!(err, TradingError::PositionLimitExceeded { .. }));
+        assert!(gateway.check_order(&limit("alice", OrderSide::Buy, 97, 20), &book).is_ok());
+    }
+
+    #[test]
+    fn test_market_order_without_mid_price_is_rejected() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig::default());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        book.add_order(limit("mm", OrderSide::Sell, 101, 500)).unwrap();
+        let order = Order::new(
+            "alice".to_string(),
+            pair(),
+            OrderSide::Buy,
+            OrderType::Market,
+            Decimal::ZERO,
+            Decimal::new(10, 0),
+        );
+
+        let err = gateway.check_order(&order, &book).unwrap_err();
+        assert!(matches!(err, TradingError::InsufficientLiquidity { .. }));
+    }
+
+    #[test]
+    fn test_rejected_orders_do_not_consume_throttle() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig {
+            max_orders_per_window: 1,
+            throttle_window_seconds: 60,
+            ..RiskControlsConfig::default()
+        });
+        let book = quoted_book();
+        let now = Utc::now();
+
+        assert!(gateway.check_order_at(&limit("alice", OrderSide::Buy, 120, 1), &book, now).is_err());
+        assert!(gateway.check_order_at(&limit("alice", OrderSide::Buy, 100, 1), &book, now).is_ok());
+        let err = gateway.check_order_at(&limit("alice", OrderSide::Buy, 100, 1), &book, now).unwrap_err();
+        assert!(matches!(err, TradingError::RateLimitExceeded { .. }));
+    }
+
+    #[test]
+    fn test_order_rate_throttle() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig {
+            max_orders_per_window: 2,
+            throttle_window_seconds: 1,
+            ..RiskControlsConfig::default()
+        });
+        let book = quoted_book();
+        let now = Utc::now();
+        let order = limit("alice", OrderSide::Buy, 100, 1);
+
+        assert!(gateway.check_order_at(&order, &book, now).is_ok());
+        assert!(gateway.check_order_at(&order, &book, now).is_ok());
+        let err = gateway.check_order_at(&order, &book, now).unwrap_err();
+        assert!(matches!(err, TradingError::RateLimitExceeded { .. }));
+
+        assert!(gateway.check_order_at(&limit("bob", OrderSide::Buy, 100, 1), &book, now).is_ok());
+        assert!(gateway.check_order_at(&order, &book, now + Duration::seconds(2)).is_ok());
+    }
+
+    #[test]
+    fn test_kill_switches() {
+        let mut gateway = PreTradeRiskGateway::new(RiskControlsConfig::default());
+        let book = quoted_book();
+
+        gateway.kill_account("alice");
+        let err = gateway.check_order(&limit("alice", OrderSide::Buy, 100, 1), &book).unwrap_err();
+        assert!(matches!(err, TradingError::KillSwitchActive { .. }));
+        assert!(gateway.check_order(&limit("bob", OrderSide::Buy, 100, 1), &book).is_ok());
+        gateway.restore_account("alice");
+
+        gateway.kill_pair(&pair().symbol());
+        let err = gateway.check_order(&limit("bob", OrderSide::Buy, 100, 1), &book).unwrap_err();
+        assert_eq!(err.category(), "kill_switch");
+        gateway.restore_pair(&pair().symbol());
+        assert!(gateway.check_order(&limit("alice", OrderSide::Buy, 100, 1), &book).is_ok());
+    }
+}
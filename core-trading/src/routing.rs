@@ -0,0 +1,726 @@
+// =====================================================================================
+// File: core-trading/src/routing.rs
+// Description: Smart order routing across order book depth and AMM pools
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+use rust_decimal::Decimal;
+
+use crate::{
+    error::{TradingError, TradingResult},
+    types::{OrderSide, OrderType, TimeInForce, TradingPair},
+    order_book::{Order, OrderBookManager},
+    matching::{MatchResult, MatchingConfig, MatchingEngine, Trade},
+    liquidity::AMMPool,
+};
+
+/// Smart order router configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingConfig {
+    /// Number of slices an order is split into when allocating across venues
+    pub slice_count: u32,
+    /// Maximum adverse deviation from the planned cost tolerated at execution
+    pub max_slippage: Decimal,
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            slice_count: 100,
+            max_slippage: Decimal::new(50, 4), // 0.50%
+        }
+    }
+}
+
+/// Execution venue
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Venue {
+    OrderBook,
+    AmmPool(Uuid),
+}
+
+/// Portion of an order routed to a single venue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteLeg {
+    pub venue: Venue,
+    /// Base quantity executed on the venue
+    pub quantity: Decimal,
+    /// Quote paid (buy) or received (sell), fees included
+    pub quote_amount: Decimal,
+    pub fee: Decimal,
+    /// Fee-inclusive average price
+    pub average_price: Decimal,
+    /// Worst book price touched; the limit used when executing a book leg
+    pub limit_price: Option<Decimal>,
+    pub price_impact: Decimal,
+}
+
+/// Allocation of an order across venues
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionPlan {
+    pub order_id: Uuid,
+    pub trading_pair: TradingPair,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub legs: Vec<RouteLeg>,
+    /// Quote paid (buy) or received (sell) across all legs
+    pub total_quote_amount: Decimal,
+    pub total_fees: Decimal,
+    pub average_price: Decimal,
+    /// Best quote amount achievable on any single venue, if one can fill the order alone
+    pub best_single_venue_quote_amount: Option<Decimal>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ExecutionPlan {
+    /// Get the leg routed to a venue
+    pub fn leg(&self, venue: Venue) -> Option<&RouteLeg> {
+        self.legs.iter().find(|leg| leg.venue == venue)
+    }
+
+    /// Improvement over the best single venue, in quote asset
+    pub fn savings(&self) -> Option<Decimal> {
+        self.best_single_venue_quote_amount.map(|single| match self.side {
+            OrderSide::Buy => single - self.total_quote_amount,
+            OrderSide::Sell => self.total_quote_amount - single,
+        })
+    }
+}
+
+/// Fill of an AMM leg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmmFill {
+    pub pool_id: Uuid,
+    pub base_amount: Decimal,
+    pub quote_amount: Decimal,
+}
+
+/// Result of executing a plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteExecution {
+    pub plan: ExecutionPlan,
+    pub trades: Vec<Trade>,
+    pub amm_fills: Vec<AmmFill>,
+    pub filled_quantity: Decimal,
+    /// Quote paid (buy) or received (sell), fees included
+    pub quote_amount: Decimal,
+    pub average_price: Decimal,
+    /// Stop orders released by the routed trades, and what they matched
+    pub triggered_stops: MatchResult,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// Contra-side book depth consumed while planning
+struct BookLadder {
+    levels: Vec<(Decimal, Decimal)>,
+    consumed: Decimal,
+}
+
+impl BookLadder {
+    /// Aggregate the crossing contra depth an order can actually trade against
+    fn new(order: &Order, order_book: &OrderBookManager, config: &MatchingConfig) -> Self {
+        let now = Utc::now();
+        let mut levels: Vec<(Decimal, Decimal)> = Vec::new();
+
+        for resting in order_book.resting_orders(order.side.opposite()) {
+            if !SmartOrderRouter::within_limit(order, resting.price) {
+                break;
+            }
+            if resting.is_expired_at(now)
+                || (config.self_trade_prevention && resting.user_id == order.user_id)
+            {
+                continue;
+            }
+            match levels.last_mut() {
+                Some((price, quantity)) if *price == resting.price => *quantity += resting.remaining_quantity,
+                _ => levels.push((resting.price, resting.remaining_quantity)),
+            }
+        }
+
+        Self { levels, consumed: Decimal::ZERO }
+    }
+
+    /// Gross notional and worst price of the next `quantity` after what is consumed
+    fn walk(&self, quantity: Decimal) -> Option<(Decimal, Decimal)> {
+        self.walk_range(self.consumed, quantity)
+    }
+
+    fn walk_range(&self, from: Decimal, quantity: Decimal) -> Option<(Decimal, Decimal)> {
+        let mut skip = from;
+        let mut remaining = quantity;
+        let mut notional = Decimal::ZERO;
+
+        for (price, level_quantity) in &self.levels {
+            let available = *level_quantity - skip.min(*level_quantity);
+            skip -= skip.min(*level_quantity);
+            if available <= Decimal::ZERO {
+                continue;
+            }
+            let take = available.min(remaining);
+            notional += take * price;
+            remaining -= take;
+            if remaining <= Decimal::ZERO {
+                return Some((notional, *price));
+            }
+        }
+
+        None
+    }
+}
+
+/// Routes orders across the order book and AMM pools.
+///
+/// The order is split into equal slices and each slice is allocated to the
+/// venue with the lowest marginal fee-inclusive cost (highest proceeds for
+/// sells). Book depth and constant-product curves are both convex, so the
+/// greedy allocation converges on the cost-minimizing split as slices shrink.
+pub struct SmartOrderRouter {
+    config: RoutingConfig,
+    matching_config: MatchingConfig,
+    engine: MatchingEngine,
+}
+
+impl SmartOrderRouter {
+    /// Create a new router
+    pub fn new(config: RoutingConfig, matching_config: MatchingConfig) -> Self {
+        Self {
+            config,
+            engine: MatchingEngine::new(matching_config.clone()),
+            matching_config,
+        }
+    }
+
+    /// Build an execution plan for an order.
+    ///
+    /// Limit orders only take liquidity whose fee-inclusive marginal price is
+    /// within the limit. Fails when the order cannot be filled in full.
+    pub fn plan(
+        &self,
+        order: &Order,
+        order_book: &OrderBookManager,
+        pools: &[AMMPool],
+    ) -> TradingResult<ExecutionPlan> {
+        let quantity = order.remaining_quantity;
+        if quantity <= Decimal::ZERO {
+            return Err(TradingError::invalid_order("Routed orders require a positive quantity"));
+        }
+        if self.config.slice_count == 0 {
+            return Err(TradingError::ConfigurationError {
+                component: "routing".to_string(),
+                message: "slice_count must be positive".to_string(),
+            });
+        }
+
+        let pools: Vec<&AMMPool> = pools
+            .iter()
+            .filter(|pool| pool.trading_pair == order.trading_pair)
+            .collect();
+        let mut ladder = BookLadder::new(order, order_book, &self.matching_config);
+        let mut pool_allocations = vec![Decimal::ZERO; pools.len()];
+
+        let slice = quantity / Decimal::from(self.config.slice_count);
+        let mut allocated = Decimal::ZERO;
+
+        while allocated < quantity {
+            let size = slice.min(quantity - allocated);
+
+            // (venue index, signed cost) where None is the book
+            let mut best: Option<(Option<usize>, Decimal)> = None;
+            if let Some((notional, _)) = ladder.walk(size) {
+                let cost = self.signed_cost(order.side, self.book_quote_amount(order.side, notional));
+                best = Some((None, cost));
+            }
+            for (index, pool) in pools.iter().enumerate() {
+                let from = pool_allocations[index];
+                let marginal = match (
+                    Self::amm_quote_amount(pool, order.side, from + size),
+                    Self::amm_quote_amount(pool, order.side, from),
+                ) {
+                    (Some(to_amount), Some(from_amount)) => to_amount - from_amount,
+                    _ => continue,
+                };
+                let cost = self.signed_cost(order.side, marginal);
+                if best.is_none_or(|(_, best_cost)| cost < best_cost) {
+                    best = Some((Some(index), cost));
+                }
+            }
+
+            let (venue, cost) = best.ok_or_else(|| {
+                TradingError::insufficient_liquidity(
+                    order.trading_pair.symbol(),
+                    format!("Only {} of {} routable", allocated, quantity),
+                )
+            })?;
+
+            let marginal_price = self.signed_cost(order.side, cost) / size;
+            if order.order_type != OrderType::Market && !Self::within_limit(order, marginal_price) {
+                return Err(TradingError::insufficient_liquidity(
+                    order.trading_pair.symbol(),
+                    format!("Only {} of {} routable within limit {}", allocated, quantity, order.price),
+                ));
+            }
+
+            match venue {
+                None => ladder.consumed += size,
+                Some(index) => pool_allocations[index] += size,
+            }
+            allocated += size;
+        }
+
+        let mut legs = Vec::new();
+        if ladder.consumed > Decimal::ZERO {
+            legs.push(self.book_leg(order.side, &ladder)?);
+        }
+        for (pool, allocation) in pools.iter().zip(&pool_allocations) {
+            if *allocation > Decimal::ZERO {
+                legs.push(Self::amm_leg(pool, order.side, *allocation)?);
+            }
+        }
+
+        let total_quote_amount: Decimal = legs.iter().map(|leg| leg.quote_amount).sum();
+        let total_fees = legs.iter().map(|leg| leg.fee).sum();
+
+        Ok(ExecutionPlan {
+            order_id: order.id,
+            trading_pair: order.trading_pair.clone(),
+            side: order.side,
+            quantity,
+            legs,
+            total_quote_amount,
+            total_fees,
+            average_price: total_quote_amount / quantity,
+            best_single_venue_quote_amount: self.best_single_venue(order, order_book, &pools, quantity),
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Execute a plan atomically.
+    ///
+    /// Every leg runs against copies of the book and pools, which replace the
+    /// originals only when all legs fill within the slippage tolerance and any
+    /// stops they trigger have been released. On failure nothing is changed and
+    /// no partial fill is reported.
+    pub fn execute(
+        &self,
+        order: &Order,
+        plan: &ExecutionPlan,
+        order_book: &mut OrderBookManager,
+        pools: &mut [AMMPool],
+    ) -> TradingResult<RouteExecution> {
+        if plan.order_id != order.id {
+            return Err(TradingError::invalid_order(format!(
+                "Plan {} does not belong to order {}",
+                plan.order_id, order.id
+            )));
+        }
+
+        // Journal broadcasts stay held on the copy until the route commits
+        let mut book = order_book.clone();
+        book.begin_feed_batch();
+        let mut pool_updates: Vec<(usize, AMMPool)> = Vec::new();
+        let mut trades = Vec::new();
+        let mut amm_fills = Vec::new();
+        let mut quote_amount = Decimal::ZERO;
+
+        for leg in &plan.legs {
+            let executed = match leg.venue {
+                Venue::OrderBook => {
+                    let (leg_trades, executed) = self.execute_book_leg(order, leg, &mut book)?;
+                    trades.extend(leg_trades);
+                    executed
+                }
+                Venue::AmmPool(pool_id) => {
+                    let index = pools
+                        .iter()
+                        .position(|pool| pool.id == pool_id)
+                        .ok_or_else(|| TradingError::LiquidityError {
+                            pool_id: pool_id.to_string(),
+                            message: "Pool not found".to_string(),
+                        })?;
+                    let mut pool = pools[index].clone();
+                    let executed = Self::execute_amm_leg(order.side, leg, &mut pool)?;
+                    amm_fills.push(AmmFill {
+                        pool_id,
+                        base_amount: leg.quantity,
+                        quote_amount: executed,
+                    });
+                    pool_updates.push((index, pool));
+                    executed
+                }
+            };
+            quote_amount += executed;
+        }
+
+        let tolerance = plan.total_quote_amount * self.config.max_slippage;
+        let slipped = match plan.side {
+            OrderSide::Buy => quote_amount > plan.total_quote_amount + tolerance,
+            OrderSide::Sell => quote_amount < plan.total_quote_amount - tolerance,
+        };
+        if slipped {
+            return Err(TradingError::SlippageTooHigh {
+                expected: plan.total_quote_amount.to_string(),
+                actual: quote_amount.to_string(),
+            });
+        }
+
+        // Release stops the book leg's prints triggered before committing
+        let mut triggered_stops = MatchResult::new();
+        self.engine.process_triggered_stops(&mut book, &mut triggered_stops)?;
+
+        // Commit every venue together
+        *order_book = book;
+        order_book.commit_feed_batch();
+        for (index, pool) in pool_updates {
+            pools[index] = pool;
+        }
+
+        Ok(RouteExecution {
+            plan: plan.clone(),
+            trades,
+            amm_fills,
+            filled_quantity: plan.quantity,
+            average_price: quote_amount / plan.quantity,
+            quote_amount,
+            triggered_stops,
+            executed_at: Utc::now(),
+        })
+    }
+
+    /// Plan and execute in one step
+    pub fn route(
+        &self,
+        order: &Order,
+        order_book: &mut OrderBookManager,
+        pools: &mut [AMMPool],
+    ) -> TradingResult<RouteExecution> {
+        let plan = self.plan(order, order_book, pools)?;
+        self.execute(order, &plan, order_book, pools)
+    }
+
+    /// Cross the book leg as an IOC at the leg's worst price; it must fill in full
+    fn execute_book_leg(
+        &self,
+        order: &Order,
+        leg: &RouteLeg,
+        book: &mut OrderBookManager,
+    ) -> TradingResult<(Vec<Trade>, Decimal)> {
+        let limit_price = leg.limit_price.unwrap_or(order.price);
+        let mut child = Order::new(
+            order.user_id.clone(),
+            order.trading_pair.clone(),
+            order.side,
+            OrderType::Limit,
+            limit_price,
+            leg.quantity,
+        )
+        .with_time_in_force(TimeInForce::IOC);
+        child.stp_mode = order.stp_mode;
+
+        let result = self.engine.match_order(&mut child, book)?;
+        if child.filled_quantity < leg.quantity {
+            return Err(TradingError::insufficient_liquidity(
+                order.trading_pair.symbol(),
+                format!(
+                    "Book leg filled {} of {}; routed order not executed",
+                    child.filled_quantity, leg.quantity
+                ),
+            ));
+        }
+
+        let notional: Decimal = result.trades.iter().map(|trade| trade.total_value()).sum();
+        Ok((result.trades, self.book_quote_amount(order.side, notional)))
+    }
+
+    /// Swap through a pool copy, returning the quote paid or received
+    fn execute_amm_leg(side: OrderSide, leg: &RouteLeg, pool: &mut AMMPool) -> TradingResult<Decimal> {
+        match side {
+            OrderSide::Buy => {
+                let input = pool.calculate_input(leg.quantity, true).ok_or_else(|| {
+                    TradingError::LiquidityError {
+                        pool_id: pool.id.to_string(),
+                        message: format!("Pool cannot deliver {}", leg.quantity),
+                    }
+                })?;
+                pool.swap(input, false)?;
+                Ok(input)
+            }
+            OrderSide::Sell => pool.swap(leg.quantity, true),
+        }
+    }
+
+    fn book_leg(&self, side: OrderSide, ladder: &BookLadder) -> TradingResult<RouteLeg> {
+        let (notional, worst_price) = ladder
+            .walk_range(Decimal::ZERO, ladder.consumed)
+            .ok_or_else(|| TradingError::matching_error("Book allocation exceeds depth"))?;
+        let quote_amount = self.book_quote_amount(side, notional);
+        let best_price = ladder.levels.first().map_or(worst_price, |(price, _)| *price);
+
+        Ok(RouteLeg {
+            venue: Venue::OrderBook,
+            quantity: ladder.consumed,
+            fee: notional * self.matching_config.matching_fee_percentage,
+            average_price: quote_amount / ladder.consumed,
+            limit_price: Some(worst_price),
+            price_impact: ((worst_price - best_price) / best_price).abs(),
+            quote_amount,
+        })
+    }
+
+    fn amm_leg(pool: &AMMPool, side: OrderSide, quantity: Decimal) -> TradingResult<RouteLeg> {
+        let quote_amount = Self::amm_quote_amount(pool, side, quantity).ok_or_else(|| {
+            TradingError::LiquidityError {
+                pool_id: pool.id.to_string(),
+                message: format!("Pool cannot deliver {}", quantity),
+            }
+        })?;
+        let (fee, price_impact) = match side {
+            OrderSide::Buy => (
+                quote_amount * pool.fee_percentage,
+                pool.calculate_price_impact(quote_amount, false),
+            ),
+            OrderSide::Sell => (
+                quantity * pool.fee_percentage * pool.current_price(),
+                pool.calculate_price_impact(quantity, true),
+            ),
+        };
+
+        Ok(RouteLeg {
+            venue: Venue::AmmPool(pool.id),
+            quantity,
+            quote_amount,
+            fee,
+            average_price: quote_amount / quantity,
+            limit_price: None,
+            price_impact,
+        })
+    }
+
+    /// Best quote amount for filling the whole order on one venue
+    fn best_single_venue(
+        &self,
+        order: &Order,
+        order_book: &OrderBookManager,
+        pools: &[&AMMPool],
+        quantity: Decimal,
+    ) -> Option<Decimal> {
+        let book = BookLadder::new(order, order_book, &self.matching_config)
+            .walk(quantity)
+            .map(|(notional, _)| self.book_quote_amount(order.side, notional));
+
+        book.into_iter()
+            .chain(pools.iter().filter_map(|pool| Self::amm_quote_amount(pool, order.side, quantity)))
+            .min_by_key(|amount| self.signed_cost(order.side, *amount))
+    }
+
+    /// Quote paid (buy) or received (sell) for a book notional after taker fees
+    fn book_quote_amount(&self, side: OrderSide, notional: Decimal) -> Decimal {
+        let fee = notional * self.matching_config.matching_fee_percentage;
+        match side {
+            OrderSide::Buy => notional + fee,
+            OrderSide::Sell => notional - fee,
+        }
+    }
+
+    /// Quote paid (buy) or received (sell) for a base quantity through a pool
+    fn amm_quote_amount(pool: &AMMPool, side: OrderSide, quantity: Decimal) -> Option<Decimal> {
+        if quantity <= Decimal::ZERO {
+            return Some(Decimal::ZERO);
+        }
+        match side {
+            OrderSide::Buy => pool.calculate_input(quantity, true),
+            OrderSide::Sell => Some(pool.calculate_output(quantity, true)),
+        }
+    }
+
+    /// Cost to minimize: quote paid for buys, negated proceeds for sells
+    fn signed_cost(&self, side: OrderSide, quote_amount: Decimal) -> Decimal {
+        match side {
+            OrderSide::Buy => quote_amount,
+            OrderSide::Sell => -quote_amount,
+        }
+    }
+
+    fn within_limit(order: &Order, price: Decimal) -> bool {
+        match (order.order_type, order.side) {
+            (OrderType::Market, _) => true,
+            (_, OrderSide::Buy) => price <= order.price,
+            (_, OrderSide::Sell) => price >= order.price,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::order_book::OrderBookConfig;
+
+    fn pair() -> TradingPair {
+        TradingPair::new("RWA".to_string(), "USD".to_string())
+    }
+
+    fn book_with_asks(levels: &[(i64, i64)]) -> OrderBookManager {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        for (price, quantity) in levels {
+            book.add_order(Order::new(
+                "maker".to_string(),
+                pair(),
+                OrderSide::Sell,
+                OrderType::Limit,
+                Decimal::new(*price, 0),
+                Decimal::new(*quantity, 0),
+            ))
+            .unwrap();
+        }
+        book
+    }
+
+    fn pool(base: i64, price: i64) -> AMMPool {
+        AMMPool::new(pair(), Decimal::new(base, 0), Decimal::new(base * price, 0), Decimal::new(30, 4))
+    }
+
+    fn buy(quantity: i64, order_type: OrderType, price: i64) -> Order {
+        Order::new(
+            "taker".to_string(),
+            pair(),
+            OrderSide::Buy,
+            order_type,
+            Decimal::new(price, 0),
+            Decimal::new(quantity, 0),
+        )
+    }
+
+    fn router() -> SmartOrderRouter {
+        SmartOrderRouter::new(RoutingConfig::default(), MatchingConfig::default())
+    }
+
+    #[test]
+    fn test_plan_splits_across_book_and_pools() {
+        let book = book_with_asks(&[(100, 10), (101, 10), (105, 50)]);
+        let pools = vec![pool(1000, 100), pool(500, 100)];
+        let order = buy(60, OrderType::Market, 0);
+
+        let plan = router().plan(&order, &book, &pools).unwrap();
+
+        assert_eq!(plan.legs.iter().map(|leg| leg.quantity).sum::<Decimal>(), Decimal::new(60, 0));
+        assert!(plan.leg(Venue::OrderBook).unwrap().quantity >= Decimal::new(20, 0));
+        assert!(plan.leg(Venue::AmmPool(pools[0].id)).unwrap().quantity > Decimal::ZERO);
+        assert!(plan.leg(Venue::AmmPool(pools[1].id)).unwrap().quantity > Decimal::ZERO);
+        // The larger pool absorbs more before its price impact catches up
+        assert!(plan.leg(Venue::AmmPool(pools[0].id)).unwrap().quantity
+            > plan.leg(Venue::AmmPool(pools[1].id)).unwrap().quantity);
+        assert!(plan.savings().unwrap() > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_sell_routes_to_best_proceeds() {
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair());
+        book.add_order(Order::new(
+            "maker".to_string(),
+            pair(),
+            OrderSide::Buy,
+            OrderType::Limit,
+            Decimal::new(90, 0),
+            Decimal::new(100, 0),
+        ))
+        .unwrap();
+        let pools = vec![pool(1000, 100)];
+        let order = Order::new(
+            "taker".to_string(),
+            pair(),
+            OrderSide::Sell,
+            OrderType::Market,
+            Decimal::ZERO,
+            Decimal::new(10, 0),
+        );
+
+        let plan = router().plan(&order, &book, &pools).unwrap();
+
+        // The pool pays ~99.7 versus 90 in the book
+        assert!(plan.leg(Venue::OrderBook).is_none());
+        assert_eq!(plan.leg(Venue::AmmPool(pools[0].id)).unwrap().quantity, Decimal::new(10, 0));
+    }
+
+    #[test]
+    fn test_execute_applies_all_legs() {
+        let mut book = book_with_asks(&[(100, 10), (101, 10)]);
+        let mut pools = vec![pool(1000, 100)];
+        let order = buy(40, OrderType::Limit, 110);
+        let router = router();
+
+        let plan = router.plan(&order, &book, &pools).unwrap();
+        let book_quantity = plan.leg(Venue::OrderBook).unwrap().quantity;
+        let mut journal = book.subscribe_journal(64);
+        let execution = router.execute(&order, &plan, &mut book, &mut pools).unwrap();
+        assert!(journal.try_recv().is_ok());
+
+        assert_eq!(execution.filled_quantity, Decimal::new(40, 0));
+        assert_eq!(execution.trades.iter().map(|trade| trade.quantity).sum::<Decimal>(), book_quantity);
+        assert_eq!(execution.amm_fills.len(), 1);
+        assert!(pools[0].base_reserve < Decimal::new(1000, 0));
+        assert!((execution.quote_amount - plan.total_quote_amount).abs() < Decimal::new(1, 6));
+    }
+
+    #[test]
+    fn test_book_leg_prints_release_stops() {
+        let mut book = book_with_asks(&[(100, 10), (101, 10), (120, 5)]);
+        let stop = Order::new(
+            "stopper".to_string(),
+            pair(),
+            OrderSide::Buy,
+            OrderType::Stop,
+            Decimal::ZERO,
+            Decimal::new(2, 0),
+        )
+        .with_stop_price(Decimal::new(100, 0));
+        book.add_stop_order(stop).unwrap();
+        let mut pools = vec![pool(1000, 100)];
+        let order = buy(40, OrderType::Limit, 110);
+        let router = router();
+
+        let plan = router.plan(&order, &book, &pools).unwrap();
+        let execution = router.execute(&order, &plan, &mut book, &mut pools).unwrap();
+
+        // The routed prints woke the stop, which lifted the ask left at 120
+        assert!(book.stop_orders().is_empty());
+        let stop_trades = &execution.triggered_stops.trades;
+        assert!(!stop_trades.is_empty());
+        assert!(stop_trades.iter().all(|trade| trade.buyer_user_id == "stopper"));
+        assert!(execution.trades.iter().all(|trade| trade.buyer_user_id == "taker"));
+    }
+
+    #[test]
+    fn test_execution_is_all_or_nothing() {
+        let mut book = book_with_asks(&[(100, 10), (101, 10)]);
+        let mut pools = vec![pool(1000, 100)];
+        let order = buy(40, OrderType::Market, 0);
+        let router = router();
+        let plan = router.plan(&order, &book, &pools).unwrap();
+
+        // Book depth disappears between planning and execution
+        let best = book.next_resting_order(OrderSide::Sell, &Default::default()).unwrap().id;
+        book.remove_order(best).unwrap();
+        let pool_before = pools[0].clone();
+        let asks_before = book.resting_orders(OrderSide::Sell).len();
+        let mut journal = book.subscribe_journal(64);
+
+        assert!(router.execute(&order, &plan, &mut book, &mut pools).is_err());
+        // Fills on the abandoned book copy are never broadcast
+        assert!(journal.try_recv().is_err());
+        assert_eq!(pools[0].base_reserve, pool_before.base_reserve);
+        assert_eq!(book.resting_orders(OrderSide::Sell).len(), asks_before);
+    }
+
+    #[test]
+    fn test_limit_and_depth_failures() {
+        let book = book_with_asks(&[(100, 10)]);
+        let pools = vec![pool(100, 100)];
+
+        // Pool price impact pushes the marginal price past the limit
+        assert!(router().plan(&buy(40, OrderType::Limit, 102), &book, &pools).is_err());
+        // Without pools the book alone cannot fill
+        assert!(router().plan(&buy(40, OrderType::Market, 0), &book, &[]).is_err());
+    }
+}
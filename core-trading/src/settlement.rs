@@ -85,8 +85,8 @@ impl SettlementInstruction {
             trading_pair: trade.trading_pair.clone(),
             buyer_id: trade.buyer_user_id.clone(),
             seller_id: trade.seller_user_id.clone(),
-            asset_to_deliver: trade.trading_pair.base.clone(),
-            asset_to_receive: trade.trading_pair.quote.clone(),
+            asset_to_deliver: trade.trading_pair.base_asset.clone(),
+            asset_to_receive: trade.trading_pair.quote_asset.clone(),
             delivery_quantity: trade.quantity,
             payment_amount: trade.price * trade.quantity,
             settlement_date,
@@ -228,6 +228,19 @@ pub enum TimeInForce {
     ATC,
 }
 
+/// Self-trade prevention mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SelfTradePreventionMode {
+    /// Cancel the remainder of the incoming order
+    CancelNewest,
+    /// Cancel the resting order and keep matching
+    CancelOldest,
+    /// Cancel both the incoming and the resting order
+    CancelBoth,
+    /// Reduce both orders by the overlapping quantity and cancel whichever is exhausted
+    DecrementAndCancel,
+}
+
 /// Trade structure
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
@@ -7,6 +7,7 @@
 
 pub mod auth;
 pub mod health;
+pub mod market_feed;
 pub mod middleware;
 pub mod proxy;
 pub mod rate_limit;
@@ -86,6 +87,7 @@ pub struct GatewayState {
     pub config: Config,
     pub service_registry: Arc<routing::ServiceRegistry>,
     pub rate_limiter: Arc<rate_limit::RateLimiter>,
+    pub market_feeds: Arc<market_feed::MarketFeedHub>,
     pub metrics: Arc<GatewayMetrics>,
 }
 
@@ -177,6 +179,12 @@ pub fn create_app(
                         ),
                 ),
         )
+        // Public market data, like health and metrics, needs no token
+        .service(
+            web::scope("/ws")
+                .wrap(middleware::RateLimit)
+                .route("/market/{symbol}", web::get().to(market_feed::market_feed_ws)),
+        )
         .service(
             web::scope("/auth")
                 .route("/login", web::post().to(proxy::proxy_to_auth_service))
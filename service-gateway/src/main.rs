@@ -11,6 +11,7 @@ use core_observability::{init_tracing, BusinessMetrics};
 use core_security::jwt::{JwtConfig, JwtManager};
 use service_gateway::{
     auth::AuthService,
+    market_feed::MarketFeedHub,
     rate_limit::{RateLimitConfig, RateLimiter},
     routing::ServiceRegistry,
     GatewayState,
@@ -57,6 +58,10 @@ async fn main() -> std::io::Result<()> {
     };
     let rate_limiter = Arc::new(RateLimiter::new(rate_limit_config));
 
+    // Market data feeds; the trading service owns the order books and
+    // attaches each one it hosts, so the gateway never matches orders
+    let market_feeds = Arc::new(MarketFeedHub::new());
+
     // Initialize microservice registry
     let service_registry = Arc::new(ServiceRegistry::new(&config).await?);
 
@@ -66,6 +71,7 @@ async fn main() -> std::io::Result<()> {
         metrics: metrics.clone(),
         service_registry,
         rate_limiter,
+        market_feeds,
     });
 
     // Extract server configuration
@@ -0,0 +1,302 @@
+// =====================================================================================
+// File: service-gateway/src/market_feed.rs
+// Description: WebSocket endpoint streaming sequenced L2/L3 market data feeds
+// Author: arkSong (arksong2018@gmail.com)
+// =====================================================================================
+
+use crate::GatewayState;
+use actix_web::{web, HttpRequest, HttpResponse, Result as ActixResult};
+use actix_ws::Message;
+use core_trading::{FeedMessage, FeedPublisher, JournalEntry, OrderBookManager, TradingError};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::{Mutex, RwLock};
+use tracing::{debug, warn};
+
+/// Market data feeds keyed by trading pair symbol.
+///
+/// The gateway only publishes; order books are owned by the trading service,
+/// which attaches them here and publishes its executions through the
+/// returned publisher.
+#[derive(Default)]
+pub struct MarketFeedHub {
+    feeds: RwLock<HashMap<String, Arc<Mutex<FeedPublisher>>>>,
+}
+
+impl MarketFeedHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expose a publisher's feed
+    pub async fn register(&self, publisher: Arc<Mutex<FeedPublisher>>) {
+        let symbol = publisher.lock().await.trading_pair().symbol();
+        self.feeds.write().await.insert(symbol, publisher);
+    }
+
+    /// Publish the feed of an order book owned by the trading service.
+    ///
+    /// The publisher follows the book's journal broadcast, so the pending
+    /// journal stays untouched for the persister.
+    pub async fn attach(
+        &self,
+        order_book: Arc<Mutex<OrderBookManager>>,
+        capacity: usize,
+    ) -> Arc<Mutex<FeedPublisher>> {
+        let (publisher, journal) = {
+            let mut book = order_book.lock().await;
+            let journal = book.subscribe_journal(capacity);
+            (Arc::new(Mutex::new(FeedPublisher::new(&book, capacity))), journal)
+        };
+
+        self.register(publisher.clone()).await;
+        tokio::spawn(follow_journal(publisher.clone(), order_book, journal));
+        publisher
+    }
+
+    /// Look up the feed for a trading pair symbol such as `RWA/USD`
+    pub async fn get(&self, symbol: &str) -> Option<Arc<Mutex<FeedPublisher>>> {
+        self.feeds.read().await.get(symbol).cloned()
+    }
+}
+
+/// Publish deltas for journal entries until the book is dropped
+async fn follow_journal(
+    publisher: Arc<Mutex<FeedPublisher>>,
+    order_book: Arc<Mutex<OrderBookManager>>,
+    mut journal: broadcast::Receiver<JournalEntry>,
+) {
+    loop {
+        let missed = match journal.recv().await {
+            Ok(entry) => match publisher.lock().await.publish_journal(std::slice::from_ref(&entry)) {
+                Ok(_) => continue,
+                Err(e) => e.to_string(),
+            },
+            Err(RecvError::Lagged(skipped)) => format!("{} journal entries skipped", skipped),
+            Err(RecvError::Closed) => return,
+        };
+
+        let mut publisher = publisher.lock().await;
+        warn!("Market feed for {} fell behind its book ({}), rebasing", publisher.trading_pair().symbol(), missed);
+        publisher.rebase(&*order_book.lock().await);
+    }
+}
+
+/// Feed subscription options
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    /// 2 for aggregated price levels, 3 for order-by-order detail
+    #[serde(default = "default_level")]
+    pub level: u8,
+}
+
+fn default_level() -> u8 {
+    2
+}
+
+/// Path symbols use a dash instead of a slash, e.g. `RWA-USD`
+pub fn parse_symbol(path_symbol: &str) -> String {
+    path_symbol.replace('-', "/").to_uppercase()
+}
+
+/// Serialize a feed message at the requested depth level
+pub fn encode(message: &FeedMessage, level: u8) -> String {
+    let message = if level >= 3 { message.clone() } else { message.to_l2() };
+    serde_json::to_string(&message).unwrap_or_default()
+}
+
+/// WebSocket market data feed.
+///
+/// Sends a snapshot followed by sequenced deltas, trades and tickers. When the
+/// connection falls behind, a fresh snapshot is sent so the client can resync;
+/// clients may also request one by sending `resync`.
+pub async fn market_feed_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    path: web::Path<String>,
+    query: web::Query<FeedQuery>,
+    data: web::Data<GatewayState>,
+) -> ActixResult<HttpResponse> {
+    let symbol = parse_symbol(&path);
+    let publisher = match data.market_feeds.get(&symbol).await {
+        Some(publisher) => publisher,
+        None => {
+            return Ok(HttpResponse::NotFound().json(json!({
+                "error": format!("No market feed for {}", symbol)
+            })));
+        }
+    };
+    let level = query.level;
+
+    let (response, mut session, mut stream) = actix_ws::handle(&req, body)?;
+
+    // Subscribe and snapshot under one lock so no message falls in between
+    let (mut consumer, snapshot) = {
+        let publisher = publisher.lock().await;
+        (publisher.subscribe(), publisher.snapshot())
+    };
+    debug!("Market feed subscriber connected for {} (L{})", symbol, level);
+
+    actix_web::rt::spawn(async move {
+        if session.text(encode(&snapshot, level)).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                feed = consumer.next() => match feed {
+                    Ok(message) => {
+                        if session.text(encode(&message, level)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(TradingError::FeedSequenceGap { expected, received, .. }) => {
+                        warn!("Market feed subscriber for {} lagged ({} -> {}), resyncing", symbol, expected, received);
+                        let snapshot = publisher.lock().await.snapshot();
+                        if consumer.resync(&snapshot).is_err()
+                            || session.text(encode(&snapshot, level)).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                incoming = stream.next() => match incoming {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        if session.pong(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Text(text))) if text.trim() == "resync" => {
+                        let snapshot = publisher.lock().await.snapshot();
+                        if consumer.resync(&snapshot).is_err()
+                            || session.text(encode(&snapshot, level)).await.is_err()
+                        {
+                            break;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => {
+                        let _ = session.close(reason).await;
+                        return;
+                    }
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break,
+                },
+            }
+        }
+
+        let _ = session.close(None).await;
+    });
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core_trading::market_feed::FeedPayload;
+    use core_trading::matching::MatchingConfig;
+    use core_trading::order_book::{Order, OrderBookConfig};
+    use core_trading::{MatchingEngine, OrderSide, OrderType, TradingPair};
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_parse_symbol() {
+        assert_eq!(parse_symbol("rwa-usd"), "RWA/USD");
+    }
+
+    #[tokio::test]
+    async fn test_hub_and_level_encoding() {
+        let pair = TradingPair::new("RWA".to_string(), "USD".to_string());
+        let mut book = OrderBookManager::new(OrderBookConfig::default(), pair.clone());
+        book.add_order(Order::new(
+            "maker".to_string(),
+            pair,
+            OrderSide::Buy,
+            OrderType::Limit,
+            Decimal::new(99, 0),
+            Decimal::new(2, 0),
+        ))
+        .unwrap();
+
+        let hub = MarketFeedHub::default();
+        hub.register(Arc::new(Mutex::new(FeedPublisher::new(&book, 16)))).await;
+        let publisher = hub.get(&parse_symbol("RWA-USD")).await.unwrap();
+        let snapshot = publisher.lock().await.snapshot();
+
+        let l2: serde_json::Value = serde_json::from_str(&encode(&snapshot, 2)).unwrap();
+        let l3: serde_json::Value = serde_json::from_str(&encode(&snapshot, 3)).unwrap();
+        assert_eq!(l2["payload"]["data"]["orders"].as_array().unwrap().len(), 0);
+        assert_eq!(l3["payload"]["data"]["orders"].as_array().unwrap().len(), 1);
+        assert!(hub.get("BTC/USD").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_attached_book_publishes_without_draining_journal() {
+        let pair = TradingPair::new("RWA".to_string(), "USD".to_string());
+        let hub = MarketFeedHub::default();
+        let config = OrderBookConfig {
+            enable_journal: true,
+            ..OrderBookConfig::default()
+        };
+        let book = Arc::new(Mutex::new(OrderBookManager::new(config, pair.clone())));
+        hub.attach(book.clone(), 16).await;
+        let mut consumer = hub.get("RWA/USD").await.unwrap().lock().await.subscribe();
+
+        book.lock()
+            .await
+            .add_order(Order::new(
+                "maker".to_string(),
+                pair,
+                OrderSide::Sell,
+                OrderType::Limit,
+                Decimal::new(101, 0),
+                Decimal::new(1, 0),
+            ))
+            .unwrap();
+
+        let message = consumer.next().await.unwrap();
+        assert_eq!(message.sequence, 1);
+        assert_eq!(consumer.best_ask(), Some(Decimal::new(101, 0)));
+        // The persister still sees every entry
+        assert_eq!(book.lock().await.drain_journal().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_owner_publishes_executions_on_attached_feed() {
+        let pair = TradingPair::new("RWA".to_string(), "USD".to_string());
+        let hub = MarketFeedHub::new();
+        let book = OrderBookManager::new(OrderBookConfig::default(), pair.clone());
+        let book = Arc::new(Mutex::new(book));
+        let publisher = hub.attach(book.clone(), 16).await;
+        let mut consumer = hub.get("RWA/USD").await.unwrap().lock().await.subscribe();
+
+        // The owning service matches on its own book and publishes the result
+        let engine = MatchingEngine::new(MatchingConfig::default());
+        let order = |user: &str, side: OrderSide| {
+            Order::new(user.to_string(), pair.clone(), side, OrderType::Limit, Decimal::new(100, 0), Decimal::new(1, 0))
+        };
+        let result = {
+            let mut book = book.lock().await;
+            engine.submit_order(order("maker", OrderSide::Sell), &mut book).unwrap();
+            engine.submit_order(order("taker", OrderSide::Buy), &mut book).unwrap()
+        };
+        publisher.lock().await.publish_execution(&result.trades, None, None);
+
+        let mut trades = 0;
+        let mut ticker = None;
+        while ticker.is_none() {
+            match consumer.next().await.unwrap().payload {
+                FeedPayload::Trade(_) => trades += 1,
+                FeedPayload::Ticker(update) => ticker = Some(update),
+                _ => {}
+            }
+        }
+        assert_eq!(trades, 1);
+        assert_eq!(ticker.unwrap().last_price, Decimal::new(100, 0));
+    }
+}